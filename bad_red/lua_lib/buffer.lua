@@ -76,6 +76,63 @@ self: Buffer - Class to instantiate the new buffer from.
 ]]
     )
 
+    P.open_typed = red.doc.build_fn(
+        function(self, type)
+            local id = coroutine.yield(red.call.buffer_open_typed(type))
+            return self:new(id)
+        end,
+        "open_typed",
+        [[
+Creates a new buffer in the editor of the given type and empty content.
+]],
+        [[
+Naive buffers avoid the bookkeeping of gap buffers, which suits small buffers such as prompts that are rarely edited in the middle.
+]],
+        [[
+Buffer - Object with the id of the new buffer.
+]],
+        [[
+self: Buffer - Class to instantiate the new buffer from.
+]],
+        [[
+type: EditorBufferType table (RedEnum) - Type of the new buffer.
+    See:
+        - `buffer.naive`
+        - `buffer.gap`
+]]
+    )
+
+    P.open_paged = red.doc.build_fn(
+        function(self, file, window_size)
+            local id = coroutine.yield(red.call.buffer_open_paged(file:id(), window_size))
+            return self:new(id)
+        end,
+        "open_paged",
+        [[
+Creates a new read-only buffer showing a file too large to hold in memory.
+]],
+        [[
+Only a window of the file's content is held in memory at once, and the rest is read from the file as the buffer is displayed or queried. Edits to the buffer are ignored, and the buffer is not linked to the file. Queries over the whole content, such as `content` or searches, still read the entire file. Content which fails to read from the file is given as empty, and writing the buffer then fails rather than writing the partial content.
+
+Ex: `
+    local log = buffer:open_paged(file:open("~/huge.log"))
+    pane:set_buffer(log)
+`
+]],
+        [[
+Buffer - Object with the id of the new buffer.
+]],
+        [[
+self: Buffer - Class to instantiate the new buffer from.
+]],
+        [[
+file: File - File whose content the buffer shows.
+]],
+        [[
+window_size: positive integer or nil - Number of bytes of the file held in memory at once. Defaults to 1 MiB.
+]]
+    )
+
     P.close = red.doc.build_fn(
         function(self)
             coroutine.yield(red.call.buffer_close(self:id()))
@@ -113,7 +170,11 @@ self: Buffer - Class table to instantiate the new buffer table from.
 
     P.insert = red.doc.build_fn(
         function(self, content)
-            coroutine.yield(red.call.buffer_insert(self:id(), content))
+            if self._id == nil then
+                coroutine.yield(red.call.active_buffer_insert(content))
+            else
+                coroutine.yield(red.call.buffer_insert(self._id, content))
+            end
         end,
         "insert",
         [[
@@ -133,7 +194,11 @@ content: string - The string content to insert. Will be inserted into `self` as
 
     P.delete = red.doc.build_fn(
         function(self, count)
-            return coroutine.yield(red.call.buffer_delete(self:id(), count))
+            if self._id == nil then
+                return coroutine.yield(red.call.active_buffer_delete(count))
+            end
+
+            return coroutine.yield(red.call.buffer_delete(self._id, count))
         end,
         "delete",
         [[
@@ -168,15 +233,29 @@ self: Buffer - Buffer object whose cursor is returned. If no buffer ID is set on
 ]]
     )
 
+    local function visible_fold(buffer, line)
+        local pane = red.pane:current()
+        if pane:buffer():id() ~= buffer:id() then
+            return nil
+        end
+
+        return pane:fold_at(line)
+    end
+
     P.cursor_up = red.doc.build_fn(
         function(self, count, skip_newlines)
             local current_line = self:cursor_line()
+            local current_fold = visible_fold(self, current_line)
+            if current_fold then
+                current_line = current_fold.start_line
+            end
             local to_line = current_line - count
 
             if to_line < 0 then
                 self:set_cursor(0)
             else
-                self:set_cursor_line(current_line - 1)
+                local to_fold = visible_fold(self, current_line - 1)
+                self:set_cursor_line(to_fold and to_fold.start_line or current_line - 1)
             end
 
             if skip_newlines and self:cursor_content() == "\n" and self:cursor_line_content() ~= "\n" then
@@ -188,7 +267,7 @@ self: Buffer - Buffer object whose cursor is returned. If no buffer ID is set on
 Moves the cursor a certain number of lines up.
 ]],
         [[
-Retains the column index of the cursor if the new line landed on is shorter than the previous column index. Provides options for dealing with newlines.
+Retains the column index of the cursor if the new line landed on is shorter than the previous column index. Folded lines in the active pane are moved over as a single line. Provides options for dealing with newlines.
 ]],
         [[
 nil
@@ -207,6 +286,10 @@ skip_newlines: bool = false - Should the cursor be allowed to stop over a newlin
     P.cursor_down = red.doc.build_fn(
         function(self, count, skip_newlines)
             local current_line = self:cursor_line()
+            local current_fold = visible_fold(self, current_line)
+            if current_fold then
+                current_line = current_fold.end_line
+            end
             local to_line = current_line + count
             local line_count = self:lines()
 
@@ -230,7 +313,7 @@ skip_newlines: bool = false - Should the cursor be allowed to stop over a newlin
 Moves the cursor a certain number of lines down.
 ]],
         [[
-Retains the column index of the cursor if the new line landed on is shorter than the previous column index. Provides options for dealing with newlines.
+Retains the column index of the cursor if the new line landed on is shorter than the previous column index. Folded lines in the active pane are moved over as a single line. Provides options for dealing with newlines.
 ]],
         [[
 nil
@@ -246,16 +329,42 @@ skip_newlines: bool = false - Should the cursor be allowed to stop over a newlin
 ]]
     )
 
+    P.cursor_moved_in_line = red.doc.build_fn(
+        function(self, count)
+            return coroutine.yield(red.call.buffer_cursor_moved_by_char_in_line(self:id(), count))
+        end,
+        "cursor_moved_in_line",
+        [[
+Returns the byte index `count` characters to the right of this buffer's cursor without crossing into another line.
+]],
+        [[
+Negative `count` moves the index to the left. Stops at the start of the cursor's line, or at the end of the line before its newline. Suits character motions in modal editing such as `h` and `l`.
+
+Ex: `
+    buffer:set_cursor(buffer:cursor_moved_in_line(1))
+`
+]],
+        [[
+non-negative integer - The byte index moved by up to `count` chars within the cursor's line.
+]],
+        [[
+self: Buffer - Buffer object whose moved cursor index is returned. If no buffer ID is set on this object, uses the active buffer instead.
+]],
+        [[
+count: integer - Number of characters to move.
+]]
+    )
+
     P.line_length = red.doc.build_fn(
         function(self, line_index)
             return coroutine.yield(red.call.buffer_line_length(self:id(), line_index))
         end,
         "line_length",
         [[
-Returns the number of characters in the line at line_index.
+Returns the number of characters in the line at line_index, not counting the line's newline.
 ]],
         [[
-Returns nil if line_index is out of bounds.
+This is the character count of `line_content` for the same line with its newline removed, so the empty last line after a trailing newline has length 0. Counted without copying the line. Returns nil if line_index is out of bounds.
 ]],
         [[
 non-negative integer - Number of characters in line `line_index`.
@@ -264,7 +373,7 @@ non-negative integer - Number of characters in line `line_index`.
 self: Buffer table - The buffer whose line length at `line_index` is returned.
 ]],
         [[
-line_index: non-negative
+line_index: non-negative integer - Index of the line whose length is returned.
 ]]
     )
 
@@ -373,6 +482,97 @@ line: non-negative integer - The line number the cursor should be set to.
 ]]
     )
 
+    P.goto_line = red.doc.build_fn(
+        function(self, line, column)
+            return coroutine.yield(red.call.buffer_goto_line(self:id(), line, column or 0))
+        end,
+        "goto_line",
+        [[
+Moves the cursor to the given column of the given line.
+]],
+        [[
+Unlike `set_cursor_line`, the column is given explicitly rather than kept from before the move, and the column index for the cursor is updated. A line past the end of the buffer moves to the last line, and a column past the end of the line moves to the line's end.
+]],
+        [[
+non-negative integer - Byte index of the cursor after the move.
+]],
+        [[
+self: Buffer - Buffer object whose cursor should be set. If no buffer ID is set on this object, sets the cursor of the active buffer.
+]],
+        [[
+line: non-negative integer - The line number the cursor should be set to.
+]],
+        [[
+column: non-negative integer = 0 - The char column on the line the cursor should be set to.
+]]
+    )
+
+    P.line_first_non_whitespace = red.doc.build_fn(
+        function(self, line)
+            return coroutine.yield(red.call.buffer_line_first_non_whitespace(self:id(), line))
+        end,
+        "line_first_non_whitespace",
+        [[
+Gets the byte index of the first char on a line which is not a space or tab.
+]],
+        [[
+Blank lines, including lines of only spaces and tabs, give the line's start.
+]],
+        [[
+non-negative integer or nil - Byte index of the first non-whitespace char, or nil if the line does not exist.
+]],
+        [[
+self: Buffer - Buffer object whose line is checked. If no buffer ID is set on this object, uses the active buffer instead.
+]],
+        [[
+line: non-negative integer - Index of the line to check.
+]]
+    )
+
+    P.paragraph_move = red.doc.build_fn(
+        function(self, forward)
+            return coroutine.yield(red.call.buffer_paragraph_move(self:id(), forward))
+        end,
+        "paragraph_move",
+        [[
+Moves the cursor to the blank line after or before the paragraph it is in.
+]],
+        [[
+Paragraphs are runs of non-blank lines, where blank lines are empty or contain only whitespace. Blank lines at the cursor are skipped over before the paragraph. Moves to the end or start of the buffer if no blank line follows or precedes the paragraph.
+]],
+        [[
+non-negative integer - Byte index of the cursor after the move.
+]],
+        [[
+self: Buffer - Buffer object whose cursor should be moved. If no buffer ID is set on this object, moves the cursor of the active buffer.
+]],
+        [[
+forward: bool - Should the cursor move toward the end of the buffer rather than the start.
+]]
+    )
+
+    P.match_indent = red.doc.build_fn(
+        function(self, forward)
+            return coroutine.yield(red.call.buffer_match_indent(self:id(), forward))
+        end,
+        "match_indent",
+        [[
+Moves the cursor to the next or previous line indented no deeper than the cursor's line.
+]],
+        [[
+Blank lines are skipped over. The cursor is placed on the first non-whitespace char of the line moved to. Moving back from within a nested block lands on the line enclosing the block, and moving forward from a block's opening line lands past the block. Indentation is measured in columns, using the buffer's tab width for tabs.
+]],
+        [[
+non-negative integer or nil - Byte index of the cursor after the move, or nil if no such line exists and the cursor was not moved.
+]],
+        [[
+self: Buffer - Buffer object whose cursor should be moved. If no buffer ID is set on this object, moves the cursor of the active buffer.
+]],
+        [[
+forward: bool - Should the cursor move toward the end of the buffer rather than the start.
+]]
+    )
+
     P.length = red.doc.build_fn(
         function(self)
             return coroutine.yield(red.call.buffer_length(self:id()))
@@ -398,7 +598,9 @@ self: Buffer - Buffer object whose length is returned. If no buffer ID is set on
         [[
 Gets the number of lines of the content of this buffer.
 ]],
-        nil,
+        [[
+Content ending in a newline has an empty last line after that newline, which is counted.
+]],
         [[
 non-negative integer - The number of lines in this buffer.
 ]],
@@ -407,6 +609,31 @@ self: Buffer - Buffer object whose number of lines is returned. If no buffer ID
 ]]
     )
 
+    P.line_count_in_range = red.doc.build_fn(
+        function(self, start_byte, end_byte)
+            return coroutine.yield(red.call.buffer_line_count_in_range(self:id(), start_byte, end_byte))
+        end,
+        "line_count_in_range",
+        [[
+Gets the number of newlines within a byte range of this buffer.
+]],
+        [[
+This is the number of line breaks the range crosses, so a range within a single line gives 0 and the range spans one more line than the count. Computed without copying the content.
+]],
+        [[
+non-negative integer - Number of newlines from `start_byte` up to but not including `end_byte`.
+]],
+        [[
+self: Buffer - Buffer object whose newlines are counted. If no buffer ID is set on this object, uses the active buffer instead.
+]],
+        [[
+start_byte: non-negative integer - Byte index the range starts at.
+]],
+        [[
+end_byte: non-negative integer - Byte index the range ends before.
+]]
+    )
+
     P.content = red.doc.build_fn(
         function(self)
             return coroutine.yield(red.call.buffer_content(self:id()))
@@ -491,9 +718,34 @@ line_index: non-negative integer - The line index of the line whose content shou
 ]]
     )
 
+    P.line_range = red.doc.build_fn(
+        function(self, start_line, end_line)
+            return coroutine.yield(red.call.buffer_line_range(self:id(), start_line, end_line))
+        end,
+        "line_range",
+        [[
+Get a list of Lua String copies of a range of lines in this buffer.
+]],
+        [[
+Copies only the lines requested, so is much cheaper than `content` for reading part of a large buffer, such as the lines visible in a pane. Lines past the end of the buffer are left out of the list.
+]],
+        [[
+table - List of the content of each line in the range, in order. Each line keeps its newline, as with `line_content`.
+]],
+        [[
+self: Buffer - Buffer object whose lines should be copied. If no buffer ID is set on this object, copies lines from the active buffer.
+]],
+        [[
+start_line: non-negative integer - First line index to copy.
+]],
+        [[
+end_line: non-negative integer - Last line index to copy, inclusive.
+]]
+    )
+
     P.cursor_line_content = red.doc.build_fn(
         function(self)
-            return self:line_content(self:cursor_line())
+            return coroutine.yield(red.call.buffer_cursor_line_content(self:id()))
         end,
         "cursor_line_content",
         [[
@@ -501,13 +753,64 @@ Get a Lua String copy of the line the cursor is currently on.
 ]],
         nil,
         [[
-string - A Lua String copy of the content of the cursor's current line.
+string? - A Lua String copy of the content of the cursor's current line, or nil if the cursor's line does not exist.
 ]],
         [[
 self: Buffer - Buffer object whose cursor line content should be copied and returned. If no buffer ID is set on this object, returns a copy of the line content from the active buffer at its current cursor line.
 ]]
     )
 
+    P.cursor_visual_column = red.doc.build_fn(
+        function(self, tab_width)
+            return coroutine.yield(red.call.buffer_cursor_visual_column(self:id(), tab_width))
+        end,
+        "cursor_visual_column",
+        [[
+Get the on-screen column of the cursor within its line.
+]],
+        [[
+Differs from the cursor's character column when the line contains tabs or wide characters before the cursor. Tabs are expanded up to the next tab stop.
+]],
+        [[
+non-negative integer - The column of the cursor, counted from 0 at the start of its line.
+]],
+        [[
+self: Buffer - Buffer object whose cursor column should be returned. If no buffer ID is set on this object, uses the active buffer instead.
+]],
+        [[
+tab_width: non-negative integer? - Width of a tab stop. Defaults to this buffer's tab width.
+]]
+    )
+
+    P.move_cursor_line_visual = red.doc.build_fn(
+        function(self, line_delta, tab_width)
+            return coroutine.yield(red.call.buffer_move_cursor_line_visual(self:id(), line_delta, tab_width))
+        end,
+        "move_cursor_line_visual",
+        [[
+Moves the cursor up or down a number of lines, keeping the cursor's on-screen column.
+]],
+        [[
+Unlike `cursor_up` and `cursor_down`, which keep the cursor's character column, lines indented with different tabs do not shift the cursor sideways. Lands on the character covering the column, or at the end of lines which are too short. The column is remembered over repeated moves, so moving through a short line returns to the same column on the next long line.
+
+Ex: `
+    buffer:move_cursor_line_visual(-1)
+`
+]],
+        [[
+non-negative integer - The byte index of the moved cursor.
+]],
+        [[
+self: Buffer - Buffer object whose cursor is moved. If no buffer ID is set on this object, uses the active buffer instead.
+]],
+        [[
+line_delta: integer - Number of lines to move down, or up if negative. Stops at the first or last line of the buffer.
+]],
+        [[
+tab_width: non-negative integer? - Width of a tab stop. Defaults to this buffer's tab width.
+]]
+    )
+
     P.cursor_content = red.doc.build_fn(
         function(self)
             return self:content_at(self:cursor(), 1)
@@ -630,23 +933,42 @@ self: Buffer - Buffer object to be unlinked from its current file. If no buffer
 
     P.write = red.doc.build_fn(
         function(self)
-            coroutine.yield(red.call.buffer_write_to_file(self:id()))
+            return coroutine.yield(red.call.buffer_write_to_file(self:id()))
         end,
         "write",
         [[
 Overwrites the on-disk content of this Buffer's linked file with the current content of this Buffer.
 ]],
         [[
-Fails if this buffer is not linked with a file already.
+Fails if this buffer is not linked with a file already. If a formatter is set for the linked file's extension, the Buffer's content is replaced with the formatter's output before writing. The formatter runs in the background, so the calling script waits for it while the editor keeps running. If the formatter fails, or the Buffer is edited while it runs, the Buffer is written unformatted.
 ]],
         [[
-nil
+string or nil - Description of the formatter's failure, or nil if no formatter failed.
 ]],
         [[
 self: Buffer - Buffer object whose content is to be written out to its linked file. If no buffer ID is set on this object, attempts to write the active buffer's content to its linked file instead.
 ]]
     )
 
+    P.revert = red.doc.build_fn(
+        function(self)
+            coroutine.yield(red.call.buffer_revert(self:id()))
+        end,
+        "revert",
+        [[
+Discards all changes to this Buffer by re-reading the content of its linked file from disk.
+]],
+        [[
+Fails if this buffer is not linked with a file already. Keeps the cursor at the same byte index where possible, clamped to the length of the reverted content. Marks the buffer as no longer dirty.
+]],
+        [[
+nil
+]],
+        [[
+self: Buffer - Buffer object whose content is to be reverted to its linked file's content. If no buffer ID is set on this object, attempts to revert the active buffer instead.
+]]
+    )
+
     P.type = red.doc.build_fn(
         function(self)
             return coroutine.yield(red.call.buffer_type(self:id()))
@@ -660,87 +982,1291 @@ Gets the buffer type of this Buffer object.
 table - buffer_type RedEnum:
     - `buffer.naive`
     - `buffer.gap`
+    - `buffer.paged`, for buffers opened with `open_paged`
 ]],
         [[
 self: Buffer - Buffer object whose type is returned. If no buffer ID is set on this object, returns the type of the active buffer instead.
 ]]
     )
 
-    P.set_type = red.doc.build_fn(
-        function(self, type)
-            return coroutine.yield(red.call.buffer_set_type(self:id(), type))
+    P.on_modified = red.doc.build_fn(
+        function(self, run)
+            coroutine.yield(red.call.set_hook("buffer_modified", run, self:id()))
         end,
-        "set_type:",
+        "on_modified",
         [[
-Sets the buffer type of this Buffer object.
+Sets a function to be called as a new script once edits to this buffer's content settle.
 ]],
         [[
-If type provided is different than the current buffer type, current buffer content is copied into the data structure for the new buffer type. All other buffer information such as ID is preserved. Copying may take O(n) or worse.
+Rather than running on every edit, the function runs once the buffer's content has gone unchanged for the `modified_delay_ms` option, so a burst of typing runs it once. This makes it suitable for expensive work such as linting.
 ]],
         [[
 nil
 ]],
         [[
-self: Buffer - Buffer object whose type is set. If no buffer ID is set on this object, sets the type of the active buffer instead.
+self: Buffer - The buffer whose edits should trigger the function.
 ]],
         [[
-type: EditorBufferType table (RedEnum) - Type of buffer to change this buffer to.
-    See:
-        - `buffer.naive`
-        - `buffer.gap`
+run: Function - The function called after this buffer is modified. Is called with the id of the modified buffer.
 ]]
     )
 
-    P.clear_styles = red.doc.build_fn(
-        function(self)
-            coroutine.yield(red.call.buffer_clear_style(self:id()))
+    P.on_reloaded = red.doc.build_fn(
+        function(self, run)
+            coroutine.yield(red.call.set_hook("buffer_reloaded", run, self:id()))
         end,
-        "clear_styles",
+        "on_reloaded",
         [[
-Removes all styles attached to this buffer.
+Sets a function to be called as a new script when this buffer is reverted because its linked file changed on disk.
 ]],
-        nil,
-        nil,
         [[
-self: Buffer Table - Lua table representing the editor buffer whose styles should be cleared.
+Only runs while the `auto_reload` option is set. Changes to the file are checked for every half second.
+]],
+        [[
+nil
+]],
+        [[
+self: Buffer - The buffer whose reloading should trigger the function.
+]],
+        [[
+run: Function - The function called after this buffer is reloaded. Is called with the id of the reloaded buffer.
 ]]
     )
 
-    P.push_style = red.doc.build_fn(
-        function(self, name, regex)
-            coroutine.yield(red.call.buffer_push_style(self:id(), name, regex))
+    P.on_file_conflict = red.doc.build_fn(
+        function(self, run)
+            coroutine.yield(red.call.set_hook("file_conflict", run, self:id()))
         end,
-        "push_style",
+        "on_file_conflict",
         [[
-Adds a new style to the front of the stack of styles for this buffer.
+Sets a function to be called as a new script when this buffer's linked file changes on disk while the buffer has changes of its own.
 ]],
         [[
-Styles nearer the top of the stack will be applied first. The name set should match a TextStyle name available in the editor's TextStyleMap. See: "red.set_text_style"
+Only runs while the `auto_reload` option is set. The buffer is left as is, so the function may choose to `revert` it or to keep its changes.
+
+Ex: `
+    buffer:on_file_conflict(function(buffer_id)
+        red.buffer:new(buffer_id):revert()
+    end)
+`
 ]],
         [[
 nil
 ]],
         [[
-self: Buffer Table - Object representing the buffer where the given style should be pushed.
+self: Buffer - The buffer whose conflicts should trigger the function.
 ]],
         [[
-name: String - The TextStyle name that should be used on any text that matches this style.
+run: Function - The function called when the conflict is found. Is called with the id of the conflicting buffer.
+]]
+    )
+
+    P.revision = red.doc.build_fn(
+        function(self)
+            return coroutine.yield(red.call.buffer_revision(self:id()))
+        end,
+        "revision",
+        [[
+Returns the number of changes made to this buffer's content since it was opened.
 ]],
         [[
-regex: String - The regex string that matches any text that should be styled with the given style name.
+The revision increases with every insertion, deletion, or replacement of the buffer's content and is never decreased, so comparing a saved revision against the current one shows whether the content has changed since it was saved. Reading the buffer does not change its revision.
+]],
+        [[
+non-negative integer - Revision of the buffer's content.
+]],
+        [[
+self: Buffer - Buffer whose revision is returned.
 ]]
     )
 
-    P.naive = {
-        type = "EditorBufferType",
-        variant = "naive"
-    }
-
+    P.hash = red.doc.build_fn(
+        function(self)
+            return coroutine.yield(red.call.buffer_hash(self:id()))
+        end,
+        "hash",
+        [[
+Returns a hash of this buffer's content.
+]],
+        [[
+Buffers with the same content always have the same hash, so comparing a stored hash against the current one shows whether the content has returned to a previous version, even after edits. The hash is computed without copying the content.
+]],
+        [[
+integer - Hash of the buffer's content.
+]],
+        [[
+self: Buffer - Buffer whose content is hashed.
+]]
+    )
+
+    P.is_modified_from_disk = red.doc.build_fn(
+        function(self)
+            return coroutine.yield(red.call.buffer_is_modified_from_disk(self:id()))
+        end,
+        "is_modified_from_disk",
+        [[
+Returns whether this buffer's content differs from its file.
+]],
+        [[
+Compares the hash of the content against its hash when last read from, written to, or reverted to its file. Unlike the dirty state checked when closing or unlinking, editing and then undoing back to the file's content is not a modification. Buffers which have never been read from or written to a file are always modified.
+]],
+        [[
+bool - Whether the content differs from its file.
+]],
+        [[
+self: Buffer - Buffer whose content is compared.
+]]
+    )
+
+    P.set_type = red.doc.build_fn(
+        function(self, type)
+            return coroutine.yield(red.call.buffer_set_type(self:id(), type))
+        end,
+        "set_type:",
+        [[
+Sets the buffer type of this Buffer object.
+]],
+        [[
+If type provided is different than the current buffer type, current buffer content is copied into the data structure for the new buffer type. All other buffer information such as ID is preserved. Copying may take O(n) or worse.
+
+Fails when changing to or from `buffer.paged`, as paged buffers hold files too large to copy into memory.
+]],
+        [[
+nil
+]],
+        [[
+self: Buffer - Buffer object whose type is set. If no buffer ID is set on this object, sets the type of the active buffer instead.
+]],
+        [[
+type: EditorBufferType table (RedEnum) - Type of buffer to change this buffer to.
+    See:
+        - `buffer.naive`
+        - `buffer.gap`
+]]
+    )
+
+    P.clear_styles = red.doc.build_fn(
+        function(self)
+            coroutine.yield(red.call.buffer_clear_styles(self:id()))
+        end,
+        "clear_styles",
+        [[
+Removes all styles attached to this buffer.
+]],
+        nil,
+        nil,
+        [[
+self: Buffer Table - Lua table representing the editor buffer whose styles should be cleared.
+]]
+    )
+
+    P.styles = red.doc.build_fn(
+        function(self)
+            local styles = coroutine.yield(red.call.buffer_styles(self:id()))
+            for i, style in ipairs(styles) do
+                styles[i] = style.values
+            end
+            return styles
+        end,
+        "styles",
+        [[
+Returns the stack of styles attached to this buffer.
+]],
+        [[
+Styles are listed in the order they were pushed, so the last style is at the top of the stack.
+]],
+        [[
+List of tables - Each holds the style's 'name' and the 'pattern' regex string it was pushed with.
+]],
+        [[
+self: Buffer Table - Object representing the buffer whose styles are returned.
+]]
+    )
+
+    P.push_style = red.doc.build_fn(
+        function(self, name, regex)
+            coroutine.yield(red.call.buffer_push_style(self:id(), name, regex))
+        end,
+        "push_style",
+        [[
+Adds a new style to the front of the stack of styles for this buffer.
+]],
+        [[
+Styles nearer the top of the stack will be applied first. The name set should match a TextStyle name available in the editor's TextStyleMap. See: "red.set_text_style"
+]],
+        [[
+nil
+]],
+        [[
+self: Buffer Table - Object representing the buffer where the given style should be pushed.
+]],
+        [[
+name: String - The TextStyle name that should be used on any text that matches this style.
+]],
+        [[
+regex: String - The regex string that matches any text that should be styled with the given style name.
+]]
+    )
+
+    P.diff = red.doc.build_fn(
+        function(self, other_text)
+            return coroutine.yield(red.call.buffer_diff(self:id(), other_text))
+        end,
+        "diff",
+        [[
+Computes a line-level diff between this buffer's content and the provided text.
+]],
+        [[
+The diff describes how to turn `other_text` into this buffer's content. Useful for showing changes against a file's content on disk. Each operation is a DiffOperation RedEnum with one of the variants:
+    - `unchanged`: `values = { buffer_line, other_line, count }`
+    - `added`: `values = { buffer_line, count }` - Lines only present in this buffer.
+    - `removed`: `values = { other_line, count }` - Lines only present in `other_text`.
+
+Line indices are zero-based and lines are split on newline characters the same way as `lines()`.
+Note: Makes a copy of this buffer's content and is O(n * m) in the number of differing lines.
+]],
+        [[
+table - Array of DiffOperation RedEnum tables in order of the lines they cover.
+]],
+        [[
+self: Buffer - Buffer object whose content is diffed. If no buffer ID is set on this object, diffs the active buffer.
+]],
+        [[
+other_text: string - The text to diff this buffer's content against.
+]]
+    )
+
+    P.set_mark = red.doc.build_fn(
+        function(self, name, byte_index)
+            coroutine.yield(red.call.buffer_set_named_mark(self:id(), name, byte_index or self:cursor()))
+        end,
+        "set_mark",
+        [[
+Sets a named mark in this buffer at the given byte index.
+]],
+        [[
+Marks follow the text they were set on: text inserted before a mark shifts the mark forward, and deleting the text under a mark moves it to the start of the deletion. Setting an existing mark name replaces it.
+]],
+        [[
+nil
+]],
+        [[
+self: Buffer - Buffer object to set the mark in. If no buffer ID is set on this object, sets the mark in the active buffer instead.
+]],
+        [[
+name: string - Single character naming the mark.
+]],
+        [[
+byte_index: non-negative integer = cursor - Byte index for the mark. Defaults to this buffer's cursor.
+]]
+    )
+
+    P.goto_mark = red.doc.build_fn(
+        function(self, name)
+            return coroutine.yield(red.call.buffer_goto_named_mark(self:id(), name))
+        end,
+        "goto_mark",
+        [[
+Moves the cursor of this buffer to a named mark.
+]],
+        [[
+Leaves the cursor unchanged if no mark with `name` has been set.
+]],
+        [[
+non-negative integer or nil - Byte index the cursor was moved to, or nil if the mark is not set.
+]],
+        [[
+self: Buffer - Buffer object whose cursor is moved. If no buffer ID is set on this object, moves the cursor of the active buffer instead.
+]],
+        [[
+name: string - Single character naming the mark.
+]]
+    )
+
+    P.add_phantom = red.doc.build_fn(
+        function(self, text, style, byte_index)
+            coroutine.yield(red.call.buffer_add_phantom(self:id(), byte_index or self:cursor(), text, style))
+        end,
+        "add_phantom",
+        [[
+Displays phantom text in this buffer before the given byte index.
+]],
+        [[
+Phantom text is only drawn by the editor. It is not part of the buffer's content, so it is not saved and does not affect cursor or byte indices. Like marks, phantom text follows the content it was placed in front of as the buffer is edited.
+]],
+        [[
+nil
+]],
+        [[
+self: Buffer - Buffer object to display the phantom text in. If no buffer ID is set on this object, uses the active buffer instead.
+]],
+        [[
+text: string - Text to display.
+]],
+        [[
+style: string - Name of the text style used to draw the phantom text.
+]],
+        [[
+byte_index: non-negative integer = cursor - Byte index the text is displayed before. Defaults to this buffer's cursor.
+]]
+    )
+
+    P.clear_phantoms = red.doc.build_fn(
+        function(self)
+            coroutine.yield(red.call.buffer_clear_phantoms(self:id()))
+        end,
+        "clear_phantoms",
+        [[
+Removes all phantom text from this buffer.
+]],
+        nil,
+        [[
+nil
+]],
+        [[
+self: Buffer - Buffer object whose phantom text is removed. If no buffer ID is set on this object, uses the active buffer instead.
+]]
+    )
+
+    P.set_sign = red.doc.build_fn(
+        function(self, line, text, style)
+            coroutine.yield(red.call.buffer_set_sign(self:id(), line, text, style))
+        end,
+        "set_sign",
+        [[
+Displays a sign beside a line of this buffer, replacing any sign already on the line.
+]],
+        [[
+Signs are drawn in a sign column to the left of the buffer's text, such as for version control changes or diagnostics. The sign column is shown in panes displaying the buffer while it has any signs, and is as wide as its widest sign up to two columns, reducing the width left for text. Signs stay on their line index as the buffer is edited.
+]],
+        [[
+nil
+]],
+        [[
+self: Buffer - Buffer object to display the sign in. If no buffer ID is set on this object, uses the active buffer instead.
+]],
+        [[
+line: non-negative integer - Index of the line the sign is displayed beside.
+]],
+        [[
+text: string - Glyph of the sign. Only its first two columns are displayed.
+]],
+        [[
+style: string - Name of the text style used to draw the sign.
+]]
+    )
+
+    P.clear_signs = red.doc.build_fn(
+        function(self)
+            coroutine.yield(red.call.buffer_clear_signs(self:id()))
+        end,
+        "clear_signs",
+        [[
+Removes all signs from this buffer.
+]],
+        nil,
+        [[
+nil
+]],
+        [[
+self: Buffer - Buffer object whose signs are removed. If no buffer ID is set on this object, uses the active buffer instead.
+]]
+    )
+
+    P.add_line_highlight = red.doc.build_fn(
+        function(self, line, style)
+            coroutine.yield(red.call.buffer_add_line_highlight(self:id(), line, style))
+        end,
+        "add_line_highlight",
+        [[
+Highlights the background of a whole line of this buffer, replacing any highlight already on the line.
+]],
+        [[
+Only the background of the style is used. It is drawn across the line's text and the rest of the pane's width, taking precedence over the backgrounds of the text's own styles, while the text keeps its own foreground. A cursor drawn in the line is still shown over the highlight. Line highlights are separate from syntax and pushed styles, and are intended for transient uses such as diff views or search results. They stay on their line index as the buffer is edited.
+]],
+        [[
+nil
+]],
+        [[
+self: Buffer - Buffer object to highlight the line of. If no buffer ID is set on this object, uses the active buffer instead.
+]],
+        [[
+line: non-negative integer - Index of the line to highlight.
+]],
+        [[
+style: string - Name of the text style whose background is used for the line.
+]]
+    )
+
+    P.clear_line_highlights = red.doc.build_fn(
+        function(self)
+            coroutine.yield(red.call.buffer_clear_line_highlights(self:id()))
+        end,
+        "clear_line_highlights",
+        [[
+Removes all line highlights from this buffer.
+]],
+        nil,
+        [[
+nil
+]],
+        [[
+self: Buffer - Buffer object whose line highlights are removed. If no buffer ID is set on this object, uses the active buffer instead.
+]]
+    )
+
+    P.add_cursor = red.doc.build_fn(
+        function(self, byte_index)
+            return coroutine.yield(red.call.buffer_add_cursor(self:id(), byte_index))
+        end,
+        "add_cursor",
+        [[
+Adds a secondary cursor to this buffer at the given byte index.
+]],
+        [[
+Text inserted with `insert` and deleted with `delete` is also inserted or deleted at each secondary cursor, with the cursors following the edits as the primary cursor does. Secondary cursors are drawn as blocks in every pane showing the buffer. Moving the primary cursor does not move the secondary cursors. Cursors which edits leave at the same byte index are merged.
+
+Ex: `
+    red.buffer:add_cursor(red.buffer:line_start_index(red.buffer:cursor_line() + 1))
+    red.buffer:insert("-- ")
+`
+]],
+        [[
+bool - True if the cursor was added. False if a cursor is already at the byte index.
+]],
+        [[
+self: Buffer - Buffer object to add the cursor to. If no buffer ID is set on this object, uses the active buffer instead.
+]],
+        [[
+byte_index: non-negative integer - Byte index of the new cursor, clamped to the buffer's length. Must be on a character boundary.
+]]
+    )
+
+    P.clear_secondary_cursors = red.doc.build_fn(
+        function(self)
+            coroutine.yield(red.call.buffer_clear_secondary_cursors(self:id()))
+        end,
+        "clear_secondary_cursors",
+        [[
+Removes every secondary cursor from this buffer, leaving only the primary cursor.
+]],
+        nil,
+        [[
+nil
+]],
+        [[
+self: Buffer - Buffer object whose secondary cursors are removed. If no buffer ID is set on this object, uses the active buffer instead.
+]]
+    )
+
+    P.secondary_cursors = red.doc.build_fn(
+        function(self)
+            return coroutine.yield(red.call.buffer_secondary_cursors(self:id()))
+        end,
+        "secondary_cursors",
+        [[
+Returns the byte indices of this buffer's secondary cursors.
+]],
+        nil,
+        [[
+List of non-negative integers - Byte indices of the secondary cursors, sorted from the start of the buffer.
+]],
+        [[
+self: Buffer - Buffer object whose secondary cursors are returned. If no buffer ID is set on this object, uses the active buffer instead.
+]]
+    )
+
+    P.set_search = red.doc.build_fn(
+        function(self, pattern)
+            return coroutine.yield(red.call.buffer_set_search(self:id(), pattern))
+        end,
+        "set_search",
+        [[
+Highlights every match of a regex in this buffer, replacing any search already highlighted.
+]],
+        [[
+Matches are drawn with the background of the "search" text style, except the match containing a pane's cursor, which uses the "search_current" style. Search backgrounds take precedence over line highlights and the backgrounds of syntax and pushed styles, while the text keeps its own foreground. A cursor drawn in a match is still shown over it. Matches never span lines, and are found again as the buffer is edited.
+
+Ex: `
+    red.set_text_style("search", red.rgb(80, 80, 0), red.rgb(255, 255, 255))
+    red.buffer:set_search("TODO")
+`
+]],
+        [[
+non-negative integer or nil - Count of matches found. Nil if the search was cleared.
+]],
+        [[
+self: Buffer - Buffer object to search. If no buffer ID is set on this object, uses the active buffer instead.
+]],
+        [[
+pattern: string or nil - Regex to highlight the matches of. Clears the search if nil.
+]]
+    )
+
+    P.set_language = red.doc.build_fn(
+        function(self, language)
+            coroutine.yield(red.call.buffer_set_language(self:id(), language))
+        end,
+        "set_language",
+        [[
+Sets the language used to highlight this buffer's syntax.
+]],
+        [[
+While a language is set, the buffer is styled by its syntax highlights, with each highlight's capture name used as the name of its style. Styles pushed with `push_style` are only used while no language is set. Errs if the language is not known. Supported languages: "lua".
+]],
+        [[
+nil
+]],
+        [[
+self: Buffer - Buffer object whose language is set. If no buffer ID is set on this object, sets the language of the active buffer instead.
+]],
+        [[
+language: string or nil - Name of the language, or nil to remove the buffer's language.
+]]
+    )
+
+    P.highlights = red.doc.build_fn(
+        function(self, start_line, end_line)
+            local highlights = coroutine.yield(red.call.buffer_highlights(self:id(), start_line, end_line or start_line))
+            if highlights == nil then
+                return nil
+            end
+
+            for i, highlight in ipairs(highlights) do
+                highlights[i] = highlight.values
+            end
+            return highlights
+        end,
+        "highlights",
+        [[
+Returns the syntax highlights found on a range of this buffer's lines.
+]],
+        [[
+Capture names follow tree-sitter highlight names, such as "keyword", "string", "comment", "number", "boolean", "constant.builtin", and "function.call". Editors built with the tree-sitter feature take the names from the bundled grammar's highlights query, which adds finer names such as "keyword.function" and "punctuation.bracket".
+]],
+        [[
+List of Highlight tables or nil - Each holds the 'start_byte' and exclusive 'end_byte' of the highlighted text and its 'capture' name, sorted by 'start_byte'. Nil if this buffer has no language set.
+]],
+        [[
+self: Buffer - Buffer object whose highlights are returned. If no buffer ID is set on this object, uses the active buffer instead.
+]],
+        [[
+start_line: non-negative integer - First line index to return highlights for.
+]],
+        [[
+end_line: non-negative integer = start_line - Last line index to return highlights for, inclusive.
+]]
+    )
+
+    P.toggle_comment = red.doc.build_fn(
+        function(self, start_line, end_line, comment_prefix)
+            coroutine.yield(red.call.buffer_toggle_comment(self:id(), start_line, end_line, comment_prefix))
+        end,
+        "toggle_comment",
+        [[
+Comments out a range of lines, or uncomments them if they are all already commented.
+]],
+        [[
+The prefix is placed after each line's indentation. Blank lines are left unchanged and are ignored when checking whether the range is already commented.
+]],
+        [[
+nil
+]],
+        [[
+self: Buffer - Buffer object whose lines are toggled. If no buffer ID is set on this object, uses the active buffer instead.
+]],
+        [[
+start_line: non-negative integer - First line index to toggle.
+]],
+        [[
+end_line: non-negative integer - Last line index to toggle, inclusive.
+]],
+        [[
+comment_prefix: string - Text marking a line as a comment, such as "-- ".
+]]
+    )
+
+    P.insert_pair = red.doc.build_fn(
+        function(self, open, close)
+            coroutine.yield(red.call.buffer_insert_pair(self:id(), open, close))
+        end,
+        "insert_pair",
+        [[
+Inserts an opening and closing pair at the cursor, leaving the cursor between them.
+]],
+        [[
+If `open` and `close` are the same, such as for quotes, and the cursor is already just before `close`, the cursor steps over it instead of inserting a new pair.
+]],
+        [[
+nil
+]],
+        [[
+self: Buffer - Buffer object into which the pair is inserted. If no buffer ID is set on this object, uses the active buffer instead.
+]],
+        [[
+open: string - Text inserted before the cursor.
+]],
+        [[
+close: string - Text inserted after the cursor.
+]]
+    )
+
+    P.insert_close = red.doc.build_fn(
+        function(self, close)
+            return coroutine.yield(red.call.buffer_insert_close(self:id(), close))
+        end,
+        "insert_close",
+        [[
+Steps the cursor over the closing text of a pair, or inserts it if it does not follow the cursor.
+]],
+        [[
+Meant to be bound to closing characters such as ')' so that typing over a pair inserted by `insert_pair` does not duplicate the close.
+]],
+        [[
+bool - Whether the cursor stepped over an existing `close`.
+]],
+        [[
+self: Buffer - Buffer object into which the close is inserted. If no buffer ID is set on this object, uses the active buffer instead.
+]],
+        [[
+close: string - Closing text to step over or insert.
+]]
+    )
+
+    P.expand_snippet = red.doc.build_fn(
+        function(self, trigger, body)
+            return coroutine.yield(red.call.expand_snippet(self:id(), trigger, body))
+        end,
+        "expand_snippet",
+        [[
+Replaces the trigger text just before the cursor with a snippet body.
+]],
+        [[
+Does nothing if the text just before the cursor is not `trigger`. Tab stops are written in the body as `$1` through `$9`, with `$0` as the final stop. The cursor is placed at the first stop and the remaining stops are visited in order by `next_snippet_stop`. Write `$$` for a literal `$`.
+]],
+        [[
+bool - Whether the snippet was expanded.
+]],
+        [[
+self: Buffer - Buffer object in which the snippet is expanded. If no buffer ID is set on this object, uses the active buffer instead.
+]],
+        [[
+trigger: string - Text expected just before the cursor, which is replaced by the snippet.
+]],
+        [[
+body: string - Snippet text to insert, including any tab stops.
+]]
+    )
+
+    P.next_snippet_stop = red.doc.build_fn(
+        function(self)
+            return coroutine.yield(red.call.snippet_next_stop(self:id()))
+        end,
+        "next_snippet_stop",
+        [[
+Moves the cursor to the next tab stop of the last snippet expanded in this buffer.
+]],
+        [[
+Tab stops follow edits made to the buffer after the snippet was expanded.
+]],
+        [[
+non-negative integer or nil - Byte index of the stop moved to, or nil if no stops remain.
+]],
+        [[
+self: Buffer - Buffer object whose cursor is moved. If no buffer ID is set on this object, uses the active buffer instead.
+]]
+    )
+
+    P.sort_lines = red.doc.build_fn(
+        function(self, start_line, end_line, reverse, numeric)
+            coroutine.yield(red.call.buffer_sort_lines(self:id(), start_line, end_line, reverse or false, numeric or false))
+        end,
+        "sort_lines",
+        [[
+Sorts a range of lines in this buffer.
+]],
+        [[
+Lines are sorted lexically unless `numeric` is set, in which case they are ordered by the number at the start of each line, with lines not starting with a number placed first.
+]],
+        [[
+nil
+]],
+        [[
+self: Buffer - Buffer object whose lines are sorted. If no buffer ID is set on this object, uses the active buffer instead.
+]],
+        [[
+start_line: non-negative integer - First line index to sort.
+]],
+        [[
+end_line: non-negative integer - Last line index to sort, inclusive.
+]],
+        [[
+reverse: bool = false - Whether to sort in descending order.
+]],
+        [[
+numeric: bool = false - Whether to sort by each line's leading number.
+]]
+    )
+
+    P.unique_lines = red.doc.build_fn(
+        function(self, start_line, end_line)
+            return coroutine.yield(red.call.buffer_unique_lines(self:id(), start_line, end_line))
+        end,
+        "unique_lines",
+        [[
+Removes consecutive duplicate lines from a range of lines in this buffer.
+]],
+        [[
+Only duplicates directly following one another are removed, so sort the range first to remove all duplicates.
+]],
+        [[
+non-negative integer - Number of lines removed.
+]],
+        [[
+self: Buffer - Buffer object whose duplicate lines are removed. If no buffer ID is set on this object, uses the active buffer instead.
+]],
+        [[
+start_line: non-negative integer - First line index to deduplicate.
+]],
+        [[
+end_line: non-negative integer - Last line index to deduplicate, inclusive.
+]]
+    )
+
+    P.reverse_lines = red.doc.build_fn(
+        function(self, start_line, end_line)
+            coroutine.yield(red.call.buffer_reverse_lines(self:id(), start_line, end_line))
+        end,
+        "reverse_lines",
+        [[
+Reverses the order of a range of lines in this buffer.
+]],
+        nil,
+        [[
+nil
+]],
+        [[
+self: Buffer - Buffer object whose lines are reversed. If no buffer ID is set on this object, uses the active buffer instead.
+]],
+        [[
+start_line: non-negative integer - First line index to reverse.
+]],
+        [[
+end_line: non-negative integer - Last line index to reverse, inclusive.
+]]
+    )
+
+    P.normalize_newlines = red.doc.build_fn(
+        function(self, to)
+            return coroutine.yield(red.call.buffer_normalize_newlines(self:id(), to))
+        end,
+        "normalize_newlines",
+        [[
+Converts every line ending in this buffer to the given convention.
+]],
+        [[
+Useful for fixing files with a mix of `\n` and `\r\n` line endings. Carriage returns which do not end a line are left as is. The cursor stays at the same column of its line.
+]],
+        [[
+non-negative integer - Number of line endings which were converted.
+]],
+        [[
+self: Buffer - Buffer object whose line endings are converted. If no buffer ID is set on this object, uses the active buffer instead.
+]],
+        [[
+to: LineEnding table (RedEnum) - The line ending to convert to.
+    See:
+        - `buffer.line_ending_lf`
+        - `buffer.line_ending_crlf`
+]]
+    )
+
+    P.reflow = red.doc.build_fn(
+        function(self, line_index, width)
+            return coroutine.yield(red.call.buffer_reflow(self:id(), line_index, width))
+        end,
+        "reflow",
+        [[
+Re-wraps the paragraph containing a line so that no line is wider than the given width.
+]],
+        [[
+The paragraph is the run of non-blank lines around `line_index`. Its words are refilled into as few lines as fit within `width` columns, joined by single spaces. Every line is given the indentation of the paragraph's first line, and words too wide to fit are placed alone on their line.
+
+Ex: `
+    buffer:reflow(buffer:cursor_line(), 80)
+`
+]],
+        [[
+non-negative integer or nil - Number of lines in the reflowed paragraph. Nil if the line is blank or past the end of the buffer.
+]],
+        [[
+self: Buffer - Buffer object whose paragraph is reflowed. If no buffer ID is set on this object, uses the active buffer instead.
+]],
+        [[
+line_index: non-negative integer - Index of any line within the paragraph to reflow.
+]],
+        [[
+width: non-negative integer - Maximum width in columns of the reflowed lines.
+]]
+    )
+
+    P.paste_lines = red.doc.build_fn(
+        function(self, text, below)
+            coroutine.yield(red.call.buffer_paste_lines(self:id(), text, below))
+        end,
+        "paste_lines",
+        [[
+Inserts text as whole lines above or below the cursor's line, leaving the cursor at the start of the first pasted line.
+]],
+        [[
+A newline is added to the end of `text` if it does not already end with one. Used to paste linewise registers.
+]],
+        [[
+nil
+]],
+        [[
+self: Buffer - Buffer object pasted into. If no buffer ID is set on this object, uses the active buffer instead.
+]],
+        [[
+text: string - Lines to paste.
+]],
+        [[
+below: bool - If true, pastes below the cursor's line. Otherwise pastes above it.
+]]
+    )
+
+    P.delete_to_line_end = red.doc.build_fn(
+        function(self)
+            return coroutine.yield(red.call.buffer_delete_to_line_end(self:id()))
+        end,
+        "delete_to_line_end",
+        [[
+Deletes from the cursor to the end of the cursor's line.
+]],
+        [[
+The line's newline is not deleted unless the cursor is already at the end of the line, in which case only the newline is deleted, joining the next line onto this one.
+]],
+        [[
+string - The deleted text.
+]],
+        [[
+self: Buffer - Buffer object to delete from. If no buffer ID is set on this object, uses the active buffer instead.
+]]
+    )
+
+    P.delete_word = red.doc.build_fn(
+        function(self, forward)
+            return coroutine.yield(red.call.buffer_delete_word(self:id(), forward))
+        end,
+        "delete_word",
+        [[
+Deletes from the cursor to the end of the next word or to the start of the previous word.
+]],
+        [[
+Words are runs of alphanumeric characters and underscores. Any whitespace, punctuation, or newlines between the cursor and the word are deleted along with it.
+]],
+        [[
+string - The deleted text.
+]],
+        [[
+self: Buffer - Buffer object to delete from. If no buffer ID is set on this object, uses the active buffer instead.
+]],
+        [[
+forward: bool - If true, deletes forward to the end of the next word. Otherwise deletes backward to the start of the previous word.
+]]
+    )
+
+    P.motion_range = red.doc.build_fn(
+        function(self, motion, count)
+            return coroutine.yield(red.call.buffer_apply_motion(self:id(), motion, count)).values
+        end,
+        "motion_range",
+        [[
+Returns the range of content between the cursor and where a motion would move it, without moving the cursor.
+]],
+        [[
+Operators such as delete, yank, or change can be applied over the returned range so that each operator works with every motion. Word motions follow the same word boundaries as `delete_word`. The `line` motion covers whole lines from the start of the cursor's line, including their newlines.
+]],
+        [[
+table - Holds the `start_byte` of the range and its `end_byte`, which is not included in the range.
+]],
+        [[
+self: Buffer - Buffer object whose cursor the motion starts from. If no buffer ID is set on this object, uses the active buffer instead.
+]],
+        [[
+motion: Motion table (RedEnum) - The motion to apply.
+    See:
+        - `buffer.motion_char`
+        - `buffer.motion_char_back`
+        - `buffer.motion_word`
+        - `buffer.motion_word_back`
+        - `buffer.motion_line_start`
+        - `buffer.motion_line_end`
+        - `buffer.motion_line`
+]],
+        [[
+count: non-negative integer or nil - Number of times the motion is repeated. Defaults to 1.
+]]
+    )
+
+    P.text_object = red.doc.build_fn(
+        function(self, object, inner)
+            local range = coroutine.yield(red.call.buffer_text_object(self:id(), object, inner or false))
+            if range == nil then
+                return nil
+            end
+
+            return range.values
+        end,
+        "text_object",
+        [[
+Returns the range of a text object around the cursor, such as the word or parentheses the cursor is within.
+]],
+        [[
+Inner objects exclude the object's delimiters or surrounding whitespace, while the rest include them. Around a word or paragraph includes the whitespace or blank lines following it, or those before it if nothing follows. Quotes are paired in order along the cursor's line, while parentheses, brackets, and braces may span lines and skip over nested pairs.
+
+Ex: `
+    local range = buffer:text_object(buffer.object_paren, true)
+    if range then
+        buffer:set_cursor(range.start_byte)
+    end
+`
+]],
+        [[
+table or nil - Holds the `start_byte` of the range and its `end_byte`, which is not included in the range. Nil if the cursor is not within the text object.
+]],
+        [[
+self: Buffer - Buffer object whose cursor the text object is found around. If no buffer ID is set on this object, uses the active buffer instead.
+]],
+        [[
+object: TextObject table (RedEnum) - The kind of text object to find.
+    See:
+        - `buffer.object_word`
+        - `buffer.object_paragraph`
+        - `buffer.object_quote`
+        - `buffer.object_paren`
+        - `buffer.object_bracket`
+        - `buffer.object_brace`
+]],
+        [[
+inner: bool = false - Should the range exclude the object's delimiters and surrounding whitespace.
+]]
+    )
+
+    P.set_block_selection = red.doc.build_fn(
+        function(self, start_line, start_col, end_line, end_col)
+            coroutine.yield(red.call.buffer_set_block_selection(self:id(), start_line, start_col, end_line, end_col))
+        end,
+        "set_block_selection",
+        [[
+Sets the rectangular block selection used by `block_insert` and `block_delete`.
+]],
+        [[
+The block spans from `start_line` to `end_line` inclusive and from `start_col` up to but not including `end_col`, where columns count chars from the start of the line. Either corner may be given first.
+]],
+        [[
+nil
+]],
+        [[
+self: Buffer - Buffer object whose block selection is set. If no buffer ID is set on this object, uses the active buffer instead.
+]],
+        [[
+start_line: non-negative integer - Line index of the first corner of the block.
+]],
+        [[
+start_col: non-negative integer - Char column of the first corner of the block.
+]],
+        [[
+end_line: non-negative integer - Line index of the second corner of the block.
+]],
+        [[
+end_col: non-negative integer - Char column of the second corner of the block.
+]]
+    )
+
+    P.clear_block_selection = red.doc.build_fn(
+        function(self)
+            coroutine.yield(red.call.buffer_clear_block_selection(self:id()))
+        end,
+        "clear_block_selection",
+        [[
+Removes the block selection from this buffer.
+]],
+        nil,
+        [[
+nil
+]],
+        [[
+self: Buffer - Buffer object whose block selection is removed. If no buffer ID is set on this object, uses the active buffer instead.
+]]
+    )
+
+    P.block_selection = red.doc.build_fn(
+        function(self)
+            local selection = coroutine.yield(red.call.buffer_block_selection(self:id()))
+            if selection == nil then
+                return nil
+            end
+
+            return selection.values
+        end,
+        "block_selection",
+        [[
+Returns the block selection of this buffer.
+]],
+        nil,
+        [[
+BlockSelection table or nil - Holds the 'start_line', 'start_col', 'end_line', and 'end_col' of the block as set by `set_block_selection`. Nil if this buffer has no block selection.
+]],
+        [[
+self: Buffer - Buffer object whose block selection is returned. If no buffer ID is set on this object, uses the active buffer instead.
+]]
+    )
+
+    P.block_insert = red.doc.build_fn(
+        function(self, text, pad)
+            coroutine.yield(red.call.buffer_block_insert(self:id(), text, pad or false))
+        end,
+        "block_insert",
+        [[
+Inserts text at the left column of every line in the block selection.
+]],
+        [[
+Does nothing if this buffer has no block selection. Leaves the cursor at the top left corner of the block.
+]],
+        [[
+nil
+]],
+        [[
+self: Buffer - Buffer object into which the text is inserted. If no buffer ID is set on this object, uses the active buffer instead.
+]],
+        [[
+text: string - Text to insert on each line.
+]],
+        [[
+pad: bool = false - Whether lines too short to reach the block's left column are padded with spaces and inserted into. If false, those lines are skipped.
+]]
+    )
+
+    P.block_delete = red.doc.build_fn(
+        function(self)
+            coroutine.yield(red.call.buffer_block_delete(self:id()))
+        end,
+        "block_delete",
+        [[
+Deletes the columns of the block selection from every line it spans.
+]],
+        [[
+Does nothing if this buffer has no block selection. Afterwards the block selection is left empty at its left column with the cursor at its top left corner.
+]],
+        [[
+nil
+]],
+        [[
+self: Buffer - Buffer object from which the block is deleted. If no buffer ID is set on this object, uses the active buffer instead.
+]]
+    )
+
+    P.increment_number = red.doc.build_fn(
+        function(self, delta)
+            return coroutine.yield(red.call.buffer_increment_number(self:id(), delta or 1))
+        end,
+        "increment_number",
+        [[
+Adds to the number under or after the cursor on the cursor's line.
+]],
+        [[
+A '-' directly before the digits is treated as the number's sign. Numbers written with leading zeros keep their width, so "009" incremented becomes "010". The cursor is left on the last digit of the new number.
+]],
+        [[
+integer or nil - The new value of the number, or nil if no number was found at or after the cursor on its line.
+]],
+        [[
+self: Buffer - Buffer object whose number is changed. If no buffer ID is set on this object, uses the active buffer instead.
+]],
+        [[
+delta: integer = 1 - Amount to add to the number. May be negative.
+]]
+    )
+
+    P.repeat_last_edit = red.doc.build_fn(
+        function(self)
+            return coroutine.yield(red.call.repeat_last_edit(self:id()))
+        end,
+        "repeat_last_edit",
+        [[
+Applies the last edit made at a cursor again at this buffer's cursor.
+]],
+        [[
+Repeats the last insert, delete, `insert_pair`, or `increment_number` made in any buffer. Other edits are not recorded.
+]],
+        [[
+bool - Whether there was an edit to repeat.
+]],
+        [[
+self: Buffer - Buffer object in which the edit is repeated. If no buffer ID is set on this object, uses the active buffer instead.
+]]
+    )
+
+    P.detect_file_type = red.doc.build_fn(
+        function(self)
+            return coroutine.yield(red.call.buffer_detect_file_type(self:id()))
+        end,
+        "detect_file_type",
+        [[
+Guesses the type of this buffer's content, such as "rust" or "sh".
+]],
+        [[
+Uses the extension of the buffer's linked file if it is a known one. Otherwise, looks at the buffer's first line for a shebang (such as `#!/bin/sh` or `#!/usr/bin/env python3`) or markers like `<?xml`.
+]],
+        [[
+string or nil - Name of the detected file type. Nil if the type could not be detected.
+]],
+        [[
+self: Buffer - Buffer object whose file type is detected. If no buffer ID is set on this object, detects the file type of the active buffer instead.
+]]
+    )
+
+    P.options = red.doc.build_fn(
+        function(self)
+            return coroutine.yield(red.call.buffer_get_options(self:id())).values
+        end,
+        "options",
+        [[
+Gets the buffer-local options set on this buffer.
+]],
+        [[
+Options not set locally on this buffer are nil and fall back to the editor-wide value in `red.opts`. Options set by `.editorconfig` files are applied when the buffer is linked to a file.
+]],
+        [[
+table - Buffer option values keyed by name:
+    - `tab_width`: positive integer or nil
+    - `expand_tab`: boolean or nil
+    - `line_ending`: LineEnding table (RedEnum) or nil - Line ending the content is converted to when written to its file.
+    - `final_newline`: boolean or nil
+    - `trim_trailing_whitespace`: boolean or nil - If true, whitespace is removed from the end of each line when written to its file.
+]],
+        [[
+self: Buffer - Buffer object whose options are returned. If no buffer ID is set on this object, returns the options of the active buffer instead.
+]]
+    )
+
+    P.update_options = red.doc.build_fn(
+        function(self, option_table)
+            coroutine.yield(red.call.buffer_update_options(self:id(), option_table))
+        end,
+        "update_options",
+        [[
+Sets buffer-local options on this buffer.
+]],
+        [[
+Only options present in `option_table` are changed. Setting an option to `"unset"` clears the buffer-local value so the editor-wide value in `red.opts` is used again. Options which are not booleans may also be cleared by setting them to `false`. Values which are invalid for their option, such as a `tab_width` of 0, are ignored.
+
+Ex: `
+    red.buffer:update_options({ tab_width = 2 })
+`
+]],
+        [[
+nil
+]],
+        [[
+self: Buffer - Buffer object whose options are updated. If no buffer ID is set on this object, updates the options of the active buffer instead.
+]],
+        [[
+option_table: table - Option names mapped to their new values. See `options` for available option names.
+]]
+    )
+
+    P.naive = {
+        type = "EditorBufferType",
+        variant = "naive"
+    }
+
     P.gap = {
         type = "EditorBufferType",
         variant = "gap"
     }
 
+    P.paged = {
+        type = "EditorBufferType",
+        variant = "paged"
+    }
+
+    P.motion_char = {
+        type = "Motion",
+        variant = "char"
+    }
+
+    P.motion_char_back = {
+        type = "Motion",
+        variant = "char_back"
+    }
+
+    P.motion_word = {
+        type = "Motion",
+        variant = "word"
+    }
+
+    P.motion_word_back = {
+        type = "Motion",
+        variant = "word_back"
+    }
+
+    P.motion_line_start = {
+        type = "Motion",
+        variant = "line_start"
+    }
+
+    P.motion_line_end = {
+        type = "Motion",
+        variant = "line_end"
+    }
+
+    P.motion_line = {
+        type = "Motion",
+        variant = "line"
+    }
+
+    P.line_ending_lf = {
+        type = "LineEnding",
+        variant = "lf"
+    }
+
+    P.line_ending_crlf = {
+        type = "LineEnding",
+        variant = "cr_lf"
+    }
+
+    P.object_word = {
+        type = "TextObject",
+        variant = "word"
+    }
+
+    P.object_paragraph = {
+        type = "TextObject",
+        variant = "paragraph"
+    }
+
+    P.object_quote = {
+        type = "TextObject",
+        variant = "quote"
+    }
+
+    P.object_paren = {
+        type = "TextObject",
+        variant = "paren"
+    }
+
+    P.object_bracket = {
+        type = "TextObject",
+        variant = "bracket"
+    }
+
+    P.object_brace = {
+        type = "TextObject",
+        variant = "brace"
+    }
+
     red.doc.document_table(
         P,
         "Buffer",
@@ -40,6 +40,690 @@ foreground: Color Table - The rgb color table for the text's foreground. See: re
 ]]
     )
 
+    P.eval = red.doc.build_fn(
+        function(script, continuation)
+            return coroutine.yield(red.call.eval_lua(script, continuation))
+        end,
+        "eval",
+        [[
+Evaluates a string of Lua as a new script and passes the value it returns to a continuation function.
+]],
+        [[
+Unlike running a script, the evaluated chunk's return value is captured. Once the chunk finishes, `continuation` is run as a new script with the returned value as its only argument. Errors while loading or running the chunk are sent to the error hook along with the offending source.
+
+Ex: `
+    red.eval("return 1 + 1", function(result)
+        red.message:send(tostring(result))
+    end)
+`
+]],
+        [[
+non-negative integer or nil - Handle identifying this evaluation. Nil if the script failed to load.
+]],
+        [[
+script: String - The Lua source to evaluate.
+]],
+        [[
+continuation: Function(value) - Function to run with the value returned by the evaluated script.
+]]
+    )
+
+    P.jump_push = red.doc.build_fn(
+        function()
+            coroutine.yield(red.call.jump_list_push())
+        end,
+        "jump_push",
+        [[
+Records the active buffer and cursor position in the jump list.
+]],
+        [[
+Call before making a large cursor movement (search, goto line, etc.) so that `jump_back` can return to the position. Recording discards any positions ahead of the current one from earlier jumps back. The jump list holds a limited number of positions, dropping the oldest first.
+]],
+        [[
+nil
+]]
+    )
+
+    P.jump_back = red.doc.build_fn(
+        function()
+            return coroutine.yield(red.call.jump_back())
+        end,
+        "jump_back",
+        [[
+Moves the active pane to the previous position in the jump list.
+]],
+        [[
+Switches the active pane's buffer if the position is in another buffer. The position left is recorded so `jump_forward` can return to it.
+]],
+        [[
+JumpPosition table or nil - The position jumped to, or nil if there is no earlier position.
+]]
+    )
+
+    P.jump_forward = red.doc.build_fn(
+        function()
+            return coroutine.yield(red.call.jump_forward())
+        end,
+        "jump_forward",
+        [[
+Moves the active pane to the next position in the jump list after jumping back.
+]],
+        nil,
+        [[
+JumpPosition table or nil - The position jumped to, or nil if there is no later position.
+]]
+    )
+
+    P.show_popup = red.doc.build_fn(
+        function(items, selected, row, col)
+            coroutine.yield(red.call.show_popup(items, selected, row, col))
+        end,
+        "show_popup",
+        [[
+Shows a bordered popup list of items over the editor's panes.
+]],
+        [[
+The popup's top left corner is drawn at the given screen row and column, shifted to keep the popup within the terminal. Only one popup is shown at a time, so calling this again replaces the current popup. If the popup is too short to show all items, the items are scrolled to keep the selected item visible.
+]],
+        [[
+nil
+]],
+        [[
+items: List of strings - Menu items to list in the popup.
+]],
+        [[
+selected: non-negative integer or nil - Index of the highlighted item, starting at 0. No item is highlighted if nil.
+]],
+        [[
+row: non-negative integer (16-bit) - Screen row of the popup's top border.
+]],
+        [[
+col: non-negative integer (16-bit) - Screen column of the popup's left border.
+]]
+    )
+
+    P.hide_popup = red.doc.build_fn(
+        function()
+            coroutine.yield(red.call.hide_popup())
+        end,
+        "hide_popup",
+        [[
+Dismisses the popup shown by `show_popup`, if any.
+]],
+        nil,
+        [[
+nil
+]]
+    )
+
+    P.prompt = red.doc.build_fn(
+        function(prompt, on_submit)
+            coroutine.yield(red.call.prompt_open(prompt, on_submit))
+        end,
+        "prompt",
+        [[
+Opens a one-line prompt over the bottom row of the editor, calling on_submit with the typed input once Enter is pressed.
+]],
+        [[
+While the prompt is open, key events edit the prompt's input rather than running the keymap. Esc closes the prompt without calling on_submit. Opening a prompt replaces any prompt which is already open.
+]],
+        [[
+nil
+]],
+        [[
+prompt: string - Text shown before the input.
+]],
+        [[
+on_submit: function(string) - Function called with the input when the prompt is submitted.
+]]
+    )
+
+    P.close_prompt = red.doc.build_fn(
+        function()
+            coroutine.yield(red.call.prompt_close())
+        end,
+        "close_prompt",
+        [[
+Closes the prompt opened by `prompt`, if any, without submitting it.
+]],
+        nil,
+        [[
+nil
+]]
+    )
+
+    P.force_redraw = red.doc.build_fn(
+        function()
+            coroutine.yield(red.call.force_redraw())
+        end,
+        "force_redraw",
+        [[
+Repaints every pane on the next render.
+]],
+        [[
+Useful after something outside the editor, such as a subprocess, has written over the screen.
+]],
+        [[
+nil
+]]
+    )
+
+    P.terminal_capabilities = red.doc.build_fn(
+        function()
+            local capabilities = coroutine.yield(red.call.terminal_capabilities()).values
+            capabilities.color_depth = capabilities.color_depth.variant
+            return capabilities
+        end,
+        "terminal_capabilities",
+        [[
+Returns the features of the terminal the editor is running in.
+]],
+        [[
+Features are detected from the environment, so they may not match a terminal which misreports itself.
+]],
+        [[
+table - Terminal features:
+    - `color_depth`: "monochrome", "ansi16", "ansi256", or "true_color", parsed from the `COLORTERM` and `TERM` environment variables
+    - `rows`: non-negative integer - Height of the terminal
+    - `cols`: non-negative integer - Width of the terminal
+    - `is_tty`: bool - Whether the editor's output is a terminal
+    - `is_unicode`: bool - Whether the locale uses UTF-8
+]]
+    )
+
+    P.batch = red.doc.build_fn(
+        function(calls)
+            return coroutine.yield(red.call.batch(calls))
+        end,
+        "batch",
+        [[
+Makes a list of RedCalls in order, returning all of their results at once.
+]],
+        [[
+Each RedCall made by a script waits on the editor separately, so making many calls one after another is slow. Batching the calls makes them all in a single step. Calls which control the script itself, such as yielding, running scripts, or hooks, cannot be batched. If any call fails, the batch raises that call's error and any later calls are not made.
+]],
+        [[
+table - List of the results of each call, at the same index as the call, with the number of calls in field `n` as `table.pack` gives. Calls returning nil leave a hole in the list, so iterate up to `n` rather than with `ipairs`.
+]],
+        [[
+calls: table - List of RedCalls made with `red.call`, such as `{ red.call.buffer_cursor(id), red.call.buffer_length(id) }`.
+]]
+    )
+
+    P.bell = red.doc.build_fn(
+        function(visual)
+            coroutine.yield(red.call.bell(visual or false))
+        end,
+        "bell",
+        [[
+Rings the bell to signal an invalid operation.
+]],
+        [[
+The bell sounds the terminal bell unless `visual` is true or the `visual_bell` option is set, in which case the active pane is flashed for 100 milliseconds.
+]],
+        [[
+nil
+]],
+        [[
+visual: bool? - Flash the active pane regardless of the `visual_bell` option. Defaults to false.
+]]
+    )
+
+    P.current_mode = red.doc.build_fn(
+        function()
+            return coroutine.yield(red.call.mode())
+        end,
+        "current_mode",
+        [[
+Returns the name of the editor's current editing mode.
+]],
+        [[
+Modes are free-form names chosen by scripts, such as "normal" or "insert". The editor starts in the "default" mode.
+]],
+        [[
+string - The current mode's name.
+]]
+    )
+
+    P.set_mode = red.doc.build_fn(
+        function(mode)
+            coroutine.yield(red.call.set_mode(mode))
+        end,
+        "set_mode",
+        [[
+Changes the editor's editing mode, which picks the keymap handling key events if one is set for the mode with `keymap.set_mode_map`.
+]],
+        [[
+If the mode changes, the "mode_changed" hook is run with a table holding the `from` and `to` mode names. Hooks may compare against the name of the mode being entered.
+]],
+        [[
+nil
+]],
+        [[
+mode: string - Name of the mode to change to.
+]]
+    )
+
+    P.on_mode_entered = red.doc.build_fn(
+        function(mode, run)
+            coroutine.yield(red.call.set_hook("mode_changed", run, mode))
+        end,
+        "on_mode_entered",
+        [[
+Sets a function to be called as a new script whenever the editor changes into the given mode.
+]],
+        nil,
+        [[
+nil
+]],
+        [[
+mode: string - Name of the mode whose entry triggers the function.
+]],
+        [[
+run: Function - Called with a table holding the `from` and `to` mode names.
+]]
+    )
+
+    P.window_title = red.doc.build_fn(
+        function()
+            return coroutine.yield(red.call.window_title())
+        end,
+        "window_title",
+        [[
+Returns the title shown in the terminal's title bar.
+]],
+        nil,
+        [[
+string - The current window title.
+]]
+    )
+
+    P.set_window_title = red.doc.build_fn(
+        function(title)
+            coroutine.yield(red.call.set_window_title(title))
+        end,
+        "set_window_title",
+        [[
+Sets the title shown in the terminal's title bar, such as to show the current file's name.
+]],
+        [[
+The title is applied on the next render and reset to "BadRed" when the editor exits.
+]],
+        [[
+nil
+]],
+        [[
+title: string - The new window title.
+]]
+    )
+
+    P.session_save = red.doc.build_fn(
+        function(path)
+            coroutine.yield(red.call.session_save(path))
+        end,
+        "session_save",
+        [[
+Writes the open buffers, the pane layout of the current tab page, and the editor options to a session file.
+]],
+        [[
+Hooks, keymaps, and other tab pages are not saved. Buffers linked to files remember the file's path.
+]],
+        [[
+nil
+]],
+        [[
+path: string - Path of the session file to write.
+]]
+    )
+
+    P.session_load = red.doc.build_fn(
+        function(path)
+            coroutine.yield(red.call.session_load(path))
+        end,
+        "session_load",
+        [[
+Replaces the open buffers, panes, and editor options with those saved in a session file by `session_save`.
+]],
+        [[
+Every tab page is closed. Buffers are linked again to the files at their saved paths, and are left modified if their saved content differs from the file's.
+]],
+        [[
+nil
+]],
+        [[
+path: string - Path of the session file to read.
+]]
+    )
+
+    P.tab_new = red.doc.build_fn(
+        function(buffer)
+            local buffer_id = buffer and buffer:id() or coroutine.yield(red.call.current_buffer_id())
+            return coroutine.yield(red.call.tab_new(buffer_id))
+        end,
+        "tab_new",
+        [[
+Opens a new tab page after the last one, with a single pane showing the given buffer, and makes it active.
+]],
+        [[
+Each tab page has its own layout of panes, while buffers are shared between all tab pages. Switching tab pages keeps the panes of the tab page being left open.
+]],
+        [[
+non-negative integer - Index of the new tab page.
+]],
+        [[
+buffer: Buffer or nil - Buffer shown in the new tab page's pane. Defaults to the current buffer if nil.
+]]
+    )
+
+    P.tab_next = red.doc.build_fn(
+        function()
+            return coroutine.yield(red.call.tab_next())
+        end,
+        "tab_next",
+        [[
+Switches to the next tab page, wrapping around to the first after the last.
+]],
+        nil,
+        [[
+non-negative integer - Index of the tab page switched to.
+]]
+    )
+
+    P.tab_switch = red.doc.build_fn(
+        function(index)
+            coroutine.yield(red.call.tab_switch(index))
+        end,
+        "tab_switch",
+        [[
+Switches to the tab page at the given index.
+]],
+        nil,
+        [[
+nil
+]],
+        [[
+index: non-negative integer - Index of the tab page to show, starting at 0.
+]]
+    )
+
+    P.tab_close = red.doc.build_fn(
+        function()
+            coroutine.yield(red.call.tab_close())
+        end,
+        "tab_close",
+        [[
+Closes the active tab page and its panes, switching to the tab page before it.
+]],
+        [[
+Buffers shown in the closed tab page's panes are left open. The only remaining tab page cannot be closed.
+]],
+        [[
+nil
+]]
+    )
+
+    P.tab_list = red.doc.build_fn(
+        function()
+            local tabs = {}
+            for i, tab in ipairs(coroutine.yield(red.call.tab_list())) do
+                tabs[i] = tab.values
+            end
+            return tabs
+        end,
+        "tab_list",
+        [[
+Returns a description of each open tab page in order.
+]],
+        nil,
+        [[
+List of tab tables - Each holds the tab page's `index`, whether it `is_active`, its `pane_count` of leaf panes, and the `active_buffer_id` of the buffer shown by its active pane, or nil if its active pane is a split.
+]]
+    )
+
+    P.grep_buffers = red.doc.build_fn(
+        function(pattern)
+            local matches = {}
+            for i, found in ipairs(coroutine.yield(red.call.grep_buffers(pattern))) do
+                matches[i] = found.values
+            end
+            return matches
+        end,
+        "grep_buffers",
+        [[
+Searches every open buffer for a regex pattern.
+]],
+        [[
+Lines are searched without their newline, so matches never span lines. Matches are listed by buffer id, then in order of their position within the buffer. Errors if `pattern` is not a valid regex.
+]],
+        [[
+List of Match tables - Each holds 'buffer_id', 'line_index', 'byte_index' of the start of the match, and 'line_text' of the full line the match is on.
+]],
+        [[
+pattern: string - Regex to search for.
+]]
+    )
+
+    P.grep_files = red.doc.build_fn(
+        function(root_path, pattern, glob)
+            local matches = {}
+            for i, found in ipairs(coroutine.yield(red.call.grep_files(root_path, pattern, glob))) do
+                matches[i] = found.values
+            end
+            return matches
+        end,
+        "grep_files",
+        [[
+Searches the files under a directory for a regex pattern.
+]],
+        [[
+Directories are searched recursively without following symlinks. Files which are not valid UTF-8 are skipped. The search runs a few files at a time so the editor stays responsive, and stops once 10000 matches are found. Use `spawn_grep_files` for a search which can be cancelled.
+]],
+        [[
+List of Match tables - Each holds the 'path' of the file, the 'line' index and byte 'col' of the start of the match, and the 'text' of the full line the match is on.
+]],
+        [[
+root_path: string - Directory to search. Expanded like file paths, so `~` and environment variables may be used. May also be a single file.
+]],
+        [[
+pattern: string - Regex to search for.
+]],
+        [[
+glob: string or nil - Only files with names matching this glob are searched. `*` matches any run of characters and `?` matches a single byte. Searches all files if nil.
+]]
+    )
+
+    P.cancel_grep_files = red.doc.build_fn(
+        function(task_id)
+            return coroutine.yield(red.call.cancel_grep_files(task_id))
+        end,
+        "cancel_grep_files",
+        [[
+Stops a search started by `spawn_grep_files`.
+]],
+        [[
+The cancelled search completes with the matches it found before being cancelled, and its `on_complete` is told it was cancelled.
+]],
+        [[
+boolean - Whether a running search with the id was found.
+]],
+        [[
+task_id: non-negative integer - Id of the search as returned by `spawn_grep_files`.
+]]
+    )
+
+    local task_callbacks = nil
+    local function on_task_complete(task_id, callback)
+        if task_callbacks == nil then
+            task_callbacks = {}
+            coroutine.yield(red.call.set_hook("task_complete", function(completion)
+                local task_callback = task_callbacks[completion.id]
+                task_callbacks[completion.id] = nil
+
+                if task_callback then
+                    task_callback(completion.result)
+                end
+            end))
+        end
+
+        task_callbacks[task_id] = callback
+    end
+
+    P.spawn_grep_files = red.doc.build_fn(
+        function(root_path, pattern, glob, on_complete)
+            local task_id = coroutine.yield(red.call.spawn_grep_files(root_path, pattern, glob))
+            on_task_complete(task_id, function(result)
+                if result.variant == "failed" then
+                    on_complete(nil, result.values[1])
+                    return
+                end
+
+                local grep_result = result.values[1].values
+                local matches = {}
+                for i, found in ipairs(grep_result.matches) do
+                    matches[i] = found.values
+                end
+                on_complete(matches, nil, grep_result.is_cancelled)
+            end)
+            return task_id
+        end,
+        "spawn_grep_files",
+        [[
+Runs `grep_files` in the background and calls `on_complete` with its matches once done.
+]],
+        [[
+The search runs on a worker thread, so the editor and other scripts continue while it runs. It may be stopped early by passing the returned id to `cancel_grep_files`. `on_complete` is run as a new script.
+]],
+        [[
+non-negative integer - Id of the background task, as reported by the "task_complete" hook.
+]],
+        [[
+root_path: string - Directory to search. See `grep_files`.
+]],
+        [[
+pattern: string - Regex to search for.
+]],
+        [[
+glob: string or nil - Only files with names matching this glob are searched. See `grep_files`.
+]],
+        [[
+on_complete: (matches, error, is_cancelled) -> nil - Called with the list of Match tables as returned by `grep_files`, and whether the search was cancelled before every file was searched.
+]]
+    )
+
+    P.spawn_read_file = red.doc.build_fn(
+        function(path, on_complete)
+            local task_id = coroutine.yield(red.call.spawn_read_file(path))
+            on_task_complete(task_id, function(result)
+                if result.variant == "failed" then
+                    on_complete(nil, result.values[1])
+                else
+                    on_complete(result.values[1])
+                end
+            end)
+            return task_id
+        end,
+        "spawn_read_file",
+        [[
+Reads a file in the background and calls `on_complete` with its content once done.
+]],
+        [[
+The file is read on a worker thread, so the editor and other scripts continue while it is read. `on_complete` is run as a new script.
+]],
+        [[
+non-negative integer - Id of the background task, as reported by the "task_complete" hook.
+]],
+        [[
+path: string - Path of the file to read. Expanded like file paths, so `~` and environment variables may be used.
+]],
+        [[
+on_complete: (content, error) -> nil - Called with the file's content as a string, or with nil and an error message if the file could not be read.
+]]
+    )
+
+    P.run_shell = red.doc.build_fn(
+        function(command, args, stdin, on_complete)
+            local task_id = coroutine.yield(red.call.run_shell(command, args or {}, stdin))
+            on_task_complete(task_id, function(result)
+                if result.variant == "failed" then
+                    on_complete(nil, result.values[1])
+                else
+                    on_complete(result.values[1].values)
+                end
+            end)
+            return task_id
+        end,
+        "run_shell",
+        [[
+Runs an external command in the background and calls `on_complete` with its output once it exits.
+]],
+        [[
+The command is run directly rather than through a shell, so `args` are passed as given. If the command cannot be run, `on_complete` is called with nil and an error message. A command which runs but fails is still reported as output, with its 'exit_code' set to the failing code.
+]],
+        [[
+non-negative integer - Id of the background task, as reported by the "task_complete" hook.
+]],
+        [[
+command: string - Name or path of the program to run.
+]],
+        [[
+args: List of strings = {} - Arguments passed to the program.
+]],
+        [[
+stdin: string or nil - Text written to the program's standard input. Standard input is left empty if nil.
+]],
+        [[
+on_complete: (output, error) -> nil - Called with an Output table holding 'stdout' and 'stderr' strings and the 'exit_code' integer. 'exit_code' is nil if the program was ended by a signal.
+]]
+    )
+
+    P.set_formatter = red.doc.build_fn(
+        function(extension, command)
+            coroutine.yield(red.call.set_formatter(extension, command))
+        end,
+        "set_formatter",
+        [[
+Sets the command used to format buffers linked to files with the given extension when they are written.
+]],
+        [[
+The buffer's content is given to the command on standard input and replaced by the command's standard output. The command is split on whitespace into a program and its arguments, and is not run through a shell. If the command exits unsuccessfully, the buffer's content is left unchanged.
+]],
+        [[
+nil
+]],
+        [[
+extension: string - File extension without the leading '.', such as "rs".
+]],
+        [[
+command: string or nil - Formatter command, such as "rustfmt --edition 2021". Removes the formatter for `extension` if nil.
+]]
+    )
+
+    P.set_file_type_options = red.doc.build_fn(
+        function(extension, options)
+            coroutine.yield(red.call.set_file_type_options(extension, options))
+        end,
+        "set_file_type_options",
+        [[
+Sets the buffer options applied to buffers when they are linked to files with the given extension.
+]],
+        [[
+Options from `.editorconfig` files applying to the file are applied afterwards, so take precedence.
+
+Ex: `
+    red.set_file_type_options("py", { expand_tab = true, tab_width = 4 })
+`
+]],
+        [[
+nil
+]],
+        [[
+extension: string - File extension without the leading '.', such as "py".
+]],
+        [[
+options: table or nil - Buffer option names mapped to their values. See `Buffer:options` for available option names. Removes the options for `extension` if nil.
+]]
+    )
+
     P.rgb = red.doc.build_fn(
         function(r, g, b)
             return {
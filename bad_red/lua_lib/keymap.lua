@@ -14,7 +14,6 @@ package.preload["keymap"] = function(modname, _)
     }
 
     local motion = require("motion")
-    local opts = require("opts")
 
     P.new_map = red.doc.build_fn(
         function(self)
@@ -55,6 +54,30 @@ new_map: Keymap table - The map to set as the global used keymap on key events.
 ]]
     )
 
+    P.mode_maps = {}
+
+    P.set_mode_map = red.doc.build_fn(
+        function(mode, map)
+            P.mode_maps[mode] = map
+        end,
+        "set_mode_map",
+        [[
+Sets the keymap which handles key events while the editor is in the given mode.
+]],
+        [[
+Key events in modes without a keymap set are handled by the current global keymap. See `red.set_mode` for changing modes.
+]],
+        [[
+nil
+]],
+        [[
+mode: string - Name of the mode the keymap is used in.
+]],
+        [[
+map: Keymap table or nil - Keymap handling key events in the mode. Removes the mode's keymap if nil.
+]]
+    )
+
     P.pop_map = red.doc.build_fn(
         function()
             P.current = P.current.parent or P.current
@@ -90,7 +113,11 @@ update_function: (Keymap table) -> Keymap table - Callback build the new map as
 
     P.event = red.doc.build_fn(
         function(key_event)
-            local map = P.sequence or P.current
+            local map = P.sequence
+            if map == nil and next(P.mode_maps) ~= nil then
+                map = P.mode_maps[coroutine.yield(red.call.mode())]
+            end
+            map = map or P.current
             local event_handler = map[key_event]
 
             if type(event_handler) == "function" then
@@ -109,7 +136,7 @@ update_function: (Keymap table) -> Keymap table - Callback build the new map as
 Handle provided key_event from the current set keymap.
 ]],
         [[
-If the current keymap contains a function at the key event, calls the function and clears out the sequence of input currently stored. If the current keymap contains a table at the key event, uses the new table as the current sequence handler to process the next input with.
+The keymap used is the one set for the editor's current mode with `set_mode_map`, or the current global keymap if the mode has none. If the keymap contains a function at the key event, calls the function and clears out the sequence of input currently stored. If the current keymap contains a table at the key event, uses the new table as the current sequence handler to process the next input with.
 ]],
         [[
 nil
@@ -119,6 +146,32 @@ key_event: string - The KeyEvent string from the BadRed editor hook being handle
 ]]
     )
 
+    P.list = red.doc.build_fn(
+        function(prefix, map)
+            local bindings = {}
+            for i, binding in ipairs(coroutine.yield(red.call.key_map_list(map or P.current, prefix))) do
+                bindings[i] = binding.values
+            end
+            return bindings
+        end,
+        "list",
+        [[
+Returns the key bindings reachable after pressing a sequence of keys, for showing which keys are available.
+]],
+        [[
+Bindings inherited from the keymap's parents are included, with bindings in a child keymap hiding those of its parents. Keys handled only by a keymap's `__index` fallback, such as raw character input, are not listed. If the prefix does not lead to a table of further bindings, the list is empty.
+]],
+        [[
+List of binding tables - Each holds the list of `keys` pressed to reach the binding, starting with the prefix, and `has_submap`, which is true if the binding is a table of further bindings rather than a function.
+]],
+        [[
+prefix: List of strings or nil - KeyEvent strings pressed before the listed bindings. Lists the keymap's own bindings if nil.
+]],
+        [[
+map: Keymap table or nil - Keymap to list the bindings of. Defaults to the current global keymap.
+]]
+    )
+
     function P.empty_map()
         local map = P:new_map()
         map.__index = function(_, _)
@@ -164,15 +217,7 @@ key_event: string - The KeyEvent string from the BadRed editor hook being handle
             end
 
             map["Tab"] = function(_)
-                if opts.expand_tabs then
-                    local text_insert = ""
-                    for _ = 1, opts.tab_width do
-                        text_insert = text_insert .. " "
-                    end
-                    red.buffer:insert(text_insert)
-                else
-                    red.buffer:insert("\t")
-                end
+                red.buffer:insert("\t")
             end
 
             map["Left"] = function(_)
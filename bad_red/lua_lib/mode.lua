@@ -148,6 +148,14 @@ package.preload["mode"] = function(modname, _)
             red.pane:set_top_line(new_top_line)
         end
 
+        map["C+f"] = function(_)
+            red.pane:scroll_page(true)
+        end
+
+        map["C+b"] = function(_)
+            red.pane:scroll_page(false)
+        end
+
         map["d"] = P.delete_mode_map(map)
         map["p"] = function(_)
             registers.append(red.buffer:current(), nil)
@@ -16,11 +16,88 @@ package.preload["opts"] = function(_, _)
         function(v) return v end
     )
 
-    doc.add_documented_field(
+    doc.add_computed_field(
+        P,
+        "expand_tab",
+        function()
+            return coroutine.yield(red.call.editor_options()).values.expand_tab
+        end,
+        function(should_expand)
+            coroutine.yield(red.call.update_options({expand_tab=should_expand}))
+        end,
+        "If true, tabs inserted into a buffer are replaced with spaces up to the next tab stop."
+    )
+
+    doc.add_computed_field(
+        P,
+        "final_newline",
+        function()
+            return coroutine.yield(red.call.editor_options()).values.final_newline
+        end,
+        function(should_add)
+            coroutine.yield(red.call.update_options({final_newline=should_add}))
+        end,
+        "If true, a newline is added to the end of a non-empty file on write if it does not already end with one. The buffer content is left unchanged."
+    )
+
+    doc.add_computed_field(
+        P,
+        "visual_bell",
+        function()
+            return coroutine.yield(red.call.editor_options()).values.visual_bell
+        end,
+        function(is_visual)
+            coroutine.yield(red.call.update_options({visual_bell=is_visual}))
+        end,
+        "If true, ringing the bell briefly flashes the active pane instead of sounding the terminal bell."
+    )
+
+    doc.add_computed_field(
+        P,
+        "inactive_cursor",
+        function()
+            return coroutine.yield(red.call.editor_options()).values.inactive_cursor
+        end,
+        function(should_draw)
+            coroutine.yield(red.call.update_options({inactive_cursor=should_draw}))
+        end,
+        "If true, inactive panes draw a dimmed block where their cursor was left, showing where editing continues once the pane is made active."
+    )
+
+    doc.add_computed_field(
         P,
-        "expand_tabs",
-        false,
-        "If true, will replace all tabs input with the corresponding number of spaces."
+        "modified_delay_ms",
+        function()
+            return coroutine.yield(red.call.editor_options()).values.modified_delay_ms
+        end,
+        function(delay_ms)
+            coroutine.yield(red.call.update_options({modified_delay=delay_ms}))
+        end,
+        "Milliseconds a buffer's content must go unchanged after an edit before its `on_modified` functions run. Values below 1 are raised to 1."
+    )
+
+    doc.add_computed_field(
+        P,
+        "auto_reload",
+        function()
+            return coroutine.yield(red.call.editor_options()).values.auto_reload
+        end,
+        function(should_reload)
+            coroutine.yield(red.call.update_options({auto_reload=should_reload}))
+        end,
+        "If true, buffers are reverted when their linked file changes on disk, running their `on_reloaded` functions. Buffers with changes from their file are left as is, running their `on_file_conflict` functions instead."
+    )
+
+    doc.add_computed_field(
+        P,
+        "poll_rate_ms",
+        function()
+            return coroutine.yield(red.call.editor_options()).values.poll_rate_ms
+        end,
+        function(new_rate)
+            coroutine.yield(red.call.update_options({poll_rate=new_rate}))
+        end,
+        "The number of milliseconds the editor waits for input before running scripts and redrawing. Lower values are more responsive but use more CPU. Values below 1 are raised to 1."
     )
 
     doc.add_computed_field(
@@ -32,7 +109,7 @@ package.preload["opts"] = function(_, _)
         function(new_width)
             coroutine.yield(red.call.update_options({tab_width=new_width}))
         end,
-        "The number of spaces a single tab is shown."
+        "The number of spaces a single tab is shown. Values below 1 or above 65535 are ignored."
     )
 
     return P
@@ -96,7 +96,9 @@ self: Pane - A pane table for the new root pane table to inherit from.
         [[
 Makes this Pane the active pane in the editor.
 ]],
-        nil,
+        [[
+Each pane keeps its own cursor into its buffer. The buffer's cursor, which edits are made at, belongs to the active pane. When a pane stops being active its cursor is saved, and it is restored when the pane is made active again, so panes showing the same buffer do not move each other's cursors.
+]],
         [[
 nil
 ]],
@@ -270,6 +272,95 @@ wrap: boolean - The new `wrap` value to set
 ]]
     )
 
+    P.wrap_mode = red.doc.build_fn(
+        function(self)
+            return coroutine.yield(red.call.pane_wrap_mode(self:id()))
+        end,
+        "wrap_mode",
+        [[
+Returns how this pane shows lines wider than the pane.
+]],
+        nil,
+        [[
+table - WrapMode RedEnum:
+    - `pane.wrap_none`
+    - `pane.wrap_char`
+    - `pane.wrap_word`
+]],
+        [[
+self: Pane - The leaf pane whose wrap mode is returned.
+]]
+    )
+
+    P.set_wrap_mode = red.doc.build_fn(
+        function(self, wrap_mode, indent)
+            coroutine.yield(red.call.pane_set_wrap_mode(self:id(), wrap_mode, indent or false))
+        end,
+        "set_wrap_mode",
+        [[
+Sets how this pane shows lines wider than the pane.
+]],
+        [[
+`pane.wrap_none` cuts lines off at the pane's edge. `pane.wrap_char` continues lines on the next row from the character at the pane's edge, which is the same as setting the `wrap` flag. `pane.wrap_word` continues lines from after the last whitespace which fits on the row, only breaking within words wider than the pane.
+]],
+        [[
+nil
+]],
+        [[
+self: Pane - The leaf pane whose wrap mode is set.
+]],
+        [[
+wrap_mode: WrapMode table (RedEnum) - How lines are wrapped.
+]],
+        [[
+indent: boolean or nil - Whether wrapped rows are indented to match the leading whitespace of the line they continue. Defaults to false.
+]]
+    )
+
+    P.tab_spaces = red.doc.build_fn(
+        function(self)
+            return coroutine.yield(red.call.pane_tab_spaces(self:id()))
+        end,
+        "tab_spaces",
+        [[
+Returns the number of columns this pane draws each tab as, if set.
+]],
+        nil,
+        [[
+non-negative integer or nil - Columns drawn for each tab. Nil if tabs are drawn up to the next tab stop.
+]],
+        [[
+self: Pane - The leaf pane whose tab spaces are returned.
+]]
+    )
+
+    P.set_tab_spaces = red.doc.build_fn(
+        function(self, spaces)
+            coroutine.yield(red.call.pane_set_tab_spaces(self:id(), spaces))
+        end,
+        "set_tab_spaces",
+        [[
+Draws each tab in this pane as a fixed number of spaces rather than up to the next tab stop.
+]],
+        [[
+Only changes how tabs are shown. The buffer keeps its tabs, unlike the `expand_tab` option, which inserts spaces in place of tabs. Tabs drawn this way use the "tab" text style if it is set, so they can be told apart from spaces. Cursor movements which keep the visual column still measure tabs by tab stops.
+
+Ex: `
+    red.set_text_style("tab", nil, red.rgb(90, 90, 90))
+    red.pane:set_tab_spaces(4)
+`
+]],
+        [[
+nil
+]],
+        [[
+self: Pane - The leaf pane whose tab spaces are set.
+]],
+        [[
+spaces: positive integer (16-bit) or nil - Columns drawn for each tab. Tabs are drawn up to the next tab stop if nil.
+]]
+    )
+
     P.v_split = red.doc.build_fn(
         function(self)
             coroutine.yield(red.call.pane_v_split(self:id()))
@@ -309,6 +400,47 @@ self: Pane - The pane to split.
 ]]
     )
 
+    P.v_split_new_buffer = red.doc.build_fn(
+        function(self)
+            local new_pane = coroutine.yield(red.call.pane_v_split_new_buffer(self:id()))
+            return P:new(new_pane.values.pane_id), red.buffer:new(new_pane.values.buffer_id)
+        end,
+        "v_split_new_buffer",
+        [[
+Splits this pane like `v_split`, but shows a new empty buffer in the new sibling pane.
+]],
+        [[
+Ex: `
+    local scratch_pane, scratch = pane:v_split_new_buffer()
+    scratch:insert("Notes")
+`
+]],
+        [[
+Pane, Buffer - The new sibling pane and the new buffer it shows.
+]],
+        [[
+self: Pane - The pane to split.
+]]
+    )
+
+    P.h_split_new_buffer = red.doc.build_fn(
+        function(self)
+            local new_pane = coroutine.yield(red.call.pane_h_split_new_buffer(self:id()))
+            return P:new(new_pane.values.pane_id), red.buffer:new(new_pane.values.buffer_id)
+        end,
+        "h_split_new_buffer",
+        [[
+Splits this pane like `h_split`, but shows a new empty buffer in the new sibling pane.
+]],
+        nil,
+        [[
+Pane, Buffer - The new sibling pane and the new buffer it shows.
+]],
+        [[
+self: Pane - The pane to split.
+]]
+    )
+
     P.close = red.doc.build_fn(
         function(self)
             local is_first = self:is_first_child()
@@ -474,7 +606,11 @@ on_first_child: boolean - True if the first child of this pane should be the one
 
     P.flex_size = red.doc.build_fn(
         function(self, percent, on_first_child)
-            coroutine.yield(red.call.pane_set_split_percent(self:id(), percent, on_first_child))
+            if on_first_child == false then
+                percent = 1.0 - percent
+            end
+
+            coroutine.yield(red.call.pane_set_split_percent(self:id(), percent))
         end,
         "flex_size",
         [[
@@ -495,6 +631,44 @@ on_first_child: boolean - True if the first child of this pane should be the one
 ]]
     )
 
+    P.equalize = red.doc.build_fn(
+        function(self)
+            coroutine.yield(red.call.pane_equalize(self:id()))
+        end,
+        "equalize",
+        [[
+Balances the sizes of every pane within this pane.
+]],
+        [[
+Each flex split within this pane is sized by the number of leaf panes on either side of it, so that every leaf pane is given an even share of space. A split with one leaf pane on one side and three on the other is split 25/75. Fixed size splits are left unchanged.
+]],
+        [[
+nil
+]],
+        [[
+self: Pane - The pane whose splits should be balanced. If this table has no pane_id, the active pane will be used. Use `Pane:root()` to balance every pane in the editor.
+]]
+    )
+
+    P.toggle_maximize = red.doc.build_fn(
+        function(self)
+            return coroutine.yield(red.call.pane_toggle_maximize(self:id()))
+        end,
+        "toggle_maximize",
+        [[
+Makes this pane fill the screen, or restores the layout from before it was maximized.
+]],
+        [[
+Maximizing changes the split types of every split between this pane and the root so that the other panes are hidden. Toggling again restores the split types as they were. Maximizing a pane while another is maximized first restores the other pane's layout.
+]],
+        [[
+bool - True if this pane is now maximized. False if the layout was restored.
+]],
+        [[
+self: Pane - The pane to maximize or restore. If this table has no pane_id, the active pane will be used.
+]]
+    )
+
     P.buffer = red.doc.build_fn(
         function(self)
             local buffer_id = coroutine.yield(red.call.pane_buffer_index(self:id()))
@@ -537,6 +711,28 @@ buffer: Buffer Table - A table representing the buffer that this pane should be
 ]]
     )
 
+    P.swap_buffers = red.doc.build_fn(
+        function(self, other)
+            coroutine.yield(red.call.pane_swap_buffers(self:id(), other:id()))
+        end,
+        "swap_buffers",
+        [[
+Exchanges the buffers displayed by this pane and another, without changing the layout of either.
+]],
+        [[
+Both panes must be leaf panes. The pane buffer changed hook is run for each pane.
+]],
+        [[
+nil
+]],
+        [[
+self: Pane - Leaf pane which will display the other pane's buffer.
+]],
+        [[
+other: Pane - Leaf pane which will display this pane's buffer.
+]]
+    )
+
     P.top_line = red.doc.build_fn(
         function(self)
             return coroutine.yield(red.call.pane_top_line(self:id()))
@@ -595,6 +791,185 @@ self: Pane Table - The pane table whose frame should be returned. If this table
 ]]
     )
 
+    P.scroll_page = red.doc.build_fn(
+        function(self, down)
+            return coroutine.yield(red.call.pane_scroll_page(self:id(), down))
+        end,
+        "scroll_page",
+        [[
+Scrolls this pane down or up by one page of its rows, returning the new top line.
+]],
+        [[
+Pages are measured in screen rows, so wrapped lines taking several rows are counted as such. Scrolling down moves past the lines drawn entirely on the pane, while scrolling up shows the lines which fit entirely above the current top line. Always scrolls by at least one line while there are lines to scroll to. The cursor is not moved.
+]],
+        [[
+non-negative integer - The pane's new top line.
+]],
+        [[
+self: Pane Table - The pane table which should be scrolled. If this table has no pane_id, the active pane will be used.
+]],
+        [[
+down: bool - True to scroll towards the end of the buffer. False to scroll towards the start.
+]]
+    )
+
+    P.cursor_screen_position = red.doc.build_fn(
+        function(self)
+            local position = coroutine.yield(red.call.pane_cursor_screen_position(self:id()))
+            return position and position.values
+        end,
+        "cursor_screen_position",
+        [[
+Returns the screen row and column at which this pane's cursor is drawn.
+]],
+        [[
+Follows the pane's scrolling, folds, and wrapping, along with tabs, signs, and phantom text. Useful for placing a popup next to the cursor.
+
+Ex: `
+    local position = red.pane:cursor_screen_position()
+    if position then
+        red.show_popup({ "one", "two" }, 0, position.row + 1, position.col)
+    end
+`
+]],
+        [[
+table or nil - Holds 'row' and 'col', non-negative integers maxing at 16 bit values. Nil if the cursor is scrolled out of the pane.
+]],
+        [[
+self: Pane Table - The pane table whose cursor position should be returned. If this table has no pane_id, the active pane will be used.
+]]
+    )
+
+    P.folds = red.doc.build_fn(
+        function(self)
+            local folds = {}
+            for i, fold in ipairs(coroutine.yield(red.call.pane_folds(self:id()))) do
+                folds[i] = fold.values
+            end
+            return folds
+        end,
+        "folds",
+        [[
+Returns the list of folded line ranges in this pane.
+]],
+        [[
+Folds are sorted by their start line and never overlap.
+]],
+        [[
+List of Fold tables - Each holds the inclusive 'start_line' and 'end_line' buffer line indices of the fold.
+]],
+        [[
+self: Pane Table - The pane whose folds are returned. If this table has no pane_id, the active pane will be used.
+]]
+    )
+
+    P.fold_at = red.doc.build_fn(
+        function(self, line)
+            for _, fold in ipairs(self:folds()) do
+                if fold.start_line <= line and line <= fold.end_line then
+                    return fold
+                end
+            end
+            return nil
+        end,
+        "fold_at",
+        [[
+Returns the fold in this pane containing the provided buffer line, if any.
+]],
+        nil,
+        [[
+Fold table or nil - The fold holding 'start_line' and 'end_line' which contains `line`.
+]],
+        [[
+self: Pane Table - The pane whose folds are searched. If this table has no pane_id, the active pane will be used.
+]],
+        [[
+line: non-negative integer - The buffer line index to find a fold for.
+]]
+    )
+
+    P.add_fold = red.doc.build_fn(
+        function(self, start_line, end_line)
+            coroutine.yield(red.call.pane_add_fold(self:id(), start_line, end_line))
+        end,
+        "add_fold",
+        [[
+Folds the inclusive range of buffer lines so they display as a single summary line.
+]],
+        [[
+Any existing folds overlapping the new range are merged into it. The summary line is drawn with the reserved "fold" style. Errs if the pane is a split rather than a leaf pane.
+]],
+        [[
+nil
+]],
+        [[
+self: Pane Table - The pane to add the fold to. If this table has no pane_id, the active pane will be used.
+]],
+        [[
+start_line: non-negative integer - First buffer line index of the fold.
+]],
+        [[
+end_line: non-negative integer - Last buffer line index of the fold.
+]]
+    )
+
+    P.remove_fold = red.doc.build_fn(
+        function(self, line)
+            local removed = coroutine.yield(red.call.pane_remove_fold(self:id(), line))
+            return removed and removed.values
+        end,
+        "remove_fold",
+        [[
+Unfolds the fold containing the provided buffer line.
+]],
+        [[
+Errs if the pane is a split rather than a leaf pane.
+]],
+        [[
+Fold table or nil - The removed fold if one contained `line`.
+]],
+        [[
+self: Pane Table - The pane to remove the fold from. If this table has no pane_id, the active pane will be used.
+]],
+        [[
+line: non-negative integer - A buffer line index within the fold to remove.
+]]
+    )
+
+    P.count = red.doc.build_fn(
+        function()
+            return coroutine.yield(red.call.pane_count())
+        end,
+        "count",
+        [[
+Returns the number of leaf panes open in the editor.
+]],
+        nil,
+        [[
+non-negative integer - Number of leaf panes reachable from the root pane.
+]]
+    )
+
+    P.tree_dump = red.doc.build_fn(
+        function()
+            local nodes = {}
+            for i, node in ipairs(coroutine.yield(red.call.pane_tree_dump())) do
+                nodes[i] = node.values
+            end
+            return nodes
+        end,
+        "tree_dump",
+        [[
+Returns a snapshot of the layout of every pane in the editor, for debugging layout scripts.
+]],
+        [[
+Nodes are listed from the root pane with each split before its children. Panes which have been closed are not included.
+]],
+        [[
+List of node tables - Each holds the node's pane `index`, its `parent_index` or nil for the root, its `node_type`, and the list of its `children` pane indices, which is empty for leaf panes.
+]]
+    )
+
     local function nested_leaf_child(pane, to_first)
         while pane:type().variant ~= "leaf" do
             pane = pane:child(to_first)
@@ -655,6 +1030,21 @@ self: Pane Table Object - The pane to start the traversal from.
 up: boolean - True if the traversal should move left. Otherwise right.
 ]]
     )
+    P.wrap_none = {
+        type = "WrapMode",
+        variant = "none"
+    }
+
+    P.wrap_char = {
+        type = "WrapMode",
+        variant = "char"
+    }
+
+    P.wrap_word = {
+        type = "WrapMode",
+        variant = "word"
+    }
+
     red.doc.document_table(
         P,
         "Pane",
@@ -0,0 +1,162 @@
+// This file is part of BadRed.
+
+// BadRed is free software: you can redistribute it and/or modify it under the terms of the GNU General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// BadRed is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+use std::ops::{Range, RangeInclusive};
+
+use bad_red_proc_macros::auto_lua;
+
+use super::{ContentBuffer, EditorBuffer};
+
+/// Rectangular region of a buffer spanning lines `start_line` through
+/// `end_line` inclusive and char columns `start_col` up to but excluding
+/// `end_col`. Either corner may come first.
+#[auto_lua]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlockSelection {
+    pub start_line: usize,
+    pub start_col: usize,
+    pub end_line: usize,
+    pub end_col: usize,
+}
+
+impl BlockSelection {
+    pub fn lines(&self) -> RangeInclusive<usize> {
+        self.start_line.min(self.end_line)..=self.start_line.max(self.end_line)
+    }
+
+    pub fn columns(&self) -> Range<usize> {
+        self.start_col.min(self.end_col)..self.start_col.max(self.end_col)
+    }
+}
+
+impl EditorBuffer {
+    /// Inserts `text` at the left column of each line in the block selection.
+    /// Lines shorter than the column are padded with spaces if `pad` is set,
+    /// else skipped.
+    pub fn block_insert(&mut self, text: &str, pad: bool) {
+        let Some(selection) = self.block_selection else {
+            return;
+        };
+        let column = selection.columns().start;
+
+        for line_index in self.block_line_indices(&selection).rev() {
+            let line_length = self.line_char_length(line_index);
+            if let Some(byte_index) = self.byte_index_at_column(line_index, column) {
+                self.set_cursor_byte_index(byte_index, false);
+                self.insert_at_cursor(text);
+            } else if pad {
+                let Some(line_end) = self.line_end_byte_index(line_index) else {
+                    continue;
+                };
+                self.set_cursor_byte_index(line_end, false);
+                self.insert_at_cursor(&" ".repeat(column - line_length));
+                self.insert_at_cursor(text);
+            }
+        }
+
+        self.set_cursor_to_block_start(&selection);
+    }
+
+    /// Deletes the columns of the block selection from each of its lines,
+    /// leaving an empty block at its left column.
+    pub fn block_delete(&mut self) {
+        let Some(mut selection) = self.block_selection else {
+            return;
+        };
+        let columns = selection.columns();
+
+        for line_index in self.block_line_indices(&selection).rev() {
+            let Some(byte_index) = self.byte_index_at_column(line_index, columns.start) else {
+                continue;
+            };
+            let delete_count = columns.end.min(self.line_char_length(line_index)) - columns.start;
+
+            self.set_cursor_byte_index(byte_index, false);
+            self.delete_at_cursor(delete_count);
+        }
+
+        selection.start_col = columns.start;
+        selection.end_col = columns.start;
+        self.block_selection = Some(selection);
+        self.set_cursor_to_block_start(&selection);
+    }
+
+    fn block_line_indices(&self, selection: &BlockSelection) -> RangeInclusive<usize> {
+        let lines = selection.lines();
+        let last_line = self.content_line_count().saturating_sub(1);
+
+        *lines.start()..=(*lines.end()).min(last_line)
+    }
+
+    fn set_cursor_to_block_start(&mut self, selection: &BlockSelection) {
+        let line_index = *selection.lines().start();
+        let byte_index = self
+            .byte_index_at_column(line_index, selection.columns().start)
+            .or_else(|| self.line_end_byte_index(line_index));
+
+        if let Some(byte_index) = byte_index {
+            self.set_cursor_byte_index(byte_index, false);
+        }
+    }
+
+    /// Number of chars on the line, excluding its newline
+    pub(super) fn line_char_length(&self, line_index: usize) -> usize {
+        self.content_line_length(line_index).unwrap_or(0)
+    }
+
+    /// Byte index of the char at `column` on the line, or of the line's end if
+    /// `column` is just past its last char. `None` if the line is shorter.
+    pub(super) fn byte_index_at_column(&self, line_index: usize, column: usize) -> Option<usize> {
+        let line_start = self.line_start_byte_index(line_index)?;
+        let line = self.content_copy_line(line_index)?;
+        let line = line.strip_suffix('\n').unwrap_or(&line);
+
+        line.char_indices()
+            .map(|(char_index, _)| char_index)
+            .chain(std::iter::once(line.len()))
+            .nth(column)
+            .map(|char_index| line_start + char_index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::buffer_with;
+
+    fn selection(start_line: usize, end_line: usize, column: usize) -> BlockSelection {
+        BlockSelection {
+            start_line,
+            start_col: column,
+            end_line,
+            end_col: column,
+        }
+    }
+
+    #[test]
+    fn block_insert_prefixes_each_line_at_the_column() {
+        let mut buffer = buffer_with("abcd\nefgh\nijkl");
+        buffer.block_selection = Some(selection(0, 2, 2));
+
+        buffer.block_insert("> ", false);
+
+        assert_eq!(buffer.content_copy(), "ab> cd\nef> gh\nij> kl");
+        assert_eq!(buffer.cursor_byte_index(), 2);
+    }
+
+    #[test]
+    fn block_insert_pads_or_skips_short_lines() {
+        let mut buffer = buffer_with("abcd\na\nijkl");
+        buffer.block_selection = Some(selection(0, 2, 3));
+        buffer.block_insert("|", true);
+        assert_eq!(buffer.content_copy(), "abc|d\na  |\nijk|l");
+
+        let mut buffer = buffer_with("abcd\na\nijkl");
+        buffer.block_selection = Some(selection(0, 2, 3));
+        buffer.block_insert("|", false);
+        assert_eq!(buffer.content_copy(), "abc|d\na\nijk|l");
+    }
+}
@@ -1,5 +1,10 @@
-pub struct ByteCharIter<I> {
-    iter: I,
+use std::{borrow::Borrow, iter::Peekable};
+
+/// Decodes utf8 chars from a stream of bytes. Invalid or truncated encodings
+/// are decoded as `char::REPLACEMENT_CHARACTER` rather than ending the stream,
+/// so corrupted content is still displayed.
+pub struct ByteCharIter<I: Iterator> {
+    iter: Peekable<I>,
 }
 
 pub fn expected_byte_length_from_starting(starting_byte: u8) -> Option<u8> {
@@ -20,49 +25,80 @@ pub fn expected_byte_length_from_starting(starting_byte: u8) -> Option<u8> {
     }
 }
 
-impl<I> ByteCharIter<I> {
+impl<I: Iterator> ByteCharIter<I> {
     pub fn new(iter: I) -> Self {
-        Self { iter }
+        Self {
+            iter: iter.peekable(),
+        }
     }
 }
 
-impl<'a, I> Iterator for ByteCharIter<I>
+impl<I> Iterator for ByteCharIter<I>
 where
-    I: Iterator<Item = &'a u8>,
+    I: Iterator,
+    I::Item: Borrow<u8>,
 {
     type Item = char;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let mut byte_buf = vec![];
-
-        let next_byte = self.iter.next()?;
+        let next_byte = *self.iter.next()?.borrow();
 
-        let Some(char_byte_length) = expected_byte_length_from_starting(*next_byte) else {
-            panic!(
-                "Invalid utf8 encoding. Found invalid first byte for utf8 encoding: {:x}",
-                next_byte
-            );
+        let Some(char_byte_length) = expected_byte_length_from_starting(next_byte) else {
+            // Stray following bytes and invalid first bytes each replace one char
+            return Some(char::REPLACEMENT_CHARACTER);
         };
 
-        byte_buf.push(*next_byte);
-        for _ in 0..char_byte_length-1 {
-            let Some(following_byte) = self.iter.next() else {
-                panic!("Invalid utf8 encoding. Reached end of byte stream with partial utf8 char parsed");
-            };
-            byte_buf.push(*following_byte);
+        let mut byte_buf = vec![next_byte];
+        for _ in 1..char_byte_length {
+            match self.iter.peek().map(|byte| *byte.borrow()) {
+                Some(following_byte) if following_byte & 0b1100_0000 == 0b1000_0000 => {
+                    byte_buf.push(following_byte);
+                    self.iter.next();
+                }
+                // The char is cut short, so the next byte is left to start the
+                // following char
+                _ => return Some(char::REPLACEMENT_CHARACTER),
+            }
         }
 
-        let decoded_string = std::str::from_utf8(byte_buf.as_slice())
-            .expect("Failed to decide utf8 character from byte iterator");
-
-        let mut chars = decoded_string.chars();
-        let Some(char) = chars.next() else {
-            panic!("Failed to convert byte iterator to valid utf8 character.");
-        };
-        if chars.next().is_some() {
-            panic!("Found multiple characters while attempting to parse a single character from utf8 byte iterator.");
-        }
+        let char = std::str::from_utf8(&byte_buf)
+            .ok()
+            .and_then(|decoded_string| decoded_string.chars().next())
+            .unwrap_or(char::REPLACEMENT_CHARACTER);
 
         Some(char)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(bytes: &[u8]) -> String {
+        ByteCharIter::new(bytes.iter()).collect()
+    }
+
+    #[test]
+    fn valid_bytes_decode_like_str() {
+        let content = "aü€😀\n";
+        assert_eq!(decode(content.as_bytes()), content);
+    }
+
+    #[test]
+    fn truncated_multibyte_tail_yields_one_replacement_and_ends() {
+        let mut iter = ByteCharIter::new([b'a', 0xe2, 0x82].iter());
+
+        assert_eq!(iter.next(), Some('a'));
+        assert_eq!(iter.next(), Some(char::REPLACEMENT_CHARACTER));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn invalid_bytes_are_replaced_without_losing_following_chars() {
+        assert_eq!(decode(&[0x80, b'a']), "\u{FFFD}a");
+        assert_eq!(decode(&[0xff, b'a']), "\u{FFFD}a");
+        assert_eq!(decode(&[0xc3, b'a']), "\u{FFFD}a");
+        // Overlong encoding of '/'
+        assert_eq!(decode(&[0xc0, 0xaf]), "\u{FFFD}");
+    }
+}
@@ -4,7 +4,7 @@
 //
 // BadRed is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
 
-use std::io::Read;
+use std::{borrow::Borrow, fs::File, io::Read, ops::Range};
 
 use crate::file_handle::FileWrite;
 
@@ -12,11 +12,24 @@ pub trait ContentBuffer {
     fn insert_at_cursor(&mut self, content: &str);
     fn delete_at_cursor(&mut self, char_count: usize) -> String;
 
+    /// Whether edits to the content are ignored
+    fn is_read_only(&self) -> bool {
+        false
+    }
+
     fn chars(&self) -> Box<dyn Iterator<Item = char> + '_>;
     fn content_byte_length(&self) -> usize;
+
+    /// Lines are separated by newlines, so content ending in a newline has an
+    /// empty last line after it. The line queries below agree on this, each
+    /// giving `None` for line indices past `content_line_count() - 1`.
     fn content_line_count(&self) -> usize;
+    /// Count of chars on the line, not including its newline
     fn content_line_length(&self, line_index: usize) -> Option<usize>;
     fn content_copy(&self) -> String;
+    /// Hash of the content's bytes, for checking whether content matches a
+    /// previous version without keeping a copy of it
+    fn content_hash(&self) -> u64;
     fn content_copy_at_byte_index(&self, byte_index: usize, char_count: usize) -> Option<String>;
     fn content_copy_line(&self, line_index: usize) -> Option<String>;
 
@@ -26,13 +39,182 @@ pub trait ContentBuffer {
     fn cursor_line_index(&self) -> usize;
     fn line_index_for_byte_index(&self, byte_index: usize) -> usize;
     fn line_start_byte_index(&self, line_index: usize) -> Option<usize>;
+    /// Byte index of the line's newline, or of the content's end on the last
+    /// line
     fn line_end_byte_index(&self, line_index: usize) -> Option<usize>;
 
+    /// Count of newlines within `range`, which is the number of line breaks
+    /// the range crosses. A range within a single line gives 0, while a range
+    /// spanning the newline at the end of its line gives 1.
+    fn line_count_in_byte_range(&self, range: Range<usize>) -> usize {
+        // Each line index counts the newlines before its byte index
+        self.line_index_for_byte_index(range.end)
+            .saturating_sub(self.line_index_for_byte_index(range.start))
+    }
+
+    /// Byte index of the first char on the line which is not a space or tab,
+    /// or of the line's start if the line is blank.
+    fn first_non_whitespace_byte(&self, line_index: usize) -> Option<usize> {
+        let line_start = self.line_start_byte_index(line_index)?;
+        let line = self.content_copy_line(line_index)?;
+        let line = line.strip_suffix('\n').unwrap_or(&line);
+
+        let indent_length = line
+            .bytes()
+            .take_while(|byte| *byte == b' ' || *byte == b'\t')
+            .count();
+        if indent_length == line.len() {
+            Some(line_start)
+        } else {
+            Some(line_start + indent_length)
+        }
+    }
+
+    /// Width in columns of the spaces and tabs starting the line, with tabs
+    /// advancing to the next multiple of `tab_width`.
+    fn indent_of_line(&self, line_index: usize, tab_width: usize) -> Option<usize> {
+        if line_index >= self.content_line_count() {
+            return None;
+        }
+        let line = self.content_copy_line(line_index)?;
+
+        let mut indent = 0;
+        for char in line.chars() {
+            match char {
+                ' ' => indent += 1,
+                '\t' => indent += tab_width - indent % tab_width.max(1),
+                _ => break,
+            }
+        }
+
+        Some(indent)
+    }
+
+    /// Index of the next or previous non-blank line whose indentation is no
+    /// deeper than that of `line_index`, skipping blank lines.
+    fn matching_indent_line(
+        &self,
+        line_index: usize,
+        forward: bool,
+        tab_width: usize,
+    ) -> Option<usize> {
+        let indent = self.indent_of_line(line_index, tab_width)?;
+        let is_match = |other_line: &usize| {
+            let is_blank = self
+                .content_copy_line(*other_line)
+                .is_some_and(|line| line.trim().is_empty());
+            !is_blank
+                && self
+                    .indent_of_line(*other_line, tab_width)
+                    .is_some_and(|other_indent| other_indent <= indent)
+        };
+
+        if forward {
+            (line_index + 1..self.content_line_count()).find(is_match)
+        } else {
+            (0..line_index).rev().find(is_match)
+        }
+    }
+
+    /// Byte index of the start of the next or previous blank line which
+    /// borders a paragraph, where a paragraph is a run of non-blank lines.
+    /// Blank lines are empty or contain only whitespace. Clamps to the start
+    /// or end of the content if no such blank line exists.
+    fn paragraph_boundary(&self, byte_index: usize, forward: bool) -> usize {
+        let line_count = self.content_line_count();
+        let is_blank = |line_index: usize| {
+            self.content_copy_line(line_index)
+                .is_some_and(|line| line.trim().is_empty())
+        };
+
+        let mut line_index = self.line_index_for_byte_index(byte_index);
+        if forward {
+            while line_index < line_count && is_blank(line_index) {
+                line_index += 1;
+            }
+            while line_index < line_count && !is_blank(line_index) {
+                line_index += 1;
+            }
+
+            if line_index < line_count {
+                self.line_start_byte_index(line_index)
+                    .unwrap_or(self.content_byte_length())
+            } else {
+                self.content_byte_length()
+            }
+        } else {
+            while line_index > 0 && is_blank(line_index) {
+                line_index -= 1;
+            }
+            while line_index > 0 && !is_blank(line_index) {
+                line_index -= 1;
+            }
+
+            self.line_start_byte_index(line_index).unwrap_or(0)
+        }
+    }
+
     fn cursor_moved_by_char(&self, char_count: isize) -> usize;
     fn index_moved_by_char(&self, start_byte_index: usize, char_count: isize) -> usize;
 
+    /// Moves `start_byte_index` by `char_count` chars like
+    /// `index_moved_by_char`, but stops at the start of its line or at the end
+    /// of its line before the newline rather than crossing into another line.
+    fn index_moved_by_char_in_line(&self, start_byte_index: usize, char_count: isize) -> usize {
+        let line_index = self.line_index_for_byte_index(start_byte_index);
+        let (Some(line_start), Some(line_end)) = (
+            self.line_start_byte_index(line_index),
+            self.line_end_byte_index(line_index),
+        ) else {
+            return start_byte_index;
+        };
+
+        self.index_moved_by_char(start_byte_index, char_count)
+            .clamp(line_start, line_end)
+    }
+
+    fn populate_from_string(&mut self, content: String);
     fn populate_from_read(&mut self, read: &mut dyn Read) -> std::io::Result<()>;
+    /// Replaces the content with that of `file`. Buffers which page their
+    /// content keep `file` to read pages from, while others read it all now.
+    fn populate_from_file(&mut self, mut file: File) -> std::io::Result<()> {
+        self.populate_from_read(&mut file)
+    }
     fn flush_to_write(&mut self, write: &mut dyn FileWrite) -> std::io::Result<()>;
+
+    /// Description of the last failed read of content which is read as it is
+    /// queried, in which case the content given in its place was empty
+    fn read_error(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Reads all of `read` as UTF-8 text. Content which is not valid UTF-8 fails
+/// with the position of its first invalid byte, as buffers cannot hold it.
+pub fn read_utf8(read: &mut dyn Read) -> std::io::Result<String> {
+    let mut bytes = vec![];
+    read.read_to_end(&mut bytes)?;
+
+    String::from_utf8(bytes).map_err(|e| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "Content is not valid UTF-8. Found invalid bytes at byte index {}",
+                e.utf8_error().valid_up_to()
+            ),
+        )
+    })
+}
+
+/// 64 bit FNV-1a hash of `bytes`
+pub fn fnv_hash<I>(bytes: I) -> u64
+where
+    I: IntoIterator,
+    I::Item: Borrow<u8>,
+{
+    bytes.into_iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte.borrow())).wrapping_mul(0x0100_0000_01b3)
+    })
 }
 
 pub enum BufferUpdate {
@@ -40,3 +222,219 @@ pub enum BufferUpdate {
     Raw,
     Command(String),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::{gap_buffer::GapBuffer, naive_buffer::NaiveBuffer};
+
+    fn buffers_with(content: &str) -> [Box<dyn ContentBuffer>; 2] {
+        let mut buffers: [Box<dyn ContentBuffer>; 2] =
+            [Box::new(GapBuffer::new()), Box::new(NaiveBuffer::new())];
+        for buffer in buffers.iter_mut() {
+            buffer.insert_at_cursor(content);
+        }
+        buffers
+    }
+
+    #[test]
+    fn trailing_newline_ends_with_an_empty_line() {
+        for buffer in buffers_with("ab\ncd\n") {
+            assert_eq!(buffer.content_line_count(), 3);
+            assert_eq!(buffer.content_copy_line(1).as_deref(), Some("cd\n"));
+            assert_eq!(buffer.content_copy_line(2).as_deref(), Some(""));
+            assert_eq!(buffer.content_copy_line(3), None);
+            assert_eq!(buffer.line_start_byte_index(2), Some(6));
+            assert_eq!(buffer.line_end_byte_index(1), Some(5));
+            assert_eq!(buffer.line_end_byte_index(2), Some(6));
+            assert_eq!(buffer.line_start_byte_index(3), None);
+            assert_eq!(buffer.line_end_byte_index(3), None);
+        }
+    }
+
+    #[test]
+    fn last_line_without_newline_ends_at_content_end() {
+        for buffer in buffers_with("ab\ncd") {
+            assert_eq!(buffer.content_line_count(), 2);
+            assert_eq!(buffer.content_copy_line(0).as_deref(), Some("ab\n"));
+            assert_eq!(buffer.content_copy_line(1).as_deref(), Some("cd"));
+            assert_eq!(buffer.content_copy_line(2), None);
+            assert_eq!(buffer.line_start_byte_index(1), Some(3));
+            assert_eq!(buffer.line_end_byte_index(0), Some(2));
+            assert_eq!(buffer.line_end_byte_index(1), Some(5));
+            assert_eq!(buffer.line_start_byte_index(2), None);
+            assert_eq!(buffer.line_end_byte_index(2), None);
+        }
+    }
+
+    #[test]
+    fn empty_content_has_one_empty_line() {
+        for buffer in buffers_with("") {
+            assert_eq!(buffer.content_line_count(), 1);
+            assert_eq!(buffer.content_copy_line(0).as_deref(), Some(""));
+            assert_eq!(buffer.line_start_byte_index(0), Some(0));
+            assert_eq!(buffer.line_end_byte_index(0), Some(0));
+            assert_eq!(buffer.content_copy_line(1), None);
+        }
+    }
+
+    #[test]
+    fn line_length_counts_copied_line_chars_without_newline() {
+        for content in ["", "ab\ncd", "ab\ncd\n", "\n\n", "h\u{e9}llo\n\u{1F600}x\n"] {
+            for buffer in buffers_with(content) {
+                for line_index in 0..=buffer.content_line_count() {
+                    let copied_length = buffer
+                        .content_copy_line(line_index)
+                        .map(|line| line.trim_end_matches('\n').chars().count());
+                    assert_eq!(buffer.content_line_length(line_index), copied_length);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn moving_cursor_line_keeps_column_within_shorter_lines() {
+        for mut buffer in buffers_with("h\u{e9}llo\nab\n\u{1F600}xyz") {
+            buffer.set_cursor_byte_index(4, false);
+
+            buffer.set_cursor_line_index(1);
+            assert_eq!(buffer.cursor_byte_index(), 9);
+            buffer.set_cursor_line_index(2);
+            assert_eq!(buffer.cursor_byte_index(), 16);
+            assert_eq!(buffer.cursor_line_index(), 2);
+            buffer.set_cursor_line_index(3);
+            assert_eq!(buffer.cursor_byte_index(), 16);
+
+            buffer.set_cursor_byte_index(14, false);
+            assert_eq!(buffer.cursor_line_index(), 2);
+            buffer.set_cursor_byte_index(7, false);
+            assert_eq!(buffer.cursor_line_index(), 1);
+        }
+    }
+
+    #[test]
+    fn paragraph_moves_land_on_bordering_blank_lines() {
+        for buffer in buffers_with("a\nb\n \nc\nd") {
+            assert_eq!(buffer.paragraph_boundary(0, true), 4);
+            assert_eq!(buffer.paragraph_boundary(4, true), buffer.content_byte_length());
+            assert_eq!(buffer.paragraph_boundary(8, false), 4);
+            assert_eq!(buffer.paragraph_boundary(2, false), 0);
+        }
+    }
+
+    #[test]
+    fn paragraph_moves_clamp_to_the_content() {
+        for buffer in buffers_with("a\nb") {
+            assert_eq!(buffer.paragraph_boundary(0, true), 3);
+            assert_eq!(buffer.paragraph_boundary(3, false), 0);
+        }
+    }
+
+    #[test]
+    fn matching_indent_leaves_nested_blocks_for_enclosing_lines() {
+        let content = "fn a() {\n    if b {\n        c();\n\n    }\n}\nfn d() {}";
+        for buffer in buffers_with(content) {
+            assert_eq!(buffer.matching_indent_line(2, true, 4), Some(4));
+            assert_eq!(buffer.matching_indent_line(2, false, 4), Some(1));
+            assert_eq!(buffer.matching_indent_line(1, true, 4), Some(4));
+            assert_eq!(buffer.matching_indent_line(0, true, 4), Some(5));
+            assert_eq!(buffer.matching_indent_line(6, true, 4), None);
+        }
+    }
+
+    #[test]
+    fn tabs_indent_to_the_next_tab_stop() {
+        for buffer in buffers_with("\tx\n  \ty\n    z") {
+            assert_eq!(buffer.indent_of_line(0, 4), Some(4));
+            assert_eq!(buffer.indent_of_line(1, 4), Some(4));
+            assert_eq!(buffer.indent_of_line(2, 4), Some(4));
+            assert_eq!(buffer.indent_of_line(3, 4), None);
+        }
+    }
+
+    #[test]
+    fn content_hash_is_the_fnv_hash_of_the_content() {
+        assert_eq!(fnv_hash(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv_hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+
+        for mut buffer in buffers_with("ab\nü") {
+            assert_eq!(buffer.content_hash(), fnv_hash("ab\nü".as_bytes()));
+
+            buffer.set_cursor_byte_index(0, false);
+            buffer.insert_at_cursor("x");
+            assert_eq!(buffer.content_hash(), fnv_hash("xab\nü".as_bytes()));
+        }
+    }
+
+    #[test]
+    fn reading_invalid_utf8_leaves_the_content_unchanged() {
+        for mut buffer in buffers_with("kept") {
+            let error = buffer
+                .populate_from_read(&mut [b'a', b'b', 0xc3].as_slice())
+                .unwrap_err();
+
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+            assert!(error.to_string().contains("byte index 2"));
+            assert_eq!(buffer.content_copy(), "kept");
+        }
+    }
+
+    #[test]
+    fn line_count_in_range_counts_newlines_within_it() {
+        for buffer in buffers_with("a\nb\nc\nd") {
+            assert_eq!(buffer.line_count_in_byte_range(0..7), 3);
+            assert_eq!(buffer.line_count_in_byte_range(0..5), 2);
+            assert_eq!(buffer.line_count_in_byte_range(1..2), 1);
+            assert_eq!(buffer.line_count_in_byte_range(2..3), 0);
+            assert_eq!(buffer.line_count_in_byte_range(4..4), 0);
+        }
+    }
+
+    #[test]
+    fn moving_in_line_stops_at_line_boundaries() {
+        for buffer in buffers_with("ab\ncdé\nf") {
+            assert_eq!(buffer.index_moved_by_char_in_line(1, 1), 2);
+            assert_eq!(buffer.index_moved_by_char_in_line(2, 1), 2);
+            assert_eq!(buffer.index_moved_by_char_in_line(3, -1), 3);
+            assert_eq!(buffer.index_moved_by_char_in_line(3, 5), 7);
+            assert_eq!(buffer.index_moved_by_char_in_line(7, -2), 4);
+            assert_eq!(buffer.index_moved_by_char_in_line(8, 1), 9);
+        }
+    }
+
+    #[test]
+    fn moving_by_chars_clamps_to_the_content_bounds() {
+        // Bytes: 'a' at 0, 'é' at 1..3, '\n' at 3, 'b' at 4, ending at 5
+        let expected_by_start: [(usize, [usize; 5]); 4] = [
+            (0, [0, 0, 0, 1, 3]),
+            (1, [0, 0, 1, 3, 4]),
+            (3, [0, 1, 3, 4, 5]),
+            (5, [3, 4, 5, 5, 5]),
+        ];
+
+        for buffer in buffers_with("aé\nb") {
+            for (start, expected_indices) in expected_by_start {
+                for (char_count, expected) in (-2..=2).zip(expected_indices) {
+                    assert_eq!(
+                        buffer.index_moved_by_char(start, char_count),
+                        expected,
+                        "moving {} chars from {}",
+                        char_count,
+                        start,
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn first_non_whitespace_skips_indentation_only() {
+        for buffer in buffers_with("  \tab\n   \nxy\n") {
+            assert_eq!(buffer.first_non_whitespace_byte(0), Some(3));
+            assert_eq!(buffer.first_non_whitespace_byte(1), Some(6));
+            assert_eq!(buffer.first_non_whitespace_byte(2), Some(10));
+            assert_eq!(buffer.first_non_whitespace_byte(3), Some(13));
+            assert_eq!(buffer.first_non_whitespace_byte(4), None);
+        }
+    }
+}
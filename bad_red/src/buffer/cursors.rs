@@ -0,0 +1,149 @@
+// This file is part of BadRed.
+
+// BadRed is free software: you can redistribute it and/or modify it under the terms of the GNU General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// BadRed is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+use super::{ContentBuffer, EditorBuffer};
+
+impl EditorBuffer {
+    /// Adds a secondary cursor at `byte_index`, clamped to the content.
+    /// Returns false without adding a cursor if the primary cursor or another
+    /// secondary cursor is already there.
+    pub fn add_cursor(&mut self, byte_index: usize) -> bool {
+        let byte_index = byte_index.min(self.content_byte_length());
+        if byte_index == self.cursor_byte_index() {
+            return false;
+        }
+
+        let Err(insert_index) = self.secondary_cursors.binary_search(&byte_index) else {
+            return false;
+        };
+        self.secondary_cursors.insert(insert_index, byte_index);
+        self.is_render_dirty = true;
+
+        true
+    }
+
+    pub fn clear_secondary_cursors(&mut self) {
+        self.secondary_cursors.clear();
+        self.is_render_dirty = true;
+    }
+
+    pub fn has_secondary_cursor_at(&self, byte_index: usize) -> bool {
+        self.secondary_cursors.binary_search(&byte_index).is_ok()
+    }
+
+    /// Inserts `content` at the primary cursor and at each secondary cursor,
+    /// leaving every cursor after its inserted text.
+    pub fn insert_at_cursors(&mut self, content: &str) {
+        self.edit_at_cursors(|buffer| {
+            buffer.insert_at_cursor(content);
+        });
+    }
+
+    /// Inserts `content` at each cursor like `insert_at_cursors`, with its
+    /// tabs replaced by the spaces reaching the next tab stop from that
+    /// cursor's column. Returns the text inserted at the primary cursor.
+    pub fn insert_expanded_at_cursors(&mut self, content: &str, tab_width: u16) -> String {
+        self.edit_at_cursors(|buffer| {
+            let expanded = buffer.expand_tabs(content, tab_width);
+            buffer.insert_at_cursor(&expanded);
+            expanded
+        })
+    }
+
+    /// Deletes `char_count` chars after the primary cursor and after each
+    /// secondary cursor. Returns the text deleted after the primary cursor.
+    pub fn delete_at_cursors(&mut self, char_count: usize) -> String {
+        self.edit_at_cursors(|buffer| buffer.delete_at_cursor(char_count))
+    }
+
+    /// Makes `edit` at each secondary cursor and then at the primary cursor,
+    /// returning the primary cursor's result. Cursors are byte indices tracked
+    /// through each edit, so edits at earlier cursors shift the later ones.
+    /// Cursors left at the same byte index by the edits are merged.
+    fn edit_at_cursors<T>(&mut self, mut edit: impl FnMut(&mut Self) -> T) -> T {
+        if self.secondary_cursors.is_empty() {
+            return edit(self);
+        }
+
+        // The primary cursor is tracked with the secondary cursors while
+        // editing at them, then moved back to once they are done
+        let primary_index = self.secondary_cursors.len();
+        self.secondary_cursors.push(self.cursor_byte_index());
+        for cursor_index in 0..primary_index {
+            let byte_index = self.secondary_cursors[cursor_index];
+            self.set_cursor_byte_index(byte_index, false);
+            edit(self);
+        }
+
+        let primary_byte_index = self.secondary_cursors.remove(primary_index);
+        self.set_cursor_byte_index(primary_byte_index, false);
+        let result = edit(self);
+
+        let cursor_byte_index = self.cursor_byte_index();
+        self.secondary_cursors.sort_unstable();
+        self.secondary_cursors.dedup();
+        self.secondary_cursors
+            .retain(|byte_index| *byte_index != cursor_byte_index);
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::buffer_with;
+
+    #[test]
+    fn inserting_shifts_later_cursors_past_earlier_insertions() {
+        let mut buffer = buffer_with("a b c");
+        buffer.set_cursor_byte_index(2, false);
+        buffer.add_cursor(0);
+        buffer.add_cursor(4);
+
+        buffer.insert_at_cursors("xy");
+
+        assert_eq!(buffer.content_copy(), "xya xyb xyc");
+        assert_eq!(buffer.cursor_byte_index(), 6);
+        assert_eq!(buffer.secondary_cursors, vec![2, 10]);
+    }
+
+    #[test]
+    fn deleting_merges_cursors_which_meet() {
+        let mut buffer = buffer_with("abcd");
+        buffer.set_cursor_byte_index(0, false);
+        buffer.add_cursor(1);
+        buffer.add_cursor(3);
+
+        assert_eq!(buffer.delete_at_cursors(1), "a");
+
+        assert_eq!(buffer.content_copy(), "c");
+        assert_eq!(buffer.cursor_byte_index(), 0);
+        assert_eq!(buffer.secondary_cursors, vec![1]);
+    }
+
+    #[test]
+    fn tabs_expand_from_each_cursor_column() {
+        let mut buffer = buffer_with("a\nabc");
+        buffer.set_cursor_byte_index(1, false);
+        buffer.add_cursor(5);
+
+        assert_eq!(buffer.insert_expanded_at_cursors("\t", 4), "   ");
+
+        assert_eq!(buffer.content_copy(), "a   \nabc ");
+    }
+
+    #[test]
+    fn add_cursor_refuses_occupied_indices() {
+        let mut buffer = buffer_with("abc");
+        buffer.set_cursor_byte_index(1, false);
+
+        assert!(!buffer.add_cursor(1));
+        assert!(buffer.add_cursor(9));
+        assert!(!buffer.add_cursor(3));
+        assert_eq!(buffer.secondary_cursors, vec![3]);
+    }
+}
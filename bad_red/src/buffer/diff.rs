@@ -0,0 +1,218 @@
+// This file is part of BadRed.
+
+// BadRed is free software: you can redistribute it and/or modify it under the terms of the GNU General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// BadRed is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+use bad_red_proc_macros::auto_lua;
+
+#[auto_lua]
+#[derive(Clone, PartialEq, Debug)]
+pub enum DiffOperation {
+    Unchanged {
+        buffer_line: usize,
+        other_line: usize,
+        count: usize,
+    },
+    Added {
+        buffer_line: usize,
+        count: usize,
+    },
+    Removed {
+        other_line: usize,
+        count: usize,
+    },
+}
+
+/// Computes a line-level diff which transforms `other_content` into `buffer_content`.
+///
+/// Lines are split on '\n' the same way buffers count lines, so line indices in the
+/// resulting operations can be used directly as buffer line indices. Added lines only
+/// exist in `buffer_content` and removed lines only exist in `other_content`.
+pub fn line_diff(buffer_content: &str, other_content: &str) -> Vec<DiffOperation> {
+    let buffer_lines: Vec<&str> = buffer_content.split('\n').collect();
+    let other_lines: Vec<&str> = other_content.split('\n').collect();
+
+    let prefix_length = buffer_lines
+        .iter()
+        .zip(other_lines.iter())
+        .take_while(|(buffer_line, other_line)| buffer_line == other_line)
+        .count();
+    let suffix_length = buffer_lines[prefix_length..]
+        .iter()
+        .rev()
+        .zip(other_lines[prefix_length..].iter().rev())
+        .take_while(|(buffer_line, other_line)| buffer_line == other_line)
+        .count();
+
+    let buffer_middle = &buffer_lines[prefix_length..buffer_lines.len() - suffix_length];
+    let other_middle = &other_lines[prefix_length..other_lines.len() - suffix_length];
+
+    // Longest common subsequence lengths for each pair of middle suffixes
+    let row_length = buffer_middle.len() + 1;
+    let mut lcs = vec![0u32; (other_middle.len() + 1) * row_length];
+    for other_index in (0..other_middle.len()).rev() {
+        for buffer_index in (0..buffer_middle.len()).rev() {
+            lcs[other_index * row_length + buffer_index] =
+                if other_middle[other_index] == buffer_middle[buffer_index] {
+                    lcs[(other_index + 1) * row_length + buffer_index + 1] + 1
+                } else {
+                    lcs[(other_index + 1) * row_length + buffer_index]
+                        .max(lcs[other_index * row_length + buffer_index + 1])
+                };
+        }
+    }
+
+    let mut operations = vec![];
+    for line in 0..prefix_length {
+        push_operation(
+            &mut operations,
+            DiffOperation::Unchanged {
+                buffer_line: line,
+                other_line: line,
+                count: 1,
+            },
+        );
+    }
+
+    let (mut buffer_index, mut other_index) = (0, 0);
+    while buffer_index < buffer_middle.len() || other_index < other_middle.len() {
+        let buffer_line = prefix_length + buffer_index;
+        let other_line = prefix_length + other_index;
+
+        let operation = if buffer_index == buffer_middle.len() {
+            other_index += 1;
+            DiffOperation::Removed {
+                other_line,
+                count: 1,
+            }
+        } else if other_index == other_middle.len() {
+            buffer_index += 1;
+            DiffOperation::Added {
+                buffer_line,
+                count: 1,
+            }
+        } else if buffer_middle[buffer_index] == other_middle[other_index] {
+            buffer_index += 1;
+            other_index += 1;
+            DiffOperation::Unchanged {
+                buffer_line,
+                other_line,
+                count: 1,
+            }
+        } else if lcs[(other_index + 1) * row_length + buffer_index]
+            >= lcs[other_index * row_length + buffer_index + 1]
+        {
+            other_index += 1;
+            DiffOperation::Removed {
+                other_line,
+                count: 1,
+            }
+        } else {
+            buffer_index += 1;
+            DiffOperation::Added {
+                buffer_line,
+                count: 1,
+            }
+        };
+
+        push_operation(&mut operations, operation);
+    }
+
+    for suffix_index in 0..suffix_length {
+        push_operation(
+            &mut operations,
+            DiffOperation::Unchanged {
+                buffer_line: prefix_length + buffer_middle.len() + suffix_index,
+                other_line: prefix_length + other_middle.len() + suffix_index,
+                count: 1,
+            },
+        );
+    }
+
+    operations
+}
+
+fn push_operation(operations: &mut Vec<DiffOperation>, operation: DiffOperation) {
+    match (operations.last_mut(), operation) {
+        (
+            Some(DiffOperation::Unchanged { count, .. }),
+            DiffOperation::Unchanged {
+                count: added_count,
+                ..
+            },
+        )
+        | (
+            Some(DiffOperation::Added { count, .. }),
+            DiffOperation::Added {
+                count: added_count,
+                ..
+            },
+        )
+        | (
+            Some(DiffOperation::Removed { count, .. }),
+            DiffOperation::Removed {
+                count: added_count,
+                ..
+            },
+        ) => *count += added_count,
+        (_, operation) => operations.push(operation),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_insert_and_one_remove_are_reported_in_line_order() {
+        assert_eq!(
+            line_diff("one\nnew\ntwo", "one\ntwo\nthree"),
+            vec![
+                DiffOperation::Unchanged {
+                    buffer_line: 0,
+                    other_line: 0,
+                    count: 1,
+                },
+                DiffOperation::Added {
+                    buffer_line: 1,
+                    count: 1,
+                },
+                DiffOperation::Unchanged {
+                    buffer_line: 2,
+                    other_line: 1,
+                    count: 1,
+                },
+                DiffOperation::Removed {
+                    other_line: 2,
+                    count: 1,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_content_is_a_single_empty_line() {
+        assert_eq!(
+            line_diff("", ""),
+            vec![DiffOperation::Unchanged {
+                buffer_line: 0,
+                other_line: 0,
+                count: 1,
+            }]
+        );
+        assert_eq!(
+            line_diff("a\nb", ""),
+            vec![
+                DiffOperation::Removed {
+                    other_line: 0,
+                    count: 1,
+                },
+                DiffOperation::Added {
+                    buffer_line: 0,
+                    count: 2,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,326 @@
+// This file is part of BadRed.
+
+// BadRed is free software: you can redistribute it and/or modify it under the terms of the GNU General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// BadRed is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+use super::{ContentBuffer, EditorBuffer};
+
+impl EditorBuffer {
+    /// Inserts `open` followed by `close` at the cursor, leaving the cursor
+    /// between them. If `open` and `close` are the same and already follow the
+    /// cursor, steps over them instead.
+    pub fn insert_pair(&mut self, open: &str, close: &str) {
+        if open == close && self.step_over(close) {
+            return;
+        }
+
+        self.insert_at_cursor(open);
+        let between_index = self.cursor_byte_index();
+        self.insert_at_cursor(close);
+        self.set_cursor_byte_index(between_index, false);
+    }
+
+    /// Steps the cursor over `close` if it directly follows the cursor, else
+    /// inserts `close` at the cursor. Returns whether the cursor stepped over.
+    pub fn insert_close(&mut self, close: &str) -> bool {
+        if self.step_over(close) {
+            return true;
+        }
+
+        self.insert_at_cursor(close);
+        false
+    }
+
+    /// Adds `delta` to the number under or after the cursor on the cursor's
+    /// line, keeping the number's width if it has leading zeros. Leaves the
+    /// cursor on the last digit and returns the new value, or `None` if no
+    /// number was found.
+    pub fn increment_number(&mut self, delta: i64) -> Option<i64> {
+        let line_index = self.cursor_line_index();
+        let line_start = self.line_start_byte_index(line_index)?;
+        let line = self.content_copy_line(line_index)?;
+        let cursor_offset = self.cursor_byte_index() - line_start;
+
+        let bytes = line.as_bytes();
+        let mut digits_start = 0;
+        let (digits_start, digits_end) = loop {
+            while bytes.get(digits_start).is_some_and(|byte| !byte.is_ascii_digit()) {
+                digits_start += 1;
+            }
+            if digits_start >= bytes.len() {
+                return None;
+            }
+
+            let digits_end = digits_start
+                + bytes[digits_start..]
+                    .iter()
+                    .take_while(|byte| byte.is_ascii_digit())
+                    .count();
+            if digits_end > cursor_offset {
+                break (digits_start, digits_end);
+            }
+            digits_start = digits_end;
+        };
+
+        let digits = &line[digits_start..digits_end];
+        let is_negative = digits_start > 0 && bytes[digits_start - 1] == b'-';
+        let number_start = if is_negative { digits_start - 1 } else { digits_start };
+
+        let value: i64 = digits.parse().ok()?;
+        let value = if is_negative { -value } else { value };
+        let new_value = value.checked_add(delta)?;
+
+        let width = if digits.len() > 1 && digits.starts_with('0') {
+            digits.len()
+        } else {
+            0
+        };
+        let sign = if new_value < 0 { "-" } else { "" };
+        let replacement = format!("{}{:0width$}", sign, new_value.unsigned_abs(), width = width);
+
+        self.set_cursor_byte_index(line_start + number_start, false);
+        self.delete_at_cursor(digits_end - number_start);
+        self.insert_at_cursor(&replacement);
+        self.set_cursor_byte_index(line_start + number_start + replacement.len() - 1, false);
+
+        Some(new_value)
+    }
+
+    /// Moves the cursor to the char `column` on line `line_index`, clamping
+    /// both to the buffer's content. Returns the new cursor byte index.
+    pub fn goto_line(&mut self, line_index: usize, column: usize) -> usize {
+        let line_index = line_index.min(self.content_line_count().saturating_sub(1));
+        let column = column.min(self.line_char_length(line_index));
+
+        if let Some(byte_index) = self.byte_index_at_column(line_index, column) {
+            self.set_cursor_byte_index(byte_index, false);
+        }
+
+        self.cursor_byte_index()
+    }
+
+    /// Deletes from the cursor to the end of the cursor's line, or deletes the
+    /// line's newline if the cursor is already at the end of the line. Returns
+    /// the deleted text.
+    pub fn delete_to_line_end(&mut self) -> String {
+        let cursor_byte_index = self.cursor_byte_index();
+        let line_index = self.cursor_line_index();
+        let (Some(line_start), Some(line_end), Some(line)) = (
+            self.line_start_byte_index(line_index),
+            self.line_end_byte_index(line_index),
+            self.content_copy_line(line_index),
+        ) else {
+            return String::new();
+        };
+
+        if cursor_byte_index == self.content_byte_length() {
+            return String::new();
+        }
+
+        let char_count = if cursor_byte_index < line_end {
+            line[cursor_byte_index - line_start..line_end - line_start]
+                .chars()
+                .count()
+        } else {
+            1
+        };
+
+        self.delete_at_cursor(char_count)
+    }
+
+    /// Deletes from the cursor to the end of the next word, or to the start of
+    /// the previous word if not `forward`, including any whitespace and
+    /// punctuation between the cursor and that word. Returns the deleted text.
+    pub fn delete_word(&mut self, forward: bool) -> String {
+        let char_count = self.chars_to_word_boundary(self.cursor_byte_index(), forward);
+        if !forward && char_count > 0 {
+            let word_start = self.cursor_moved_by_char(-(char_count as isize));
+            self.set_cursor_byte_index(word_start, false);
+        }
+
+        self.delete_at_cursor(char_count)
+    }
+
+    /// Counts the chars from `byte_index` to the end of the next word, or to
+    /// the start of the previous word if not `forward`. Words are runs of
+    /// alphanumeric chars and underscores, and may be on later or earlier
+    /// lines.
+    pub(super) fn chars_to_word_boundary(&self, byte_index: usize, forward: bool) -> usize {
+        let is_word_char = |char: char| char.is_alphanumeric() || char == '_';
+        let mut line_index = self.line_index_for_byte_index(byte_index);
+        let mut char_count = 0;
+        let mut has_seen_word = false;
+
+        loop {
+            let (Some(line_start), Some(line)) = (
+                self.line_start_byte_index(line_index),
+                self.content_copy_line(line_index),
+            ) else {
+                return char_count;
+            };
+
+            // Lines other than the first are searched in full
+            let line_chars: Vec<char> = if forward {
+                let offset = byte_index.saturating_sub(line_start).min(line.len());
+                line[offset..].chars().collect()
+            } else {
+                let offset = (byte_index - line_start).min(line.len());
+                line[..offset].chars().rev().collect()
+            };
+
+            for char in line_chars {
+                if is_word_char(char) {
+                    has_seen_word = true;
+                } else if has_seen_word {
+                    return char_count;
+                }
+                char_count += 1;
+            }
+
+            if forward {
+                line_index += 1;
+            } else if line_index == 0 {
+                return char_count;
+            } else {
+                line_index -= 1;
+            }
+        }
+    }
+
+    fn step_over(&mut self, text: &str) -> bool {
+        let cursor_byte_index = self.cursor_byte_index();
+        if text.is_empty()
+            || self
+                .content_copy_at_byte_index(cursor_byte_index, text.chars().count())
+                .as_deref()
+                != Some(text)
+        {
+            return false;
+        }
+
+        self.set_cursor_byte_index(cursor_byte_index + text.len(), false);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::buffer_with;
+
+    #[test]
+    fn delete_to_line_end_deletes_rest_of_line() {
+        let mut buffer = buffer_with("one two\nthree");
+        buffer.set_cursor_byte_index(3, false);
+
+        assert_eq!(buffer.delete_to_line_end(), " two");
+        assert_eq!(buffer.content_copy_line(0).as_deref(), Some("one\n"));
+    }
+
+    #[test]
+    fn delete_to_line_end_joins_lines_at_line_end() {
+        let mut buffer = buffer_with("one\ntwo");
+        buffer.set_cursor_byte_index(3, false);
+
+        assert_eq!(buffer.delete_to_line_end(), "\n");
+        assert_eq!(buffer.content_copy_line(0).as_deref(), Some("onetwo"));
+    }
+
+    #[test]
+    fn delete_to_line_end_at_end_of_buffer_deletes_nothing() {
+        let mut buffer = buffer_with("one\ntwo");
+
+        assert_eq!(buffer.delete_to_line_end(), "");
+        assert_eq!(buffer.content_byte_length(), 7);
+
+        let mut buffer = buffer_with("one\n");
+        assert_eq!(buffer.delete_to_line_end(), "");
+        assert_eq!(buffer.content_byte_length(), 4);
+    }
+
+    #[test]
+    fn increment_number_keeps_leading_zero_width() {
+        let mut buffer = buffer_with("x = 009;");
+        buffer.set_cursor_byte_index(0, false);
+
+        assert_eq!(buffer.increment_number(1), Some(10));
+        assert_eq!(buffer.content_copy(), "x = 010;");
+        assert_eq!(buffer.cursor_byte_index(), 6);
+    }
+
+    #[test]
+    fn increment_number_includes_a_leading_minus() {
+        let mut buffer = buffer_with("-1");
+        buffer.set_cursor_byte_index(1, false);
+
+        assert_eq!(buffer.increment_number(-1), Some(-2));
+        assert_eq!(buffer.content_copy(), "-2");
+    }
+
+    #[test]
+    fn increment_number_without_a_number_changes_nothing() {
+        let mut buffer = buffer_with("12 apples\npears");
+
+        assert_eq!(buffer.increment_number(1), None);
+        assert_eq!(buffer.content_copy(), "12 apples\npears");
+    }
+
+    #[test]
+    fn inserting_a_pair_then_its_close_steps_over_it() {
+        let mut buffer = buffer_with("f");
+
+        buffer.insert_pair("(", ")");
+        assert_eq!(buffer.content_copy(), "f()");
+        assert_eq!(buffer.cursor_byte_index(), 2);
+
+        assert!(buffer.insert_close(")"));
+        assert_eq!(buffer.content_copy(), "f()");
+        assert_eq!(buffer.cursor_byte_index(), 3);
+    }
+
+    #[test]
+    fn inserting_a_matching_quote_steps_over_it() {
+        let mut buffer = buffer_with("");
+
+        buffer.insert_pair("\"", "\"");
+        buffer.insert_at_cursor("a");
+        buffer.insert_pair("\"", "\"");
+
+        assert_eq!(buffer.content_copy(), "\"a\"");
+        assert_eq!(buffer.cursor_byte_index(), 3);
+    }
+
+    #[test]
+    fn goto_line_clamps_the_column_to_the_line_end() {
+        let mut buffer = buffer_with("one\ntwo three\nfour");
+
+        assert_eq!(buffer.goto_line(1, 3), 7);
+        assert_eq!(buffer.goto_line(0, 50), 3);
+        assert_eq!(buffer.goto_line(2, 50), 18);
+        assert_eq!(buffer.goto_line(50, 1), 15);
+        assert_eq!(buffer.cursor_byte_index(), 15);
+    }
+
+    #[test]
+    fn delete_word_forward_from_the_start_includes_whitespace_before_the_word() {
+        let mut buffer = buffer_with("  one, two\n  three");
+        buffer.set_cursor_byte_index(0, false);
+
+        assert_eq!(buffer.delete_word(true), "  one");
+        assert_eq!(buffer.delete_word(true), ", two");
+        assert_eq!(buffer.delete_word(true), "\n  three");
+        assert_eq!(buffer.content_copy(), "");
+    }
+
+    #[test]
+    fn delete_word_backward_from_the_end_crosses_whitespace_runs() {
+        let mut buffer = buffer_with("one  \n two  ");
+
+        assert_eq!(buffer.delete_word(false), "two  ");
+        assert_eq!(buffer.content_copy(), "one  \n ");
+        assert_eq!(buffer.delete_word(false), "one  \n ");
+        assert_eq!(buffer.content_copy(), "");
+        assert_eq!(buffer.cursor_byte_index(), 0);
+    }
+}
@@ -4,23 +4,84 @@
 //
 // BadRed is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
 
-use std::io::Read;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{Cursor, Read},
+    str::FromStr,
+};
 
 use bad_red_proc_macros::auto_lua;
+use mlua::{FromLua, IntoLua, Lua};
 
-use crate::{file_handle::FileWrite, styling::Styling};
+use crate::{display::width_for, file_handle::FileWrite, styling::Styling};
 
-use super::{content_buffer::ContentBuffer, gap_buffer::GapBuffer, naive_buffer::NaiveBuffer};
+use super::{
+    content_buffer::ContentBuffer, gap_buffer::GapBuffer, naive_buffer::NaiveBuffer,
+    BlockSelection, Highlight, Language, LineEnding, PagedBuffer, SearchState, SyntaxHighlighter,
+};
 
 pub struct EditorBuffer {
     pub content: Box<dyn ContentBuffer>,
 
     pub is_render_dirty: bool,
     pub is_content_dirty: bool,
+    /// Count of changes made to the content, for checking whether the content
+    /// has changed since it was last seen
+    pub revision: u64,
+    /// Hash of the content as last read from or written to its file
+    pub saved_hash: Option<u64>,
 
     pub buffer_type: EditorBufferType,
 
     pub styling: Styling,
+    pub options: BufferOptions,
+
+    pub named_marks: HashMap<char, usize>,
+    /// Saved cursors of inactive panes showing this buffer, by tab page index
+    /// and pane index. Each tab page numbers its panes separately.
+    pub pane_cursors: HashMap<(usize, usize), usize>,
+    /// Byte indices of cursors besides the primary cursor which edits made
+    /// through `insert_at_cursors` and `delete_at_cursors` are also made at,
+    /// kept sorted
+    pub secondary_cursors: Vec<usize>,
+    pub phantoms: Vec<PhantomText>,
+    /// Signs drawn in the sign column, by line index
+    pub signs: HashMap<usize, Sign>,
+    /// Names of the styles whose backgrounds are drawn across whole lines, by
+    /// line index
+    pub line_highlights: HashMap<usize, String>,
+    /// Pending tab stops of the last expanded snippet, with the next stop last
+    pub snippet_stops: Vec<usize>,
+    pub block_selection: Option<BlockSelection>,
+    /// Matches of the search drawn with the search styles, kept up to date as
+    /// the content changes
+    pub search: Option<SearchState>,
+    /// Visual column kept by vertical moves which preserve the visual column,
+    /// so moving through shorter lines returns to the same column. Cleared
+    /// once the cursor is moved any other way.
+    visual_col_index: Option<u16>,
+
+    pub language: Option<Language>,
+    syntax: Option<Box<dyn SyntaxHighlighter>>,
+}
+
+/// Text drawn into a buffer's display at `byte_index` without being part of
+/// the buffer's content.
+#[auto_lua]
+#[derive(Clone, Debug)]
+pub struct PhantomText {
+    pub byte_index: usize,
+    pub text: String,
+    pub style: String,
+}
+
+/// Short glyph drawn in the sign column beside a line, such as a diagnostic
+/// or version control marker.
+#[derive(Clone, Debug)]
+pub struct Sign {
+    pub text: String,
+    pub style: String,
 }
 
 #[auto_lua]
@@ -28,6 +89,8 @@ pub struct EditorBuffer {
 pub enum EditorBufferType {
     Naive,
     Gap,
+    /// Read-only content paged in from a file, see `PagedBuffer`
+    Paged,
 }
 
 impl EditorBuffer {
@@ -36,55 +99,329 @@ impl EditorBuffer {
     }
 
     pub fn new_typed(buffer_type: EditorBufferType) -> Self {
-        let content: Box<dyn ContentBuffer> = match buffer_type {
-            EditorBufferType::Naive => Box::new(NaiveBuffer::new()),
-            EditorBufferType::Gap => Box::new(GapBuffer::new()),
-        };
+        Self::with_content(buffer_type, Self::content_for_type(buffer_type))
+    }
 
+    /// Creates a buffer around existing content, such as a `PagedBuffer`
+    /// which cannot be created empty from a buffer type.
+    pub fn with_content(buffer_type: EditorBufferType, content: Box<dyn ContentBuffer>) -> Self {
         Self {
             content,
             is_render_dirty: false,
             is_content_dirty: false,
+            revision: 0,
+            saved_hash: None,
             buffer_type,
             styling: Styling::new(),
+            options: BufferOptions::default(),
+            named_marks: HashMap::new(),
+            pane_cursors: HashMap::new(),
+            secondary_cursors: vec![],
+            phantoms: vec![],
+            signs: HashMap::new(),
+            line_highlights: HashMap::new(),
+            snippet_stops: vec![],
+            block_selection: None,
+            search: None,
+            visual_col_index: None,
+
+            language: None,
+            syntax: None,
         }
     }
 
-    pub fn set_type(&mut self, buffer_type: EditorBufferType) {
-        if buffer_type == self.buffer_type {
-            return
+    /// Byte indices stored alongside the content which must follow edits
+    fn tracked_indices_mut(&mut self) -> impl Iterator<Item = &mut usize> {
+        self.named_marks
+            .values_mut()
+            .chain(
+                self.phantoms
+                    .iter_mut()
+                    .map(|phantom| &mut phantom.byte_index),
+            )
+            .chain(self.snippet_stops.iter_mut())
+            .chain(self.pane_cursors.values_mut())
+            .chain(self.secondary_cursors.iter_mut())
+    }
+
+    /// Replaces all content of the buffer, keeping the cursor, marks, and
+    /// phantom text at their byte indices clamped to the new content.
+    pub fn replace_content(&mut self, content: String) {
+        let clamp = |byte_index: usize| {
+            let mut byte_index = byte_index.min(content.len());
+            while !content.is_char_boundary(byte_index) {
+                byte_index -= 1;
+            }
+            byte_index
+        };
+
+        let cursor_byte_index = clamp(self.cursor_byte_index());
+        for tracked_index in self.tracked_indices_mut() {
+            *tracked_index = clamp(*tracked_index);
         }
 
-        match buffer_type {
-            EditorBufferType::Naive => {
-                let mut new_buffer = NaiveBuffer::new();
-                let content = self.content.content_copy();
-                new_buffer.populate_from_string(content);
-                self.content = Box::new(new_buffer);
-            },
-            EditorBufferType::Gap => {
-                let mut new_buffer = GapBuffer::new();
-                let content = self.content.content_copy();
-                new_buffer.populate_from_vec(content.as_bytes());
-                self.content = Box::new(new_buffer);
-            },
+        self.populate_from_string(content);
+        self.set_cursor_byte_index(cursor_byte_index, false);
+    }
+
+    /// Replaces all content of the buffer with that of `file` as
+    /// `populate_from_file` does, clamping the cursor, marks, and phantom text
+    /// to the new content like `replace_content`.
+    pub fn replace_content_from_file(&mut self, file: File) -> std::io::Result<()> {
+        let cursor_byte_index = self.cursor_byte_index();
+        self.populate_from_file(file)?;
+
+        let tracked_indices: Vec<usize> = self.tracked_indices_mut().map(|index| *index).collect();
+        let clamped_indices: Vec<usize> = tracked_indices
+            .into_iter()
+            .map(|byte_index| self.char_boundary_at_or_before(byte_index))
+            .collect();
+        for (tracked_index, clamped_index) in self.tracked_indices_mut().zip(clamped_indices) {
+            *tracked_index = clamped_index;
         }
 
+        let cursor_byte_index = self.char_boundary_at_or_before(cursor_byte_index);
+        self.set_cursor_byte_index(cursor_byte_index, false);
+
+        Ok(())
+    }
+
+    /// Whether the content differs from when it was last read from or written
+    /// to its file. Unlike `is_content_dirty`, edits which are undone back to
+    /// the file's content are not modifications. Buffers which have never been
+    /// read from or written to a file are always modified.
+    pub fn is_modified_from_disk(&self) -> bool {
+        self.saved_hash != Some(self.content_hash())
+    }
+
+    /// Moves the content into a content buffer of `buffer_type`, keeping the
+    /// cursor's byte index. Paged buffers hold files too large to copy into
+    /// memory, so buffers are never changed to or from paged. Returns whether
+    /// the buffer has `buffer_type`.
+    pub fn set_type(&mut self, buffer_type: EditorBufferType) -> bool {
+        if buffer_type == self.buffer_type {
+            return true;
+        }
+        if buffer_type == EditorBufferType::Paged || self.buffer_type == EditorBufferType::Paged {
+            return false;
+        }
+
+        let cursor_byte_index = self.content.cursor_byte_index();
+
+        let mut new_buffer = Self::content_for_type(buffer_type);
+        new_buffer.populate_from_string(self.content.content_copy());
+        new_buffer.set_cursor_byte_index(cursor_byte_index, false);
+        self.content = new_buffer;
+
         self.buffer_type = buffer_type;
+        true
+    }
+
+    /// Sets the language used to highlight this buffer. Buffers with no
+    /// language are styled by their regex `styling` instead.
+    pub fn set_language(&mut self, language: Option<Language>) {
+        self.language = language;
+        self.syntax = language.map(|language| language.highlighter());
+        self.is_render_dirty = true;
+    }
+
+    /// Returns the syntax highlights on lines `start_line` through `end_line`
+    /// inclusive, or `None` if this buffer has no language set.
+    pub fn highlights(&self, start_line: usize, end_line: usize) -> Option<Vec<Highlight>> {
+        self.syntax
+            .as_ref()
+            .map(|syntax| syntax.highlights(self.content.as_ref(), start_line, end_line))
+    }
+
+    fn invalidate_syntax_from_line(&mut self, line_index: usize) {
+        if let Some(syntax) = &mut self.syntax {
+            syntax.invalidate_from_line(line_index);
+        }
+    }
+
+    fn refresh_search(&mut self) {
+        if let Some(search) = &mut self.search {
+            search.refresh(self.content.as_ref());
+        }
+    }
+
+    /// Marks the content as matching its file after being read from it
+    fn mark_populated_from_file(&mut self) {
+        self.is_content_dirty = false;
+        self.is_render_dirty = true;
+        self.revision += 1;
+        self.invalidate_syntax_from_line(0);
+        self.refresh_search();
+        self.saved_hash = Some(self.content.content_hash());
+    }
+
+    fn refresh_search_edited(&mut self, start: usize, old_end: usize, new_end: usize) {
+        if let Some(search) = &mut self.search {
+            search.refresh_edited(self.content.as_ref(), start, old_end, new_end);
+        }
+    }
+
+    pub fn visual_column_at(&self, byte_index: usize, tab_width: u16) -> u16 {
+        let line_index = self.line_index_for_byte_index(byte_index);
+        let line_start = self.line_start_byte_index(line_index).unwrap_or(0);
+        let line_content = self.content_copy_line(line_index).unwrap_or_default();
+
+        let mut column = 0;
+        for (char_index, char) in line_content.char_indices() {
+            if line_start + char_index >= byte_index {
+                break;
+            }
+
+            column += width_for(char, column, tab_width) as u16;
+        }
+
+        column
+    }
+
+    /// Byte index of the char on `line_index` which covers the visual
+    /// `column`, or of the end of the line before its newline if the line is
+    /// not that wide.
+    pub fn byte_index_at_visual_column(
+        &self,
+        line_index: usize,
+        column: u16,
+        tab_width: u16,
+    ) -> Option<usize> {
+        let line_start = self.line_start_byte_index(line_index)?;
+        let line_end = self.line_end_byte_index(line_index)?;
+        let line_content = self.content_copy_line(line_index)?;
+
+        let mut char_column = 0;
+        for (char_index, char) in line_content[..line_end - line_start].char_indices() {
+            char_column += width_for(char, char_column, tab_width) as u16;
+            if char_column > column {
+                return Some(line_start + char_index);
+            }
+        }
+
+        Some(line_end)
+    }
+
+    /// Moves the cursor `line_delta` lines down, or up if negative, landing as
+    /// close as possible to the cursor's visual column rather than its char
+    /// column. Lines are clamped to the content. Returns the new cursor byte
+    /// index.
+    pub fn move_cursor_line_visual(&mut self, line_delta: isize, tab_width: u16) -> usize {
+        let column = self
+            .visual_col_index
+            .unwrap_or_else(|| self.visual_column_at(self.cursor_byte_index(), tab_width));
+        let line_index = self
+            .cursor_line_index()
+            .saturating_add_signed(line_delta)
+            .min(self.content_line_count().saturating_sub(1));
+
+        if let Some(byte_index) = self.byte_index_at_visual_column(line_index, column, tab_width) {
+            self.is_render_dirty = true;
+            self.content.set_cursor_byte_index(byte_index, false);
+            self.visual_col_index = Some(column);
+        }
+
+        self.cursor_byte_index()
+    }
+
+    /// Replaces the tabs in `content` with the spaces they would take up if
+    /// `content` were inserted at the cursor.
+    pub fn expand_tabs(&self, content: &str, tab_width: u16) -> String {
+        let mut column = self.visual_column_at(self.cursor_byte_index(), tab_width);
+        let mut expanded = String::with_capacity(content.len());
+        for char in content.chars() {
+            match char {
+                '\t' => {
+                    let spaces = width_for(char, column, tab_width);
+                    expanded.push_str(&" ".repeat(spaces));
+                    column += spaces as u16;
+                }
+                '\n' => {
+                    expanded.push(char);
+                    column = 0;
+                }
+                _ => {
+                    expanded.push(char);
+                    column += width_for(char, column, tab_width) as u16;
+                }
+            }
+        }
+
+        expanded
+    }
+
+    fn content_for_type(buffer_type: EditorBufferType) -> Box<dyn ContentBuffer> {
+        match buffer_type {
+            EditorBufferType::Naive => Box::new(NaiveBuffer::new()),
+            EditorBufferType::Gap => Box::new(GapBuffer::new()),
+            EditorBufferType::Paged => Box::new(
+                PagedBuffer::new(Box::new(Cursor::new(vec![])), PagedBuffer::DEFAULT_WINDOW_SIZE)
+                    .expect("Failed to index empty content"),
+            ),
+        }
     }
 }
 
 impl ContentBuffer for EditorBuffer {
     fn insert_at_cursor(&mut self, content: &str) {
+        if self.content.is_read_only() {
+            return;
+        }
+
         self.is_render_dirty = true;
         self.is_content_dirty = true;
+        self.revision += 1;
+
+        self.invalidate_syntax_from_line(self.content.cursor_line_index());
+        self.visual_col_index = None;
+
+        let cursor_byte_index = self.content.cursor_byte_index();
+        for mark in self.tracked_indices_mut() {
+            if *mark >= cursor_byte_index {
+                *mark += content.len();
+            }
+        }
+
         self.content.insert_at_cursor(content);
+        self.refresh_search_edited(
+            cursor_byte_index,
+            cursor_byte_index,
+            cursor_byte_index + content.len(),
+        );
     }
 
     fn delete_at_cursor(&mut self, char_count: usize) -> String {
+        if self.content.is_read_only() {
+            return String::new();
+        }
+
         self.is_render_dirty = true;
         self.is_content_dirty = true;
-        self.content.delete_at_cursor(char_count)
+        self.revision += 1;
+
+        self.invalidate_syntax_from_line(self.content.cursor_line_index());
+        self.visual_col_index = None;
+
+        let cursor_byte_index = self.content.cursor_byte_index();
+        let deleted = self.content.delete_at_cursor(char_count);
+        for mark in self.tracked_indices_mut() {
+            if *mark >= cursor_byte_index + deleted.len() {
+                *mark -= deleted.len();
+            } else if *mark > cursor_byte_index {
+                *mark = cursor_byte_index;
+            }
+        }
+        self.refresh_search_edited(
+            cursor_byte_index,
+            cursor_byte_index + deleted.len(),
+            cursor_byte_index,
+        );
+
+        deleted
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.content.is_read_only()
     }
 
     fn chars(&self) -> Box<dyn Iterator<Item = char> + '_> {
@@ -107,6 +444,10 @@ impl ContentBuffer for EditorBuffer {
         self.content.content_copy()
     }
 
+    fn content_hash(&self) -> u64 {
+        self.content.content_hash()
+    }
+
     fn content_copy_at_byte_index(&self, byte_index: usize, length: usize) -> Option<String> {
         self.content.content_copy_at_byte_index(byte_index, length)
     }
@@ -116,11 +457,16 @@ impl ContentBuffer for EditorBuffer {
     }
 
     fn set_cursor_byte_index(&mut self, index: usize, keep_col_index: bool) {
+        if !keep_col_index {
+            self.visual_col_index = None;
+        }
+
         self.content.set_cursor_byte_index(index, keep_col_index);
     }
 
     fn set_cursor_line_index(&mut self, index: usize) {
         self.is_render_dirty = true;
+        self.visual_col_index = None;
 
         self.content.set_cursor_line_index(index);
     }
@@ -145,6 +491,10 @@ impl ContentBuffer for EditorBuffer {
         self.content.line_end_byte_index(line_index)
     }
 
+    fn line_count_in_byte_range(&self, range: std::ops::Range<usize>) -> usize {
+        self.content.line_count_in_byte_range(range)
+    }
+
     fn cursor_moved_by_char(&self, char_count: isize) -> usize {
         self.content.cursor_moved_by_char(char_count)
     }
@@ -153,16 +503,370 @@ impl ContentBuffer for EditorBuffer {
         self.content.index_moved_by_char(start_byte_index, char_count)
     }
 
-    fn populate_from_read(&mut self, read: &mut dyn Read) -> std::io::Result<()> {
+    fn populate_from_string(&mut self, content: String) {
         self.is_content_dirty = false;
         self.is_render_dirty = true;
+        self.revision += 1;
+        self.invalidate_syntax_from_line(0);
+
+        self.content.populate_from_string(content);
+        self.refresh_search();
+    }
+
+    fn populate_from_read(&mut self, read: &mut dyn Read) -> std::io::Result<()> {
+        // Content which fails to read is left as it was
+        self.content.populate_from_read(read)?;
+        self.mark_populated_from_file();
 
-        self.content.populate_from_read(read)
+        Ok(())
+    }
+
+    fn populate_from_file(&mut self, file: File) -> std::io::Result<()> {
+        self.content.populate_from_file(file)?;
+        self.mark_populated_from_file();
+
+        Ok(())
     }
 
     fn flush_to_write(&mut self, write: &mut dyn FileWrite) -> std::io::Result<()> {
         self.is_content_dirty = false;
 
-        self.content.flush_to_write(write)
+        self.content.flush_to_write(write)?;
+        self.saved_hash = Some(self.content.content_hash());
+
+        Ok(())
+    }
+
+    fn read_error(&self) -> Option<String> {
+        self.content.read_error()
+    }
+}
+
+/// Options set on a single buffer. Options which are `None` fall back to the
+/// editor-wide option, or to leaving the content as is if there is none.
+#[auto_lua]
+#[derive(Clone, Default)]
+pub struct BufferOptions {
+    pub tab_width: Option<u16>,
+    pub expand_tab: Option<bool>,
+    /// Line ending the content is converted to when written to its file
+    pub line_ending: Option<LineEnding>,
+    pub final_newline: Option<bool>,
+    /// Remove whitespace from the end of each line when written to its file
+    pub trim_trailing_whitespace: Option<bool>,
+}
+
+impl BufferOptions {
+    pub fn update(&mut self, update_list: BufferOptionList) {
+        for update in update_list.0 {
+            match update {
+                BufferOptionType::TabWidth(new_width) => self.tab_width = new_width,
+                BufferOptionType::ExpandTab(expand_tab) => self.expand_tab = expand_tab,
+                BufferOptionType::LineEnding(line_ending) => self.line_ending = line_ending,
+                BufferOptionType::FinalNewline(final_newline) => {
+                    self.final_newline = final_newline
+                }
+                BufferOptionType::TrimTrailingWhitespace(trim_trailing_whitespace) => {
+                    self.trim_trailing_whitespace = trim_trailing_whitespace
+                }
+            }
+        }
+    }
+
+    /// Sets each option which is set in `other`, keeping the rest as they are
+    pub fn merge(&mut self, other: BufferOptions) {
+        self.tab_width = other.tab_width.or(self.tab_width);
+        self.expand_tab = other.expand_tab.or(self.expand_tab);
+        self.line_ending = other.line_ending.or(self.line_ending);
+        self.final_newline = other.final_newline.or(self.final_newline);
+        self.trim_trailing_whitespace = other
+            .trim_trailing_whitespace
+            .or(self.trim_trailing_whitespace);
+    }
+}
+
+#[auto_lua]
+pub enum BufferOptionType {
+    TabWidth(Option<u16>),
+    ExpandTab(Option<bool>),
+    LineEnding(Option<LineEnding>),
+    FinalNewline(Option<bool>),
+    TrimTrailingWhitespace(Option<bool>),
+}
+
+/// Tab width given by a Lua value, if it is a whole number of columns which
+/// is at least 1 and fits in a `u16`
+pub fn tab_width_from_lua(value: &mlua::Value) -> Option<u16> {
+    value
+        .as_u32()
+        .and_then(|width| u16::try_from(width).ok())
+        .filter(|&width| width > 0)
+}
+
+/// List of buffer-local option updates. A value of `"unset"` for an option
+/// clears the buffer-local value so that the editor-wide option is used. For
+/// options which are not booleans, `false` also clears the value.
+pub struct BufferOptionList(Vec<BufferOptionType>);
+
+impl<'lua> FromLua<'lua> for BufferOptionList {
+    fn from_lua(value: mlua::Value<'lua>, lua: &'lua Lua) -> mlua::Result<Self> {
+        let mut option_list = vec![];
+
+        for pair in value
+            .as_table()
+            .ok_or_else(|| mlua::Error::FromLuaConversionError {
+                from: "Value",
+                to: "BufferOptionList",
+                message: Some(
+                    "Expected lua table as representation of BufferOptionList".to_string(),
+                ),
+            })?
+            .clone()
+            .pairs::<mlua::Value, mlua::Value>()
+        {
+            let (option_key, option_value) = pair?;
+            let Some(key_str) = option_key.as_str() else {
+                continue;
+            };
+            let Ok(key) = BufferOptionTypeName::from_str(key_str) else {
+                continue;
+            };
+            if option_value.as_str() == Some("unset") {
+                option_list.push(match key {
+                    BufferOptionTypeName::TabWidth => BufferOptionType::TabWidth(None),
+                    BufferOptionTypeName::ExpandTab => BufferOptionType::ExpandTab(None),
+                    BufferOptionTypeName::LineEnding => BufferOptionType::LineEnding(None),
+                    BufferOptionTypeName::FinalNewline => BufferOptionType::FinalNewline(None),
+                    BufferOptionTypeName::TrimTrailingWhitespace => {
+                        BufferOptionType::TrimTrailingWhitespace(None)
+                    }
+                });
+                continue;
+            }
+
+            match key {
+                BufferOptionTypeName::TabWidth => {
+                    let value = match option_value {
+                        mlua::Value::Boolean(false) => None,
+                        value => {
+                            let Some(value) = tab_width_from_lua(&value) else {
+                                continue;
+                            };
+                            Some(value)
+                        }
+                    };
+
+                    option_list.push(BufferOptionType::TabWidth(value));
+                }
+                BufferOptionTypeName::ExpandTab => {
+                    let Some(value) = option_value.as_boolean() else {
+                        continue;
+                    };
+
+                    option_list.push(BufferOptionType::ExpandTab(Some(value)));
+                }
+                BufferOptionTypeName::LineEnding => {
+                    let value = match option_value {
+                        mlua::Value::Boolean(false) => None,
+                        value => {
+                            let Ok(value) = LineEnding::from_lua(value, lua) else {
+                                continue;
+                            };
+                            Some(value)
+                        }
+                    };
+
+                    option_list.push(BufferOptionType::LineEnding(value));
+                }
+                BufferOptionTypeName::FinalNewline => {
+                    let Some(value) = option_value.as_boolean() else {
+                        continue;
+                    };
+
+                    option_list.push(BufferOptionType::FinalNewline(Some(value)));
+                }
+                BufferOptionTypeName::TrimTrailingWhitespace => {
+                    let Some(value) = option_value.as_boolean() else {
+                        continue;
+                    };
+
+                    option_list.push(BufferOptionType::TrimTrailingWhitespace(Some(value)));
+                }
+            }
+        }
+
+        Ok(BufferOptionList(option_list))
+    }
+}
+
+impl<'lua> IntoLua<'lua> for BufferOptionList {
+    fn into_lua(self, lua: &'lua Lua) -> mlua::Result<mlua::Value<'lua>> {
+        let table = lua.create_table()?;
+        for item in self.0 {
+            match item {
+                BufferOptionType::TabWidth(width) => match width {
+                    Some(width) => table.set(BufferOptionTypeName::TabWidth, width)?,
+                    None => table.set(BufferOptionTypeName::TabWidth, false)?,
+                },
+                BufferOptionType::ExpandTab(expand_tab) => match expand_tab {
+                    Some(expand_tab) => table.set(BufferOptionTypeName::ExpandTab, expand_tab)?,
+                    None => table.set(BufferOptionTypeName::ExpandTab, "unset")?,
+                },
+                BufferOptionType::LineEnding(line_ending) => match line_ending {
+                    Some(line_ending) => {
+                        table.set(BufferOptionTypeName::LineEnding, line_ending)?
+                    }
+                    None => table.set(BufferOptionTypeName::LineEnding, false)?,
+                },
+                BufferOptionType::FinalNewline(final_newline) => match final_newline {
+                    Some(final_newline) => {
+                        table.set(BufferOptionTypeName::FinalNewline, final_newline)?
+                    }
+                    None => table.set(BufferOptionTypeName::FinalNewline, "unset")?,
+                },
+                BufferOptionType::TrimTrailingWhitespace(trim) => match trim {
+                    Some(trim) => table.set(BufferOptionTypeName::TrimTrailingWhitespace, trim)?,
+                    None => table.set(BufferOptionTypeName::TrimTrailingWhitespace, "unset")?,
+                },
+            }
+        }
+
+        table.into_lua(lua)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tab_widths_from_lua_are_positive_and_fit() {
+        assert_eq!(tab_width_from_lua(&mlua::Value::Integer(4)), Some(4));
+        assert_eq!(tab_width_from_lua(&mlua::Value::Integer(0)), None);
+        assert_eq!(tab_width_from_lua(&mlua::Value::Integer(-2)), None);
+        assert_eq!(tab_width_from_lua(&mlua::Value::Integer(65536)), None);
+        assert_eq!(tab_width_from_lua(&mlua::Value::Nil), None);
+    }
+
+    #[test]
+    fn edits_bump_the_revision_and_reads_keep_it() {
+        let mut buffer = EditorBuffer::new();
+        let start = buffer.revision;
+
+        buffer.insert_at_cursor("one");
+        let first = buffer.revision;
+        assert!(first > start);
+
+        buffer.insert_at_cursor(" two");
+        let second = buffer.revision;
+        assert!(second > first);
+
+        let _ = buffer.content_copy();
+        let _ = buffer.content_copy_line(0);
+        let _ = buffer.content_line_count();
+        buffer.set_cursor_byte_index(0, false);
+        assert_eq!(buffer.revision, second);
+
+        buffer.delete_at_cursor(1);
+        assert!(buffer.revision > second);
+    }
+
+    #[test]
+    fn reverting_edits_matches_the_saved_content_again() {
+        let mut buffer = EditorBuffer::new();
+        assert!(buffer.is_modified_from_disk());
+
+        buffer.populate_from_read(&mut "one\ntwo\n".as_bytes()).unwrap();
+        assert!(!buffer.is_modified_from_disk());
+
+        buffer.set_cursor_byte_index(4, false);
+        buffer.insert_at_cursor("x");
+        assert!(buffer.is_modified_from_disk());
+        assert!(buffer.is_content_dirty);
+
+        buffer.set_cursor_byte_index(4, false);
+        buffer.delete_at_cursor(1);
+        assert!(!buffer.is_modified_from_disk());
+        assert!(buffer.is_content_dirty);
+    }
+
+    #[test]
+    fn switching_types_keeps_the_cursor() {
+        let mut buffer = EditorBuffer::new();
+        buffer.insert_at_cursor("one\ntwö\nthree");
+        buffer.set_cursor_byte_index(8, false);
+
+        for buffer_type in [EditorBufferType::Naive, EditorBufferType::Gap] {
+            assert!(buffer.set_type(buffer_type));
+            assert_eq!(buffer.content_copy(), "one\ntwö\nthree");
+            assert_eq!(buffer.cursor_byte_index(), 8);
+            assert_eq!(buffer.cursor_line_index(), 1);
+        }
+
+        assert!(!buffer.set_type(EditorBufferType::Paged));
+    }
+
+    #[test]
+    fn switching_a_populated_buffer_between_naive_and_gap_keeps_its_lines() {
+        let mut buffer = EditorBuffer::new_typed(EditorBufferType::Naive);
+        buffer.populate_from_string("one\ntwö\n\nfour\n".to_string());
+        buffer.set_cursor_byte_index(11, false);
+
+        for buffer_type in [EditorBufferType::Gap, EditorBufferType::Naive] {
+            assert!(buffer.set_type(buffer_type));
+            assert!(buffer.buffer_type == buffer_type);
+            assert_eq!(buffer.content_copy(), "one\ntwö\n\nfour\n");
+            assert_eq!(buffer.content_line_count(), 5);
+            assert_eq!(buffer.line_start_byte_index(3), Some(10));
+            assert_eq!(buffer.cursor_byte_index(), 11);
+            assert_eq!(buffer.cursor_line_index(), 3);
+        }
+    }
+
+    #[test]
+    fn expanded_tabs_fill_to_the_next_tab_stop_from_the_cursor() {
+        let mut buffer = EditorBuffer::new();
+        buffer.insert_at_cursor("ab");
+
+        assert_eq!(buffer.expand_tabs("\t", 4), "  ");
+        assert_eq!(buffer.expand_tabs("\tx\t", 4), "  x   ");
+        assert_eq!(buffer.expand_tabs("\n\t", 4), "\n    ");
+
+        buffer.insert_expanded_at_cursors("\t", 4);
+        assert_eq!(buffer.content_copy(), "ab  ");
+    }
+
+    #[test]
+    fn named_marks_follow_edits_before_them() {
+        let mut buffer = EditorBuffer::new();
+        buffer.insert_at_cursor("one\ntwo");
+        buffer.named_marks.insert('a', 4);
+
+        buffer.set_cursor_byte_index(0, false);
+        buffer.insert_at_cursor("zero\n");
+        assert_eq!(buffer.named_marks.get(&'a'), Some(&9));
+
+        buffer.set_cursor_byte_index(10, false);
+        buffer.insert_at_cursor("x");
+        assert_eq!(buffer.named_marks.get(&'a'), Some(&9));
+
+        buffer.set_cursor_byte_index(8, false);
+        buffer.delete_at_cursor(3);
+        assert_eq!(buffer.named_marks.get(&'a'), Some(&8));
+        assert_eq!(buffer.content_copy(), "zero\nonewo");
+    }
+
+    #[test]
+    fn visual_line_moves_keep_the_column_across_tabs() {
+        let mut buffer = EditorBuffer::new();
+        buffer.insert_at_cursor("\tab\n    cd\n  \tx\nz");
+        buffer.set_cursor_byte_index(1, false);
+        assert_eq!(buffer.visual_column_at(1, 4), 4);
+
+        assert_eq!(buffer.move_cursor_line_visual(1, 4), 8);
+        assert_eq!(buffer.move_cursor_line_visual(1, 4), 14);
+        assert_eq!(buffer.visual_column_at(14, 4), 4);
+        assert_eq!(buffer.move_cursor_line_visual(1, 4), 17);
+        assert_eq!(buffer.move_cursor_line_visual(-3, 4), 1);
     }
 }
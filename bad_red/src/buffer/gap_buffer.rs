@@ -68,7 +68,8 @@ impl GapBuffer {
         char_count
     }
 
-    pub fn populate_from_vec(&mut self, vec: &[u8]) {
+    /// Replaces the content with `vec`, which must already be valid UTF-8
+    fn populate_from_utf8(&mut self, vec: &[u8]) {
         self.underlying_buf = UnderlyingBuf::from(vec);
 
         let mut char_byte_index = 0;
@@ -82,6 +83,26 @@ impl GapBuffer {
         }
 
         self.sorted_newline_indices = newline_indices;
+        self.char_col_index = 0;
+        self.line_index = 0;
+    }
+
+    /// Whether the cached newline indices match the newlines found by
+    /// rescanning the content. The rescan is O(n), so it is only checked
+    /// after each mutation when built with the `test-support` feature.
+    #[cfg(any(test, feature = "test-support"))]
+    pub fn verify_newline_invariant(&self) -> bool {
+        let mut char_byte_index = 0;
+        let mut newline_indices = vec![];
+        for char in self.chars() {
+            if char == '\n' {
+                newline_indices.push(char_byte_index);
+            }
+
+            char_byte_index += char.len_utf8();
+        }
+
+        newline_indices == self.sorted_newline_indices
     }
 }
 
@@ -116,11 +137,17 @@ impl ContentBuffer for GapBuffer {
                 self.char_col_index += 1;
             }
         }
+
+        #[cfg(feature = "test-support")]
+        assert!(
+            self.verify_newline_invariant(),
+            "Newline indices no longer match content after inserting at cursor."
+        );
     }
 
     #[cfg(debug_assertions)]
     fn delete_at_cursor(&mut self, char_count: usize) -> String {
-        use std::{collections::VecDeque, mem};
+        use std::collections::VecDeque;
 
         let cursor_byte_index = self.cursor_byte_index();
 
@@ -148,25 +175,17 @@ impl ContentBuffer for GapBuffer {
             .sorted_newline_indices
             .partition_point(|i| *i < (cursor_byte_index + bytes_to_remove));
 
-        let old_newlines = mem::take(&mut self.sorted_newline_indices);
-
-        let (kept_newlines, removed_newlines): (Vec<_>, Vec<_>) = old_newlines
-            .into_iter()
-            .enumerate()
-            .partition(|(i, newline_index)| {
-                !(first_remove_index <= *i && *i < first_unremove_index)
-            });
-
-        self.sorted_newline_indices = kept_newlines
-            .into_iter()
-            .map(|(_, newline_index)| newline_index)
+        // Removed newlines are drained in place so the remaining indices are
+        // shifted without reallocating the list
+        let mut removed_newlines: VecDeque<_> = self
+            .sorted_newline_indices
+            .drain(first_remove_index..first_unremove_index)
             .collect();
 
         for shifted_newline_index in first_remove_index..self.sorted_newline_indices.len() {
             self.sorted_newline_indices[shifted_newline_index] -= bytes_to_remove;
         }
 
-        let mut removed_newlines = VecDeque::from(removed_newlines);
         let mut removed_bytes = Vec::<u8>::new();
         for i in 0..bytes_to_remove {
             let Some(removed_byte) = self.underlying_buf.pop_after_cursor() else {
@@ -180,7 +199,7 @@ impl ContentBuffer for GapBuffer {
 
             removed_bytes.push(removed_byte);
 
-            if let Some((_, next_newline_index)) = removed_newlines.front() {
+            if let Some(next_newline_index) = removed_newlines.front() {
                 if i + cursor_byte_index == *next_newline_index {
                     assert_eq!(
                         char::try_from(removed_byte).expect("Failed to convert expected newline byte to char"),
@@ -196,6 +215,11 @@ impl ContentBuffer for GapBuffer {
             removed_newlines.is_empty(),
             "Expected all removed newlines to be found while removing bytes from buffer."
         );
+        #[cfg(feature = "test-support")]
+        assert!(
+            self.verify_newline_invariant(),
+            "Newline indices no longer match content after deleting at cursor."
+        );
 
         String::from_utf8(removed_bytes)
             .expect("Expected valid utf-8 string to be removed from buffer. Found: invalid string")
@@ -203,8 +227,6 @@ impl ContentBuffer for GapBuffer {
 
     #[cfg(not(debug_assertions))]
     fn delete_at_cursor(&mut self, char_count: usize) -> String {
-        use std::mem;
-
         let cursor_byte_index = self.cursor_byte_index();
 
         let mut bytes_to_remove = 0;
@@ -228,21 +250,10 @@ impl ContentBuffer for GapBuffer {
             .sorted_newline_indices
             .partition_point(|i| *i < (cursor_byte_index + bytes_to_remove));
 
-        let old_newlines = mem::take(&mut self.sorted_newline_indices);
-
-        let kept_newlines: Vec<_> = old_newlines
-            .into_iter()
-            .enumerate()
-            .filter_map(|(i, newline_index)| {
-                if first_remove_index <= i && i < first_unremove_index {
-                    None
-                } else {
-                    Some(newline_index)
-                }
-            })
-            .collect();
-
-        self.sorted_newline_indices = kept_newlines;
+        // Removed newlines are drained in place so the remaining indices are
+        // shifted without reallocating the list
+        self.sorted_newline_indices
+            .drain(first_remove_index..first_unremove_index);
 
         for shifted_newline_index in first_remove_index..self.sorted_newline_indices.len() {
             self.sorted_newline_indices[shifted_newline_index] -= bytes_to_remove;
@@ -273,21 +284,11 @@ impl ContentBuffer for GapBuffer {
     }
 
     fn content_line_length(&self, line_index: usize) -> Option<usize> {
-        let line_end = self.sorted_newline_indices.get(line_index)?;
-        let line_start = line_index.checked_sub(1)
-            .map(|index| self.sorted_newline_indices.get(index))
-            .flatten()
-            .map(|prev_line_start| prev_line_start + 1)
-            .unwrap_or_else(|| 0);
-
-        let mut char_count = 0;
-        for i in line_start..=*line_end {
-            if let Some(_) = super::expected_byte_length_from_starting(self.underlying_buf[i]) {
-                char_count += 1;
-            }
-        }
+        // Counted between the line's bounding newlines without copying the line
+        let line_start = self.line_start_byte_index(line_index)?;
+        let line_end = self.line_end_byte_index(line_index)?;
 
-        Some(char_count)
+        Some(self.char_count_in(line_start..line_end) as usize)
     }
 
     fn content_copy(&self) -> String {
@@ -296,6 +297,10 @@ impl ContentBuffer for GapBuffer {
         String::from_utf8(utf8_bytes).expect("Found invalid utf8 encoding in GapBuffer")
     }
 
+    fn content_hash(&self) -> u64 {
+        super::fnv_hash(self.underlying_buf.iter())
+    }
+
     fn content_copy_at_byte_index(
         &self,
         mut byte_index: usize,
@@ -320,6 +325,10 @@ impl ContentBuffer for GapBuffer {
     }
 
     fn content_copy_line(&self, line_index: usize) -> Option<String> {
+        if line_index >= self.content_line_count() {
+            return None;
+        }
+
         let mut start_index = line_index
             .checked_sub(1)
             .map(|newline_index| {
@@ -368,7 +377,7 @@ impl ContentBuffer for GapBuffer {
                     self.line_index = lookup_newline_index + 1;
                 }
                 None => {
-                    self.char_col_index = index;
+                    self.char_col_index = self.char_count_in(0..index) as usize;
                     self.line_index = 0;
                 }
             }
@@ -446,94 +455,67 @@ impl ContentBuffer for GapBuffer {
             })
     }
 
-    fn cursor_moved_by_char(&self, mut char_count: isize) -> usize {
-        if char_count == 0 {
-            0
-        } else if char_count < 0 {
-            let precursor_iter = self.underlying_buf.precursor_iter();
-            let mut byte_count = 0;
+    fn line_count_in_byte_range(&self, range: Range<usize>) -> usize {
+        let first_newline = self
+            .sorted_newline_indices
+            .partition_point(|newline| *newline < range.start);
+        let end_newline = self
+            .sorted_newline_indices
+            .partition_point(|newline| *newline < range.end);
 
-            for precursor_byte in precursor_iter {
-                if let Some(_) = super::expected_byte_length_from_starting(*precursor_byte) {
-                    char_count += 1;
-                }
+        end_newline.saturating_sub(first_newline)
+    }
 
-                byte_count += 1;
+    fn cursor_moved_by_char(&self, char_count: isize) -> usize {
+        self.index_moved_by_char(self.underlying_buf.cursor_index(), char_count)
+    }
 
-                if char_count == 0 {
-                    break;
-                }
-            }
+    /// Moves `start_byte_index` by `char_count` chars, clamping to the start
+    /// or end of the content if the move would pass either. A count of zero
+    /// returns `start_byte_index` unchanged.
+    fn index_moved_by_char(&self, start_byte_index: usize, char_count: isize) -> usize {
+        if char_count == 0 {
+            return start_byte_index;
+        }
 
-            self.underlying_buf.cursor_index() - byte_count
-        } else {
-            let postcursor_iter = self.underlying_buf.postcursor_iter();
-            let mut byte_count = 0;
+        let length = self.underlying_buf.len();
+        let is_char_start = |byte_index: usize| {
+            super::expected_byte_length_from_starting(self.underlying_buf[byte_index]).is_some()
+        };
 
-            for postcursor_byte in postcursor_iter {
-                if let Some(_) = super::expected_byte_length_from_starting(*postcursor_byte) {
-                    char_count -= 1;
+        let mut result_byte_index = start_byte_index.min(length);
+        for _ in 0..char_count.unsigned_abs() {
+            if char_count > 0 {
+                if result_byte_index == length {
+                    break;
                 }
 
-                byte_count += 1;
-
-                if char_count == 0 {
+                result_byte_index += 1;
+                while result_byte_index < length && !is_char_start(result_byte_index) {
+                    result_byte_index += 1;
+                }
+            } else {
+                if result_byte_index == 0 {
                     break;
                 }
-            }
-
-            self.underlying_buf.cursor_index() + byte_count
-        }
-    }
-
-    fn index_moved_by_char(&self, start_byte_index: usize, mut char_count: isize) -> usize {
-        let mut result_byte_index = start_byte_index;
 
-        // Need to move all the way to character start char
-        char_count += char_count.signum();
-
-        loop {
-            let Some(byte) = self.underlying_buf.get(result_byte_index) else {
-                let Some(new_result) = result_byte_index.checked_add_signed(char_count.signum())
-                else {
-                    return 0;
-                };
-                result_byte_index = new_result;
-                char_count -= char_count.signum();
-
-                if result_byte_index >= self.underlying_buf.len() {
-                    return self.underlying_buf.len()
-                } else {
-                    continue;
+                result_byte_index -= 1;
+                while result_byte_index > 0 && !is_char_start(result_byte_index) {
+                    result_byte_index -= 1;
                 }
-            };
-
-            if let Some(_) = super::expected_byte_length_from_starting(*byte) {
-                char_count -= char_count.signum();
-            }
-
-            if char_count == 0 {
-                break;
-            }
-
-            let Some(new_result) = result_byte_index.checked_add_signed(char_count.signum()) else {
-                return 0;
-            };
-            result_byte_index = new_result;
-
-            if result_byte_index == self.underlying_buf.len() {
-                return self.underlying_buf.len();
             }
         }
 
         result_byte_index
     }
 
-    fn populate_from_read(&mut self, read: &mut dyn std::io::prelude::Read) -> std::io::Result<()> {
-        let mut string = String::new();
-        read.read_to_string(&mut string)?;
+    fn populate_from_string(&mut self, content: String) {
+        self.populate_from_utf8(content.as_bytes());
+    }
 
-        self.populate_from_vec(string.as_bytes());
+    fn populate_from_read(&mut self, read: &mut dyn std::io::prelude::Read) -> std::io::Result<()> {
+        let content = super::read_utf8(read)?;
+        self.populate_from_string(content);
 
         Ok(())
     }
@@ -547,3 +529,62 @@ impl ContentBuffer for GapBuffer {
         write.write_file(write_buffer.as_slice())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn newline_indices_follow_edits() {
+        let mut buffer = GapBuffer::new();
+        buffer.insert_at_cursor("ü\nb\n\nc");
+        assert!(buffer.verify_newline_invariant());
+
+        buffer.set_cursor_byte_index(2, false);
+        buffer.insert_at_cursor("x\ny");
+        assert!(buffer.verify_newline_invariant());
+
+        buffer.set_cursor_byte_index(0, false);
+        buffer.delete_at_cursor(4);
+        assert!(buffer.verify_newline_invariant());
+        assert_eq!(buffer.content_copy(), "\nb\n\nc");
+
+        buffer.populate_from_string("one\ntwo\n".to_string());
+        assert!(buffer.verify_newline_invariant());
+    }
+
+    #[test]
+    fn stale_newline_indices_fail_the_check() {
+        let mut buffer = GapBuffer::new();
+        buffer.insert_at_cursor("a\nb");
+        buffer.sorted_newline_indices = vec![2];
+
+        assert!(!buffer.verify_newline_invariant());
+    }
+
+    #[test]
+    fn deleting_across_many_lines_keeps_the_newline_allocation() {
+        let content: String = (0..10_000).map(|line| format!("line {}\n", line)).collect();
+        let mut buffer = GapBuffer::new();
+        buffer.populate_from_string(content.clone());
+        let indices_pointer = buffer.sorted_newline_indices.as_ptr();
+        let indices_capacity = buffer.sorted_newline_indices.capacity();
+
+        // Delete lines 5000 through 5099 along with their newlines
+        let delete_start = content.match_indices('\n').nth(4999).unwrap().0 + 1;
+        let delete_end = content.match_indices('\n').nth(5099).unwrap().0 + 1;
+        buffer.set_cursor_byte_index(delete_start, false);
+        let deleted = buffer.delete_at_cursor(delete_end - delete_start);
+
+        assert_eq!(deleted, content[delete_start..delete_end]);
+        assert_eq!(buffer.sorted_newline_indices.as_ptr(), indices_pointer);
+        assert_eq!(buffer.sorted_newline_indices.capacity(), indices_capacity);
+        assert_eq!(buffer.sorted_newline_indices.len(), 9_900);
+        assert!(buffer.verify_newline_invariant());
+
+        let mut expected = content.clone();
+        expected.replace_range(delete_start..delete_end, "");
+        assert_eq!(buffer.content_copy(), expected);
+        assert_eq!(buffer.line_start_byte_index(5000), Some(delete_start));
+    }
+}
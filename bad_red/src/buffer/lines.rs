@@ -0,0 +1,481 @@
+// This file is part of BadRed.
+
+// BadRed is free software: you can redistribute it and/or modify it under the terms of the GNU General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// BadRed is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+use bad_red_proc_macros::auto_lua;
+
+use super::{ContentBuffer, EditorBuffer};
+
+/// Convention for the bytes which end each line
+#[auto_lua]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LineEnding {
+    /// `\n`
+    Lf,
+    /// `\r\n`
+    CrLf,
+}
+
+impl EditorBuffer {
+    /// Comments out lines `start_line` through `end_line` inclusive by placing
+    /// `comment_prefix` after each line's indentation, or uncomments them if
+    /// every non-blank line in the range is already commented.
+    pub fn toggle_comment(&mut self, start_line: usize, end_line: usize, comment_prefix: &str) {
+        let lines = self.lines_in_range(start_line, end_line);
+        let indent_length = |line: &str| line.len() - line.trim_start().len();
+
+        let is_commented = lines
+            .iter()
+            .filter(|line| !line.trim().is_empty())
+            .all(|line| line[indent_length(line)..].starts_with(comment_prefix));
+
+        let toggled: Vec<String> = lines
+            .iter()
+            .map(|line| {
+                if line.trim().is_empty() {
+                    return line.clone();
+                }
+
+                let (indent, rest) = line.split_at(indent_length(line));
+                if is_commented {
+                    format!("{}{}", indent, &rest[comment_prefix.len()..])
+                } else {
+                    format!("{}{}{}", indent, comment_prefix, rest)
+                }
+            })
+            .collect();
+
+        self.replace_lines_in_range(start_line, &lines, &toggled);
+    }
+
+    /// Sorts lines `start_line` through `end_line` inclusive. Numeric sorts
+    /// order lines by their leading number, placing lines without one first.
+    pub fn sort_lines(&mut self, start_line: usize, end_line: usize, reverse: bool, numeric: bool) {
+        let lines = self.lines_in_range(start_line, end_line);
+        let mut sorted = lines.clone();
+
+        if numeric {
+            sorted.sort_by(|lhs, rhs| {
+                leading_number(lhs)
+                    .partial_cmp(&leading_number(rhs))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+        } else {
+            sorted.sort();
+        }
+
+        if reverse {
+            sorted.reverse();
+        }
+
+        self.replace_lines_in_range(start_line, &lines, &sorted);
+    }
+
+    /// Removes consecutive duplicate lines within lines `start_line` through
+    /// `end_line` inclusive, returning the number of lines removed.
+    pub fn unique_lines(&mut self, start_line: usize, end_line: usize) -> usize {
+        let lines = self.lines_in_range(start_line, end_line);
+        let mut unique = lines.clone();
+        unique.dedup();
+
+        self.replace_lines_in_range(start_line, &lines, &unique);
+
+        lines.len() - unique.len()
+    }
+
+    /// Reverses the order of lines `start_line` through `end_line` inclusive
+    pub fn reverse_lines(&mut self, start_line: usize, end_line: usize) {
+        let lines = self.lines_in_range(start_line, end_line);
+        let mut reversed = lines.clone();
+        reversed.reverse();
+
+        self.replace_lines_in_range(start_line, &lines, &reversed);
+    }
+
+    /// Re-wraps the paragraph of non-blank lines containing `line_index` so
+    /// each line is at most `width` columns wide, filling each line with as
+    /// many words as fit. Every line is indented like the paragraph's first
+    /// line, and words wider than `width` are placed alone on their line.
+    /// Returns the number of lines in the reflowed paragraph, or `None` if
+    /// `line_index` is blank or past the end of the content.
+    pub fn reflow(&mut self, line_index: usize, width: usize, tab_width: usize) -> Option<usize> {
+        let line_count = self.content_line_count();
+        let is_blank = |line_index: usize| {
+            (line_index < line_count)
+                .then(|| self.content_copy_line(line_index))
+                .flatten()
+                .map(|line| line.trim().is_empty())
+        };
+        if is_blank(line_index)? {
+            return None;
+        }
+
+        let mut first_line = line_index;
+        while first_line > 0 && is_blank(first_line - 1) == Some(false) {
+            first_line -= 1;
+        }
+        let mut last_line = line_index;
+        while is_blank(last_line + 1) == Some(false) {
+            last_line += 1;
+        }
+
+        let lines = self.lines_in_range(first_line, last_line);
+        let indent = &lines[0][..lines[0].len() - lines[0].trim_start().len()];
+        let indent_width = self.indent_of_line(first_line, tab_width)?;
+        // Keep carriage returns ending each line of `\r\n` content
+        let line_suffix = if lines[0].ends_with('\r') { "\r" } else { "" };
+
+        let mut reflowed = vec![];
+        let mut line = indent.to_string();
+        let mut line_width = indent_width;
+        for word in lines.iter().flat_map(|line| line.split_whitespace()) {
+            let word_width = word.chars().count();
+            let is_line_empty = line.len() == indent.len();
+
+            if !is_line_empty && line_width + 1 + word_width > width {
+                line.push_str(line_suffix);
+                reflowed.push(std::mem::replace(&mut line, indent.to_string()));
+                line_width = indent_width;
+            } else if !is_line_empty {
+                line.push(' ');
+                line_width += 1;
+            }
+
+            line.push_str(word);
+            line_width += word_width;
+        }
+        line.push_str(line_suffix);
+        reflowed.push(line);
+
+        let reflowed_count = reflowed.len();
+        self.replace_lines_in_range(first_line, &lines, &reflowed);
+
+        Some(reflowed_count)
+    }
+
+    /// Inserts `text` as whole lines above or below the cursor's line, adding
+    /// a newline to the end of `text` if it has none. Leaves the cursor at the
+    /// start of the first pasted line.
+    pub fn paste_lines(&mut self, text: &str, below: bool) {
+        let mut block = text.to_string();
+        if !block.ends_with('\n') {
+            block.push('\n');
+        }
+
+        let line_index = self.cursor_line_index();
+        let insert_line_index = if below { line_index + 1 } else { line_index };
+        let paste_byte_index = match self.line_start_byte_index(insert_line_index) {
+            Some(line_start) => line_start,
+            None => {
+                // Pasting below an unterminated final line first ends that line
+                self.set_cursor_byte_index(self.content_byte_length(), false);
+                self.insert_at_cursor("\n");
+                self.content_byte_length()
+            }
+        };
+
+        self.set_cursor_byte_index(paste_byte_index, false);
+        self.insert_at_cursor(&block);
+        self.set_cursor_byte_index(paste_byte_index, false);
+    }
+
+    /// Converts every line ending in the buffer to `to`, keeping the cursor at
+    /// the same column of its line. Carriage returns which do not end a line
+    /// are left as is. Returns the number of line endings converted.
+    pub fn normalize_newlines(&mut self, to: LineEnding) -> usize {
+        let content = self.content_copy();
+        let bytes = content.as_bytes();
+
+        // Byte index of the `\r` to remove or of the `\n` to place a `\r`
+        // before, for each line ending to convert
+        let convert_indices: Vec<usize> = content
+            .match_indices('\n')
+            .filter_map(|(newline_index, _)| {
+                let has_return = newline_index > 0 && bytes[newline_index - 1] == b'\r';
+                match (to, has_return) {
+                    (LineEnding::Lf, true) => Some(newline_index - 1),
+                    (LineEnding::CrLf, false) => Some(newline_index),
+                    _ => None,
+                }
+            })
+            .collect();
+
+        // Converting from the end keeps the indices of earlier line endings
+        // valid, while marks and other tracked indices only shift past each
+        // converted line ending
+        let mut cursor_byte_index = self.cursor_byte_index();
+        for byte_index in convert_indices.iter().rev().copied() {
+            self.set_cursor_byte_index(byte_index, false);
+            match to {
+                LineEnding::Lf => {
+                    self.delete_at_cursor(1);
+                    if byte_index < cursor_byte_index {
+                        cursor_byte_index -= 1;
+                    }
+                }
+                LineEnding::CrLf => {
+                    self.insert_at_cursor("\r");
+                    if byte_index < cursor_byte_index {
+                        cursor_byte_index += 1;
+                    }
+                }
+            }
+        }
+        self.set_cursor_byte_index(cursor_byte_index, false);
+
+        convert_indices.len()
+    }
+
+    /// Removes whitespace from the end of every line, keeping the carriage
+    /// returns which end lines of `\r\n` content. Returns the number of lines
+    /// changed.
+    pub fn trim_trailing_whitespace(&mut self) -> usize {
+        let lines = self.lines_in_range(0, self.content_line_count());
+        let trimmed: Vec<String> = lines
+            .iter()
+            .map(|line| match line.strip_suffix('\r') {
+                Some(line) => format!("{}\r", line.trim_end()),
+                None => line.trim_end().to_string(),
+            })
+            .collect();
+
+        let changed_count = lines
+            .iter()
+            .zip(&trimmed)
+            .filter(|(line, trimmed)| line != trimmed)
+            .count();
+        self.replace_lines_in_range(0, &lines, &trimmed);
+
+        changed_count
+    }
+
+    /// Copies lines `start_line` through `end_line` inclusive without their
+    /// newlines, clamped to the lines in the buffer.
+    fn lines_in_range(&self, start_line: usize, end_line: usize) -> Vec<String> {
+        let end_line = end_line.min(self.content_line_count().saturating_sub(1));
+
+        (start_line..=end_line)
+            .filter_map(|line_index| self.content_copy_line(line_index))
+            .map(|line| line.strip_suffix('\n').map(str::to_string).unwrap_or(line))
+            .collect()
+    }
+
+    /// Replaces the lines `old_lines` starting at `start_line` with
+    /// `new_lines`. The newline ending the last line of the range is kept as
+    /// is, so an unterminated final line stays unterminated.
+    fn replace_lines_in_range(&mut self, start_line: usize, old_lines: &[String], new_lines: &[String]) {
+        if old_lines == new_lines {
+            return;
+        }
+        let Some(start_byte_index) = self.line_start_byte_index(start_line) else {
+            return;
+        };
+
+        let cursor_byte_index = self.cursor_byte_index();
+        let old_char_count = old_lines.join("\n").chars().count();
+
+        self.set_cursor_byte_index(start_byte_index, false);
+        self.delete_at_cursor(old_char_count);
+        self.insert_at_cursor(&new_lines.join("\n"));
+
+        let cursor_byte_index = self.char_boundary_at_or_before(cursor_byte_index);
+        self.set_cursor_byte_index(cursor_byte_index, false);
+    }
+
+    /// Clamps `byte_index` to the content and moves it back to the start of
+    /// the char it falls within.
+    pub(super) fn char_boundary_at_or_before(&self, byte_index: usize) -> usize {
+        let byte_index = byte_index.min(self.content_byte_length());
+        if byte_index == self.content_byte_length() {
+            return byte_index;
+        }
+
+        let line_index = self.line_index_for_byte_index(byte_index);
+        let (Some(line_start), Some(line)) = (
+            self.line_start_byte_index(line_index),
+            self.content_copy_line(line_index),
+        ) else {
+            return byte_index;
+        };
+
+        line.char_indices()
+            .map(|(char_index, _)| line_start + char_index)
+            .take_while(|char_byte_index| *char_byte_index <= byte_index)
+            .last()
+            .unwrap_or(line_start)
+    }
+}
+
+/// Parses the number at the start of `line`, ignoring leading whitespace
+fn leading_number(line: &str) -> Option<f64> {
+    let line = line.trim_start();
+    let number_length = line
+        .char_indices()
+        .take_while(|(index, char)| {
+            char.is_ascii_digit() || *char == '.' || (*index == 0 && (*char == '-' || *char == '+'))
+        })
+        .count();
+
+    line[..number_length].parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::buffer_with;
+
+    #[test]
+    fn normalize_newlines_converts_mixed_endings_to_lf() {
+        let mut buffer = buffer_with("one\r\ntwo\nthree\r\nfour");
+
+        assert_eq!(buffer.normalize_newlines(LineEnding::Lf), 2);
+        assert_eq!(buffer.content_copy(), "one\ntwo\nthree\nfour");
+    }
+
+    #[test]
+    fn normalize_newlines_converts_mixed_endings_to_crlf() {
+        let mut buffer = buffer_with("one\ntwo\r\nthree\n");
+
+        assert_eq!(buffer.normalize_newlines(LineEnding::CrLf), 2);
+        assert_eq!(buffer.content_copy(), "one\r\ntwo\r\nthree\r\n");
+    }
+
+    #[test]
+    fn normalize_newlines_leaves_lone_carriage_returns() {
+        let mut buffer = buffer_with("one\rtwo\r\n");
+
+        assert_eq!(buffer.normalize_newlines(LineEnding::Lf), 1);
+        assert_eq!(buffer.content_copy(), "one\rtwo\n");
+        assert_eq!(buffer.normalize_newlines(LineEnding::Lf), 0);
+    }
+
+    #[test]
+    fn normalize_newlines_keeps_cursor_and_marks_in_place() {
+        let mut buffer = buffer_with("one\r\ntwo\r\nthree");
+        buffer.named_marks.insert('a', 6);
+        buffer.set_cursor_byte_index(12, false);
+
+        buffer.normalize_newlines(LineEnding::Lf);
+
+        assert_eq!(buffer.content_copy(), "one\ntwo\nthree");
+        assert_eq!(buffer.named_marks.get(&'a'), Some(&5));
+        assert_eq!(buffer.cursor_byte_index(), 10);
+    }
+
+    #[test]
+    fn reflow_wraps_a_long_line_to_the_width() {
+        let words = "The quick brown fox jumps over the lazy dog while the cat \
+                     watches from a sunny windowsill and wonders what all the fuss \
+                     could possibly be about on such a quiet afternoon";
+        let mut buffer = buffer_with(&format!("{}\n\nafter", words));
+
+        let line_count = buffer.reflow(0, 40, 4).unwrap();
+
+        let content = buffer.content_copy();
+        let (paragraph, rest) = content.split_once("\n\n").unwrap();
+        assert_eq!(rest, "after");
+        assert_eq!(paragraph.lines().count(), line_count);
+        assert!(line_count > 1);
+        assert!(paragraph.lines().all(|line| line.chars().count() <= 40));
+        assert_eq!(
+            paragraph.split_whitespace().collect::<Vec<_>>(),
+            words.split_whitespace().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn reflow_keeps_the_first_lines_indent() {
+        let mut buffer = buffer_with("\n  one two\nthree four five\n");
+
+        assert_eq!(buffer.reflow(2, 11, 4), Some(3));
+        assert_eq!(buffer.content_copy(), "\n  one two\n  three\n  four five\n");
+        assert_eq!(buffer.reflow(0, 11, 4), None);
+    }
+
+    #[test]
+    fn toggling_a_comment_twice_restores_the_lines() {
+        let original = "fn main() {\n    let x = 1;\n\n    x\n}";
+        let mut buffer = buffer_with(original);
+
+        buffer.toggle_comment(1, 3, "// ");
+        assert_eq!(buffer.content_copy(), "fn main() {\n    // let x = 1;\n\n    // x\n}");
+
+        buffer.toggle_comment(1, 3, "// ");
+        assert_eq!(buffer.content_copy(), original);
+    }
+
+    #[test]
+    fn toggling_a_partly_commented_range_comments_every_line() {
+        let mut buffer = buffer_with("// one\n  two");
+
+        buffer.toggle_comment(0, 1, "// ");
+        assert_eq!(buffer.content_copy(), "// // one\n  // two");
+    }
+
+    #[test]
+    fn numeric_sorts_order_by_value_and_lexical_sorts_by_text() {
+        let mut buffer = buffer_with("10 apples\n2 pears\n1 fig");
+        buffer.sort_lines(0, 2, false, true);
+        assert_eq!(buffer.content_copy(), "1 fig\n2 pears\n10 apples");
+
+        let mut buffer = buffer_with("10 apples\n2 pears\n1 fig");
+        buffer.sort_lines(0, 2, false, false);
+        assert_eq!(buffer.content_copy(), "1 fig\n10 apples\n2 pears");
+
+        buffer.sort_lines(0, 2, true, true);
+        assert_eq!(buffer.content_copy(), "10 apples\n2 pears\n1 fig");
+    }
+
+    #[test]
+    fn numeric_sorts_place_lines_without_numbers_first() {
+        let mut buffer = buffer_with("2\nnone\n1\n");
+        buffer.sort_lines(0, 2, false, true);
+        assert_eq!(buffer.content_copy(), "none\n1\n2\n");
+    }
+
+    #[test]
+    fn unique_lines_collapses_adjacent_duplicates_only() {
+        let mut buffer = buffer_with("a\na\na\nb\na\nc");
+
+        assert_eq!(buffer.unique_lines(0, 5), 2);
+        assert_eq!(buffer.content_copy(), "a\nb\na\nc");
+        assert_eq!(buffer.unique_lines(0, 3), 0);
+    }
+
+    #[test]
+    fn reversing_keeps_the_final_line_unterminated() {
+        let mut buffer = buffer_with("one\ntwo\nthree");
+
+        buffer.reverse_lines(0, 2);
+        assert_eq!(buffer.content_copy(), "three\ntwo\none");
+
+        let mut buffer = buffer_with("one\ntwo\nthree\n");
+        buffer.reverse_lines(0, 2);
+        assert_eq!(buffer.content_copy(), "three\ntwo\none\n");
+    }
+
+    #[test]
+    fn pasting_two_lines_below_leaves_the_cursor_on_the_first() {
+        let mut buffer = buffer_with("one\ntwo\nthree");
+        buffer.set_cursor_byte_index(1, false);
+
+        buffer.paste_lines("a\nb", true);
+
+        assert_eq!(buffer.content_line_count(), 5);
+        assert_eq!(buffer.content_copy(), "one\na\nb\ntwo\nthree");
+        assert_eq!(buffer.cursor_line_index(), 1);
+        assert_eq!(buffer.cursor_byte_index(), 4);
+    }
+
+    #[test]
+    fn pasting_below_an_unterminated_last_line_ends_it() {
+        let mut buffer = buffer_with("one");
+
+        buffer.paste_lines("a\n", true);
+
+        assert_eq!(buffer.content_copy(), "one\na\n");
+        assert_eq!(buffer.cursor_byte_index(), 4);
+    }
+}
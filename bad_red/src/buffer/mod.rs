@@ -6,15 +6,40 @@
 
 pub use content_buffer::*;
 pub use editor_buffer::*;
+pub use diff::*;
+pub use search::*;
+pub use syntax::*;
+pub use block::*;
+pub use motion::*;
+pub use lines::LineEnding;
+pub use paged_buffer::{PageSource, PagedBuffer};
 pub use byte_char_iter::expected_byte_length_from_starting;
 
 mod content_buffer;
 mod editor_buffer;
+mod diff;
+mod search;
+mod syntax;
+mod snippet;
+mod editing;
+mod cursors;
+mod lines;
+mod block;
+mod motion;
 
 mod naive_buffer;
 mod gap_buffer;
+mod paged_buffer;
 
 mod byte_char_iter;
 
 pub const ZERO_WIDTH_JOINER: char = '\u{200D}';
 
+
+/// Editor buffer holding `content` with its cursor at the end, for tests
+#[cfg(test)]
+pub(crate) fn buffer_with(content: &str) -> EditorBuffer {
+    let mut buffer = EditorBuffer::new();
+    buffer.insert_at_cursor(content);
+    buffer
+}
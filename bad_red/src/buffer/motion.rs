@@ -0,0 +1,428 @@
+// This file is part of BadRed.
+
+// BadRed is free software: you can redistribute it and/or modify it under the terms of the GNU General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// BadRed is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+use bad_red_proc_macros::auto_lua;
+
+use super::{ContentBuffer, EditorBuffer};
+
+/// Named cursor movement which an operator such as delete or yank can be
+/// applied over
+#[auto_lua]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Motion {
+    Char,
+    CharBack,
+    /// To the end of the next word
+    Word,
+    /// To the start of the previous word
+    WordBack,
+    LineStart,
+    /// To the end of the line before its newline
+    LineEnd,
+    /// Whole lines from the start of the cursor's line, including newlines
+    Line,
+}
+
+/// Byte range of a buffer's content from `start_byte` up to but not
+/// including `end_byte`
+#[auto_lua]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct MotionRange {
+    pub start_byte: usize,
+    pub end_byte: usize,
+}
+
+impl EditorBuffer {
+    /// Returns the range between the cursor and where `motion` repeated
+    /// `count` times would move it, without moving the cursor. A `count` of
+    /// zero moves as if it were one.
+    pub fn motion_range(&self, motion: Motion, count: usize) -> MotionRange {
+        let cursor_byte_index = self.cursor_byte_index();
+        let count = count.max(1);
+        let cursor_line = self.cursor_line_index();
+        let last_line = self.content_line_count().saturating_sub(1);
+
+        let target = match motion {
+            Motion::Char | Motion::CharBack => {
+                let shift = count.min(isize::MAX as usize) as isize;
+                let shift = if motion == Motion::Char {
+                    shift
+                } else {
+                    -shift
+                };
+                if cursor_byte_index == 0 && shift < 0 {
+                    0
+                } else {
+                    self.index_moved_by_char(cursor_byte_index, shift)
+                }
+            }
+            Motion::Word | Motion::WordBack => {
+                let forward = motion == Motion::Word;
+                let mut byte_index = cursor_byte_index;
+                for _ in 0..count {
+                    let char_count = self.chars_to_word_boundary(byte_index, forward);
+                    if char_count == 0 {
+                        break;
+                    }
+
+                    let shift = char_count as isize;
+                    byte_index =
+                        self.index_moved_by_char(byte_index, if forward { shift } else { -shift });
+                }
+                byte_index
+            }
+            Motion::LineStart => self.line_start_byte_index(cursor_line).unwrap_or(0),
+            Motion::LineEnd => {
+                let line = cursor_line.saturating_add(count - 1).min(last_line);
+                self.line_end_byte_index(line)
+                    .unwrap_or(self.content_byte_length())
+            }
+            Motion::Line => {
+                let start_byte = self.line_start_byte_index(cursor_line).unwrap_or(0);
+                let end_byte = self
+                    .line_start_byte_index(cursor_line.saturating_add(count))
+                    .unwrap_or(self.content_byte_length());
+
+                return MotionRange {
+                    start_byte,
+                    end_byte,
+                };
+            }
+        };
+
+        MotionRange {
+            start_byte: cursor_byte_index.min(target),
+            end_byte: cursor_byte_index.max(target),
+        }
+    }
+}
+
+/// Region of content around the cursor which an operator can be applied over
+#[auto_lua]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TextObject {
+    Word,
+    Paragraph,
+    Quote,
+    Paren,
+    Bracket,
+    Brace,
+}
+
+impl EditorBuffer {
+    /// Returns the range of `object` around the cursor, or `None` if the
+    /// cursor is not within such an object. Inner objects exclude their
+    /// delimiters and surrounding whitespace, while the rest include them.
+    pub fn text_object_range(&self, object: TextObject, inner: bool) -> Option<MotionRange> {
+        match object {
+            TextObject::Word => self.word_object_range(inner),
+            TextObject::Paragraph => self.paragraph_object_range(inner),
+            TextObject::Quote => self.quote_object_range(inner),
+            TextObject::Paren => self.pair_object_range('(', ')', inner),
+            TextObject::Bracket => self.pair_object_range('[', ']', inner),
+            TextObject::Brace => self.pair_object_range('{', '}', inner),
+        }
+    }
+
+    /// Returns the byte index of the bracket matching the one at
+    /// `byte_index`, searching forward from opening brackets and backward
+    /// from closing brackets while skipping nested pairs. Returns `None` if
+    /// there is no bracket at `byte_index` or it is unmatched.
+    pub fn matching_bracket(&self, byte_index: usize) -> Option<usize> {
+        let content = self.content_copy();
+        let bracket = content.get(byte_index..)?.chars().next()?;
+        let (open, close, forward) = match bracket {
+            '(' => ('(', ')', true),
+            '[' => ('[', ']', true),
+            '{' => ('{', '}', true),
+            ')' => ('(', ')', false),
+            ']' => ('[', ']', false),
+            '}' => ('{', '}', false),
+            _ => return None,
+        };
+
+        if forward {
+            find_unmatched(content[byte_index + 1..].char_indices(), open, close)
+                .map(|offset| byte_index + 1 + offset)
+        } else {
+            find_unmatched(content[..byte_index].char_indices().rev(), close, open)
+        }
+    }
+
+    fn word_object_range(&self, inner: bool) -> Option<MotionRange> {
+        let line_index = self.cursor_line_index();
+        let line_start = self.line_start_byte_index(line_index)?;
+        let line = self.content_copy_line(line_index)?;
+        let line = line.strip_suffix('\n').unwrap_or(&line);
+        let offset = self.cursor_byte_index() - line_start;
+        let cursor_char = line.get(offset..)?.chars().next()?;
+
+        let class = |char: char| {
+            if char.is_alphanumeric() || char == '_' {
+                0
+            } else if char.is_whitespace() {
+                1
+            } else {
+                2
+            }
+        };
+        let cursor_class = class(cursor_char);
+        let start = line[..offset]
+            .char_indices()
+            .rev()
+            .take_while(|(_, char)| class(*char) == cursor_class)
+            .last()
+            .map(|(index, _)| index)
+            .unwrap_or(offset);
+        let end = line[offset..]
+            .char_indices()
+            .find(|(_, char)| class(*char) != cursor_class)
+            .map(|(index, _)| offset + index)
+            .unwrap_or(line.len());
+
+        let (start, end) = if inner || cursor_class == 1 {
+            (start, end)
+        } else {
+            // Around a word includes its trailing whitespace, or its leading
+            // whitespace if it ends the line
+            let trailing_end = line[end..]
+                .char_indices()
+                .find(|(_, char)| !char.is_whitespace())
+                .map(|(index, _)| end + index)
+                .unwrap_or(line.len());
+            if trailing_end > end {
+                (start, trailing_end)
+            } else {
+                let leading_start = line[..start]
+                    .char_indices()
+                    .rev()
+                    .take_while(|(_, char)| char.is_whitespace())
+                    .last()
+                    .map(|(index, _)| index)
+                    .unwrap_or(start);
+                (leading_start, end)
+            }
+        };
+
+        Some(MotionRange {
+            start_byte: line_start + start,
+            end_byte: line_start + end,
+        })
+    }
+
+    fn paragraph_object_range(&self, inner: bool) -> Option<MotionRange> {
+        let line_count = self.content_line_count();
+        let is_blank = |line_index: usize| {
+            (line_index < line_count)
+                .then(|| self.content_copy_line(line_index))
+                .flatten()
+                .map(|line| line.trim().is_empty())
+        };
+        let cursor_line = self.cursor_line_index();
+        let is_cursor_blank = is_blank(cursor_line)?;
+
+        let mut first_line = cursor_line;
+        while first_line > 0 && is_blank(first_line - 1) == Some(is_cursor_blank) {
+            first_line -= 1;
+        }
+        let mut last_line = cursor_line;
+        while is_blank(last_line + 1) == Some(is_cursor_blank) {
+            last_line += 1;
+        }
+
+        if !inner {
+            // Around a paragraph includes the blank lines after it, or before
+            // it if it ends the content
+            if is_blank(last_line + 1).is_some() {
+                last_line += 1;
+                while is_blank(last_line + 1) == Some(!is_cursor_blank) {
+                    last_line += 1;
+                }
+            } else {
+                while first_line > 0 && is_blank(first_line - 1) == Some(!is_cursor_blank) {
+                    first_line -= 1;
+                }
+            }
+        }
+
+        Some(MotionRange {
+            start_byte: self.line_start_byte_index(first_line)?,
+            end_byte: self
+                .line_start_byte_index(last_line + 1)
+                .unwrap_or(self.content_byte_length()),
+        })
+    }
+
+    fn quote_object_range(&self, inner: bool) -> Option<MotionRange> {
+        let line_index = self.cursor_line_index();
+        let line_start = self.line_start_byte_index(line_index)?;
+        let line = self.content_copy_line(line_index)?;
+        let offset = self.cursor_byte_index() - line_start;
+
+        // Quotes pair up in order along the line, and the innermost pair
+        // around the cursor is used
+        let (open, close) = ['"', '\'', '`']
+            .into_iter()
+            .filter_map(|quote| {
+                let quotes: Vec<usize> = line
+                    .char_indices()
+                    .filter(|(_, char)| *char == quote)
+                    .map(|(index, _)| index)
+                    .collect();
+                quotes
+                    .chunks_exact(2)
+                    .map(|pair| (pair[0], pair[1]))
+                    .find(|(open, close)| *open <= offset && offset <= *close)
+            })
+            .min_by_key(|(open, close)| close - open)?;
+
+        Some(if inner {
+            MotionRange {
+                start_byte: line_start + open + 1,
+                end_byte: line_start + close,
+            }
+        } else {
+            MotionRange {
+                start_byte: line_start + open,
+                end_byte: line_start + close + 1,
+            }
+        })
+    }
+
+    fn pair_object_range(&self, open: char, close: char, inner: bool) -> Option<MotionRange> {
+        let content = self.content_copy();
+        let cursor_byte_index = self.cursor_byte_index();
+        let open_index = match content.get(cursor_byte_index..)?.chars().next() {
+            Some(char) if char == open => cursor_byte_index,
+            Some(char) if char == close => self.matching_bracket(cursor_byte_index)?,
+            _ => find_unmatched(
+                content[..cursor_byte_index].char_indices().rev(),
+                close,
+                open,
+            )?,
+        };
+        let close_index = self.matching_bracket(open_index)?;
+
+        Some(if inner {
+            MotionRange {
+                start_byte: open_index + open.len_utf8(),
+                end_byte: close_index,
+            }
+        } else {
+            MotionRange {
+                start_byte: open_index,
+                end_byte: close_index + close.len_utf8(),
+            }
+        })
+    }
+}
+
+/// Returns the index of the first `target` in `chars` which is not paired
+/// with an earlier `nested`.
+fn find_unmatched(
+    chars: impl Iterator<Item = (usize, char)>,
+    nested: char,
+    target: char,
+) -> Option<usize> {
+    let mut depth = 0;
+    for (index, char) in chars {
+        if char == nested {
+            depth += 1;
+        } else if char == target {
+            if depth == 0 {
+                return Some(index);
+            }
+            depth -= 1;
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::buffer_with;
+
+    fn buffer_with_cursor(content: &str, cursor_byte_index: usize) -> EditorBuffer {
+        let mut buffer = buffer_with(content);
+        buffer.set_cursor_byte_index(cursor_byte_index, false);
+        buffer
+    }
+
+    fn range(start_byte: usize, end_byte: usize) -> MotionRange {
+        MotionRange {
+            start_byte,
+            end_byte,
+        }
+    }
+
+    #[test]
+    fn word_motion_with_a_count_spans_that_many_words() {
+        let buffer = buffer_with_cursor("one two three", 0);
+
+        assert_eq!(buffer.motion_range(Motion::Word, 2), range(0, 7));
+        assert_eq!(buffer.motion_range(Motion::Word, 1), range(0, 3));
+        assert_eq!(buffer.motion_range(Motion::Word, 0), range(0, 3));
+    }
+
+    #[test]
+    fn backward_motions_end_at_the_cursor() {
+        let buffer = buffer_with_cursor("one two three", 8);
+
+        assert_eq!(buffer.motion_range(Motion::WordBack, 1), range(4, 8));
+        assert_eq!(buffer.motion_range(Motion::CharBack, 2), range(6, 8));
+        assert_eq!(buffer.motion_range(Motion::LineStart, 1), range(0, 8));
+        assert_eq!(buffer_with_cursor("ab", 0).motion_range(Motion::CharBack, 1), range(0, 0));
+    }
+
+    #[test]
+    fn line_motions_cover_counted_lines() {
+        let buffer = buffer_with_cursor("ab\ncd\nef", 1);
+
+        assert_eq!(buffer.motion_range(Motion::LineEnd, 1), range(1, 2));
+        assert_eq!(buffer.motion_range(Motion::LineEnd, 2), range(1, 5));
+        assert_eq!(buffer.motion_range(Motion::Line, 2), range(0, 6));
+        assert_eq!(buffer.motion_range(Motion::Line, 5), range(0, 8));
+    }
+
+    #[test]
+    fn paren_objects_are_inside_or_around_the_parens() {
+        let buffer = buffer_with_cursor("f(abc)", 3);
+
+        assert_eq!(buffer.text_object_range(TextObject::Paren, true), Some(range(2, 5)));
+        assert_eq!(buffer.text_object_range(TextObject::Paren, false), Some(range(1, 6)));
+        assert_eq!(buffer.text_object_range(TextObject::Brace, true), None);
+    }
+
+    #[test]
+    fn nested_pairs_use_the_innermost_around_the_cursor() {
+        let buffer = buffer_with_cursor("{a [b (c) d] e}", 4);
+
+        assert_eq!(buffer.text_object_range(TextObject::Bracket, true), Some(range(4, 11)));
+        assert_eq!(buffer.text_object_range(TextObject::Brace, false), Some(range(0, 15)));
+        assert_eq!(buffer.matching_bracket(3), Some(11));
+        assert_eq!(buffer.matching_bracket(11), Some(3));
+    }
+
+    #[test]
+    fn word_and_quote_objects() {
+        let buffer = buffer_with_cursor("say \"hi there\" now", 6);
+
+        assert_eq!(buffer.text_object_range(TextObject::Word, true), Some(range(5, 7)));
+        assert_eq!(buffer.text_object_range(TextObject::Word, false), Some(range(5, 8)));
+        assert_eq!(buffer.text_object_range(TextObject::Quote, true), Some(range(5, 13)));
+        assert_eq!(buffer.text_object_range(TextObject::Quote, false), Some(range(4, 14)));
+    }
+
+    #[test]
+    fn paragraph_objects_include_following_blank_lines_around() {
+        let buffer = buffer_with_cursor("a\nb\n\nc\n", 2);
+
+        assert_eq!(buffer.text_object_range(TextObject::Paragraph, true), Some(range(0, 4)));
+        assert_eq!(buffer.text_object_range(TextObject::Paragraph, false), Some(range(0, 5)));
+    }
+}
@@ -4,7 +4,7 @@
 //
 // BadRed is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
 
-use std::io::Read;
+use std::{cell::OnceCell, io::Read};
 
 use crate::file_handle::FileWrite;
 
@@ -14,6 +14,11 @@ pub struct NaiveBuffer {
     pub cursor_byte_index: usize,
     pub cursor_line_index: usize,
     pub content: String,
+
+    char_col_index: usize,
+    /// Byte indices of each newline in `content`, built when first needed
+    /// and then kept up to date by edits
+    sorted_newline_indices: OnceCell<Vec<usize>>,
 }
 
 impl NaiveBuffer {
@@ -22,27 +27,27 @@ impl NaiveBuffer {
             cursor_byte_index: 0,
             cursor_line_index: 0,
             content: String::new(),
+            char_col_index: 0,
+            sorted_newline_indices: OnceCell::new(),
         }
     }
 
-    fn cursor_line_index_for_cursor(&self, mut cursor_byte_index: usize) -> usize {
-        let chars = self.content.chars().collect::<Vec<_>>();
-        let mut line_char_count = 0;
-
-        while chars
-            .get(cursor_byte_index)
-            .map(|c| *c != '\n')
-            .unwrap_or(false)
-        {
-            line_char_count += 1;
+    fn newline_indices(&self) -> &Vec<usize> {
+        self.sorted_newline_indices.get_or_init(|| {
+            self.content
+                .match_indices('\n')
+                .map(|(newline_index, _)| newline_index)
+                .collect()
+        })
+    }
 
-            let Some(new_cursor_byte_index) = cursor_byte_index.checked_sub(1) else {
-                break;
-            };
-            cursor_byte_index = new_cursor_byte_index
-        }
+    fn char_col_index_for_byte_index(&self, byte_index: usize) -> usize {
+        let line_start = self.content[..byte_index]
+            .rfind('\n')
+            .map(|newline_index| newline_index + 1)
+            .unwrap_or(0);
 
-        line_char_count
+        self.content[line_start..byte_index].chars().count()
     }
 
     fn shift_byte_cursor_by_character(
@@ -76,12 +81,6 @@ impl NaiveBuffer {
 
         Some(byte_index)
     }
-
-    pub fn populate_from_string(&mut self, string: String) {
-        self.content = string;
-        self.cursor_byte_index = 0;
-        self.cursor_line_index = 0;
-    }
 }
 
 impl ContentBuffer for NaiveBuffer {
@@ -91,8 +90,31 @@ impl ContentBuffer for NaiveBuffer {
         } else {
             self.content.insert_str(self.cursor_byte_index, content);
         }
+        let cursor_byte_index = self.cursor_byte_index;
         self.cursor_byte_index += content.as_bytes().len();
-        self.cursor_line_index = self.cursor_line_index_for_cursor(self.cursor_byte_index);
+
+        if let Some(sorted_newline_indices) = self.sorted_newline_indices.get_mut() {
+            let first_shifted_index =
+                sorted_newline_indices.partition_point(|i| *i < cursor_byte_index);
+            for newline_index in &mut sorted_newline_indices[first_shifted_index..] {
+                *newline_index += content.len();
+            }
+
+            let inserted_newlines = content
+                .match_indices('\n')
+                .map(|(content_index, _)| content_index + cursor_byte_index);
+            sorted_newline_indices
+                .splice(first_shifted_index..first_shifted_index, inserted_newlines);
+        }
+
+        for content_char in content.chars() {
+            if content_char == '\n' {
+                self.char_col_index = 0;
+                self.cursor_line_index += 1;
+            } else {
+                self.char_col_index += 1;
+            }
+        }
     }
 
     fn delete_at_cursor(&mut self, mut char_count: usize) -> String {
@@ -112,6 +134,18 @@ impl ContentBuffer for NaiveBuffer {
             first_non_delete += 1;
         }
 
+        let bytes_removed = first_non_delete - self.cursor_byte_index;
+        if let Some(sorted_newline_indices) = self.sorted_newline_indices.get_mut() {
+            let first_remove_index =
+                sorted_newline_indices.partition_point(|i| *i < self.cursor_byte_index);
+            let first_unremove_index =
+                sorted_newline_indices.partition_point(|i| *i < first_non_delete);
+            sorted_newline_indices.drain(first_remove_index..first_unremove_index);
+            for newline_index in &mut sorted_newline_indices[first_remove_index..] {
+                *newline_index -= bytes_removed;
+            }
+        }
+
         let string_to_delete = self.content[self.cursor_byte_index..first_non_delete].to_string();
         let new_content = format!(
             "{}{}",
@@ -132,49 +166,24 @@ impl ContentBuffer for NaiveBuffer {
     }
 
     fn content_line_count(&self) -> usize {
-        let mut count = 0;
-
-        for char in self.content.chars() {
-            if char == '\n' {
-                count += 1
-            }
-        }
-
-        count + 1
+        self.newline_indices().len() + 1
     }
 
-    fn content_line_length(&self, mut line_index: usize) -> Option<usize> {
-        let mut char_count = 0;
-        let mut chars = self.content.chars();
-
-        for char in &mut chars {
-            if line_index == 0 {
-                break;
-            }
-
-            if char == '\n' {
-                line_index -= 1;
-            }
-        }
-
-        for char in chars {
-            char_count += 1;
-            if char == '\n' {
-                break;
-            }
-        }
+    fn content_line_length(&self, line_index: usize) -> Option<usize> {
+        let line_start = self.line_start_byte_index(line_index)?;
+        let line_end = self.line_end_byte_index(line_index)?;
 
-        if line_index == 0 {
-            Some(char_count)
-        } else {
-            None
-        }
+        Some(self.content[line_start..line_end].chars().count())
     }
 
     fn content_copy(&self) -> String {
         self.content.clone()
     }
 
+    fn content_hash(&self) -> u64 {
+        super::fnv_hash(self.content.as_bytes().iter())
+    }
+
     fn content_copy_at_byte_index(&self, byte_index: usize, char_count: usize) -> Option<String> {
         if let Some((first_excluded_char_byte_index, _)) = self
             .content
@@ -211,60 +220,29 @@ impl ContentBuffer for NaiveBuffer {
 
     fn set_cursor_byte_index(&mut self, index: usize, keep_col_index: bool) {
         self.cursor_byte_index = index;
+        self.cursor_line_index = self.line_index_for_byte_index(index);
 
-        if keep_col_index {
-            let mut col_index = 0;
-            for (char_index, char) in self.content.char_indices() {
-                if char_index == index {
-                    break;
-                }
-
-                col_index += 1;
-                if char == '\n' {
-                    col_index = 0;
-                }
-            }
-
-            self.cursor_line_index = col_index;
+        if !keep_col_index {
+            self.char_col_index = self.char_col_index_for_byte_index(index);
         }
     }
 
     fn set_cursor_line_index(&mut self, index: usize) {
-        let mut newline_count = 0;
-        let mut new_byte_index: Option<usize> = None;
+        let Some(line_start) = self.line_start_byte_index(index) else {
+            return;
+        };
 
-        let mut char_iter = self.content.char_indices();
-        for (char_index, char) in &mut char_iter {
-            if newline_count == index {
-                new_byte_index = Some(char_index);
+        let mut new_byte_index = line_start;
+        for (line_char_count, char) in self.content[line_start..].chars().enumerate() {
+            if line_char_count == self.char_col_index || char == '\n' {
                 break;
             }
 
-            if char == '\n' {
-                newline_count += 1;
-            }
+            new_byte_index += char.len_utf8();
         }
 
-        let new_byte_index = match new_byte_index {
-            Some(mut last_byte_index) => {
-                let mut line_count = 0;
-                for (char_index, char) in &mut char_iter {
-                    if line_count == self.cursor_line_index {
-                        break;
-                    }
-
-                    line_count += 1;
-                    if char == '\n' {
-                        break;
-                    }
-                    last_byte_index = char_index;
-                }
-                last_byte_index
-            }
-            None => self.content_byte_length(),
-        };
-
         self.cursor_byte_index = new_byte_index;
+        self.cursor_line_index = self.line_index_for_byte_index(new_byte_index);
     }
 
     fn cursor_byte_index(&self) -> usize {
@@ -272,58 +250,29 @@ impl ContentBuffer for NaiveBuffer {
     }
 
     fn cursor_line_index(&self) -> usize {
-        let mut newline_count = 0;
-
-        for (char_index, char) in self.content.char_indices() {
-            if char_index == self.cursor_byte_index {
-                break;
-            }
-
-            if char == '\n' {
-                newline_count += 1;
-            }
-        }
-
-        newline_count
+        self.cursor_line_index
     }
 
     fn line_index_for_byte_index(&self, byte_index: usize) -> usize {
-        let mut line_count = 0;
-        let mut current_byte = 0;
-        for char in self.content.chars() {
-            if current_byte >= byte_index {
-                break;
-            }
-
-            if char == '\n' {
-                line_count += 1;
-            }
-
-            current_byte += char.len_utf8();
-        }
-
-        line_count
+        self.newline_indices().partition_point(|i| *i < byte_index)
     }
 
-    fn line_start_byte_index(&self, mut line_index: usize) -> Option<usize> {
-        let mut byte_count = 0;
-        let mut chars = self.content.chars();
-        for char in &mut chars {
-            if line_index == 0 {
-                break;
-            }
-            byte_count += char.len_utf8();
-            if char == '\n' {
-                line_index -= 1;
-            }
-        }
+    fn line_start_byte_index(&self, line_index: usize) -> Option<usize> {
+        let Some(previous_line) = line_index.checked_sub(1) else {
+            return Some(0);
+        };
 
-        Some(byte_count)
+        self.newline_indices()
+            .get(previous_line)
+            .map(|byte_index| byte_index + 1)
     }
 
     fn line_end_byte_index(&self, line_index: usize) -> Option<usize> {
-        self.line_start_byte_index(line_index + 1)
-            .or_else(|| Some(self.content.len()))
+        let newline_indices = self.newline_indices();
+        newline_indices
+            .get(line_index)
+            .copied()
+            .or_else(|| (line_index == newline_indices.len()).then_some(self.content.len()))
     }
 
     fn cursor_moved_by_char(&self, char_count: isize) -> usize {
@@ -336,10 +285,17 @@ impl ContentBuffer for NaiveBuffer {
             .unwrap_or(0)
     }
 
+    fn populate_from_string(&mut self, content: String) {
+        self.sorted_newline_indices = OnceCell::new();
+        self.content = content;
+        self.cursor_byte_index = 0;
+        self.cursor_line_index = 0;
+        self.char_col_index = 0;
+    }
+
     fn populate_from_read(&mut self, read: &mut dyn Read) -> std::io::Result<()> {
-        let mut string = String::new();
-        read.read_to_string(&mut string)?;
-        self.populate_from_string(string);
+        let content = super::read_utf8(read)?;
+        self.populate_from_string(content);
 
         Ok(())
     }
@@ -360,3 +316,61 @@ fn is_byte_utf8_start_char(byte: u8) -> bool {
     let multibyte_start_char_mask = 0b_1100_0000;
     return (multibyte_start_char_mask & byte) == multibyte_start_char_mask;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scanned_newline_indices(buffer: &NaiveBuffer) -> Vec<usize> {
+        buffer
+            .content
+            .match_indices('\n')
+            .map(|(newline_index, _)| newline_index)
+            .collect()
+    }
+
+    #[test]
+    fn newline_indices_are_only_built_when_needed() {
+        let mut buffer = NaiveBuffer::new();
+        buffer.populate_from_string("one\ntwo\nthree".to_string());
+        buffer.insert_at_cursor("zero\n");
+        assert!(buffer.sorted_newline_indices.get().is_none());
+
+        assert_eq!(buffer.content_line_count(), 4);
+        assert_eq!(
+            buffer.sorted_newline_indices.get(),
+            Some(&scanned_newline_indices(&buffer))
+        );
+    }
+
+    #[test]
+    fn newline_indices_follow_edits_spanning_newlines() {
+        let mut buffer = NaiveBuffer::new();
+        buffer.populate_from_string("one\ntwo\nthree\nfour".to_string());
+        assert_eq!(buffer.content_line_count(), 4);
+
+        // Delete "two\nthr" along with the newline before it
+        buffer.set_cursor_byte_index(3, false);
+        buffer.delete_at_cursor(8);
+        assert_eq!(buffer.content, "oneee\nfour");
+        assert_eq!(
+            buffer.sorted_newline_indices.get(),
+            Some(&scanned_newline_indices(&buffer))
+        );
+
+        buffer.set_cursor_byte_index(2, false);
+        buffer.insert_at_cursor("x\ny\n");
+        assert_eq!(buffer.content, "onx\ny\neee\nfour");
+        assert_eq!(
+            buffer.sorted_newline_indices.get(),
+            Some(&scanned_newline_indices(&buffer))
+        );
+
+        assert_eq!(buffer.content_line_count(), 4);
+        assert_eq!(buffer.line_index_for_byte_index(6), 2);
+        assert_eq!(buffer.line_index_for_byte_index(10), 3);
+        assert_eq!(buffer.line_start_byte_index(3), Some(10));
+        assert_eq!(buffer.line_end_byte_index(3), Some(14));
+        assert_eq!(buffer.line_end_byte_index(4), None);
+    }
+}
@@ -0,0 +1,443 @@
+// This file is part of BadRed.
+
+// BadRed is free software: you can redistribute it and/or modify it under the terms of the GNU General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// BadRed is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+use std::{
+    cell::RefCell,
+    fs::File,
+    io::{self, Cursor, Read, Seek, SeekFrom},
+    ops::Range,
+};
+
+use crate::file_handle::FileWrite;
+
+use super::{byte_char_iter::ByteCharIter, ContentBuffer};
+
+/// Source of a paged buffer's content, read from as pages are needed
+pub trait PageSource: Read + Seek {}
+
+impl<T: Read + Seek> PageSource for T {}
+
+/// Bytes of the source currently held in memory, starting at byte `start`
+struct Page {
+    start: usize,
+    bytes: Vec<u8>,
+}
+
+/// Read-only buffer for files too large to hold in memory. Only the indices of
+/// the content's newlines are kept for the whole file, while the content
+/// itself is read a page of `window_size` bytes at a time as it is queried.
+/// Edits are ignored.
+pub struct PagedBuffer {
+    source: RefCell<Box<dyn PageSource>>,
+    page: RefCell<Page>,
+    window_size: usize,
+    /// Last failure to read a page from the source since it was indexed
+    read_error: RefCell<Option<String>>,
+
+    length: usize,
+    sorted_newline_indices: Vec<usize>,
+
+    cursor_byte_index: usize,
+    cursor_line_index: usize,
+    char_col_index: usize,
+}
+
+impl PagedBuffer {
+    pub const DEFAULT_WINDOW_SIZE: usize = 1 << 20;
+
+    pub fn new(source: Box<dyn PageSource>, window_size: usize) -> io::Result<Self> {
+        let mut new = Self {
+            source: RefCell::new(source),
+            page: RefCell::new(Page {
+                start: 0,
+                bytes: vec![],
+            }),
+            window_size: window_size.max(1),
+            read_error: RefCell::new(None),
+            length: 0,
+            sorted_newline_indices: vec![],
+            cursor_byte_index: 0,
+            cursor_line_index: 0,
+            char_col_index: 0,
+        };
+        new.index_source()?;
+
+        Ok(new)
+    }
+
+    /// Scans the whole source a page at a time for its length and newlines
+    fn index_source(&mut self) -> io::Result<()> {
+        let source = self.source.get_mut();
+        source.seek(SeekFrom::Start(0))?;
+
+        let mut chunk = vec![0; self.window_size];
+        let mut length = 0;
+        let mut newline_indices = vec![];
+        loop {
+            let read_length = match source.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(read_length) => read_length,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+
+            newline_indices.extend(
+                chunk[..read_length]
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, byte)| **byte == b'\n')
+                    .map(|(index, _)| length + index),
+            );
+            length += read_length;
+        }
+
+        self.length = length;
+        self.sorted_newline_indices = newline_indices;
+        *self.page.get_mut() = Page {
+            start: 0,
+            bytes: vec![],
+        };
+        *self.read_error.get_mut() = None;
+        self.cursor_byte_index = 0;
+        self.cursor_line_index = 0;
+        self.char_col_index = 0;
+
+        Ok(())
+    }
+
+    fn populate_from_source(&mut self, source: Box<dyn PageSource>) -> io::Result<()> {
+        *self.source.get_mut() = source;
+        self.index_source()
+    }
+
+    /// Copies the bytes in `range`, first paging in the part of the source
+    /// around it if it is not already in memory. Reads which fail give no
+    /// bytes rather than stopping the editor, and are kept as the buffer's
+    /// read error so they are not mistaken for empty content.
+    fn read_range(&self, range: Range<usize>) -> Vec<u8> {
+        let range = range.start.min(self.length)..range.end.min(self.length);
+        let mut page = self.page.borrow_mut();
+
+        let page_end = page.start + page.bytes.len();
+        if range.start < page.start || range.end > page_end {
+            // Keep some content before the range so moving back a little
+            // does not immediately page again
+            let start = range.start.saturating_sub(self.window_size / 4);
+            let end = (start + self.window_size).max(range.end).min(self.length);
+
+            let mut bytes = vec![0; end - start];
+            let mut source = self.source.borrow_mut();
+            let read_result = source
+                .seek(SeekFrom::Start(start as u64))
+                .and_then(|_| source.read_exact(&mut bytes));
+            if let Err(e) = read_result {
+                bytes.clear();
+                *self.read_error.borrow_mut() =
+                    Some(format!("Failed to read bytes {}..{} of paged content. {}", start, end, e));
+            }
+
+            *page = Page { start, bytes };
+        }
+
+        page.bytes
+            .get(range.start - page.start..range.end - page.start)
+            .map(|bytes| bytes.to_vec())
+            .unwrap_or_default()
+    }
+
+    /// Every byte of the content from `start`, paged in as it is iterated
+    fn bytes_from(&self, start: usize) -> impl Iterator<Item = u8> + '_ {
+        (start..self.length)
+            .step_by(self.window_size)
+            .flat_map(move |page_start| self.read_range(page_start..page_start + self.window_size))
+    }
+
+    fn byte_at(&self, byte_index: usize) -> Option<u8> {
+        self.read_range(byte_index..byte_index + 1).first().copied()
+    }
+
+    fn is_char_start(&self, byte_index: usize) -> bool {
+        self.byte_at(byte_index)
+            .is_some_and(|byte| super::expected_byte_length_from_starting(byte).is_some())
+    }
+
+    fn char_count_in(&self, range: Range<usize>) -> usize {
+        String::from_utf8_lossy(&self.read_range(range))
+            .chars()
+            .count()
+    }
+}
+
+impl ContentBuffer for PagedBuffer {
+    fn insert_at_cursor(&mut self, _content: &str) {}
+
+    fn delete_at_cursor(&mut self, _char_count: usize) -> String {
+        String::new()
+    }
+
+    fn is_read_only(&self) -> bool {
+        true
+    }
+
+    fn chars(&self) -> Box<dyn Iterator<Item = char> + '_> {
+        Box::new(ByteCharIter::new(self.bytes_from(0)))
+    }
+
+    fn content_byte_length(&self) -> usize {
+        self.length
+    }
+
+    fn content_line_count(&self) -> usize {
+        self.sorted_newline_indices.len() + 1
+    }
+
+    fn content_line_length(&self, line_index: usize) -> Option<usize> {
+        let line_start = self.line_start_byte_index(line_index)?;
+        let line_end = self.line_end_byte_index(line_index)?;
+
+        Some(self.char_count_in(line_start..line_end))
+    }
+
+    fn content_copy(&self) -> String {
+        let bytes: Vec<u8> = self.bytes_from(0).collect();
+
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    fn content_hash(&self) -> u64 {
+        super::fnv_hash(self.bytes_from(0))
+    }
+
+    fn content_copy_at_byte_index(&self, byte_index: usize, char_count: usize) -> Option<String> {
+        if !self.is_char_start(byte_index) && char_count > 0 {
+            return None;
+        }
+
+        // Chars are at most 4 bytes long
+        let bytes = self.read_range(byte_index..byte_index + char_count * 4);
+        let copy: String = String::from_utf8_lossy(&bytes)
+            .chars()
+            .take(char_count)
+            .collect();
+
+        (copy.chars().count() == char_count).then_some(copy)
+    }
+
+    fn content_copy_line(&self, line_index: usize) -> Option<String> {
+        let line_start = self.line_start_byte_index(line_index)?;
+        let line_end = self
+            .sorted_newline_indices
+            .get(line_index)
+            .map(|newline| newline + 1)
+            .unwrap_or(self.length);
+
+        Some(String::from_utf8_lossy(&self.read_range(line_start..line_end)).into_owned())
+    }
+
+    fn set_cursor_byte_index(&mut self, index: usize, keep_col_index: bool) {
+        self.cursor_byte_index = index.min(self.length);
+        self.cursor_line_index = self.line_index_for_byte_index(self.cursor_byte_index);
+
+        if !keep_col_index {
+            let line_start = self
+                .line_start_byte_index(self.cursor_line_index)
+                .unwrap_or(0);
+            self.char_col_index = self.char_count_in(line_start..self.cursor_byte_index);
+        }
+    }
+
+    fn set_cursor_line_index(&mut self, index: usize) {
+        let Some(line_start) = self.line_start_byte_index(index) else {
+            return;
+        };
+        let line_end = self.line_end_byte_index(index).unwrap_or(self.length);
+
+        let line = self.read_range(line_start..line_end);
+        let column_offset = String::from_utf8_lossy(&line)
+            .char_indices()
+            .nth(self.char_col_index)
+            .map(|(offset, _)| offset)
+            .unwrap_or(line.len());
+
+        self.cursor_byte_index = (line_start + column_offset).min(line_end);
+        self.cursor_line_index = index;
+    }
+
+    fn cursor_byte_index(&self) -> usize {
+        self.cursor_byte_index
+    }
+
+    fn cursor_line_index(&self) -> usize {
+        self.cursor_line_index
+    }
+
+    fn line_index_for_byte_index(&self, byte_index: usize) -> usize {
+        match self.sorted_newline_indices.binary_search(&byte_index) {
+            Ok(on_newline_index) => on_newline_index,
+            Err(insert_newline_index) => insert_newline_index,
+        }
+    }
+
+    fn line_start_byte_index(&self, line_index: usize) -> Option<usize> {
+        let Some(previous_line) = line_index.checked_sub(1) else {
+            return Some(0);
+        };
+
+        self.sorted_newline_indices
+            .get(previous_line)
+            .map(|byte_index| byte_index + 1)
+    }
+
+    fn line_end_byte_index(&self, line_index: usize) -> Option<usize> {
+        self.sorted_newline_indices
+            .get(line_index)
+            .copied()
+            .or_else(|| (line_index == self.sorted_newline_indices.len()).then_some(self.length))
+    }
+
+    fn cursor_moved_by_char(&self, char_count: isize) -> usize {
+        self.index_moved_by_char(self.cursor_byte_index, char_count)
+    }
+
+    fn index_moved_by_char(&self, start_byte_index: usize, char_count: isize) -> usize {
+        if char_count == 0 {
+            return start_byte_index;
+        }
+
+        let mut result_byte_index = start_byte_index.min(self.length);
+        for _ in 0..char_count.unsigned_abs() {
+            if char_count > 0 {
+                if result_byte_index == self.length {
+                    break;
+                }
+
+                result_byte_index += 1;
+                while result_byte_index < self.length && !self.is_char_start(result_byte_index) {
+                    result_byte_index += 1;
+                }
+            } else {
+                if result_byte_index == 0 {
+                    break;
+                }
+
+                result_byte_index -= 1;
+                while result_byte_index > 0 && !self.is_char_start(result_byte_index) {
+                    result_byte_index -= 1;
+                }
+            }
+        }
+
+        result_byte_index
+    }
+
+    fn populate_from_string(&mut self, content: String) {
+        self.populate_from_source(Box::new(Cursor::new(content.into_bytes())))
+            .expect("Failed to index in-memory content");
+    }
+
+    /// Reads all of `read` into memory, as a plain reader cannot be paged
+    fn populate_from_read(&mut self, read: &mut dyn Read) -> io::Result<()> {
+        let mut bytes = vec![];
+        read.read_to_end(&mut bytes)?;
+
+        self.populate_from_source(Box::new(Cursor::new(bytes)))
+    }
+
+    fn populate_from_file(&mut self, file: File) -> io::Result<()> {
+        self.populate_from_source(Box::new(file))
+    }
+
+    /// Writes the content, failing rather than writing partial content if a
+    /// page fails to read
+    fn flush_to_write(&mut self, write: &mut dyn FileWrite) -> io::Result<()> {
+        *self.read_error.get_mut() = None;
+        let bytes: Vec<u8> = self.bytes_from(0).collect();
+        if let Some(read_error) = self.read_error() {
+            return Err(io::Error::other(read_error));
+        }
+
+        write.write_file(&bytes)
+    }
+
+    fn read_error(&self) -> Option<String> {
+        self.read_error.borrow().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, io::Write, rc::Rc};
+
+    use super::*;
+
+    /// Source which fails every read once `is_failing` is set
+    struct FailingSource {
+        content: Cursor<Vec<u8>>,
+        is_failing: Rc<Cell<bool>>,
+    }
+
+    impl Read for FailingSource {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.is_failing.get() {
+                return Err(io::Error::other("source is gone"));
+            }
+            self.content.read(buf)
+        }
+    }
+
+    impl Seek for FailingSource {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            self.content.seek(pos)
+        }
+    }
+
+    struct VecWrite(Vec<u8>);
+
+    impl FileWrite for VecWrite {
+        fn write_file(&mut self, buf: &[u8]) -> io::Result<()> {
+            self.0 = buf.to_vec();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn failed_reads_are_kept_as_the_read_error() {
+        let is_failing = Rc::new(Cell::new(false));
+        let source = FailingSource {
+            content: Cursor::new(b"one\ntwo\n".to_vec()),
+            is_failing: is_failing.clone(),
+        };
+        let mut buffer = PagedBuffer::new(Box::new(source), 4).unwrap();
+        assert_eq!(buffer.content_line_count(), 3);
+        assert_eq!(buffer.read_error(), None);
+
+        is_failing.set(true);
+        assert_eq!(buffer.content_copy_line(1).as_deref(), Some(""));
+        assert!(buffer.read_error().is_some());
+
+        let mut write = VecWrite(b"untouched".to_vec());
+        assert!(buffer.flush_to_write(&mut write).is_err());
+        assert_eq!(write.0, b"untouched");
+    }
+
+    #[test]
+    fn populating_from_a_file_pages_its_content() {
+        let path = std::env::temp_dir().join(format!("bad_red_paged_{}", std::process::id()));
+        let mut file = File::create(&path).unwrap();
+        for line_index in 0..100 {
+            writeln!(file, "line {:03}", line_index).unwrap();
+        }
+        drop(file);
+
+        let mut buffer = PagedBuffer::new(Box::new(Cursor::new(vec![])), 16).unwrap();
+        buffer.populate_from_file(File::open(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(buffer.content_byte_length(), 900);
+        assert_eq!(buffer.content_copy_line(50).as_deref(), Some("line 050\n"));
+        assert!(buffer.page.borrow().bytes.len() <= 16);
+    }
+}
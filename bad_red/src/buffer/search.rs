@@ -0,0 +1,222 @@
+// This file is part of BadRed.
+
+// BadRed is free software: you can redistribute it and/or modify it under the terms of the GNU General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// BadRed is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+use std::ops::Range;
+
+use bad_red_proc_macros::auto_lua;
+use regex::Regex;
+
+use super::ContentBuffer;
+
+#[auto_lua]
+#[derive(Clone, Debug)]
+pub struct LineMatch {
+    pub line_index: usize,
+    pub byte_index: usize,
+    pub line_text: String,
+}
+
+#[auto_lua]
+#[derive(Clone, Debug)]
+pub struct BufferMatch {
+    pub buffer_id: usize,
+    pub line_index: usize,
+    pub byte_index: usize,
+    pub line_text: String,
+}
+
+/// Finds every match of `regex` within the lines of `buffer`.
+///
+/// Each line is searched without its terminating newline, so matches never
+/// span lines. `byte_index` is the buffer byte index of the start of the match
+/// and `line_text` is the full line the match was found on.
+pub fn search_lines(buffer: &dyn ContentBuffer, regex: &Regex) -> Vec<LineMatch> {
+    let mut matches = vec![];
+
+    for line_index in 0..buffer.content_line_count() {
+        let (Some(line), Some(line_start)) = (
+            buffer.content_copy_line(line_index),
+            buffer.line_start_byte_index(line_index),
+        ) else {
+            continue;
+        };
+        let line_text = line.strip_suffix('\n').unwrap_or(&line);
+
+        for found in regex.find_iter(line_text) {
+            matches.push(LineMatch {
+                line_index,
+                byte_index: line_start + found.start(),
+                line_text: line_text.to_string(),
+            });
+        }
+    }
+
+    matches
+}
+
+/// Matches of a regex searched for in a buffer, kept so the renderer can draw
+/// them with the reserved search styles.
+pub struct SearchState {
+    regex: Regex,
+    /// Byte ranges of each non-empty match, sorted by start and not
+    /// overlapping
+    pub matches: Vec<Range<usize>>,
+}
+
+impl SearchState {
+    pub fn new(buffer: &dyn ContentBuffer, regex: Regex) -> Self {
+        let mut search = Self {
+            regex,
+            matches: vec![],
+        };
+        search.refresh(buffer);
+
+        search
+    }
+
+    /// Searches all of `buffer` again, such as after its content was replaced
+    pub fn refresh(&mut self, buffer: &dyn ContentBuffer) {
+        self.matches = self.line_matches(buffer, 0, buffer.content_line_count());
+    }
+
+    /// Searches only the lines of `buffer` touched by an edit which replaced
+    /// the bytes `start..old_end` with the bytes `start..new_end`. Matches on
+    /// other lines are kept, shifted past the edit.
+    pub fn refresh_edited(
+        &mut self,
+        buffer: &dyn ContentBuffer,
+        start: usize,
+        old_end: usize,
+        new_end: usize,
+    ) {
+        let first_line = buffer.line_index_for_byte_index(start);
+        let last_line = buffer.line_index_for_byte_index(new_end);
+        let Some(lines_start) = buffer.line_start_byte_index(first_line) else {
+            self.refresh(buffer);
+            return;
+        };
+        let lines_end = buffer
+            .line_start_byte_index(last_line + 1)
+            .unwrap_or(buffer.content_byte_length());
+        // Matches never span lines, so those starting within the edited lines
+        // before the edit are all replaced
+        let old_lines_end = lines_end - new_end + old_end;
+
+        let replaced_start = self
+            .matches
+            .partition_point(|found| found.start < lines_start);
+        let replaced_end = self
+            .matches
+            .partition_point(|found| found.start < old_lines_end);
+        for found in &mut self.matches[replaced_end..] {
+            *found = found.start - old_end + new_end..found.end - old_end + new_end;
+        }
+
+        let edited_matches = self.line_matches(buffer, first_line, last_line + 1);
+        self.matches
+            .splice(replaced_start..replaced_end, edited_matches);
+    }
+
+    /// Non-empty matches within the lines `start_line..end_line` of `buffer`
+    fn line_matches(
+        &self,
+        buffer: &dyn ContentBuffer,
+        start_line: usize,
+        end_line: usize,
+    ) -> Vec<Range<usize>> {
+        let mut matches = vec![];
+        for line_index in start_line..end_line.min(buffer.content_line_count()) {
+            let (Some(line), Some(line_start)) = (
+                buffer.content_copy_line(line_index),
+                buffer.line_start_byte_index(line_index),
+            ) else {
+                continue;
+            };
+            let line_text = line.strip_suffix('\n').unwrap_or(&line);
+
+            matches.extend(
+                self.regex
+                    .find_iter(line_text)
+                    .filter(|found| !found.is_empty())
+                    .map(|found| line_start + found.start()..line_start + found.end()),
+            );
+        }
+
+        matches
+    }
+
+    /// Index within `matches` of the match containing `byte_index`, if any
+    pub fn match_at(&self, byte_index: usize) -> Option<usize> {
+        let index = self
+            .matches
+            .partition_point(|found| found.end <= byte_index);
+
+        self.matches
+            .get(index)
+            .filter(|found| found.start <= byte_index)
+            .map(|_| index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::{EditorBuffer, EditorBufferType};
+
+    fn searched_buffer(buffer_type: EditorBufferType, content: &str) -> EditorBuffer {
+        let mut buffer = EditorBuffer::new_typed(buffer_type);
+        buffer.insert_at_cursor(content);
+        let regex = Regex::new("ab+").unwrap();
+        buffer.search = Some(SearchState::new(buffer.content.as_ref(), regex));
+        buffer
+    }
+
+    fn assert_matches_full_search(buffer: &EditorBuffer) {
+        let search = buffer.search.as_ref().unwrap();
+        let full = SearchState::new(buffer.content.as_ref(), search.regex.clone());
+        assert_eq!(search.matches, full.matches);
+    }
+
+    #[test]
+    fn edits_keep_matches_equal_to_a_full_search() {
+        for buffer_type in [EditorBufferType::Gap, EditorBufferType::Naive] {
+            let mut buffer = searched_buffer(buffer_type, "ab x abb\nxx\nabbb ab\n");
+            assert_eq!(buffer.search.as_ref().unwrap().matches.len(), 4);
+
+            // Extends a match, splits a line, joins lines, and edits at the end
+            buffer.set_cursor_byte_index(1, false);
+            buffer.insert_at_cursor("b");
+            assert_matches_full_search(&buffer);
+
+            buffer.set_cursor_byte_index(5, false);
+            buffer.insert_at_cursor("\nab");
+            assert_matches_full_search(&buffer);
+
+            buffer.set_cursor_byte_index(11, false);
+            buffer.delete_at_cursor(4);
+            assert_matches_full_search(&buffer);
+
+            buffer.set_cursor_byte_index(buffer.content_byte_length(), false);
+            buffer.insert_at_cursor("abab");
+            assert_matches_full_search(&buffer);
+
+            buffer.set_cursor_byte_index(0, false);
+            buffer.delete_at_cursor(buffer.content.chars().count());
+            assert_matches_full_search(&buffer);
+        }
+    }
+
+    #[test]
+    fn match_at_finds_the_match_containing_an_index() {
+        let buffer = searched_buffer(EditorBufferType::Gap, "xab abb");
+        let search = buffer.search.as_ref().unwrap();
+
+        assert_eq!(search.match_at(0), None);
+        assert_eq!(search.match_at(1), Some(0));
+        assert_eq!(search.match_at(3), None);
+        assert_eq!(search.match_at(6), Some(1));
+    }
+}
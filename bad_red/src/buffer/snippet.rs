@@ -0,0 +1,137 @@
+// This file is part of BadRed.
+
+// BadRed is free software: you can redistribute it and/or modify it under the terms of the GNU General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// BadRed is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+use super::{ContentBuffer, EditorBuffer};
+
+impl EditorBuffer {
+    /// Replaces `trigger` with `body` if the cursor is just after `trigger`,
+    /// returning whether the snippet was expanded.
+    ///
+    /// `$1` through `$9` in `body` mark tab stops visited in order, ending at
+    /// `$0`. The cursor is moved to the first stop and the rest are kept for
+    /// `next_snippet_stop`. `$$` inserts a literal `$`.
+    pub fn expand_snippet(&mut self, trigger: &str, body: &str) -> bool {
+        let cursor_byte_index = self.cursor_byte_index();
+        let trigger_char_count = trigger.chars().count();
+        let trigger_start = self.index_moved_by_char(
+            cursor_byte_index,
+            -(trigger_char_count as isize),
+        );
+        if cursor_byte_index - trigger_start != trigger.len()
+            || self
+                .content_copy_at_byte_index(trigger_start, trigger_char_count)
+                .as_deref()
+                != Some(trigger)
+        {
+            return false;
+        }
+
+        let (text, mut stops) = parse_snippet(body);
+        // Stops are visited from 1 up, with 0 as the final stop
+        stops.sort_by_key(|(stop_number, _)| if *stop_number == 0 { 10 } else { *stop_number });
+        stops.dedup_by_key(|(stop_number, _)| *stop_number);
+
+        self.set_cursor_byte_index(trigger_start, false);
+        self.delete_at_cursor(trigger_char_count);
+        self.insert_at_cursor(&text);
+
+        self.snippet_stops = stops
+            .into_iter()
+            .rev()
+            .map(|(_, offset)| trigger_start + offset)
+            .collect();
+        self.next_snippet_stop();
+
+        true
+    }
+
+    /// Moves the cursor to the next pending tab stop of the last expanded
+    /// snippet, returning its byte index if there was one.
+    pub fn next_snippet_stop(&mut self) -> Option<usize> {
+        let stop = self.snippet_stops.pop()?;
+        self.set_cursor_byte_index(stop, false);
+
+        Some(stop)
+    }
+}
+
+/// Splits a snippet body into its text and its tab stops, given as the stop's
+/// number and byte offset into the text.
+fn parse_snippet(body: &str) -> (String, Vec<(u32, usize)>) {
+    let mut text = String::with_capacity(body.len());
+    let mut stops = vec![];
+
+    let mut chars = body.chars().peekable();
+    while let Some(next_char) = chars.next() {
+        if next_char != '$' {
+            text.push(next_char);
+            continue;
+        }
+
+        match chars.peek().copied() {
+            Some('$') => {
+                chars.next();
+                text.push('$');
+            }
+            Some(digit) if digit.is_ascii_digit() => {
+                chars.next();
+                stops.push((digit.to_digit(10).unwrap_or(0), text.len()));
+            }
+            _ => text.push('$'),
+        }
+    }
+
+    (text, stops)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::buffer_with;
+
+    #[test]
+    fn expanding_leaves_the_cursor_at_the_first_stop() {
+        let mut buffer = buffer_with("local f = fn");
+
+        assert!(buffer.expand_snippet("fn", "function ($1) $0end"));
+        assert_eq!(buffer.content_copy_line(0).as_deref(), Some("local f = function () end"));
+        assert_eq!(buffer.cursor_byte_index(), "local f = function (".len());
+    }
+
+    #[test]
+    fn expanding_requires_the_trigger_before_the_cursor() {
+        let mut buffer = buffer_with("fn x");
+
+        assert!(!buffer.expand_snippet("fn", "function ($1) $0end"));
+        assert_eq!(buffer.content_copy_line(0).as_deref(), Some("fn x"));
+        assert!(buffer.snippet_stops.is_empty());
+    }
+
+    #[test]
+    fn next_stop_visits_stops_in_order_and_ends_at_zero() {
+        let mut buffer = buffer_with("for");
+
+        assert!(buffer.expand_snippet("for", "for $1 in $2 do\n\t$0\nend"));
+        assert_eq!(buffer.cursor_byte_index(), "for ".len());
+
+        assert_eq!(buffer.next_snippet_stop(), Some("for  in ".len()));
+        assert_eq!(buffer.cursor_byte_index(), "for  in ".len());
+
+        assert_eq!(buffer.next_snippet_stop(), Some("for  in  do\n\t".len()));
+        assert_eq!(buffer.next_snippet_stop(), None);
+        assert_eq!(buffer.cursor_byte_index(), "for  in  do\n\t".len());
+    }
+
+    #[test]
+    fn stops_move_with_edits_before_them() {
+        let mut buffer = buffer_with("fn");
+
+        assert!(buffer.expand_snippet("fn", "f($1, $2)"));
+        buffer.insert_at_cursor("abc");
+
+        assert_eq!(buffer.next_snippet_stop(), Some("f(abc, ".len()));
+    }
+}
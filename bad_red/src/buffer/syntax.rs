@@ -0,0 +1,530 @@
+// This file is part of BadRed.
+
+// BadRed is free software: you can redistribute it and/or modify it under the terms of the GNU General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// BadRed is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+use std::cell::RefCell;
+
+use bad_red_proc_macros::auto_lua;
+
+use super::ContentBuffer;
+
+/// Byte range of a buffer's content tagged with the name of the syntax capture
+/// it belongs to. Capture names follow tree-sitter's highlight names such as
+/// "keyword", "string", and "comment" so themes can map them onto styles.
+#[auto_lua]
+#[derive(Clone, PartialEq, Debug)]
+pub struct Highlight {
+    pub start_byte: usize,
+    pub end_byte: usize,
+    pub capture: String,
+}
+
+#[auto_lua]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Language {
+    Lua,
+}
+
+impl Language {
+    /// Highlighter for the language, which uses its bundled tree-sitter
+    /// grammar when built with the `tree-sitter` feature and its lexer
+    /// otherwise.
+    pub fn highlighter(&self) -> Box<dyn SyntaxHighlighter> {
+        match self {
+            #[cfg(feature = "tree-sitter")]
+            Language::Lua => Box::new(TreeSitterHighlighter::new(
+                tree_sitter_lua::LANGUAGE.into(),
+                tree_sitter_lua::HIGHLIGHTS_QUERY,
+            )),
+            #[cfg(not(feature = "tree-sitter"))]
+            Language::Lua => Box::new(LuaHighlighter::new()),
+        }
+    }
+}
+
+/// Backend producing highlights for a buffer's content. Backends may cache
+/// state between calls, which must be invalidated as the content is edited.
+pub trait SyntaxHighlighter {
+    /// Discards any cached state derived from `line_index` or later lines
+    fn invalidate_from_line(&mut self, line_index: usize);
+
+    /// Returns the highlights on lines `start_line` through `end_line`
+    /// inclusive, sorted by start byte.
+    fn highlights(
+        &self,
+        buffer: &dyn ContentBuffer,
+        start_line: usize,
+        end_line: usize,
+    ) -> Vec<Highlight>;
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum LuaLineState {
+    Normal,
+    LongString(usize),
+    LongComment(usize),
+}
+
+/// Lexer-based highlighter for Lua. Caches the lexer state at the start of
+/// each line so multi-line strings and comments only need lexing once.
+pub struct LuaHighlighter {
+    line_states: RefCell<Vec<LuaLineState>>,
+}
+
+impl LuaHighlighter {
+    const KEYWORDS: [&'static str; 19] = [
+        "and", "break", "do", "else", "elseif", "end", "for", "function", "goto", "if", "in",
+        "local", "not", "or", "repeat", "return", "then", "until", "while",
+    ];
+
+    pub fn new() -> Self {
+        Self {
+            line_states: RefCell::new(vec![LuaLineState::Normal]),
+        }
+    }
+
+    fn lex_line(
+        line: &str,
+        line_start: usize,
+        state: LuaLineState,
+        highlights: &mut Vec<Highlight>,
+    ) -> LuaLineState {
+        let mut push = |start: usize, end: usize, capture: &str| {
+            highlights.push(Highlight {
+                start_byte: line_start + start,
+                end_byte: line_start + end,
+                capture: capture.to_string(),
+            })
+        };
+
+        let mut index = 0;
+        match state {
+            LuaLineState::Normal => (),
+            LuaLineState::LongString(level) | LuaLineState::LongComment(level) => {
+                let capture = if let LuaLineState::LongComment(_) = state {
+                    "comment"
+                } else {
+                    "string"
+                };
+                let Some(end) = long_bracket_close(line, 0, level) else {
+                    push(0, line.len(), capture);
+                    return state;
+                };
+
+                push(0, end, capture);
+                index = end;
+            }
+        }
+
+        let bytes = line.as_bytes();
+        while index < bytes.len() {
+            let rest = &line[index..];
+            let next_byte = bytes[index];
+
+            if let Some(comment) = rest.strip_prefix("--") {
+                let Some(level) = long_bracket_open(comment) else {
+                    push(index, line.len(), "comment");
+                    return LuaLineState::Normal;
+                };
+
+                let content_start = index + 2 + level + 2;
+                let Some(end) = long_bracket_close(line, content_start, level) else {
+                    push(index, line.len(), "comment");
+                    return LuaLineState::LongComment(level);
+                };
+                push(index, end, "comment");
+                index = end;
+            } else if let Some(level) = long_bracket_open(rest) {
+                let content_start = index + level + 2;
+                let Some(end) = long_bracket_close(line, content_start, level) else {
+                    push(index, line.len(), "string");
+                    return LuaLineState::LongString(level);
+                };
+                push(index, end, "string");
+                index = end;
+            } else if next_byte == b'"' || next_byte == b'\'' {
+                let mut end = index + 1;
+                while end < bytes.len() && bytes[end] != next_byte {
+                    end += if bytes[end] == b'\\' { 2 } else { 1 };
+                }
+                let end = (end + 1).min(bytes.len());
+                push(index, end, "string");
+                index = end;
+            } else if next_byte.is_ascii_digit()
+                || (next_byte == b'.' && bytes.get(index + 1).is_some_and(u8::is_ascii_digit))
+            {
+                let mut end = index;
+                while end < bytes.len() {
+                    let is_exponent = matches!(bytes[end], b'e' | b'E' | b'p' | b'P');
+                    end += 1;
+                    if is_exponent && matches!(bytes.get(end), Some(b'+' | b'-')) {
+                        end += 1;
+                    }
+                    if !bytes
+                        .get(end)
+                        .is_some_and(|byte| byte.is_ascii_alphanumeric() || *byte == b'.')
+                    {
+                        break;
+                    }
+                }
+                push(index, end, "number");
+                index = end;
+            } else if next_byte.is_ascii_alphabetic() || next_byte == b'_' {
+                let mut end = index + 1;
+                while bytes
+                    .get(end)
+                    .is_some_and(|byte| byte.is_ascii_alphanumeric() || *byte == b'_')
+                {
+                    end += 1;
+                }
+
+                let word = &line[index..end];
+                if Self::KEYWORDS.contains(&word) {
+                    push(index, end, "keyword");
+                } else if word == "true" || word == "false" {
+                    push(index, end, "boolean");
+                } else if word == "nil" {
+                    push(index, end, "constant.builtin");
+                } else if line[end..].trim_start().starts_with('(') {
+                    push(index, end, "function.call");
+                }
+                index = end;
+            } else {
+                index += rest.chars().next().map(char::len_utf8).unwrap_or(1);
+            }
+        }
+
+        LuaLineState::Normal
+    }
+}
+
+impl Default for LuaHighlighter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SyntaxHighlighter for LuaHighlighter {
+    fn invalidate_from_line(&mut self, line_index: usize) {
+        // The state at the start of the edited line only depends on prior lines
+        self.line_states.get_mut().truncate(line_index + 1);
+    }
+
+    fn highlights(
+        &self,
+        buffer: &dyn ContentBuffer,
+        start_line: usize,
+        end_line: usize,
+    ) -> Vec<Highlight> {
+        let mut line_states = self.line_states.borrow_mut();
+        let last_line = end_line.min(buffer.content_line_count().saturating_sub(1));
+
+        let mut highlights = vec![];
+        let mut line_index = start_line.min(line_states.len() - 1);
+        while line_index <= last_line {
+            let (Some(line), Some(line_start)) = (
+                buffer.content_copy_line(line_index),
+                buffer.line_start_byte_index(line_index),
+            ) else {
+                break;
+            };
+            let line = line.strip_suffix('\n').unwrap_or(&line);
+
+            let mut line_highlights = vec![];
+            let next_state = Self::lex_line(
+                line,
+                line_start,
+                line_states[line_index],
+                &mut line_highlights,
+            );
+            if line_index >= start_line {
+                highlights.append(&mut line_highlights);
+            }
+
+            if line_states.len() == line_index + 1 {
+                line_states.push(next_state);
+            }
+            line_index += 1;
+        }
+
+        highlights
+    }
+}
+
+/// Highlighter running a tree-sitter grammar's highlights query. The parse
+/// tree is kept between calls and reparsed from the whole content after an
+/// edit invalidates it.
+#[cfg(feature = "tree-sitter")]
+pub struct TreeSitterHighlighter {
+    parser: RefCell<tree_sitter::Parser>,
+    query: tree_sitter::Query,
+    tree: RefCell<Option<tree_sitter::Tree>>,
+}
+
+#[cfg(feature = "tree-sitter")]
+impl TreeSitterHighlighter {
+    /// Creates a highlighter for `language` from its `highlights_query`, which
+    /// must be a bundled query valid for the language.
+    pub fn new(language: tree_sitter::Language, highlights_query: &str) -> Self {
+        let mut parser = tree_sitter::Parser::new();
+        parser
+            .set_language(&language)
+            .expect("Bundled tree-sitter grammar is incompatible with the tree-sitter library");
+        let query = tree_sitter::Query::new(&language, highlights_query)
+            .expect("Bundled tree-sitter highlights query failed to compile");
+
+        Self {
+            parser: RefCell::new(parser),
+            query,
+            tree: RefCell::new(None),
+        }
+    }
+}
+
+#[cfg(feature = "tree-sitter")]
+impl SyntaxHighlighter for TreeSitterHighlighter {
+    fn invalidate_from_line(&mut self, _line_index: usize) {
+        *self.tree.get_mut() = None;
+    }
+
+    /// Captures may be nested, such as a call's name inside the call, so each
+    /// byte takes the capture of the smallest node covering it. Captures of
+    /// the same node are resolved in favour of the later pattern in the query.
+    fn highlights(
+        &self,
+        buffer: &dyn ContentBuffer,
+        start_line: usize,
+        end_line: usize,
+    ) -> Vec<Highlight> {
+        use tree_sitter::StreamingIterator;
+
+        let content = buffer.content_copy();
+        let mut tree = self.tree.borrow_mut();
+        if tree.is_none() {
+            *tree = self.parser.borrow_mut().parse(&content, None);
+        }
+        let Some(tree) = tree.as_ref() else {
+            return vec![];
+        };
+
+        let Some(range_start) = buffer.line_start_byte_index(start_line) else {
+            return vec![];
+        };
+        let range_end = buffer
+            .line_start_byte_index(end_line + 1)
+            .unwrap_or(content.len());
+
+        let mut cursor = tree_sitter::QueryCursor::new();
+        cursor.set_byte_range(range_start..range_end);
+        let mut captures = cursor.captures(&self.query, tree.root_node(), content.as_bytes());
+        let mut nodes = vec![];
+        while let Some((query_match, capture_index)) = captures.next() {
+            let capture = query_match.captures[*capture_index];
+            nodes.push((capture.node.byte_range(), capture.index));
+        }
+        nodes.sort_by_key(|(range, _)| std::cmp::Reverse(range.len()));
+
+        let mut byte_captures = vec![None; range_end - range_start];
+        for (range, capture_index) in nodes {
+            let start = range.start.clamp(range_start, range_end) - range_start;
+            let end = range.end.clamp(range_start, range_end) - range_start;
+            byte_captures[start..end].fill(Some(capture_index));
+        }
+
+        let capture_names = self.query.capture_names();
+        let mut highlights: Vec<Highlight> = vec![];
+        for (offset, capture_index) in byte_captures.into_iter().enumerate() {
+            let Some(capture_index) = capture_index else {
+                continue;
+            };
+            let byte_index = range_start + offset;
+            let capture = capture_names[capture_index as usize];
+            match highlights.last_mut() {
+                Some(last) if last.end_byte == byte_index && last.capture == capture => {
+                    last.end_byte += 1;
+                }
+                _ => highlights.push(Highlight {
+                    start_byte: byte_index,
+                    end_byte: byte_index + 1,
+                    capture: capture.to_string(),
+                }),
+            }
+        }
+
+        highlights
+    }
+}
+
+/// Returns the level of the Lua long bracket `[[`, `[=[`, ... at the start of `text`
+fn long_bracket_open(text: &str) -> Option<usize> {
+    let after_open = text.strip_prefix('[')?;
+    let level = after_open.bytes().take_while(|byte| *byte == b'=').count();
+
+    after_open[level..].starts_with('[').then_some(level)
+}
+
+/// Returns the index just past the long bracket close of `level` found at or after `from`
+fn long_bracket_close(line: &str, from: usize, level: usize) -> Option<usize> {
+    let close = format!("]{}]", "=".repeat(level));
+
+    line.get(from..)?
+        .find(&close)
+        .map(|close_index| from + close_index + close.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::EditorBuffer;
+
+    /// Text and capture name of each highlight of `content` on the lines
+    fn highlights_of(content: &str, start_line: usize, end_line: usize) -> Vec<(String, String)> {
+        highlighted_by(&LuaHighlighter::new(), content, start_line, end_line)
+    }
+
+    fn highlighted_by(
+        highlighter: &dyn SyntaxHighlighter,
+        content: &str,
+        start_line: usize,
+        end_line: usize,
+    ) -> Vec<(String, String)> {
+        let mut buffer = EditorBuffer::new();
+        buffer.insert_at_cursor(content);
+
+        highlighter
+            .highlights(buffer.content.as_ref(), start_line, end_line)
+            .into_iter()
+            .map(|highlight| {
+                (
+                    content[highlight.start_byte..highlight.end_byte].to_string(),
+                    highlight.capture,
+                )
+            })
+            .collect()
+    }
+
+    fn captures(expected: &[(&str, &str)]) -> Vec<(String, String)> {
+        expected
+            .iter()
+            .map(|(text, capture)| (text.to_string(), capture.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn lua_snippet_produces_expected_captures() {
+        let content = "local x = print(\"hi\", 0x1F, true, nil) -- done\n";
+
+        assert_eq!(
+            highlights_of(content, 0, 0),
+            captures(&[
+                ("local", "keyword"),
+                ("print", "function.call"),
+                ("\"hi\"", "string"),
+                ("0x1F", "number"),
+                ("true", "boolean"),
+                ("nil", "constant.builtin"),
+                ("-- done", "comment"),
+            ])
+        );
+    }
+
+    #[test]
+    fn long_strings_and_comments_continue_across_lines() {
+        let content = "x = [[one\ntwo]] y\n--[==[a\nb]==] z\n";
+
+        assert_eq!(
+            highlights_of(content, 0, 3),
+            captures(&[
+                ("[[one", "string"),
+                ("two]]", "string"),
+                ("--[==[a", "comment"),
+                ("b]==]", "comment"),
+            ])
+        );
+        // Starting past the opening line still lexes the lines before it
+        assert_eq!(
+            highlights_of(content, 1, 1),
+            captures(&[("two]]", "string")])
+        );
+    }
+
+    #[cfg(feature = "tree-sitter")]
+    fn lua_tree_sitter_highlighter() -> TreeSitterHighlighter {
+        TreeSitterHighlighter::new(
+            tree_sitter_lua::LANGUAGE.into(),
+            tree_sitter_lua::HIGHLIGHTS_QUERY,
+        )
+    }
+
+    #[cfg(feature = "tree-sitter")]
+    #[test]
+    fn tree_sitter_snippet_produces_expected_capture_ranges() {
+        let content = "local x = print(\"hi\", nil) -- done\n";
+        let mut buffer = EditorBuffer::new();
+        buffer.insert_at_cursor(content);
+
+        let ranges: Vec<_> = lua_tree_sitter_highlighter()
+            .highlights(buffer.content.as_ref(), 0, 0)
+            .into_iter()
+            .map(|highlight| (highlight.start_byte, highlight.end_byte, highlight.capture))
+            .collect();
+        let expected = [
+            (0, 5, "keyword"),
+            (6, 7, "variable"),
+            (8, 9, "operator"),
+            (10, 15, "function.builtin"),
+            (15, 16, "punctuation.bracket"),
+            (16, 20, "string"),
+            (20, 21, "punctuation.delimiter"),
+            (22, 25, "constant.builtin"),
+            (25, 26, "punctuation.bracket"),
+            (27, 34, "comment"),
+        ];
+        assert_eq!(
+            ranges,
+            expected
+                .map(|(start, end, capture)| (start, end, capture.to_string()))
+                .to_vec()
+        );
+    }
+
+    #[cfg(feature = "tree-sitter")]
+    #[test]
+    fn tree_sitter_nested_captures_take_the_innermost_node() {
+        let content = "function f(a)\n  return a.b\nend\n";
+
+        assert_eq!(
+            highlighted_by(&lua_tree_sitter_highlighter(), content, 1, 1),
+            captures(&[
+                ("return", "keyword.return"),
+                ("a", "variable"),
+                (".", "punctuation.delimiter"),
+                ("b", "field"),
+            ])
+        );
+    }
+
+    #[cfg(feature = "tree-sitter")]
+    #[test]
+    fn tree_sitter_highlights_follow_edits() {
+        let mut buffer = EditorBuffer::new();
+        buffer.insert_at_cursor("x = 1\n");
+        buffer.set_language(Some(Language::Lua));
+        assert_eq!(buffer.highlights(0, 0).unwrap()[2].capture, "number");
+
+        buffer.set_cursor_byte_index(0, false);
+        buffer.insert_at_cursor("-- ");
+
+        let highlights = buffer.highlights(0, 0).unwrap();
+        assert_eq!(
+            highlights,
+            vec![Highlight {
+                start_byte: 0,
+                end_byte: 8,
+                capture: "comment".to_string(),
+            }]
+        );
+    }
+}
@@ -10,26 +10,42 @@ use crossterm::{
     terminal::{self, *},
 };
 use regex::{Match, Regex};
-use std::io::{self, ErrorKind, Stdout, Write};
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, ErrorKind, Stdout, Write},
+    time::Instant,
+};
 use unicode_width::UnicodeWidthChar;
 
 use crate::{
-    buffer::{ContentBuffer, EditorBuffer},
+    buffer::{ContentBuffer, EditorBuffer, Highlight},
     editor_frame::EditorFrame,
-    editor_state::{Editor, EditorState},
-    pane::{Pane, PaneNode, PaneNodeType, PaneTree, Split},
+    editor_state::{Bell, Editor, EditorState},
+    pane::{Fold, Pane, PaneNode, PaneNodeType, PaneTree, Split, WrapMode},
+    popup::Popup,
+    prompt::Prompt,
     styling::{self, Styling},
 };
 
+/// Widest sign column drawn, in columns
+pub const MAX_SIGN_WIDTH: u16 = 2;
+
 pub struct Display {
     stdout: Stdout,
+    title: String,
+    /// Screen location of each leaf pane's cursor as of the pane's last render
+    pane_cursor_locations: HashMap<usize, (u16, u16)>,
 }
 
 impl Display {
-    const TITLE: &'static str = "BadRed";
+    pub const TITLE: &'static str = "BadRed";
 
     pub fn new(stdout: Stdout) -> io::Result<Self> {
-        let mut new = Self { stdout };
+        let mut new = Self {
+            stdout,
+            title: Self::TITLE.to_string(),
+            pane_cursor_locations: HashMap::new(),
+        };
         if let Err(e) = new.setup_display() {
             let _ = new.cleanup_display();
 
@@ -52,13 +68,24 @@ impl Display {
     }
 
     pub fn cleanup_display(&mut self) -> io::Result<()> {
-        queue!(self.stdout, LeaveAlternateScreen, cursor::Show)?;
+        queue!(
+            self.stdout,
+            SetTitle(Self::TITLE),
+            LeaveAlternateScreen,
+            cursor::Show
+        )?;
 
         disable_raw_mode()?;
 
         self.stdout.flush()
     }
 
+    /// Returns the screen location of the cursor of the leaf pane at
+    /// `pane_id` when it was last rendered, whether or not it was active.
+    pub fn pane_cursor_location(&self, pane_id: usize) -> Option<(u16, u16)> {
+        self.pane_cursor_locations.get(&pane_id).copied()
+    }
+
     pub fn render(&mut self, editor: &Editor) -> io::Result<()> {
         let editor_state = &editor.state;
         let window_size = terminal::window_size()?;
@@ -69,13 +96,28 @@ impl Display {
             cols: window_size.columns,
         };
 
+        if self.title != editor_state.window_title {
+            self.title = editor_state.window_title.clone();
+            queue!(self.stdout, SetTitle(&self.title))?;
+        }
+
         queue!(self.stdout, cursor::SavePosition, cursor::Hide)?;
-        let cursor = self.render_to_pane(
+        let mut cursor = self.render_to_pane(
             editor_state,
             &editor_frame,
             &editor_state.pane_tree,
             editor_state.pane_tree.root_index(),
         )?;
+        retain_shown_pane_locations(&mut self.pane_cursor_locations, &editor_state.pane_tree);
+        if let Some(popup) = &editor_state.popup {
+            self.render_popup(popup, &editor_frame)?;
+        }
+        if let Some(prompt) = &editor_state.prompt {
+            cursor = self.render_prompt(prompt, &editor_frame)?;
+        }
+        if editor_state.pending_bell == Some(Bell::Audible) {
+            queue!(self.stdout, style::Print('\x07'))?;
+        }
         queue!(self.stdout, cursor::RestorePosition)?;
         if let Some((row, col)) = cursor {
             queue!(self.stdout, cursor::MoveTo(col, row), cursor::Show)?;
@@ -91,6 +133,11 @@ impl Display {
         pane_tree: &PaneTree,
         node_index: usize,
     ) -> io::Result<Option<(u16, u16)>> {
+        // Panes hidden by a maximized pane have no space to render into
+        if editor_frame.rows == 0 || editor_frame.cols == 0 {
+            return Ok(None);
+        }
+
         let node = pane_tree
             .tree
             .get(node_index)
@@ -105,6 +152,9 @@ impl Display {
             PaneNodeType::Leaf(ref pane) => {
                 let pane_cursor =
                     self.render_leaf_pane(node, pane, node_index, editor_state, editor_frame)?;
+                if let Some(pane_cursor) = pane_cursor {
+                    self.pane_cursor_locations.insert(node_index, pane_cursor);
+                }
                 if editor_state.active_pane_index == node_index {
                     Ok(pane_cursor)
                 } else {
@@ -276,61 +326,100 @@ impl Display {
         editor_state: &EditorState,
         editor_frame: &EditorFrame,
     ) -> io::Result<Option<(u16, u16)>> {
-        let mut cursor_screen_location: Option<(u16, u16)> = None;
         let buffer = buffer_by_id(editor_state, pane.buffer_id)?;
 
         if !needs_render(buffer, pane_node, editor_state, pane_id) {
             return Ok(None);
         }
 
+        let is_active = editor_state.active_pane_index == pane_id;
+        let cursor_byte_index = pane_cursor_byte_index(
+            buffer,
+            editor_state.active_tab_index,
+            pane_id,
+            is_active,
+        );
+        let draw_cursor_block = draws_inactive_cursor(editor_state, is_active);
+        let tab_width = editor_state.options.tab_width_for(buffer);
+        let cursor_screen_location =
+            cursor_screen_location(buffer, pane, editor_frame, cursor_byte_index, tab_width);
+        let sign_width = sign_column_width(buffer, editor_frame.cols);
+
         let mut current_buffer_line_index = pane.top_line;
         let mut pane_lines_remaining = editor_frame.rows;
 
         let default_regex = Self::default_style_regex()?;
+        let is_flashing = editor_state.is_visual_bell_shown(Instant::now())
+            && editor_state.active_pane_index == pane_id;
 
         crossterm::queue!(
             self.stdout,
             cursor::MoveTo(editor_frame.x_col, editor_frame.y_row)
         )?;
+        if is_flashing {
+            queue!(self.stdout, style::SetAttribute(style::Attribute::Reverse))?;
+        }
 
         while pane_lines_remaining > 0 {
             let mut column_index = editor_frame.x_col;
-            if let Some(buffer_line_copy) = buffer.content_copy_line(current_buffer_line_index)
+            let row_background = line_background(buffer, editor_state, current_buffer_line_index);
+            self.render_sign(
+                buffer,
+                editor_state,
+                current_buffer_line_index,
+                sign_width,
+                &mut column_index,
+            )?;
+
+            if let Some(fold) = pane
+                .fold_containing(current_buffer_line_index)
+                .filter(|_| current_buffer_line_index < buffer.content_line_count())
             {
-                if let Some(mut current_byte_index) =
-                    buffer.line_start_byte_index(current_buffer_line_index)
-                {
-                    self.render_line(
-                        buffer_line_copy,
-                        buffer,
-                        &default_regex,
-                        editor_state,
-                        editor_frame,
-                        pane,
-                        &mut current_byte_index,
-                        &mut cursor_screen_location,
-                        &mut pane_lines_remaining,
-                        &mut column_index,
-                    )?;
-                } else {
-                    if cursor_screen_location.is_none() {
-                        cursor_screen_location =
-                            Some((editor_frame.rows - pane_lines_remaining, 0));
-                    }
-                };
+                self.render_fold(fold, buffer, editor_state, editor_frame, &mut column_index)?;
+                current_buffer_line_index = fold.end_line;
+            } else if let (Some(line), Some(line_start_byte_index)) = (
+                buffer.content_copy_line(current_buffer_line_index),
+                buffer.line_start_byte_index(current_buffer_line_index),
+            ) {
+                let mut layout = LineLayout::new(
+                    buffer,
+                    pane,
+                    &line,
+                    line_start_byte_index,
+                    (column_index, editor_frame.x_col + editor_frame.cols),
+                    pane_lines_remaining,
+                    tab_width,
+                );
+                self.render_line(
+                    &mut layout,
+                    &default_regex,
+                    editor_state,
+                    editor_frame,
+                    cursor_byte_index,
+                    draw_cursor_block,
+                )?;
+                column_index = layout.column;
+                pane_lines_remaining -= layout.wrapped_rows;
             }
 
+            // Line highlights extend past the end of the line's text
+            let fill = style::style(
+                vec![
+                    " ";
+                    (editor_frame.x_col + editor_frame.cols)
+                        .saturating_sub(column_index)
+                        .into()
+                ]
+                .join(""),
+            );
+            let fill = if let Some(background) = row_background {
+                fill.on(Color::from(background))
+            } else {
+                fill
+            };
             crossterm::queue!(
                 self.stdout,
-                style::Print(
-                    vec![
-                        " ";
-                        (editor_frame.x_col + editor_frame.cols)
-                            .saturating_sub(column_index)
-                            .into()
-                    ]
-                    .join("")
-                ),
+                style::PrintStyledContent(fill),
                 cursor::MoveDown(1),
                 cursor::MoveToColumn(editor_frame.x_col),
             )?;
@@ -338,9 +427,36 @@ impl Display {
             current_buffer_line_index += 1;
         }
 
+        if is_flashing {
+            queue!(self.stdout, style::SetAttribute(style::Attribute::NoReverse))?;
+        }
+
         return Ok(cursor_screen_location);
     }
 
+    fn render_fold(
+        &mut self,
+        fold: &Fold,
+        buffer: &EditorBuffer,
+        editor_state: &EditorState,
+        editor_frame: &EditorFrame,
+        column_index: &mut u16,
+    ) -> io::Result<()> {
+        let summary = fold_summary(fold, buffer);
+        let text_style = editor_state.style_map.get(Styling::FOLD_NAME);
+
+        for summary_char in summary.chars() {
+            if *column_index >= editor_frame.x_col + editor_frame.cols {
+                break;
+            }
+
+            render_char(&mut self.stdout, 1, summary_char, text_style, None)?;
+            *column_index += 1;
+        }
+
+        Ok(())
+    }
+
     fn default_style_regex() -> io::Result<Regex> {
         Regex::new(Self::DEFAULT_STYLE_MATCH).map_err(|e| {
             io::Error::new(
@@ -350,97 +466,320 @@ impl Display {
         })
     }
 
+    /// Draws the line laid out by `layout`, wrapping onto later rows of the
+    /// pane as the layout does.
     fn render_line(
         &mut self,
-        mut buffer_line_copy: String,
-        buffer: &EditorBuffer,
+        layout: &mut LineLayout,
         default_regex: &Regex,
         editor_state: &EditorState,
         editor_frame: &EditorFrame,
-        pane: &Pane,
-        current_byte_index: &mut usize,
-        cursor_screen_location: &mut Option<(u16, u16)>,
-        pane_lines_remaining: &mut u16,
-        column_index: &mut u16,
+        cursor_byte_index: usize,
+        draw_cursor_block: bool,
     ) -> io::Result<()> {
-        let starting_column = *column_index;
-
-        'line_render: while !buffer_line_copy.is_empty() {
-            let mut matched_style: Option<(Match, &str)> = None;
-            for style in buffer.styling.style_list.iter().rev() {
-                if let Some(found) = style.regex.find(&buffer_line_copy) {
-                    matched_style = Some((found, &style.name));
+        let buffer = layout.buffer;
+        let pane = layout.pane;
+        let line_index = buffer.line_index_for_byte_index(layout.byte_index);
+        let line_highlights = buffer.highlights(line_index, line_index);
+
+        let mut rest = layout.line;
+        'line_render: while !rest.is_empty() {
+            let (match_end, style) = if let Some(highlights) = &line_highlights {
+                highlighted_match(highlights, layout.byte_index, rest.len())
+            } else {
+                let mut matched_style: Option<(Match, &str)> = None;
+                for style in buffer.styling.style_list.iter().rev() {
+                    if let Some(found) = style.regex.as_ref().and_then(|regex| regex.find(rest)) {
+                        matched_style = Some((found, &style.name));
+                    }
                 }
-            }
-            let (found, style) = matched_style.unwrap_or_else(|| {
-                (
-                    default_regex.find(&buffer_line_copy).unwrap(),
-                    Styling::DEFAULT_NAME,
-                )
-            });
+                let (found, style) = matched_style.unwrap_or_else(|| {
+                    (default_regex.find(rest).unwrap(), Styling::DEFAULT_NAME)
+                });
+
+                (found.end(), style)
+            };
             let text_style = editor_state.style_map.get(style);
-            let rest = buffer_line_copy.split_off(found.end());
-            let matched_text = buffer_line_copy;
-            buffer_line_copy = rest;
+            let tab_style = pane
+                .tab_spaces
+                .and_then(|_| editor_state.style_map.get(Styling::TAB_NAME))
+                .or(text_style);
+            let (matched_text, new_rest) = rest.split_at(match_end);
+            rest = new_rest;
 
             for matched_char in matched_text.chars() {
-                if *current_byte_index == buffer.cursor_byte_index()
-                    && cursor_screen_location.is_none()
-                {
-                    *cursor_screen_location = Some((
-                        editor_frame.y_row + editor_frame.rows - *pane_lines_remaining,
-                        *column_index,
-                    ));
+                let wrapped_rows = layout.wrapped_rows;
+                let Some(placement) = layout.place(matched_char) else {
+                    break 'line_render;
+                };
+                if layout.wrapped_rows != wrapped_rows {
+                    self.move_to_wrapped_row(editor_frame, layout.continuation_column)?;
                 }
 
-                let char_width =
-                    width_for(matched_char, *column_index, editor_state.options.tab_width);
-                if char_width == 0 {
+                let mut phantom_column = placement.phantom_column;
+                self.render_phantoms(
+                    buffer,
+                    editor_state,
+                    editor_frame,
+                    placement.byte_index,
+                    &mut phantom_column,
+                )?;
+
+                let is_cursor_char = placement.byte_index == cursor_byte_index;
+                let is_cursor_block = ((draw_cursor_block && is_cursor_char)
+                    || buffer.has_secondary_cursor_at(placement.byte_index))
+                    && matched_char != '\n';
+                if is_cursor_block {
+                    queue!(self.stdout, style::SetAttribute(style::Attribute::Reverse))?;
+                    queue!(self.stdout, style::SetAttribute(style::Attribute::Dim))?;
+                }
+                if placement.is_escaped {
                     // Print as utf8 code point to handle display
                     let code_point_literal = matched_char.escape_unicode().to_string();
-                    *column_index += code_point_literal
-                        .chars()
-                        .map(|c| c.width().unwrap_or(1) as u16)
-                        .sum::<u16>();
                     crossterm::queue!(self.stdout, style::Print(code_point_literal))?;
-                } else if matched_char == '\n' {
-                    break 'line_render;
-                } else {
-                    *column_index += char_width as u16;
-                    render_char(&mut self.stdout, char_width, matched_char, text_style)?;
+                } else if matched_char != '\n' {
+                    let background = char_background(
+                        buffer,
+                        editor_state,
+                        line_index,
+                        placement.byte_index,
+                        cursor_byte_index,
+                    );
+                    let char_style = if matched_char == '\t' {
+                        tab_style
+                    } else {
+                        text_style
+                    };
+                    render_char(
+                        &mut self.stdout,
+                        placement.width.into(),
+                        matched_char,
+                        char_style,
+                        background,
+                    )?;
+                }
+                if is_cursor_block {
+                    queue!(self.stdout, style::SetAttribute(style::Attribute::NoReverse))?;
+                    queue!(self.stdout, style::SetAttribute(style::Attribute::NormalIntensity))?;
                 }
 
-                *current_byte_index += matched_char.len_utf8();
-                if *column_index >= (editor_frame.x_col + editor_frame.cols) {
-                    if !pane.should_wrap {
-                        break 'line_render;
-                    } else {
-                        let Some(new_pane_lines_remaining) = pane_lines_remaining.checked_sub(1)
-                        else {
-                            break 'line_render;
-                        };
-                        *pane_lines_remaining = new_pane_lines_remaining;
-                        *column_index = starting_column;
-                        crossterm::queue!(
-                            self.stdout,
-                            cursor::MoveDown(1),
-                            cursor::MoveToColumn(starting_column)
-                        )?;
-                    }
+                let wrapped_rows = layout.wrapped_rows;
+                let is_line_continued = layout.advance(matched_char, &placement);
+                if layout.wrapped_rows != wrapped_rows {
+                    self.move_to_wrapped_row(editor_frame, layout.continuation_column)?;
+                }
+                if !is_line_continued {
+                    break 'line_render;
                 }
             }
         }
 
-        if *current_byte_index == buffer.cursor_byte_index() {
-            *cursor_screen_location = Some((
-                editor_frame.y_row + editor_frame.rows - *pane_lines_remaining,
-                *column_index,
-            ));
+        let mut phantom_column = layout.place_end();
+        if layout.byte_index == buffer.content_byte_length() {
+            self.render_phantoms(
+                buffer,
+                editor_state,
+                editor_frame,
+                layout.byte_index,
+                &mut phantom_column,
+            )?;
+        }
+
+        let is_cursor_end = layout.byte_index == cursor_byte_index && draw_cursor_block;
+        if (is_cursor_end || buffer.has_secondary_cursor_at(layout.byte_index))
+            && layout.column < layout.end_column
+        {
+            queue!(self.stdout, style::PrintStyledContent(" ".reverse().dim()))?;
+            layout.column += 1;
         }
 
         Ok(())
     }
 
+    /// Moves drawing to the start of the pane's next row, blanking the sign
+    /// column and any indent before `continuation_column`
+    fn move_to_wrapped_row(
+        &mut self,
+        editor_frame: &EditorFrame,
+        continuation_column: u16,
+    ) -> io::Result<()> {
+        let indent_width = continuation_column.saturating_sub(editor_frame.x_col);
+        crossterm::queue!(
+            self.stdout,
+            cursor::MoveDown(1),
+            cursor::MoveToColumn(editor_frame.x_col),
+            style::Print(" ".repeat(indent_width.into())),
+        )?;
+
+        Ok(())
+    }
+
+    /// Draws the sign beside `line_index` padded out to `sign_width` columns,
+    /// or blank columns if the line has no sign.
+    fn render_sign(
+        &mut self,
+        buffer: &EditorBuffer,
+        editor_state: &EditorState,
+        line_index: usize,
+        sign_width: u16,
+        column_index: &mut u16,
+    ) -> io::Result<()> {
+        let sign_end_column = *column_index + sign_width;
+        if let Some((sign_text, style_name)) = fitted_sign(buffer, line_index, sign_width) {
+            let text_style = editor_state.style_map.get(style_name);
+            for sign_char in sign_text.chars() {
+                let char_width = sign_char.width().unwrap_or(0);
+                render_char(&mut self.stdout, char_width, sign_char, text_style, None)?;
+                *column_index += char_width as u16;
+            }
+        }
+
+        queue!(
+            self.stdout,
+            style::Print(" ".repeat((sign_end_column - *column_index).into()))
+        )?;
+        *column_index = sign_end_column;
+
+        Ok(())
+    }
+
+    /// Draws any phantom text placed at `byte_index` without wrapping. Phantom
+    /// text is not part of the buffer's content, so byte indices are unchanged.
+    fn render_phantoms(
+        &mut self,
+        buffer: &EditorBuffer,
+        editor_state: &EditorState,
+        editor_frame: &EditorFrame,
+        byte_index: usize,
+        column_index: &mut u16,
+    ) -> io::Result<()> {
+        let tab_width = editor_state.options.tab_width_for(buffer);
+        for phantom in buffer
+            .phantoms
+            .iter()
+            .filter(|phantom| phantom.byte_index == byte_index)
+        {
+            let text_style = editor_state.style_map.get(&phantom.style);
+            for phantom_char in phantom.text.chars() {
+                let char_width = width_for(phantom_char, *column_index, tab_width);
+                if char_width == 0 || phantom_char == '\n' {
+                    continue;
+                }
+                if *column_index as usize + char_width
+                    > (editor_frame.x_col + editor_frame.cols).into()
+                {
+                    return Ok(());
+                }
+
+                render_char(&mut self.stdout, char_width, phantom_char, text_style, None)?;
+                *column_index += char_width as u16;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn render_popup(&mut self, popup: &Popup, editor_frame: &EditorFrame) -> io::Result<()> {
+        let popup_frame = popup.frame(editor_frame);
+        if popup_frame.rows < Popup::BORDER_SIZE || popup_frame.cols < Popup::BORDER_SIZE {
+            return Ok(());
+        }
+
+        let inner_rows = popup_frame.rows - Popup::BORDER_SIZE;
+        let inner_cols = usize::from(popup_frame.cols - Popup::BORDER_SIZE);
+        let horizontal_border = "─".repeat(inner_cols);
+
+        queue!(
+            self.stdout,
+            cursor::MoveTo(popup_frame.x_col, popup_frame.y_row),
+            style::Print(format!("┌{}┐", horizontal_border)),
+        )?;
+
+        let first_item = popup.first_visible_item(inner_rows);
+        for row in 0..inner_rows {
+            let item_index = first_item + usize::from(row);
+            let mut item_text = String::new();
+            let mut item_width = 0;
+            for item_char in popup
+                .items
+                .get(item_index)
+                .map(|item| item.as_str())
+                .unwrap_or("")
+                .chars()
+            {
+                let char_width = item_char.width().unwrap_or(0);
+                if item_width + char_width > inner_cols {
+                    break;
+                }
+                item_width += char_width;
+                item_text.push(item_char);
+            }
+            item_text.push_str(&" ".repeat(inner_cols - item_width));
+
+            queue!(
+                self.stdout,
+                cursor::MoveTo(popup_frame.x_col, popup_frame.y_row + 1 + row),
+                style::Print("│"),
+            )?;
+            if popup.selected == Some(item_index) {
+                queue!(self.stdout, style::PrintStyledContent(item_text.reverse()))?;
+            } else {
+                queue!(self.stdout, style::Print(item_text))?;
+            }
+            queue!(self.stdout, style::Print("│"))?;
+        }
+
+        queue!(
+            self.stdout,
+            cursor::MoveTo(
+                popup_frame.x_col,
+                popup_frame.y_row + popup_frame.rows - 1
+            ),
+            style::Print(format!("└{}┘", horizontal_border)),
+        )
+    }
+
+    /// Draws `prompt` and its input over the bottom row of the editor, returning
+    /// the screen location of the input's cursor.
+    fn render_prompt(
+        &mut self,
+        prompt: &Prompt,
+        editor_frame: &EditorFrame,
+    ) -> io::Result<Option<(u16, u16)>> {
+        if editor_frame.rows == 0 {
+            return Ok(None);
+        }
+
+        let row = editor_frame.y_row + editor_frame.rows - 1;
+        let cols = usize::from(editor_frame.cols);
+        let input = prompt.input.content_copy();
+        let cursor_byte_index = prompt.input.cursor_byte_index();
+
+        queue!(self.stdout, cursor::MoveTo(editor_frame.x_col, row))?;
+        let mut line_width = 0;
+        let mut cursor_col = None;
+        let prompt_chars = prompt.prompt.chars().map(|char| (None, char));
+        let input_chars = input
+            .char_indices()
+            .map(|(byte_index, char)| (Some(byte_index), char));
+        for (byte_index, char) in prompt_chars.chain(input_chars) {
+            if byte_index == Some(cursor_byte_index) {
+                cursor_col = Some(line_width);
+            }
+
+            let char_width = char.width().unwrap_or(0);
+            if line_width + char_width > cols {
+                break;
+            }
+            line_width += char_width;
+            queue!(self.stdout, style::Print(char))?;
+        }
+        queue!(self.stdout, style::Print(" ".repeat(cols - line_width)))?;
+
+        let cursor_col = cursor_col.unwrap_or(line_width).min(cols.saturating_sub(1));
+        Ok(Some((row, editor_frame.x_col + cursor_col as u16)))
+    }
+
     fn render_frame_v_gap(
         &mut self,
         is_active: bool,
@@ -496,7 +835,451 @@ impl Display {
     }
 }
 
-fn width_for(character: char, at_col: u16, tab_width: u16) -> usize {
+/// Text drawn in place of the lines hidden by `fold`, counting only lines
+/// which exist in `buffer`.
+fn fold_summary(fold: &Fold, buffer: &EditorBuffer) -> String {
+    let last_line = fold
+        .end_line
+        .min(buffer.content_line_count().saturating_sub(1));
+    format!("+-- {} lines", last_line - fold.start_line + 1)
+}
+
+/// Screen location at which the leaf `pane` showing `buffer` within
+/// `editor_frame` draws the cursor at `cursor_byte_index`. Rows are laid out
+/// the same way they are drawn, following the pane's scrolling, folds,
+/// wrapping, and signs along with tabs and phantom text, so the location can
+/// be found without drawing. `None` if the cursor is scrolled out of the pane.
+pub fn cursor_screen_location(
+    buffer: &EditorBuffer,
+    pane: &Pane,
+    editor_frame: &EditorFrame,
+    cursor_byte_index: usize,
+    tab_width: u16,
+) -> Option<(u16, u16)> {
+    let cursor_line_index = buffer.line_index_for_byte_index(cursor_byte_index);
+    let start_column = editor_frame.x_col + sign_column_width(buffer, editor_frame.cols);
+    let end_column = editor_frame.x_col + editor_frame.cols;
+    let end_row = editor_frame.y_row + editor_frame.rows;
+
+    let mut line_index = pane.top_line;
+    let mut pane_lines_remaining = editor_frame.rows;
+    while pane_lines_remaining > 0 {
+        let row = end_row - pane_lines_remaining;
+        if let Some(fold) = pane
+            .fold_containing(line_index)
+            .filter(|_| line_index < buffer.content_line_count())
+        {
+            if fold.contains(cursor_line_index) {
+                return Some((row, start_column));
+            }
+            line_index = fold.end_line;
+        } else if line_index > cursor_line_index {
+            return None;
+        } else {
+            let line = buffer.content_copy_line(line_index)?;
+            let line_start_byte_index = buffer.line_start_byte_index(line_index)?;
+            let (wrapped_rows, cursor_location) = LineLayout::new(
+                buffer,
+                pane,
+                &line,
+                line_start_byte_index,
+                (start_column, end_column),
+                pane_lines_remaining,
+                tab_width,
+            )
+            .locate(cursor_byte_index);
+
+            if line_index == cursor_line_index {
+                return cursor_location
+                    .map(|(wrapped_rows, column)| (row + wrapped_rows, column))
+                    .filter(|(row, _)| *row < end_row);
+            }
+            pane_lines_remaining -= wrapped_rows;
+        }
+
+        pane_lines_remaining = pane_lines_remaining.saturating_sub(1);
+        line_index += 1;
+    }
+
+    None
+}
+
+/// Places the characters of a line on the rows of a pane, following the
+/// pane's wrapping along with tabs and phantom text. Shared by drawing lines
+/// and by finding where the cursor is drawn without drawing.
+struct LineLayout<'a> {
+    buffer: &'a EditorBuffer,
+    pane: &'a Pane,
+    line: &'a str,
+    line_start_byte_index: usize,
+    end_column: u16,
+    /// Column rows the line wraps onto start at
+    continuation_column: u16,
+    tab_width: u16,
+    rows_remaining: u16,
+    /// Byte index the current row breaks before when wrapping at words
+    row_break: Option<usize>,
+
+    /// Byte index of the next character to place
+    byte_index: usize,
+    /// Column the next character or phantom text is placed at
+    column: u16,
+    /// Count of rows the line has wrapped onto after its first
+    wrapped_rows: u16,
+}
+
+/// Where `LineLayout` places a character
+struct CharPlacement {
+    byte_index: usize,
+    /// Column the phantom text placed before the character starts at
+    phantom_column: u16,
+    /// Column the character starts at, after any phantom text
+    column: u16,
+    width: u16,
+    /// Whether the character has no width, so is drawn as its escaped code
+    /// point
+    is_escaped: bool,
+}
+
+impl<'a> LineLayout<'a> {
+    /// Lays out `line` of `buffer`, starting at `line_start_byte_index`,
+    /// between the columns of `bounds` with `rows_remaining` rows left in the
+    /// pane.
+    fn new(
+        buffer: &'a EditorBuffer,
+        pane: &'a Pane,
+        line: &'a str,
+        line_start_byte_index: usize,
+        (start_column, end_column): (u16, u16),
+        rows_remaining: u16,
+        tab_width: u16,
+    ) -> Self {
+        let continuation_column = if pane.wrap_indent {
+            start_column
+                + wrap_indent_width(
+                    line,
+                    start_column,
+                    end_column - start_column,
+                    tab_width,
+                    pane.tab_spaces,
+                )
+        } else {
+            start_column
+        };
+
+        let mut layout = Self {
+            buffer,
+            pane,
+            line,
+            line_start_byte_index,
+            end_column,
+            continuation_column,
+            tab_width,
+            rows_remaining,
+            row_break: None,
+            byte_index: line_start_byte_index,
+            column: start_column,
+            wrapped_rows: 0,
+        };
+        layout.row_break = layout.next_row_break();
+
+        layout
+    }
+
+    /// Places `character`, first wrapping onto the next row if the row breaks
+    /// before it. `None` if the row breaks but the pane has no rows left.
+    fn place(&mut self, character: char) -> Option<CharPlacement> {
+        if self.row_break == Some(self.byte_index) && character != '\n' && !self.wrap() {
+            return None;
+        }
+
+        let phantom_column = self.column;
+        self.column += phantom_width(
+            self.buffer,
+            self.byte_index,
+            self.column,
+            self.end_column,
+            self.tab_width,
+        );
+
+        let char_width =
+            display_width_for(character, self.column, self.tab_width, self.pane.tab_spaces);
+        let width = if char_width == 0 {
+            character
+                .escape_unicode()
+                .map(|c| c.width().unwrap_or(1) as u16)
+                .sum::<u16>()
+        } else {
+            char_width as u16
+        };
+
+        Some(CharPlacement {
+            byte_index: self.byte_index,
+            phantom_column,
+            column: self.column,
+            width,
+            is_escaped: char_width == 0,
+        })
+    }
+
+    /// Moves past `character` at `placement`, wrapping onto the next row if
+    /// it reached the end of its row. Returns false if nothing more of the
+    /// line is placed, as the line ended or its row ended without wrapping.
+    fn advance(&mut self, character: char, placement: &CharPlacement) -> bool {
+        if character == '\n' && !placement.is_escaped {
+            return false;
+        }
+
+        self.column = placement.column + placement.width;
+        self.byte_index += character.len_utf8();
+        if self.column >= self.end_column {
+            return self.pane.wrap_mode != WrapMode::None && self.wrap();
+        }
+
+        true
+    }
+
+    /// Places the phantom text after the end of the content if the line ends
+    /// the content, returning the column the phantom text starts at
+    fn place_end(&mut self) -> u16 {
+        let phantom_column = self.column;
+        if self.byte_index == self.buffer.content_byte_length() {
+            self.column += phantom_width(
+                self.buffer,
+                self.byte_index,
+                self.column,
+                self.end_column,
+                self.tab_width,
+            );
+        }
+
+        phantom_column
+    }
+
+    /// Places the whole line. Returns the count of rows the line wraps onto
+    /// after its first, and the rows past the first and column at which the
+    /// cursor at `cursor_byte_index` is drawn if it is drawn on the line.
+    fn locate(mut self, cursor_byte_index: usize) -> (u16, Option<(u16, u16)>) {
+        let mut cursor_location = None;
+        for character in self.line.chars() {
+            let Some(placement) = self.place(character) else {
+                break;
+            };
+            if placement.byte_index == cursor_byte_index && cursor_location.is_none() {
+                cursor_location = Some((self.wrapped_rows, placement.column));
+            }
+            if !self.advance(character, &placement) {
+                break;
+            }
+        }
+
+        self.place_end();
+        if self.byte_index == cursor_byte_index {
+            cursor_location = Some((self.wrapped_rows, self.column));
+        }
+
+        (self.wrapped_rows, cursor_location)
+    }
+
+    /// Byte index the row starting at the next character breaks before when
+    /// wrapping at words
+    fn next_row_break(&self) -> Option<usize> {
+        (self.pane.wrap_mode == WrapMode::Word).then(|| {
+            self.byte_index
+                + word_wrap_break(
+                    &self.line[self.byte_index - self.line_start_byte_index..],
+                    self.column,
+                    self.end_column,
+                    self.tab_width,
+                    self.pane.tab_spaces,
+                )
+        })
+    }
+
+    /// Moves to the start of the next row. Returns false without moving if
+    /// the pane has no rows left.
+    fn wrap(&mut self) -> bool {
+        let Some(rows_remaining) = self.rows_remaining.checked_sub(1) else {
+            return false;
+        };
+        self.rows_remaining = rows_remaining;
+        self.wrapped_rows += 1;
+        self.column = self.continuation_column;
+        self.row_break = self.next_row_break();
+
+        true
+    }
+}
+
+/// Count of screen rows the line at `line_index` of `buffer` takes when drawn
+/// `width` columns wide, following the wrapping of `pane`. Lines past the end
+/// of the content take a single empty row.
+pub fn line_visual_rows(
+    buffer: &EditorBuffer,
+    pane: &Pane,
+    line_index: usize,
+    width: u16,
+    tab_width: u16,
+) -> usize {
+    let (Some(line), Some(line_start_byte_index), true) = (
+        buffer.content_copy_line(line_index),
+        buffer.line_start_byte_index(line_index),
+        width > 0,
+    ) else {
+        return 1;
+    };
+
+    let (wrapped_rows, _) = LineLayout::new(
+        buffer,
+        pane,
+        &line,
+        line_start_byte_index,
+        (0, width),
+        u16::MAX,
+        tab_width,
+    )
+    .locate(usize::MAX);
+
+    usize::from(wrapped_rows) + 1
+}
+
+/// Columns taken by the phantom text drawn at `byte_index` from `column`, which
+/// is cut off at `end_column` as `render_phantoms` draws it.
+fn phantom_width(
+    buffer: &EditorBuffer,
+    byte_index: usize,
+    column: u16,
+    end_column: u16,
+    tab_width: u16,
+) -> u16 {
+    let mut width = 0;
+    for phantom in buffer
+        .phantoms
+        .iter()
+        .filter(|phantom| phantom.byte_index == byte_index)
+    {
+        for phantom_char in phantom.text.chars() {
+            let char_width = width_for(phantom_char, column + width, tab_width);
+            if char_width == 0 || phantom_char == '\n' {
+                continue;
+            }
+            if (column + width) as usize + char_width > end_column.into() {
+                return width;
+            }
+
+            width += char_width as u16;
+        }
+    }
+
+    width
+}
+
+/// Returns the byte length of the start of `line` which fits on a screen row
+/// from `start_column` up to `end_column` when wrapping at word boundaries.
+/// The row breaks after the last whitespace which fits, before whitespace
+/// which does not fit, or within a word if no whitespace fits. At least one
+/// character is always included so that wrapping makes progress.
+pub fn word_wrap_break(
+    line: &str,
+    start_column: u16,
+    end_column: u16,
+    tab_width: u16,
+    tab_spaces: Option<u16>,
+) -> usize {
+    let mut column = start_column;
+    let mut last_break = None;
+    for (byte_index, character) in line.char_indices() {
+        if character == '\n' {
+            return byte_index;
+        }
+
+        let char_width = match display_width_for(character, column, tab_width, tab_spaces) {
+            // Zero width characters are drawn as their escaped code point
+            0 => character.escape_unicode().count(),
+            char_width => char_width,
+        };
+        let char_width = u16::try_from(char_width).unwrap_or(u16::MAX);
+        if byte_index > 0 && column.saturating_add(char_width) > end_column {
+            // Whitespace past the edge ends the row without splitting a word
+            if character.is_whitespace() {
+                return byte_index;
+            }
+            return last_break.unwrap_or(byte_index);
+        }
+
+        column = column.saturating_add(char_width);
+        if character.is_whitespace() {
+            last_break = Some(byte_index + character.len_utf8());
+        }
+    }
+
+    line.len()
+}
+
+/// Columns taken by the sign column of `buffer` in a pane `pane_cols` wide.
+/// The column fits the buffer's widest sign up to `MAX_SIGN_WIDTH`, leaving at
+/// least one column for text, and is hidden while the buffer has no signs.
+pub fn sign_column_width(buffer: &EditorBuffer, pane_cols: u16) -> u16 {
+    let Some(widest_sign) = buffer
+        .signs
+        .values()
+        .map(|sign| sign.text.chars().filter_map(|c| c.width()).sum::<usize>())
+        .max()
+    else {
+        return 0;
+    };
+
+    (widest_sign.clamp(1, MAX_SIGN_WIDTH.into()) as u16).min(pane_cols.saturating_sub(1))
+}
+
+/// Text of the sign beside `line_index` cut to fit `sign_width` columns along
+/// with the name of its style, or `None` if the line has no sign. Chars
+/// without a width are left out.
+fn fitted_sign(
+    buffer: &EditorBuffer,
+    line_index: usize,
+    sign_width: u16,
+) -> Option<(String, &str)> {
+    let sign = buffer.signs.get(&line_index)?;
+
+    let mut width = 0;
+    let text = sign
+        .text
+        .chars()
+        .filter(|sign_char| sign_char.width().unwrap_or(0) > 0)
+        .take_while(|sign_char| {
+            width += sign_char.width().unwrap_or(0) as u16;
+            width <= sign_width
+        })
+        .collect();
+
+    Some((text, &sign.style))
+}
+
+/// Width of the leading whitespace of `line` drawn from `start_column`, used
+/// to indent wrapped rows. No indent is used if it would take half the row.
+fn wrap_indent_width(
+    line: &str,
+    start_column: u16,
+    row_width: u16,
+    tab_width: u16,
+    tab_spaces: Option<u16>,
+) -> u16 {
+    let mut column = start_column;
+    for character in line.chars().take_while(|c| *c == ' ' || *c == '\t') {
+        let char_width = display_width_for(character, column, tab_width, tab_spaces);
+        column = column.saturating_add(char_width as u16);
+    }
+
+    let indent = column - start_column;
+    if indent >= row_width / 2 {
+        0
+    } else {
+        indent
+    }
+}
+
+pub fn width_for(character: char, at_col: u16, tab_width: u16) -> usize {
     if character == '\t' {
         (tab_width - at_col % tab_width).into()
     } else {
@@ -504,40 +1287,120 @@ fn width_for(character: char, at_col: u16, tab_width: u16) -> usize {
     }
 }
 
+/// Width `character` is drawn at in a pane whose tabs are drawn as
+/// `tab_spaces` columns, if set, rather than reaching the next tab stop. Tabs
+/// are always at least one column wide.
+fn display_width_for(
+    character: char,
+    at_col: u16,
+    tab_width: u16,
+    tab_spaces: Option<u16>,
+) -> usize {
+    match tab_spaces {
+        Some(spaces) if character == '\t' => spaces.max(1).into(),
+        _ => width_for(character, at_col, tab_width),
+    }
+}
+
+/// Draws `character` in `text_style`. A `line_background` from a search match
+/// or line highlight takes precedence over the text style's own background.
 fn render_char(
     stdout: &mut Stdout,
     width: usize,
     character: char,
     text_style: Option<&styling::TextStyle>,
+    line_background: Option<&styling::Color>,
 ) -> io::Result<()> {
-    if character == '\t' {
-        if let Some(text_style) = text_style {
-            let spacing = " ".repeat(width).with(Color::from(&text_style.foreground));
+    let content = if character == '\t' {
+        style::style(" ".repeat(width))
+    } else {
+        style::style(character.to_string())
+    };
+    let content = if let Some(text_style) = text_style {
+        content.with(Color::from(&text_style.foreground))
+    } else {
+        content
+    };
+    let background = line_background.or(text_style.and_then(|style| style.background.as_ref()));
+    let content = if let Some(background) = background {
+        content.on(Color::from(background))
+    } else {
+        content
+    };
 
-            let spacing = if let Some(ref background) = &text_style.background {
-                spacing.on(Color::from(background))
-            } else {
-                spacing
-            };
+    queue!(stdout, style::PrintStyledContent(content))
+}
 
-            queue!(stdout, style::PrintStyledContent(spacing))?;
-        } else {
-            queue!(stdout, style::Print(" ".repeat(width)))?;
-        }
+/// Background of the line highlight on `line_index` of `buffer`, if any
+fn line_background<'a>(
+    buffer: &EditorBuffer,
+    editor_state: &'a EditorState,
+    line_index: usize,
+) -> Option<&'a styling::Color> {
+    let style = buffer.line_highlights.get(&line_index)?;
+    editor_state.style_map.get(style)?.background.as_ref()
+}
+
+/// Background drawn behind the char at `byte_index` on `line_index`, with a
+/// search match's background drawn over the line highlight's
+fn char_background<'a>(
+    buffer: &EditorBuffer,
+    editor_state: &'a EditorState,
+    line_index: usize,
+    byte_index: usize,
+    cursor_byte_index: usize,
+) -> Option<&'a styling::Color> {
+    search_background(buffer, editor_state, byte_index, cursor_byte_index)
+        .or_else(|| line_background(buffer, editor_state, line_index))
+}
+
+/// Background of the search style for the search match of `buffer` containing
+/// `byte_index`, if any. The match containing the cursor is drawn in the
+/// current match style, falling back to the search style if it has no
+/// background.
+fn search_background<'a>(
+    buffer: &EditorBuffer,
+    editor_state: &'a EditorState,
+    byte_index: usize,
+    cursor_byte_index: usize,
+) -> Option<&'a styling::Color> {
+    let search = buffer.search.as_ref()?;
+    let match_index = search.match_at(byte_index)?;
+    let search_style = |name: &str| editor_state.style_map.get(name)?.background.as_ref();
+
+    if search.match_at(cursor_byte_index) == Some(match_index) {
+        search_style(Styling::SEARCH_CURRENT_NAME).or_else(|| search_style(Styling::SEARCH_NAME))
     } else {
-        if let Some(text_style) = text_style {
-            let character = character.with(Color::from(&text_style.foreground));
-            if let Some(background) = &text_style.background {
-                character.on(Color::from(background));
-            }
+        search_style(Styling::SEARCH_NAME)
+    }
+}
 
-            queue!(stdout, style::PrintStyledContent(character))?;
-        } else {
-            queue!(stdout, style::Print(character))?;
+/// Returns the length of the run of text starting at `byte_index` which shares
+/// a single highlight capture, along with the capture's style name.
+fn highlighted_match(
+    highlights: &[Highlight],
+    byte_index: usize,
+    remaining_length: usize,
+) -> (usize, &str) {
+    for highlight in highlights {
+        if highlight.end_byte <= byte_index {
+            continue;
         }
+
+        return if highlight.start_byte <= byte_index {
+            (
+                (highlight.end_byte - byte_index).min(remaining_length),
+                &highlight.capture,
+            )
+        } else {
+            (
+                (highlight.start_byte - byte_index).min(remaining_length),
+                Styling::DEFAULT_NAME,
+            )
+        };
     }
 
-    Ok(())
+    (remaining_length, Styling::DEFAULT_NAME)
 }
 
 fn buffer_by_id(editor_state: &EditorState, buffer_id: usize) -> io::Result<&EditorBuffer> {
@@ -552,6 +1415,49 @@ fn buffer_by_id(editor_state: &EditorState, buffer_id: usize) -> io::Result<&Edi
     })
 }
 
+/// Byte index of the cursor shown in the leaf pane at `pane_id` of the tab
+/// page at `tab_index`. The active pane shows the buffer's cursor, while
+/// inactive panes show the cursor saved when they were last active, if any.
+pub fn pane_cursor_byte_index(
+    buffer: &EditorBuffer,
+    tab_index: usize,
+    pane_id: usize,
+    is_active: bool,
+) -> usize {
+    if is_active {
+        buffer.cursor_byte_index()
+    } else {
+        buffer
+            .pane_cursors
+            .get(&(tab_index, pane_id))
+            .copied()
+            .unwrap_or(buffer.cursor_byte_index())
+    }
+}
+
+/// Forgets the screen locations of panes no longer shown in `pane_tree`, such
+/// as panes which have been closed.
+fn retain_shown_pane_locations(
+    pane_cursor_locations: &mut HashMap<usize, (u16, u16)>,
+    pane_tree: &PaneTree,
+) {
+    let shown: HashSet<usize> = pane_tree
+        .dump()
+        .into_iter()
+        .filter(|node| matches!(node.node_type, PaneNodeType::Leaf(_)))
+        .map(|node| node.index)
+        .collect();
+
+    pane_cursor_locations.retain(|pane_id, _| shown.contains(pane_id));
+}
+
+/// Whether a pane draws its cursor as a dimmed block rather than leaving the
+/// terminal's cursor to show it. Only inactive panes do, and only if the
+/// `inactive_cursor` option is set.
+fn draws_inactive_cursor(editor_state: &EditorState, is_active: bool) -> bool {
+    !is_active && editor_state.options.inactive_cursor
+}
+
 fn needs_render(
     buffer: &EditorBuffer,
     pane_node: &PaneNode,
@@ -566,3 +1472,285 @@ impl Drop for Display {
         let _ = self.cleanup_display();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        buffer::{buffer_with, PhantomText, SearchState, Sign},
+        styling::{Color, TextStyle},
+    };
+
+    fn frame(rows: u16, cols: u16) -> EditorFrame {
+        EditorFrame {
+            x_col: 0,
+            y_row: 0,
+            rows,
+            cols,
+        }
+    }
+
+    fn wrapping_pane(wrap_mode: WrapMode) -> Pane {
+        let mut pane = Pane::new(0);
+        pane.wrap_mode = wrap_mode;
+        pane
+    }
+
+    #[test]
+    fn char_wrapped_cursor_is_located_on_the_row_it_is_drawn() {
+        let buffer = buffer_with("abcdefgh");
+        let pane = wrapping_pane(WrapMode::Char);
+
+        assert_eq!(cursor_screen_location(&buffer, &pane, &frame(5, 4), 5, 4), Some((1, 1)));
+        assert_eq!(cursor_screen_location(&buffer, &pane, &frame(5, 4), 8, 4), Some((2, 0)));
+        assert_eq!(line_visual_rows(&buffer, &pane, 0, 4, 4), 3);
+    }
+
+    #[test]
+    fn unwrapped_line_takes_one_row() {
+        let buffer = buffer_with("abcdefgh");
+        let pane = Pane::new(0);
+
+        assert_eq!(line_visual_rows(&buffer, &pane, 0, 4, 4), 1);
+        assert_eq!(cursor_screen_location(&buffer, &pane, &frame(5, 4), 2, 4), Some((0, 2)));
+    }
+
+    #[test]
+    fn cursor_after_tab_is_located_at_the_tab_stop() {
+        let buffer = buffer_with("\tx\n");
+        let mut pane = Pane::new(0);
+
+        assert_eq!(cursor_screen_location(&buffer, &pane, &frame(5, 20), 1, 4), Some((0, 4)));
+        pane.tab_spaces = Some(2);
+        assert_eq!(cursor_screen_location(&buffer, &pane, &frame(5, 20), 1, 4), Some((0, 2)));
+    }
+
+    #[test]
+    fn word_wrapped_cursor_moves_with_its_word() {
+        let buffer = buffer_with("aa bb cc");
+        let pane = wrapping_pane(WrapMode::Word);
+
+        assert_eq!(cursor_screen_location(&buffer, &pane, &frame(5, 7), 6, 4), Some((1, 0)));
+        assert_eq!(line_visual_rows(&buffer, &pane, 0, 7, 4), 2);
+    }
+
+    #[test]
+    fn wrap_indented_rows_continue_at_the_indent() {
+        let buffer = buffer_with("  abcdef");
+        let mut pane = wrapping_pane(WrapMode::Char);
+        pane.wrap_indent = true;
+
+        assert_eq!(cursor_screen_location(&buffer, &pane, &frame(5, 6), 6, 4), Some((1, 2)));
+    }
+
+    #[test]
+    fn cursor_wrapped_past_the_pane_is_not_located() {
+        let buffer = buffer_with("abcdefgh");
+        let pane = wrapping_pane(WrapMode::Char);
+
+        assert_eq!(cursor_screen_location(&buffer, &pane, &frame(1, 4), 5, 4), None);
+    }
+
+    #[test]
+    fn word_wrap_breaks_after_last_whitespace_which_fits() {
+        assert_eq!(word_wrap_break("aa bb cc", 0, 7, 4, None), 6);
+        assert_eq!(word_wrap_break("aa bb cc", 2, 7, 4, None), 5);
+        assert_eq!(word_wrap_break("aa bb cc", 0, 20, 4, None), 8);
+        assert_eq!(word_wrap_break("aa bb\ncc", 0, 20, 4, None), 5);
+    }
+
+    #[test]
+    fn word_wrap_breaks_before_whitespace_past_the_edge() {
+        assert_eq!(word_wrap_break("abcd efg", 0, 4, 4, None), 4);
+        assert_eq!(word_wrap_break("ab\tcd", 0, 3, 4, None), 2);
+    }
+
+    #[test]
+    fn word_wrap_splits_words_wider_than_the_row() {
+        assert_eq!(word_wrap_break("abcdefgh", 0, 4, 4, None), 4);
+        assert_eq!(word_wrap_break("abcdefgh ij", 0, 4, 4, None), 4);
+        // A single character wider than the row still makes progress
+        assert_eq!(word_wrap_break("\u{1F600}b", 0, 1, 4, None), 4);
+    }
+
+    #[test]
+    fn word_wrap_measures_zero_width_characters_as_escapes() {
+        // "\u{200b}" is drawn 8 columns wide
+        assert_eq!(word_wrap_break("\u{200b}ab", 0, 10, 4, None), 5);
+        assert_eq!(word_wrap_break("\u{200b}ab", 0, 9, 4, None), 4);
+        assert_eq!(word_wrap_break("\u{200b}ab", 0, 4, 4, None), 3);
+    }
+
+    #[test]
+    fn wrap_indent_is_dropped_at_half_the_row() {
+        assert_eq!(wrap_indent_width("  x", 0, 8, 4, None), 2);
+        assert_eq!(wrap_indent_width("  x", 0, 5, 4, None), 0);
+        assert_eq!(wrap_indent_width("  x", 0, 4, 4, None), 0);
+        assert_eq!(wrap_indent_width("\tx", 0, 10, 4, None), 4);
+        assert_eq!(wrap_indent_width("\tx", 0, 8, 4, None), 0);
+        assert_eq!(wrap_indent_width("\tx", 0, 8, 4, Some(2)), 2);
+        assert_eq!(wrap_indent_width("x  ", 0, 8, 4, None), 0);
+    }
+
+    #[test]
+    fn later_lines_start_below_wrapped_lines() {
+        let buffer = buffer_with("abcdef\nxy");
+        let pane = wrapping_pane(WrapMode::Char);
+
+        assert_eq!(cursor_screen_location(&buffer, &pane, &frame(5, 4), 8, 4), Some((2, 1)));
+    }
+
+    #[test]
+    fn folded_lines_are_drawn_as_a_single_summary_row() {
+        let buffer = buffer_with("a\nb\nc\nd\ne");
+        let mut pane = Pane::new(0);
+        pane.add_fold(1, 3);
+
+        let fold = pane.fold_containing(2).unwrap();
+        assert_eq!(fold_summary(fold, &buffer), "+-- 3 lines");
+        assert_eq!(cursor_screen_location(&buffer, &pane, &frame(5, 10), 0, 4), Some((0, 0)));
+        // Lines inside the fold are located on its summary row
+        assert_eq!(cursor_screen_location(&buffer, &pane, &frame(5, 10), 4, 4), Some((1, 0)));
+        // "e" follows on the row after the summary
+        assert_eq!(cursor_screen_location(&buffer, &pane, &frame(5, 10), 9, 4), Some((2, 1)));
+    }
+
+    #[test]
+    fn folds_past_the_buffer_end_only_count_existing_lines() {
+        let buffer = buffer_with("a\nb\nc");
+        let mut pane = Pane::new(0);
+        pane.add_fold(1, 10);
+
+        assert_eq!(fold_summary(pane.fold_containing(1).unwrap(), &buffer), "+-- 2 lines");
+    }
+
+    #[test]
+    fn phantom_text_is_drawn_before_its_char_without_changing_content() {
+        let mut buffer = buffer_with("abcd");
+        buffer.phantoms.push(PhantomText {
+            byte_index: 2,
+            text: ">>".to_string(),
+            style: "phantom".to_string(),
+        });
+        assert_eq!(buffer.content_byte_length(), 4);
+        assert_eq!(buffer.content_copy(), "abcd");
+
+        let pane = Pane::new(0);
+        let mut layout = LineLayout::new(&buffer, &pane, "abcd", 0, (0, 10), 1, 4);
+        let columns: Vec<(u16, u16)> = "abcd"
+            .chars()
+            .map(|character| {
+                let placement = layout.place(character).unwrap();
+                layout.advance(character, &placement);
+                (placement.phantom_column, placement.column)
+            })
+            .collect();
+
+        assert_eq!(columns, [(0, 0), (1, 1), (2, 4), (5, 5)]);
+        assert_eq!(cursor_screen_location(&buffer, &pane, &frame(5, 10), 2, 4), Some((0, 4)));
+    }
+
+    #[test]
+    fn only_inactive_panes_draw_their_cursor_when_enabled() {
+        let mut editor_state = EditorState::new(std::time::Duration::from_millis(10));
+        editor_state.options.inactive_cursor = true;
+        assert!(draws_inactive_cursor(&editor_state, false));
+        assert!(!draws_inactive_cursor(&editor_state, true));
+
+        editor_state.options.inactive_cursor = false;
+        assert!(!draws_inactive_cursor(&editor_state, false));
+        assert!(!draws_inactive_cursor(&editor_state, true));
+    }
+
+    #[test]
+    fn closed_panes_lose_their_cursor_locations() {
+        let mut pane_tree = PaneTree::new(0);
+        let split_index = pane_tree.vsplit(0, 0).unwrap();
+        let mut locations = HashMap::from([(0, (1, 1)), (1, (1, 5)), (split_index, (0, 0))]);
+
+        retain_shown_pane_locations(&mut locations, &pane_tree);
+        assert_eq!(locations, HashMap::from([(0, (1, 1)), (1, (1, 5))]));
+
+        pane_tree.close_child(split_index, false, 0).unwrap();
+        retain_shown_pane_locations(&mut locations, &pane_tree);
+        assert_eq!(locations, HashMap::from([(0, (1, 1))]));
+    }
+
+    fn sign(text: &str) -> Sign {
+        Sign {
+            text: text.to_string(),
+            style: "sign".to_string(),
+        }
+    }
+
+    #[test]
+    fn sign_column_fits_the_widest_sign() {
+        let mut buffer = buffer_with("a\nb\nc");
+        assert_eq!(sign_column_width(&buffer, 10), 0);
+
+        buffer.signs.insert(1, sign("E"));
+        assert_eq!(sign_column_width(&buffer, 10), 1);
+
+        buffer.signs.insert(2, sign("+++"));
+        assert_eq!(sign_column_width(&buffer, 10), MAX_SIGN_WIDTH);
+        assert_eq!(sign_column_width(&buffer, 2), 1);
+    }
+
+    #[test]
+    fn signs_are_drawn_beside_their_lines() {
+        let mut buffer = buffer_with("a\nb\nc");
+        buffer.signs.insert(1, sign("E"));
+        buffer.signs.insert(2, sign("\u{200b}+++"));
+        let sign_width = sign_column_width(&buffer, 10);
+
+        assert_eq!(fitted_sign(&buffer, 0, sign_width), None);
+        assert_eq!(fitted_sign(&buffer, 1, sign_width), Some(("E".to_string(), "sign")));
+        assert_eq!(fitted_sign(&buffer, 2, sign_width), Some(("++".to_string(), "sign")));
+
+        let pane = Pane::new(0);
+        assert_eq!(cursor_screen_location(&buffer, &pane, &frame(5, 10), 2, 4), Some((1, 2)));
+    }
+
+    fn background_style(r: u8) -> TextStyle {
+        TextStyle {
+            background: Some(Color { r, g: 0, b: 0 }),
+            foreground: Color { r: 0, g: 0, b: 0 },
+        }
+    }
+
+    fn red_of(color: Option<&Color>) -> Option<u8> {
+        color.map(|color| color.r)
+    }
+
+    #[test]
+    fn highlighted_lines_draw_their_style_until_cleared() {
+        let mut editor_state = EditorState::new(std::time::Duration::from_millis(10));
+        editor_state.style_map.insert("diff".to_string(), background_style(1));
+        let mut buffer = buffer_with("ab\ncd");
+        buffer.line_highlights.insert(1, "diff".to_string());
+
+        assert_eq!(red_of(char_background(&buffer, &editor_state, 0, 0, 0)), None);
+        assert_eq!(red_of(char_background(&buffer, &editor_state, 1, 3, 0)), Some(1));
+
+        buffer.line_highlights.clear();
+        assert_eq!(red_of(char_background(&buffer, &editor_state, 1, 3, 0)), None);
+    }
+
+    #[test]
+    fn search_matches_draw_over_line_highlights() {
+        let mut editor_state = EditorState::new(std::time::Duration::from_millis(10));
+        editor_state.style_map.insert("diff".to_string(), background_style(1));
+        editor_state
+            .style_map
+            .insert(Styling::SEARCH_NAME.to_string(), background_style(2));
+        let mut buffer = buffer_with("ab\ncd");
+        buffer.line_highlights.insert(1, "diff".to_string());
+        buffer.search = Some(SearchState::new(
+            buffer.content.as_ref(),
+            Regex::new("d").unwrap(),
+        ));
+
+        assert_eq!(red_of(char_background(&buffer, &editor_state, 1, 3, 0)), Some(1));
+        assert_eq!(red_of(char_background(&buffer, &editor_state, 1, 4, 0)), Some(2));
+    }
+}
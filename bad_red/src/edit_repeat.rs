@@ -0,0 +1,65 @@
+// This file is part of BadRed.
+
+// BadRed is free software: you can redistribute it and/or modify it under the terms of the GNU General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// BadRed is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+use crate::buffer::{ContentBuffer, EditorBuffer};
+
+/// Content change made relative to a buffer's cursor, recorded so that it can
+/// be applied again at another cursor position.
+#[derive(Clone, PartialEq, Debug)]
+pub enum RepeatableEdit {
+    Insert(String),
+    Delete(usize),
+    InsertPair { open: String, close: String },
+    IncrementNumber(i64),
+}
+
+impl RepeatableEdit {
+    pub fn apply(&self, buffer: &mut EditorBuffer) {
+        match self {
+            RepeatableEdit::Insert(content) => buffer.insert_at_cursor(content),
+            RepeatableEdit::Delete(char_count) => {
+                buffer.delete_at_cursor(*char_count);
+            }
+            RepeatableEdit::InsertPair { open, close } => buffer.insert_pair(open, close),
+            RepeatableEdit::IncrementNumber(delta) => {
+                buffer.increment_number(*delta);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_inserts_apply_at_the_moved_cursor() {
+        let mut buffer = EditorBuffer::new();
+        buffer.insert_at_cursor("ab");
+        let edit = RepeatableEdit::Insert("x".to_string());
+
+        edit.apply(&mut buffer);
+        buffer.set_cursor_byte_index(0, false);
+        edit.apply(&mut buffer);
+
+        assert_eq!(buffer.content_copy(), "xabx");
+        assert_eq!(buffer.cursor_byte_index(), 1);
+    }
+
+    #[test]
+    fn repeated_increments_find_the_number_after_the_cursor() {
+        let mut buffer = EditorBuffer::new();
+        buffer.insert_at_cursor("1 1");
+        buffer.set_cursor_byte_index(0, false);
+        let edit = RepeatableEdit::IncrementNumber(2);
+
+        edit.apply(&mut buffer);
+        buffer.set_cursor_byte_index(2, false);
+        edit.apply(&mut buffer);
+
+        assert_eq!(buffer.content_copy(), "3 3");
+    }
+}
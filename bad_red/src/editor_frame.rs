@@ -16,6 +16,14 @@ pub struct EditorFrame {
     pub cols: u16,
 }
 
+/// Row and column of a location on the screen
+#[auto_lua]
+#[derive(Clone, Copy, Debug)]
+pub struct ScreenPosition {
+    pub row: u16,
+    pub col: u16,
+}
+
 impl EditorFrame {
     pub fn with_x_col(&self, x_col: u16) -> Self {
         let mut new = self.clone();
@@ -4,21 +4,42 @@
 //
 // BadRed is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
 
-use std::{str::FromStr, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::{Read, Seek},
+    path::Path,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use bad_red_proc_macros::auto_lua;
 use bimap::BiMap;
 use crossterm::event::KeyEvent;
-use mlua::{FromLua, IntoLua, Lua};
+use mlua::{FromLua, IntoLua, Lua, MetaMethod, UserData, UserDataFields, UserDataMethods};
+use regex::Regex;
 
 use crate::{
-    buffer::{ContentBuffer, EditorBuffer},
-    file_handle::FileHandle,
-    hook_map::{HookMap, HookType, HookTypeName},
+    buffer::{
+        fnv_hash, search_lines, tab_width_from_lua, BufferMatch, BufferOptions, ContentBuffer,
+        EditorBuffer, EditorBufferType, PagedBuffer,
+    },
+    display::Display,
+    edit_repeat::RepeatableEdit,
+    editorconfig,
+    file_handle::{FileHandle, FinalNewlineWrite},
+    hook_map::{HookMap, HookType, HookTypeName, PaneBufferChange},
+    jump_list::{JumpList, JumpPosition},
     keymap::RedKeyEvent,
     pane::{self, PaneTree, Split},
+    popup::Popup,
+    prompt::{Prompt, PromptKeyResult},
     script_runtime::{SchedulerYield, ScriptScheduler},
+    session::{Session, SessionBuffer, SessionPane},
+    shell::{self, ShellOutput},
     styling::TextStyleMap,
+    task::{TaskResult, TaskRunner},
 };
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -47,6 +68,38 @@ impl Error {
     pub fn into_lua(self) -> mlua::Error {
         mlua::Error::ExternalError(Arc::new(self))
     }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::Unrecoverable(_) => "unrecoverable",
+            Error::Recoverable(_) => "recoverable",
+            Error::Script(_) => "script",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            Error::Unrecoverable(message) | Error::Recoverable(message) | Error::Script(message) => {
+                message
+            }
+        }
+    }
+}
+
+/// Errors raised into Lua by a failing RedCall expose their `kind` and
+/// `message` as fields so that scripts catching them with `pcall` can inspect
+/// them.
+impl UserData for Error {
+    fn add_fields<'lua, F: UserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_field_method_get("kind", |_, error| Ok(error.kind()));
+        fields.add_field_method_get("message", |_, error| Ok(error.message().to_string()));
+    }
+
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_meta_method(MetaMethod::ToString, |_, error, ()| {
+            Ok(format!("{} error: {}", error.kind(), error.message()))
+        });
+    }
 }
 
 pub struct Editor<'a> {
@@ -92,7 +145,31 @@ impl<'a> Editor<'a> {
     }
 
     pub fn handle_key_event(&mut self, key_event: KeyEvent) -> Result<()> {
-        let red_key_event = RedKeyEvent::from(key_event);
+        self.handle_red_key_event(RedKeyEvent::from(key_event))
+    }
+
+    /// Handles a key event as `handle_key_event` does, for callers such as the
+    /// headless harness which build key events without a terminal.
+    pub fn handle_red_key_event(&mut self, red_key_event: RedKeyEvent) -> Result<()> {
+        if let Some(prompt) = &mut self.state.prompt {
+            return match prompt.handle_key_event(&red_key_event) {
+                PromptKeyResult::Handled => Ok(()),
+                PromptKeyResult::Submit(input) => {
+                    let prompt = self.state.close_prompt();
+                    match prompt {
+                        Some(prompt) => self
+                            .script_scheduler
+                            .spawn_registered_function(prompt.on_submit, input),
+                        None => Ok(()),
+                    }
+                }
+                PromptKeyResult::Cancel => {
+                    self.state.close_prompt();
+                    Ok(())
+                }
+            };
+        }
+
         let Some(function_iter) = self.hook_map.function_iter(HookTypeName::KeyEvent, None) else {
             return Ok(());
         };
@@ -160,9 +237,44 @@ pub struct EditorState {
     pub style_map: TextStyleMap,
 
     pub buffer_file_map: BiMap<usize, usize>,
+
+    pub jump_list: JumpList,
+    /// Last cursor-relative content change, repeated by `RepeatLastEdit`
+    pub last_edit: Option<RepeatableEdit>,
+
+    /// Tab pages other than the active one, whose layout is `pane_tree`. The
+    /// active tab page is ordered at `active_tab_index` among these.
+    pub inactive_tabs: Vec<TabPage>,
+    pub active_tab_index: usize,
+
+    pub popup: Option<Popup>,
+    pub prompt: Option<Prompt>,
+
+    /// Formatter commands to run on save, by file extension
+    pub formatters: HashMap<String, String>,
+    /// Options set on buffers when they are linked to a file, by file
+    /// extension
+    pub file_type_options: HashMap<String, BufferOptions>,
+
+    /// Title shown in the terminal's title bar
+    pub window_title: String,
+
+    /// Name of the editing mode scripts have set, such as "normal" or
+    /// "insert", used to pick which keymap handles key events
+    pub mode: String,
+
+    /// Bell rung since the last render, shown by the next render
+    pub pending_bell: Option<Bell>,
+    /// Time the visual bell rung last stops flashing, until it has been
+    /// redrawn without the flash
+    pub visual_bell_until: Option<Instant>,
 }
 
 impl EditorState {
+    pub const DEFAULT_MODE: &'static str = "default";
+    /// How long a visual bell flashes the active pane
+    pub const VISUAL_BELL_DURATION: Duration = Duration::from_millis(100);
+
     pub fn new(input_poll_rate: Duration) -> Self {
         Self {
             active_pane_index: 0,
@@ -172,9 +284,37 @@ impl EditorState {
             pane_tree: PaneTree::new(0),
 
             buffer_file_map: BiMap::new(),
-            options: EditorOptions { tab_width: 8 },
+            options: EditorOptions {
+                tab_width: 8,
+                expand_tab: false,
+                final_newline: false,
+                visual_bell: false,
+                inactive_cursor: false,
+                modified_delay_ms: 300,
+                auto_reload: false,
+                poll_rate_ms: input_poll_rate.as_millis() as u64,
+            },
 
             style_map: TextStyleMap::new(),
+
+            jump_list: JumpList::new(),
+            last_edit: None,
+
+            inactive_tabs: vec![],
+            active_tab_index: 0,
+
+            popup: None,
+            prompt: None,
+
+            formatters: HashMap::new(),
+            file_type_options: HashMap::new(),
+
+            window_title: Display::TITLE.to_string(),
+
+            mode: Self::DEFAULT_MODE.to_string(),
+
+            pending_bell: None,
+            visual_bell_until: None,
         }
     }
 
@@ -182,10 +322,68 @@ impl EditorState {
         self.buffers.get(id).map(|b| b.as_ref()).flatten()
     }
 
+    /// Marks every pane and buffer as needing to be rendered so that the next
+    /// render repaints the whole screen.
+    pub fn force_redraw(&mut self) {
+        for pane_node in self.pane_tree.tree.iter_mut().flatten() {
+            pane_node.is_dirty = true;
+        }
+        for buffer in self.buffers.iter_mut().flatten() {
+            buffer.is_render_dirty = true;
+        }
+    }
+
+    /// Closes the open prompt, if any, redrawing the panes it was drawn over
+    pub fn close_prompt(&mut self) -> Option<Prompt> {
+        let prompt = self.prompt.take();
+        if prompt.is_some() {
+            self.force_redraw();
+        }
+
+        prompt
+    }
+
+    /// Rings the bell on the next render. The bell is visual if requested or
+    /// if the `visual_bell` option is set, and audible otherwise.
+    pub fn ring_bell(&mut self, visual: bool) -> Bell {
+        let bell = if visual || self.options.visual_bell {
+            Bell::Visual
+        } else {
+            Bell::Audible
+        };
+        self.pending_bell = Some(bell);
+        if bell == Bell::Visual {
+            self.visual_bell_until = Some(Instant::now() + Self::VISUAL_BELL_DURATION);
+        }
+
+        bell
+    }
+
+    /// Whether a visual bell is flashing the active pane at `now`
+    pub fn is_visual_bell_shown(&self, now: Instant) -> bool {
+        self.visual_bell_until.is_some_and(|until| now < until)
+    }
+
+    /// Applies option updates, including to state derived from the options
+    pub fn update_options(&mut self, update_list: EditorOptionList) {
+        self.options.update(update_list);
+        self.input_poll_rate = Duration::from_millis(self.options.poll_rate_ms);
+    }
+
     pub fn mut_buffer_by_id(&mut self, id: usize) -> Option<&mut EditorBuffer> {
         self.buffers.get_mut(id).map(|b| b.as_mut()).flatten()
     }
 
+    /// Id of the buffer shown in the active pane
+    pub fn active_buffer_id(&self) -> Result<usize> {
+        self.pane_tree
+            .pane_by_index(self.active_pane_index)
+            .map(|pane| pane.buffer_id)
+            .ok_or_else(|| {
+                Error::Script("Attempted to find active buffer id without active pane".to_string())
+            })
+    }
+
     pub fn active_buffer(&mut self) -> Option<&mut EditorBuffer> {
         let pane = self.pane_tree.pane_by_index(self.active_pane_index)?;
 
@@ -195,21 +393,359 @@ impl EditorState {
             .flatten()
     }
 
+    /// Makes the pane at `index` active. A buffer has one cursor used for
+    /// editing, which belongs to the active pane. The cursor of the pane being
+    /// left is saved in its buffer and restored once that pane is active again,
+    /// so panes sharing a buffer keep independent cursors.
+    pub fn set_active_pane(&mut self, index: usize) {
+        let previous_index = self.active_pane_index;
+        if previous_index == index {
+            return;
+        }
+
+        self.save_active_pane_cursor();
+        if let Some(previous) = self.pane_tree.pane_node_mut_by_index(previous_index) {
+            // Redraw the pane being left so it shows its inactive cursor
+            previous.is_dirty = true;
+        }
+        self.active_pane_index = index;
+        self.restore_active_pane_cursor();
+    }
+
+    fn save_active_pane_cursor(&mut self) {
+        let pane_index = self.active_pane_index;
+        let Some(buffer_id) = self.pane_tree.pane_by_index(pane_index).map(|pane| pane.buffer_id)
+        else {
+            return;
+        };
+
+        let tab_index = self.active_tab_index;
+        if let Some(buffer) = self.mut_buffer_by_id(buffer_id) {
+            let cursor_byte_index = buffer.cursor_byte_index();
+            buffer
+                .pane_cursors
+                .insert((tab_index, pane_index), cursor_byte_index);
+        }
+    }
+
+    fn restore_active_pane_cursor(&mut self) {
+        let pane_index = self.active_pane_index;
+        let Some(buffer_id) = self.pane_tree.pane_by_index(pane_index).map(|pane| pane.buffer_id)
+        else {
+            return;
+        };
+
+        let tab_index = self.active_tab_index;
+        if let Some(buffer) = self.mut_buffer_by_id(buffer_id) {
+            if let Some(cursor_byte_index) = buffer.pane_cursors.remove(&(tab_index, pane_index)) {
+                buffer.set_cursor_byte_index(cursor_byte_index, false);
+                buffer.is_render_dirty = true;
+            }
+        }
+    }
+
+    pub fn tab_count(&self) -> usize {
+        self.inactive_tabs.len() + 1
+    }
+
+    /// Opens a new tab page with a single pane showing `buffer_id` after the
+    /// last tab page and makes it active. Returns the new tab page's index.
+    pub fn new_tab(&mut self, buffer_id: usize) -> Result<usize> {
+        if self.buffer_by_id(buffer_id).is_none() {
+            return Err(Error::Script(format!(
+                "Attempted to open tab page for non-existent buffer: {}",
+                buffer_id
+            )));
+        }
+
+        let new_tab_index = self.tab_count();
+        self.inactive_tabs.push(TabPage {
+            pane_tree: PaneTree::new(buffer_id),
+            active_pane_index: 0,
+        });
+        self.switch_tab(new_tab_index)?;
+
+        Ok(new_tab_index)
+    }
+
+    /// Shows the pane layout of the tab page at `index`, keeping the layout of
+    /// the tab page being left so it can be switched back to.
+    pub fn switch_tab(&mut self, index: usize) -> Result<()> {
+        if index >= self.tab_count() {
+            return Err(Error::Script(format!(
+                "Attempted to switch to tab page index out of bounds: {}",
+                index
+            )));
+        }
+        if index == self.active_tab_index {
+            return Ok(());
+        }
+
+        self.save_active_pane_cursor();
+
+        let target_index = if index < self.active_tab_index {
+            index
+        } else {
+            index - 1
+        };
+        let target = self.inactive_tabs.remove(target_index);
+        let previous = TabPage {
+            pane_tree: std::mem::replace(&mut self.pane_tree, target.pane_tree),
+            active_pane_index: std::mem::replace(
+                &mut self.active_pane_index,
+                target.active_pane_index,
+            ),
+        };
+        let previous_index = if index < self.active_tab_index {
+            self.active_tab_index - 1
+        } else {
+            self.active_tab_index
+        };
+        self.inactive_tabs.insert(previous_index, previous);
+        self.active_tab_index = index;
+
+        self.restore_active_pane_cursor();
+        self.force_redraw();
+
+        Ok(())
+    }
+
+    /// Closes the first or second child of the split pane at `index`, making
+    /// the kept child active and forgetting cursors saved for the closed
+    /// panes. Returns the index of the closed child.
+    pub fn close_pane_child(&mut self, index: usize, first_child: bool) -> Result<usize> {
+        let (new_active_pane_index, closed_id) = self
+            .pane_tree
+            .close_child(index, first_child, self.active_pane_index)
+            .map_err(|e| Error::Script(format!("Failed to close pane child: {}", e)))?
+            .ok_or_else(|| {
+                Error::Script(format!("No such pane found while closing child: {}", index))
+            })?;
+
+        self.set_active_pane(new_active_pane_index);
+        self.prune_pane_cursors();
+
+        Ok(closed_id)
+    }
+
+    /// Closes the active tab page along with its panes, showing the tab page
+    /// before it, or after it if it was the first. Buffers are left open.
+    /// Returns the indices of the closed leaf panes.
+    pub fn close_tab(&mut self) -> Result<Vec<usize>> {
+        if self.inactive_tabs.is_empty() {
+            return Err(Error::Script(
+                "Attempted to close the only tab page".to_string(),
+            ));
+        }
+
+        let closed_index = self.active_tab_index;
+        let next_index = closed_index.saturating_sub(1);
+        let next = self.inactive_tabs.remove(next_index);
+        let closed_tree = std::mem::replace(&mut self.pane_tree, next.pane_tree);
+        self.active_pane_index = next.active_pane_index;
+        self.active_tab_index = next_index;
+
+        // Tab pages after the closed one move down an index, so their saved
+        // cursors move with them
+        for buffer in self.buffers.iter_mut().flatten() {
+            buffer.pane_cursors = buffer
+                .pane_cursors
+                .drain()
+                .filter(|((tab_index, _), _)| *tab_index != closed_index)
+                .map(|((tab_index, pane_index), byte_index)| {
+                    let tab_index = if tab_index > closed_index {
+                        tab_index - 1
+                    } else {
+                        tab_index
+                    };
+                    ((tab_index, pane_index), byte_index)
+                })
+                .collect();
+        }
+
+        self.restore_active_pane_cursor();
+        self.force_redraw();
+
+        Ok(closed_tree
+            .dump()
+            .into_iter()
+            .filter(|node| matches!(node.node_type, pane::PaneNodeType::Leaf(_)))
+            .map(|node| node.index)
+            .collect())
+    }
+
+    /// Forgets the saved cursors of panes which no longer show their buffer,
+    /// such as panes which have been closed.
+    pub fn prune_pane_cursors(&mut self) {
+        let mut shown = HashSet::new();
+        for (tab_index, pane_tree) in self.tab_pane_trees() {
+            for node in pane_tree.dump() {
+                if let pane::PaneNodeType::Leaf(pane) = node.node_type {
+                    shown.insert((pane.buffer_id, tab_index, node.index));
+                }
+            }
+        }
+
+        for (buffer_id, buffer) in self.buffers.iter_mut().enumerate() {
+            if let Some(buffer) = buffer {
+                buffer.pane_cursors.retain(|(tab_index, pane_index), _| {
+                    shown.contains(&(buffer_id, *tab_index, *pane_index))
+                });
+            }
+        }
+    }
+
+    /// Pane layouts of every tab page with the tab page's index
+    fn tab_pane_trees(&self) -> impl Iterator<Item = (usize, &PaneTree)> {
+        let active_tab_index = self.active_tab_index;
+        self.inactive_tabs
+            .iter()
+            .enumerate()
+            .map(move |(index, tab)| {
+                let index = if index < active_tab_index {
+                    index
+                } else {
+                    index + 1
+                };
+                (index, &tab.pane_tree)
+            })
+            .chain(std::iter::once((active_tab_index, &self.pane_tree)))
+    }
+
+    pub fn tab_list(&self) -> Vec<TabPageInfo> {
+        let info = |index: usize, pane_tree: &PaneTree, active_pane_index: usize| TabPageInfo {
+            index,
+            is_active: index == self.active_tab_index,
+            pane_count: pane_tree.leaf_count(),
+            active_buffer_id: pane_tree
+                .pane_by_index(active_pane_index)
+                .map(|pane| pane.buffer_id),
+        };
+
+        let mut tabs: Vec<TabPageInfo> = self
+            .inactive_tabs
+            .iter()
+            .enumerate()
+            .map(|(index, tab)| {
+                let index = if index < self.active_tab_index {
+                    index
+                } else {
+                    index + 1
+                };
+                info(index, &tab.pane_tree, tab.active_pane_index)
+            })
+            .collect();
+        tabs.insert(
+            self.active_tab_index,
+            info(self.active_tab_index, &self.pane_tree, self.active_pane_index),
+        );
+
+        tabs
+    }
+
+    pub fn active_jump_position(&self) -> Option<JumpPosition> {
+        let pane = self.pane_tree.pane_by_index(self.active_pane_index)?;
+        let buffer = self.buffer_by_id(pane.buffer_id)?;
+
+        Some(JumpPosition {
+            buffer_id: pane.buffer_id,
+            byte_index: buffer.cursor_byte_index(),
+        })
+    }
+
+    /// Moves the active pane to the buffer and cursor of the given position.
+    /// Returns the change made if the active pane had to switch buffers.
+    pub fn jump_to(&mut self, position: JumpPosition) -> Result<Option<PaneBufferChange>> {
+        let buffer = self.mut_buffer_by_id(position.buffer_id).ok_or_else(|| {
+            Error::Recoverable(format!(
+                "Attempted to jump to position in closed buffer: {}",
+                position.buffer_id
+            ))
+        })?;
+        let byte_index = position.byte_index.min(buffer.content_byte_length());
+        buffer.set_cursor_byte_index(byte_index, false);
+
+        let active_pane_index = self.active_pane_index;
+        let pane = self
+            .pane_tree
+            .pane_mut_by_index(active_pane_index)
+            .ok_or_else(|| {
+                Error::Unrecoverable(format!(
+                    "Attempted to jump without an active leaf pane at index: {}",
+                    active_pane_index
+                ))
+            })?;
+        if pane.buffer_id == position.buffer_id {
+            return Ok(None);
+        }
+
+        pane.buffer_id = position.buffer_id;
+        Ok(Some(PaneBufferChange {
+            pane_id: active_pane_index,
+            buffer_id: position.buffer_id,
+        }))
+    }
+
     pub fn clear_dirty(&mut self) {
         for buffer in &mut self.buffers {
             if let Some(buffer) = buffer {
                 buffer.is_render_dirty = false;
             }
         }
+
+        self.pending_bell = None;
+        // A visual bell is kept until a render after its flash has ended, so
+        // the pane is redrawn without it
+        if !self.is_visual_bell_shown(Instant::now()) {
+            self.visual_bell_until = None;
+        }
     }
 
     pub fn create_buffer(&mut self) -> usize {
+        self.create_buffer_typed(EditorBufferType::Gap)
+    }
+
+    pub fn create_buffer_typed(&mut self, buffer_type: EditorBufferType) -> usize {
         let new_buffer_id = self.buffers.len();
-        self.buffers.push(Some(EditorBuffer::new()));
+        self.buffers.push(Some(EditorBuffer::new_typed(buffer_type)));
 
         new_buffer_id
     }
 
+    /// Creates a read-only buffer of the file at `file_id` which only holds
+    /// `window_size` bytes of the file in memory at once, reading the rest
+    /// from the file as it is displayed or queried.
+    pub fn create_paged_buffer(&mut self, file_id: usize, window_size: usize) -> Result<usize> {
+        let file_handle = self
+            .files
+            .get(file_id)
+            .and_then(|f| f.as_ref())
+            .ok_or_else(|| {
+                Error::Recoverable(format!(
+                    "Attempted to page invalid file id into a buffer: {}",
+                    file_id
+                ))
+            })?;
+
+        let path: &str = &file_handle.path;
+        let content = File::open(path)
+            .and_then(|file| PagedBuffer::new(Box::new(file), window_size))
+            .map_err(|e| {
+                Error::Recoverable(format!(
+                    "Failed to page file id {} into a buffer. {}",
+                    file_id, e
+                ))
+            })?;
+
+        let new_buffer_id = self.buffers.len();
+        self.buffers.push(Some(EditorBuffer::with_content(
+            EditorBufferType::Paged,
+            Box::new(content),
+        )));
+
+        Ok(new_buffer_id)
+    }
+
     pub fn remove_buffer(&mut self, index: usize) -> Result<()> {
         if self
             .buffers
@@ -281,6 +817,10 @@ impl EditorState {
         Ok(())
     }
 
+    /// Links the buffer to the file, optionally replacing the buffer's content
+    /// with the file's. Options registered for the file's extension are set on
+    /// the buffer, followed by those from `.editorconfig` files applying to the
+    /// file.
     pub fn link_buffer(
         &mut self,
         buffer_id: usize,
@@ -323,13 +863,34 @@ impl EditorState {
                 ))
             })?;
 
-        self.buffer_file_map.insert(buffer_id, file_id);
-
         if should_populate_buffer {
-            buffer
-                .populate_from_read(&mut file_handle)
-                .map_err(|e| Error::Recoverable(format!("Failed to read from file: {:#?}", e)))?;
+            let populate_result = if buffer.buffer_type == EditorBufferType::Paged {
+                file_handle
+                    .open_read_only()
+                    .and_then(|file| buffer.populate_from_file(file))
+            } else {
+                buffer.populate_from_read(&mut file_handle)
+            };
+            populate_result.map_err(|e| {
+                Error::Recoverable(format!(
+                    "Failed to read file id {} into buffer id {}. {}",
+                    file_id, buffer_id, e
+                ))
+            })?;
+        }
+
+        if let Some(file_type_options) = file_handle
+            .extension()
+            .and_then(|extension| self.file_type_options.get(&extension))
+        {
+            buffer.options.merge(file_type_options.clone());
         }
+        let path: &str = &file_handle.path;
+        buffer
+            .options
+            .merge(editorconfig::buffer_options_for(Path::new(path)));
+
+        self.buffer_file_map.insert(buffer_id, file_id);
 
         Ok(())
     }
@@ -374,7 +935,63 @@ impl EditorState {
             })
     }
 
-    pub fn write_buffer(&mut self, buffer_id: usize) -> Result<()> {
+    /// Starts writing the buffer to its linked file. Trailing whitespace is
+    /// trimmed and newlines normalized as the buffer's options set, then the
+    /// formatter set for the file's extension is started on the buffer's
+    /// content as a task of `tasks`. The returned formatter is given the
+    /// task's result once it completes and then passed to `write_buffer`.
+    /// `None` if no formatter is set or the buffer has no changes to write.
+    pub fn start_write(
+        &mut self,
+        buffer_id: usize,
+        tasks: &mut TaskRunner,
+    ) -> Result<Option<BufferFormat>> {
+        let Some(file_id) = self.buffer_file_map.get_by_left(&buffer_id) else {
+            return Err(Error::Recoverable(format!(
+                "Attempted to write from buffer id that has no file associated. Buffer id: {}",
+                buffer_id
+            )));
+        };
+        let extension = self
+            .files
+            .get(*file_id)
+            .and_then(|f| f.as_ref())
+            .and_then(|file_handle| file_handle.extension());
+        let buffer = self
+            .buffers
+            .get_mut(buffer_id)
+            .and_then(|b| b.as_mut())
+            .ok_or_else(|| {
+                Error::Recoverable(format!(
+                    "Attempted to write from invalid buffer id: {}",
+                    buffer_id
+                ))
+            })?;
+        if !buffer.is_content_dirty {
+            return Ok(None);
+        }
+
+        if buffer.options.trim_trailing_whitespace == Some(true) {
+            buffer.trim_trailing_whitespace();
+        }
+        if let Some(line_ending) = buffer.options.line_ending {
+            buffer.normalize_newlines(line_ending);
+        }
+
+        Ok(extension
+            .and_then(|extension| self.formatters.get(&extension))
+            .map(|command| BufferFormat::start(buffer, command, tasks)))
+    }
+
+    /// Writes the buffer to its linked file, first replacing its content with
+    /// the output of `format` if it finished successfully. Returns a
+    /// description of the formatter's failure, in which case the buffer is
+    /// written unformatted.
+    pub fn write_buffer(
+        &mut self,
+        buffer_id: usize,
+        format: Option<BufferFormat>,
+    ) -> Result<Option<String>> {
         let Some(file_id) = self.buffer_file_map.get_by_left(&buffer_id) else {
             return Err(Error::Recoverable(format!(
                 "Attempted to write from buffer id that has no file associated. Buffer id: {}",
@@ -393,7 +1010,7 @@ impl EditorState {
                 ))
             })?;
         if !buffer.is_content_dirty {
-            return Ok(());
+            return Ok(None);
         }
 
         let file_handle = self
@@ -408,42 +1025,392 @@ impl EditorState {
                 ))
             })?;
 
-        buffer.flush_to_write(file_handle).map_err(|e| {
+        let formatter_failure = format.and_then(|format| format.apply(buffer).err());
+
+        let write_result = if self.options.final_newline_for(buffer) {
+            buffer.flush_to_write(&mut FinalNewlineWrite(file_handle))
+        } else {
+            buffer.flush_to_write(file_handle)
+        };
+
+        write_result.map(|_| formatter_failure).map_err(|e| {
             Error::Recoverable(format!(
                 "Failed to write buffer id {} contents out to file id {}. {}",
                 buffer_id, file_id, e
             ))
         })
     }
-}
-
-impl EditorState {
-    pub fn vsplit(&mut self, index: usize) -> Result<()> {
-        let active_pane = self.pane_tree.pane_node_by_index(index).ok_or_else(|| {
-            Error::Unrecoverable(format!(
-                "Attempted to split pane but could not find pane at index: {}",
-                index
-            ))
-        })?;
-
-        let mut current_pane = active_pane;
-
-        let buffer_id = loop {
-            match &current_pane.node_type {
-                pane::PaneNodeType::Leaf(pane) => break pane.buffer_id,
-                pane::PaneNodeType::VSplit(split) | pane::PaneNodeType::HSplit(split) => {
-                    current_pane = self
-                        .pane_tree
-                        .pane_node_by_index(split.first)
-                        .ok_or_else(|| {
-                            Error::Unrecoverable(format!(
-                                "Attemped to find leaf for split pane but pane does not exist at index: {}",
-                                split.first
-                            ))
-                        })?;
-                }
-            };
-        };
+
+    pub fn revert_buffer(&mut self, buffer_id: usize) -> Result<()> {
+        let Some(file_id) = self.buffer_file_map.get_by_left(&buffer_id) else {
+            return Err(Error::Recoverable(format!(
+                "Attempted to revert buffer id that has no file associated. Buffer id: {}",
+                buffer_id
+            )));
+        };
+        let buffer = self
+            .buffers
+            .get_mut(buffer_id)
+            .and_then(|b| b.as_mut())
+            .ok_or_else(|| {
+                Error::Recoverable(format!(
+                    "Attempted to revert invalid buffer id: {}",
+                    buffer_id
+                ))
+            })?;
+        let file_handle = self
+            .files
+            .get_mut(*file_id)
+            .and_then(|f| f.as_mut())
+            .ok_or_else(|| {
+                Error::Recoverable(format!(
+                    "Attempted to revert buffer id {} from invalid file id: {}",
+                    buffer_id, file_id
+                ))
+            })?;
+
+        if buffer.buffer_type == EditorBufferType::Paged {
+            file_handle
+                .open_read_only()
+                .and_then(|file| buffer.replace_content_from_file(file))
+                .map_err(|e| {
+                    Error::Recoverable(format!(
+                        "Failed to read file id {} while reverting buffer id {}. {}",
+                        file_id, buffer_id, e
+                    ))
+                })?;
+
+            return Ok(());
+        }
+
+        let mut content = String::new();
+        file_handle
+            .rewind()
+            .and_then(|_| file_handle.read_to_string(&mut content))
+            .map_err(|e| {
+                Error::Recoverable(format!(
+                    "Failed to read file id {} while reverting buffer id {}. {}",
+                    file_id, buffer_id, e
+                ))
+            })?;
+
+        buffer.replace_content(content);
+        buffer.saved_hash = Some(buffer.content_hash());
+
+        Ok(())
+    }
+
+    /// Every match of `regex` within the lines of every open buffer, ordered
+    /// by buffer id and then by position within the buffer
+    pub fn grep_buffers(&self, regex: &Regex) -> Vec<BufferMatch> {
+        let mut matches = vec![];
+        for (buffer_id, buffer) in self.buffers.iter().enumerate() {
+            let Some(buffer) = buffer else {
+                continue;
+            };
+
+            matches.extend(search_lines(buffer, regex).into_iter().map(|line_match| {
+                BufferMatch {
+                    buffer_id,
+                    line_index: line_match.line_index,
+                    byte_index: line_match.byte_index,
+                    line_text: line_match.line_text,
+                }
+            }));
+        }
+
+        matches
+    }
+
+    /// Paths of the files which are linked to buffers
+    pub fn linked_file_paths(&self) -> HashSet<String> {
+        self.buffer_file_map
+            .right_values()
+            .filter_map(|file_id| self.files.get(*file_id)?.as_ref())
+            .map(|file_handle| file_handle.path.to_string())
+            .collect()
+    }
+
+    /// Handles the file at `path` changing outside the editor, returning the
+    /// hook to run for the buffer linked to it if any. A buffer without
+    /// changes from its file's content is reverted to the new content, while
+    /// a buffer with changes is left as is and reported as conflicting.
+    /// Changes which leave the file as the buffer last saw it, such as the
+    /// editor's own writes, are ignored.
+    pub fn handle_file_change(&mut self, path: &str) -> Result<Option<HookType>> {
+        let Some(file_id) = self.files.iter().position(|file_handle| {
+            file_handle
+                .as_ref()
+                .is_some_and(|file_handle| file_handle.path.as_ref() == path)
+        }) else {
+            return Ok(None);
+        };
+        let Some(buffer_id) = self.buffer_file_map.get_by_right(&file_id).copied() else {
+            return Ok(None);
+        };
+        let (Some(buffer), Some(file_handle)) = (
+            self.buffers.get(buffer_id).and_then(|b| b.as_ref()),
+            self.files.get_mut(file_id).and_then(|f| f.as_mut()),
+        ) else {
+            return Ok(None);
+        };
+
+        let mut content = vec![];
+        file_handle
+            .reopen()
+            .and_then(|_| file_handle.read_to_end(&mut content))
+            .map_err(|e| {
+                Error::Recoverable(format!(
+                    "Failed to read changed file id {} linked to buffer id {}. {}",
+                    file_id, buffer_id, e
+                ))
+            })?;
+
+        if buffer.saved_hash == Some(fnv_hash(&content)) {
+            Ok(None)
+        } else if buffer.is_modified_from_disk() {
+            Ok(Some(HookType::FileConflict { buffer_id }))
+        } else {
+            self.revert_buffer(buffer_id)?;
+            Ok(Some(HookType::BufferReloaded { buffer_id }))
+        }
+    }
+
+    /// Snapshot of the buffers, the active tab page's pane layout, and the
+    /// editor options. Other tab pages are not included. Paged buffers are
+    /// snapshot without their content, which is paged in again from their
+    /// linked file when restored.
+    pub fn session(&self) -> Session {
+        let buffers = self
+            .buffers
+            .iter()
+            .enumerate()
+            .map(|(buffer_id, buffer)| {
+                let buffer = buffer.as_ref()?;
+                let path = self
+                    .buffer_file_map
+                    .get_by_left(&buffer_id)
+                    .and_then(|file_id| self.files.get(*file_id)?.as_ref())
+                    .map(|file_handle| file_handle.path.to_string());
+
+                Some(SessionBuffer {
+                    buffer_type: buffer.buffer_type,
+                    content: if buffer.buffer_type == EditorBufferType::Paged {
+                        String::new()
+                    } else {
+                        buffer.content_copy()
+                    },
+                    cursor_byte_index: buffer.cursor_byte_index(),
+                    pane_cursors: buffer
+                        .pane_cursors
+                        .iter()
+                        .filter(|((tab_index, _), _)| *tab_index == self.active_tab_index)
+                        .map(|((_, pane_index), byte_index)| (*pane_index, *byte_index))
+                        .collect(),
+                    path,
+                })
+            })
+            .collect();
+
+        let panes = self
+            .pane_tree
+            .tree
+            .iter()
+            .map(|node| {
+                node.as_ref().map(|node| SessionPane {
+                    parent_index: node.parent_index,
+                    node_type: node.node_type.clone(),
+                })
+            })
+            .collect();
+
+        Session {
+            options: self.options.clone(),
+            buffers,
+            panes,
+            root_pane_index: self.pane_tree.root_index(),
+            active_pane_index: self.active_pane_index,
+        }
+    }
+
+    /// Replaces the buffers, files, and pane layout with those of `session`,
+    /// closing every other tab page. Buffers are linked again to the files at
+    /// their saved paths, and are left dirty if their content differs from
+    /// their file's.
+    pub fn restore_session(&mut self, session: Session) -> Result<()> {
+        let is_active_leaf = matches!(
+            session.panes.get(session.active_pane_index),
+            Some(Some(SessionPane {
+                node_type: pane::PaneNodeType::Leaf(_),
+                ..
+            }))
+        );
+        if !is_active_leaf {
+            return Err(Error::Recoverable(format!(
+                "Attempted to restore session without an active leaf pane at index: {}",
+                session.active_pane_index
+            )));
+        }
+        let has_missing_buffer = session.panes.iter().flatten().any(|pane| match &pane.node_type {
+            pane::PaneNodeType::Leaf(pane) => {
+                !matches!(session.buffers.get(pane.buffer_id), Some(Some(_)))
+            }
+            pane::PaneNodeType::VSplit(_) | pane::PaneNodeType::HSplit(_) => false,
+        });
+        if has_missing_buffer {
+            return Err(Error::Recoverable(
+                "Attempted to restore session with a pane showing a missing buffer".to_string(),
+            ));
+        }
+
+        let pane_tree = PaneTree::from_nodes(
+            session.root_pane_index,
+            session
+                .panes
+                .into_iter()
+                .map(|pane| {
+                    pane.map(|pane| pane::PaneNode {
+                        node_type: pane.node_type,
+                        parent_index: pane.parent_index,
+                        is_dirty: true,
+                    })
+                })
+                .collect(),
+        )
+        .map_err(Error::Recoverable)?;
+
+        let mut paths = vec![];
+        let mut buffers = vec![];
+        for (buffer_id, session_buffer) in session.buffers.into_iter().enumerate() {
+            let Some(session_buffer) = session_buffer else {
+                buffers.push(None);
+                continue;
+            };
+
+            let mut buffer = EditorBuffer::new_typed(session_buffer.buffer_type);
+            let cursor_byte_index = session_buffer
+                .cursor_byte_index
+                .min(session_buffer.content.len());
+            buffer.replace_content(session_buffer.content);
+            buffer.set_cursor_byte_index(cursor_byte_index, false);
+            buffer.pane_cursors = session_buffer
+                .pane_cursors
+                .into_iter()
+                .map(|(pane_index, byte_index)| ((0, pane_index), byte_index))
+                .collect();
+            buffers.push(Some(buffer));
+
+            if let Some(path) = session_buffer.path {
+                let paged_cursor = (session_buffer.buffer_type == EditorBufferType::Paged)
+                    .then_some(session_buffer.cursor_byte_index);
+                paths.push((buffer_id, path, paged_cursor));
+            }
+        }
+
+        self.buffers = buffers;
+        self.files = vec![];
+        self.buffer_file_map = BiMap::new();
+        self.pane_tree = pane_tree;
+        self.active_pane_index = session.active_pane_index;
+        self.inactive_tabs = vec![];
+        self.active_tab_index = 0;
+        self.jump_list = JumpList::new();
+        self.last_edit = None;
+        self.popup = None;
+        self.prompt = None;
+        self.options = session.options;
+        self.input_poll_rate = Duration::from_millis(self.options.poll_rate_ms);
+
+        for (buffer_id, path, paged_cursor) in paths {
+            let file_id = self.open_file(path)?;
+            if let Some(cursor_byte_index) = paged_cursor {
+                // Paged content was not saved, so is paged in from the file
+                self.link_buffer(buffer_id, file_id, true)?;
+                if let Some(buffer) = self.mut_buffer_by_id(buffer_id) {
+                    buffer.set_cursor_byte_index(cursor_byte_index, false);
+                }
+                continue;
+            }
+            self.link_buffer(buffer_id, file_id, false)?;
+
+            let saved_hash = self
+                .files
+                .get(file_id)
+                .and_then(|f| f.as_ref())
+                .and_then(|file_handle| std::fs::read(file_handle.path.as_ref()).ok())
+                .map(|content| fnv_hash(&content));
+            if let Some(buffer) = self.mut_buffer_by_id(buffer_id) {
+                buffer.saved_hash = saved_hash;
+                buffer.is_content_dirty = buffer.is_modified_from_disk();
+            }
+        }
+
+        self.force_redraw();
+
+        Ok(())
+    }
+
+    /// Writes the session returned by `session` to the file at `path`
+    pub fn save_session(&self, path: &str) -> Result<()> {
+        let path = shellexpand::full(path).map_err(|e| {
+            Error::Recoverable(format!(
+                "Failed to expand session path due to: {}",
+                e.var_name
+            ))
+        })?;
+
+        std::fs::write(path.as_ref(), self.session().to_text()).map_err(|e| {
+            Error::Recoverable(format!("Failed to write session to {}: {}", path, e))
+        })
+    }
+
+    /// Restores the session written to the file at `path` by `save_session`
+    pub fn load_session(&mut self, path: &str) -> Result<()> {
+        let path = shellexpand::full(path).map_err(|e| {
+            Error::Recoverable(format!(
+                "Failed to expand session path due to: {}",
+                e.var_name
+            ))
+        })?;
+
+        let text = std::fs::read_to_string(path.as_ref()).map_err(|e| {
+            Error::Recoverable(format!("Failed to read session from {}: {}", path, e))
+        })?;
+        let session = Session::parse(&text, self.options.clone()).map_err(|e| {
+            Error::Recoverable(format!("Failed to parse session from {}: {}", path, e))
+        })?;
+
+        self.restore_session(session)
+    }
+}
+
+impl EditorState {
+    pub fn vsplit(&mut self, index: usize) -> Result<()> {
+        let active_pane = self.pane_tree.pane_node_by_index(index).ok_or_else(|| {
+            Error::Unrecoverable(format!(
+                "Attempted to split pane but could not find pane at index: {}",
+                index
+            ))
+        })?;
+
+        let mut current_pane = active_pane;
+
+        let buffer_id = loop {
+            match &current_pane.node_type {
+                pane::PaneNodeType::Leaf(pane) => break pane.buffer_id,
+                pane::PaneNodeType::VSplit(split) | pane::PaneNodeType::HSplit(split) => {
+                    current_pane = self
+                        .pane_tree
+                        .pane_node_by_index(split.first)
+                        .ok_or_else(|| {
+                            Error::Unrecoverable(format!(
+                                "Attemped to find leaf for split pane but pane does not exist at index: {}",
+                                split.first
+                            ))
+                        })?;
+                }
+            };
+        };
 
         let new_split_root_index = self
             .pane_tree
@@ -496,6 +1463,57 @@ impl EditorState {
         Ok(())
     }
 
+    /// Splits the pane at `index` like `vsplit`, but shows a new empty buffer
+    /// in the new pane. Returns the new pane's id and its buffer's id.
+    pub fn vsplit_new_buffer(&mut self, index: usize) -> Result<PaneBufferChange> {
+        self.split_new_buffer(index, PaneTree::vsplit)
+    }
+
+    /// Splits the pane at `index` like `hsplit`, but shows a new empty buffer
+    /// in the new pane. Returns the new pane's id and its buffer's id.
+    pub fn hsplit_new_buffer(&mut self, index: usize) -> Result<PaneBufferChange> {
+        self.split_new_buffer(index, PaneTree::hsplit)
+    }
+
+    fn split_new_buffer(
+        &mut self,
+        index: usize,
+        split: impl FnOnce(&mut PaneTree, usize, usize) -> pane::Result<usize>,
+    ) -> Result<PaneBufferChange> {
+        if self.pane_tree.pane_node_by_index(index).is_none() {
+            return Err(Error::Recoverable(format!(
+                "Attempted to split pane but could not find pane at index: {}",
+                index
+            )));
+        }
+
+        let buffer_id = self.create_buffer();
+        let new_split_root_index =
+            split(&mut self.pane_tree, index, buffer_id).map_err(Error::Recoverable)?;
+
+        if self.active_pane_index == index {
+            self.active_pane_index = new_split_root_index
+        }
+
+        let pane_id = match self
+            .pane_tree
+            .pane_node_by_index(new_split_root_index)
+            .map(|node| &node.node_type)
+        {
+            Some(pane::PaneNodeType::VSplit(split) | pane::PaneNodeType::HSplit(split)) => {
+                split.second
+            }
+            _ => {
+                return Err(Error::Unrecoverable(format!(
+                    "Split pane not found after splitting at index: {}",
+                    index
+                )))
+            }
+        };
+
+        Ok(PaneBufferChange { pane_id, buffer_id })
+    }
+
     pub fn move_active_up(&mut self) -> Result<()> {
         let active_pane = self
             .pane_tree
@@ -510,7 +1528,7 @@ impl EditorState {
             return Ok(());
         };
 
-        self.active_pane_index = parent_index;
+        self.set_active_pane(parent_index);
         Ok(())
     }
 
@@ -531,7 +1549,8 @@ impl EditorState {
         match &active_pane.node_type {
             pane::PaneNodeType::Leaf(_) => (),
             pane::PaneNodeType::VSplit(split) | pane::PaneNodeType::HSplit(split) => {
-                self.active_pane_index = get_index(split)
+                let index = get_index(split);
+                self.set_active_pane(index)
             }
         }
 
@@ -582,13 +1601,51 @@ impl EditorState {
 #[derive(Clone)]
 pub struct EditorOptions {
     pub tab_width: u16,
+    pub expand_tab: bool,
+    pub final_newline: bool,
+    /// Flash the active pane rather than sounding the terminal bell
+    pub visual_bell: bool,
+    /// Draw a dimmed cursor in inactive panes where their cursor was left
+    pub inactive_cursor: bool,
+    /// Time a buffer's content must go unchanged after an edit before the
+    /// `BufferModified` hook runs for it. At least 1.
+    pub modified_delay_ms: u64,
+    /// Revert buffers without changes when their linked file changes on disk
+    pub auto_reload: bool,
+    /// Time waited for input before running scripts and redrawing. At least 1.
+    pub poll_rate_ms: u64,
 }
 
 impl EditorOptions {
+    pub fn tab_width_for(&self, buffer: &EditorBuffer) -> u16 {
+        buffer.options.tab_width.unwrap_or(self.tab_width)
+    }
+
+    pub fn expand_tab_for(&self, buffer: &EditorBuffer) -> bool {
+        buffer.options.expand_tab.unwrap_or(self.expand_tab)
+    }
+
+    pub fn final_newline_for(&self, buffer: &EditorBuffer) -> bool {
+        buffer.options.final_newline.unwrap_or(self.final_newline)
+    }
+
     pub fn update(&mut self, update_list: EditorOptionList) {
         for update in update_list.0 {
             match update {
                 EditorOptionType::TabWidth(new_width) => self.tab_width = new_width,
+                EditorOptionType::ExpandTab(expand_tab) => self.expand_tab = expand_tab,
+                EditorOptionType::FinalNewline(final_newline) => {
+                    self.final_newline = final_newline
+                }
+                EditorOptionType::VisualBell(visual_bell) => self.visual_bell = visual_bell,
+                EditorOptionType::InactiveCursor(inactive_cursor) => {
+                    self.inactive_cursor = inactive_cursor
+                }
+                EditorOptionType::ModifiedDelay(modified_delay_ms) => {
+                    self.modified_delay_ms = modified_delay_ms.max(1)
+                }
+                EditorOptionType::AutoReload(auto_reload) => self.auto_reload = auto_reload,
+                EditorOptionType::PollRate(poll_rate_ms) => self.poll_rate_ms = poll_rate_ms.max(1),
             }
         }
     }
@@ -597,6 +1654,34 @@ impl EditorOptions {
 #[auto_lua]
 pub enum EditorOptionType {
     TabWidth(u16),
+    ExpandTab(bool),
+    FinalNewline(bool),
+    VisualBell(bool),
+    InactiveCursor(bool),
+    ModifiedDelay(u64),
+    AutoReload(bool),
+    PollRate(u64),
+}
+
+/// Saved pane layout of a tab page which is not being shown
+pub struct TabPage {
+    pub pane_tree: PaneTree,
+    pub active_pane_index: usize,
+}
+
+#[auto_lua]
+#[derive(Clone, Debug)]
+pub struct TabPageInfo {
+    pub index: usize,
+    pub is_active: bool,
+    pub pane_count: usize,
+    pub active_buffer_id: Option<usize>,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Bell {
+    Audible,
+    Visual,
 }
 
 pub struct EditorOptionList(Vec<EditorOptionType>);
@@ -627,11 +1712,60 @@ impl<'lua> FromLua<'lua> for EditorOptionList {
 
             match key {
                 EditorOptionTypeName::TabWidth => {
+                    let Some(value) = tab_width_from_lua(&option_value) else {
+                        continue;
+                    };
+
+                    option_list.push(EditorOptionType::TabWidth(value));
+                }
+                EditorOptionTypeName::ExpandTab => {
+                    let Some(value) = option_value.as_boolean() else {
+                        continue;
+                    };
+
+                    option_list.push(EditorOptionType::ExpandTab(value));
+                }
+                EditorOptionTypeName::FinalNewline => {
+                    let Some(value) = option_value.as_boolean() else {
+                        continue;
+                    };
+
+                    option_list.push(EditorOptionType::FinalNewline(value));
+                }
+                EditorOptionTypeName::VisualBell => {
+                    let Some(value) = option_value.as_boolean() else {
+                        continue;
+                    };
+
+                    option_list.push(EditorOptionType::VisualBell(value));
+                }
+                EditorOptionTypeName::InactiveCursor => {
+                    let Some(value) = option_value.as_boolean() else {
+                        continue;
+                    };
+
+                    option_list.push(EditorOptionType::InactiveCursor(value));
+                }
+                EditorOptionTypeName::ModifiedDelay => {
                     let Some(value) = option_value.as_u32() else {
                         continue;
                     };
 
-                    option_list.push(EditorOptionType::TabWidth(value as u16));
+                    option_list.push(EditorOptionType::ModifiedDelay(value as u64));
+                }
+                EditorOptionTypeName::AutoReload => {
+                    let Some(value) = option_value.as_boolean() else {
+                        continue;
+                    };
+
+                    option_list.push(EditorOptionType::AutoReload(value));
+                }
+                EditorOptionTypeName::PollRate => {
+                    let Some(value) = option_value.as_u32() else {
+                        continue;
+                    };
+
+                    option_list.push(EditorOptionType::PollRate(value as u64));
                 }
             }
         }
@@ -648,9 +1782,683 @@ impl<'lua> IntoLua<'lua> for EditorOptionList {
                 EditorOptionType::TabWidth(width) => {
                     table.set(EditorOptionTypeName::TabWidth, width)?
                 }
+                EditorOptionType::ExpandTab(expand_tab) => {
+                    table.set(EditorOptionTypeName::ExpandTab, expand_tab)?
+                }
+                EditorOptionType::FinalNewline(final_newline) => {
+                    table.set(EditorOptionTypeName::FinalNewline, final_newline)?
+                }
+                EditorOptionType::VisualBell(visual_bell) => {
+                    table.set(EditorOptionTypeName::VisualBell, visual_bell)?
+                }
+                EditorOptionType::InactiveCursor(inactive_cursor) => {
+                    table.set(EditorOptionTypeName::InactiveCursor, inactive_cursor)?
+                }
+                EditorOptionType::ModifiedDelay(modified_delay_ms) => {
+                    table.set(EditorOptionTypeName::ModifiedDelay, modified_delay_ms)?
+                }
+                EditorOptionType::AutoReload(auto_reload) => {
+                    table.set(EditorOptionTypeName::AutoReload, auto_reload)?
+                }
+                EditorOptionType::PollRate(poll_rate_ms) => {
+                    table.set(EditorOptionTypeName::PollRate, poll_rate_ms)?
+                }
             }
         }
 
         table.into_lua(lua)
     }
 }
+
+/// Formatter running in the background on a buffer's content before the
+/// buffer is written
+pub struct BufferFormat {
+    command: String,
+    /// Hash of the content given to the formatter, so output for content
+    /// edited while the formatter ran is not used
+    content_hash: u64,
+    task_id: Option<usize>,
+    output: Option<std::result::Result<ShellOutput, String>>,
+}
+
+impl BufferFormat {
+    fn start(buffer: &EditorBuffer, command: &str, tasks: &mut TaskRunner) -> Self {
+        let mut command_parts = command.split_whitespace();
+        let (task_id, output) = match command_parts.next() {
+            Some(program) => {
+                let program = program.to_string();
+                let args: Vec<String> = command_parts.map(String::from).collect();
+                let stdin = buffer.content_copy();
+                let task_id = tasks.spawn(move || {
+                    match shell::run(&program, &args, Some(stdin)) {
+                        Ok(output) => TaskResult::ShellOutput(output),
+                        Err(message) => TaskResult::Failed(message),
+                    }
+                });
+
+                (Some(task_id), None)
+            }
+            None => (None, Some(Err("Formatter command is empty".to_string()))),
+        };
+
+        Self {
+            command: command.to_string(),
+            content_hash: buffer.content_hash(),
+            task_id,
+            output,
+        }
+    }
+
+    /// Id of the task running the formatter, whose result is given to
+    /// `finish`. `None` if the formatter could not be started.
+    pub fn task_id(&self) -> Option<usize> {
+        self.task_id
+    }
+
+    /// Records the result of the formatter's task
+    pub fn finish(&mut self, result: TaskResult) {
+        self.output = Some(match result {
+            TaskResult::ShellOutput(output) => Ok(output),
+            TaskResult::Failed(message) => Err(message),
+            _ => Err(format!(
+                "Formatter \"{}\" completed without shell output",
+                self.command
+            )),
+        });
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.output.is_some()
+    }
+
+    /// Replaces the buffer's content with the formatter's output. Errs with a
+    /// description of why if the formatter failed or had not finished, or if
+    /// the buffer was edited since the formatter started.
+    fn apply(self, buffer: &mut EditorBuffer) -> std::result::Result<(), String> {
+        let output = self.output.unwrap_or_else(|| {
+            Err(format!("Formatter \"{}\" has not finished", self.command))
+        })?;
+        if !output.is_success() {
+            return Err(format!(
+                "Formatter \"{}\" failed with exit code {:?}. {}",
+                self.command, output.exit_code, output.stderr
+            ));
+        }
+        if buffer.content_hash() != self.content_hash {
+            return Err(format!(
+                "Buffer was edited while formatter \"{}\" ran",
+                self.command
+            ));
+        }
+
+        if output.stdout != buffer.content_copy() {
+            buffer.replace_content(output.stdout);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor_state_with(content: &str) -> EditorState {
+        let mut editor_state = EditorState::new(Duration::from_millis(10));
+        editor_state
+            .mut_buffer_by_id(0)
+            .unwrap()
+            .insert_at_cursor(content);
+        editor_state
+    }
+
+    fn set_cursor(editor_state: &mut EditorState, byte_index: usize) {
+        editor_state
+            .mut_buffer_by_id(0)
+            .unwrap()
+            .set_cursor_byte_index(byte_index, false);
+    }
+
+    fn cursor(editor_state: &EditorState) -> usize {
+        editor_state.buffer_by_id(0).unwrap().cursor_byte_index()
+    }
+
+    #[test]
+    fn tab_pages_sharing_a_buffer_keep_their_own_cursors() {
+        let mut editor_state = editor_state_with("one two three");
+        set_cursor(&mut editor_state, 2);
+
+        editor_state.new_tab(0).unwrap();
+        set_cursor(&mut editor_state, 8);
+
+        editor_state.switch_tab(0).unwrap();
+        assert_eq!(cursor(&editor_state), 2);
+
+        editor_state.switch_tab(1).unwrap();
+        assert_eq!(cursor(&editor_state), 8);
+    }
+
+    #[test]
+    fn closing_a_tab_page_forgets_its_cursors() {
+        let mut editor_state = editor_state_with("one two three");
+        editor_state.new_tab(0).unwrap();
+        editor_state.new_tab(0).unwrap();
+        set_cursor(&mut editor_state, 8);
+        editor_state.switch_tab(1).unwrap();
+        set_cursor(&mut editor_state, 4);
+        editor_state.switch_tab(2).unwrap();
+
+        // Tab page 1 closes, leaving tab page 2's cursor at index 1
+        editor_state.switch_tab(1).unwrap();
+        assert_eq!(editor_state.close_tab().unwrap(), vec![0]);
+        assert_eq!(editor_state.active_tab_index, 0);
+
+        let pane_cursors = &editor_state.buffer_by_id(0).unwrap().pane_cursors;
+        assert_eq!(pane_cursors.get(&(1, 0)), Some(&8));
+        assert_eq!(pane_cursors.len(), 1);
+    }
+
+    #[test]
+    fn closing_a_pane_forgets_its_cursor() {
+        let mut editor_state = editor_state_with("one two three");
+        let split_index = editor_state.pane_tree.vsplit(0, 0).unwrap();
+        set_cursor(&mut editor_state, 4);
+        editor_state.set_active_pane(1);
+        set_cursor(&mut editor_state, 8);
+        editor_state.set_active_pane(0);
+        assert!(editor_state
+            .buffer_by_id(0)
+            .unwrap()
+            .pane_cursors
+            .contains_key(&(0, 1)));
+
+        assert_eq!(editor_state.close_pane_child(split_index, false).unwrap(), 1);
+        assert_eq!(editor_state.active_pane_index, 0);
+        assert!(editor_state.buffer_by_id(0).unwrap().pane_cursors.is_empty());
+        assert_eq!(cursor(&editor_state), 4);
+    }
+
+    #[test]
+    fn typed_buffers_report_their_requested_type() {
+        let mut editor_state = EditorState::new(Duration::from_millis(10));
+        let naive_id = editor_state.create_buffer_typed(EditorBufferType::Naive);
+        let gap_id = editor_state.create_buffer();
+
+        let buffer_type = |id| editor_state.buffer_by_id(id).unwrap().buffer_type;
+        assert!(buffer_type(naive_id) == EditorBufferType::Naive);
+        assert!(buffer_type(gap_id) == EditorBufferType::Gap);
+    }
+
+    /// Starts writing buffer 0, linked to a new file holding `content`, with
+    /// `formatter` set for the file, returning the file's path and the
+    /// running formatter.
+    fn start_formatted_write(
+        editor_state: &mut EditorState,
+        tasks: &mut TaskRunner,
+        name: &str,
+        formatter: &str,
+    ) -> (std::path::PathBuf, BufferFormat) {
+        let path = std::env::temp_dir().join(format!(
+            "bad_red_format_{}_{}.txt",
+            name,
+            std::process::id()
+        ));
+        std::fs::write(&path, "").unwrap();
+        let file_id = editor_state
+            .open_file(path.to_string_lossy().into_owned())
+            .unwrap();
+        editor_state.link_buffer(0, file_id, false).unwrap();
+        editor_state
+            .formatters
+            .insert("txt".to_string(), formatter.to_string());
+        editor_state.mut_buffer_by_id(0).unwrap().is_content_dirty = true;
+
+        let format = editor_state.start_write(0, tasks).unwrap().unwrap();
+        (path, format)
+    }
+
+    fn wait_for(tasks: &TaskRunner, format: &mut BufferFormat) {
+        for _ in 0..500 {
+            for completion in tasks.completed() {
+                if Some(completion.id) == format.task_id() {
+                    format.finish(completion.result);
+                    return;
+                }
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        panic!("Formatter did not finish");
+    }
+
+    #[test]
+    fn formatter_output_is_written_once_finished() {
+        let mut editor_state = editor_state_with("hello\n");
+        let mut tasks = TaskRunner::new();
+        let (path, mut format) =
+            start_formatted_write(&mut editor_state, &mut tasks, "upper", "tr a-z A-Z");
+
+        wait_for(&tasks, &mut format);
+        assert_eq!(editor_state.write_buffer(0, Some(format)).unwrap(), None);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "HELLO\n");
+        assert_eq!(editor_state.buffer_by_id(0).unwrap().content_copy(), "HELLO\n");
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn buffer_edited_while_formatting_is_written_unformatted() {
+        let mut editor_state = editor_state_with("hello\n");
+        let mut tasks = TaskRunner::new();
+        let (path, mut format) =
+            start_formatted_write(&mut editor_state, &mut tasks, "edited", "tr a-z A-Z");
+        set_cursor(&mut editor_state, 0);
+        editor_state.mut_buffer_by_id(0).unwrap().insert_at_cursor("x");
+
+        wait_for(&tasks, &mut format);
+        assert!(editor_state.write_buffer(0, Some(format)).unwrap().is_some());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "xhello\n");
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn failed_formatter_is_reported_and_written_unformatted() {
+        let mut editor_state = editor_state_with("hello\n");
+        let mut tasks = TaskRunner::new();
+        let (path, mut format) =
+            start_formatted_write(&mut editor_state, &mut tasks, "failed", "false");
+
+        wait_for(&tasks, &mut format);
+        let failure = editor_state.write_buffer(0, Some(format)).unwrap().unwrap();
+        assert!(failure.contains("\"false\" failed"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "hello\n");
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn linking_a_file_which_is_not_utf8_fails_recoverably() {
+        let path = std::env::temp_dir().join(format!(
+            "bad_red_invalid_utf8_{}.txt",
+            std::process::id()
+        ));
+        std::fs::write(&path, [b'a', 0xff, b'b']).unwrap();
+        let mut editor_state = editor_state_with("kept");
+        let file_id = editor_state
+            .open_file(path.to_string_lossy().into_owned())
+            .unwrap();
+
+        let result = editor_state.link_buffer(0, file_id, true);
+        let _ = std::fs::remove_file(&path);
+
+        match result {
+            Err(Error::Recoverable(message)) => assert!(message.contains("byte index 1")),
+            _ => panic!("Expected a recoverable error linking invalid UTF-8"),
+        }
+        assert_eq!(editor_state.buffer_by_id(0).unwrap().content_copy(), "kept");
+        assert!(!editor_state.buffer_file_map.contains_left(&0));
+    }
+
+    #[test]
+    fn file_changes_reload_clean_buffers_and_report_dirty_ones() {
+        let path = std::env::temp_dir().join(format!(
+            "bad_red_file_change_{}.txt",
+            std::process::id()
+        ));
+        std::fs::write(&path, "first\n").unwrap();
+        let path_string = path.to_string_lossy().into_owned();
+        let mut editor_state = EditorState::new(Duration::from_millis(10));
+        let file_id = editor_state.open_file(path_string.clone()).unwrap();
+        editor_state.link_buffer(0, file_id, true).unwrap();
+
+        let unchanged = editor_state.handle_file_change(&path_string);
+
+        std::fs::write(&path, "second\n").unwrap();
+        let clean = editor_state.handle_file_change(&path_string);
+        let reloaded_content = editor_state.buffer_by_id(0).unwrap().content_copy();
+
+        editor_state
+            .mut_buffer_by_id(0)
+            .unwrap()
+            .insert_at_cursor("edit ");
+        std::fs::write(&path, "third\n").unwrap();
+        let dirty = editor_state.handle_file_change(&path_string);
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(unchanged.unwrap(), None);
+        assert_eq!(clean.unwrap(), Some(HookType::BufferReloaded { buffer_id: 0 }));
+        assert_eq!(reloaded_content, "second\n");
+        assert_eq!(dirty.unwrap(), Some(HookType::FileConflict { buffer_id: 0 }));
+        assert_eq!(
+            editor_state.buffer_by_id(0).unwrap().content_copy(),
+            "edit second\n"
+        );
+    }
+
+    #[test]
+    fn reverting_discards_edits_and_clamps_the_cursor() {
+        let path = std::env::temp_dir().join(format!("bad_red_revert_{}.txt", std::process::id()));
+        std::fs::write(&path, "one\ntwo\n").unwrap();
+        let mut editor_state = EditorState::new(Duration::from_millis(10));
+        let file_id = editor_state
+            .open_file(path.to_string_lossy().into_owned())
+            .unwrap();
+        editor_state.link_buffer(0, file_id, true).unwrap();
+
+        let buffer = editor_state.mut_buffer_by_id(0).unwrap();
+        buffer.set_cursor_byte_index(8, false);
+        buffer.insert_at_cursor("three\nfour\n");
+        assert!(buffer.is_content_dirty);
+
+        let revert_result = editor_state.revert_buffer(0);
+        let reverted_buffer = editor_state.buffer_by_id(0).unwrap();
+        let reverted = (reverted_buffer.content_copy(), reverted_buffer.is_content_dirty);
+        std::fs::write(&path, "x").unwrap();
+        let shrunk_result = editor_state.revert_buffer(0);
+        let _ = std::fs::remove_file(&path);
+
+        revert_result.unwrap();
+        assert_eq!(reverted, ("one\ntwo\n".to_string(), false));
+        shrunk_result.unwrap();
+        let buffer = editor_state.buffer_by_id(0).unwrap();
+        assert_eq!(buffer.content_copy(), "x");
+        assert_eq!(buffer.cursor_byte_index(), 1);
+        assert!(!buffer.is_content_dirty);
+        assert!(editor_state.revert_buffer(1).is_err());
+    }
+
+    #[test]
+    fn reverting_a_paged_buffer_clamps_its_marks() {
+        let path = std::env::temp_dir().join(format!(
+            "bad_red_revert_paged_{}.txt",
+            std::process::id()
+        ));
+        std::fs::write(&path, "one\ntwo\nthree\n").unwrap();
+        let mut editor_state = EditorState::new(Duration::from_millis(10));
+        let file_id = editor_state
+            .open_file(path.to_string_lossy().into_owned())
+            .unwrap();
+        let buffer_id = editor_state.create_paged_buffer(file_id, 4).unwrap();
+        editor_state.link_buffer(buffer_id, file_id, false).unwrap();
+
+        let buffer = editor_state.mut_buffer_by_id(buffer_id).unwrap();
+        buffer.set_cursor_byte_index(12, false);
+        buffer.named_marks.insert('a', 13);
+
+        std::fs::write(&path, "one\n").unwrap();
+        let revert_result = editor_state.revert_buffer(buffer_id);
+        let _ = std::fs::remove_file(&path);
+
+        revert_result.unwrap();
+        let buffer = editor_state.buffer_by_id(buffer_id).unwrap();
+        assert_eq!(buffer.content_copy(), "one\n");
+        assert_eq!(buffer.cursor_byte_index(), 4);
+        assert_eq!(buffer.named_marks.get(&'a'), Some(&4));
+    }
+
+    #[test]
+    fn saving_adds_a_final_newline_only_when_missing() {
+        let path = std::env::temp_dir().join(format!(
+            "bad_red_final_newline_{}.txt",
+            std::process::id()
+        ));
+
+        let mut written = vec![];
+        for content in ["one", "one\n", ""] {
+            std::fs::write(&path, "old").unwrap();
+            let mut editor_state = EditorState::new(Duration::from_millis(10));
+            editor_state.options.final_newline = true;
+            let file_id = editor_state
+                .open_file(path.to_string_lossy().into_owned())
+                .unwrap();
+            editor_state.link_buffer(0, file_id, true).unwrap();
+
+            let buffer = editor_state.mut_buffer_by_id(0).unwrap();
+            buffer.set_cursor_byte_index(0, false);
+            buffer.delete_at_cursor(3);
+            buffer.insert_at_cursor(content);
+            let write_result = editor_state.write_buffer(0, None);
+            written.push((
+                write_result.map(|_| ()),
+                std::fs::read_to_string(&path).ok(),
+                editor_state.buffer_by_id(0).unwrap().content_copy(),
+            ));
+        }
+        let _ = std::fs::remove_file(&path);
+
+        for ((write_result, file_content, buffer_content), (content, expected)) in
+            written.into_iter().zip([("one", "one\n"), ("one\n", "one\n"), ("", "")])
+        {
+            write_result.unwrap();
+            assert_eq!(file_content.as_deref(), Some(expected));
+            // Only the written file gains the newline
+            assert_eq!(buffer_content, content);
+        }
+    }
+
+    #[test]
+    fn grepping_buffers_reports_each_match_by_buffer_and_line() {
+        let mut editor_state = EditorState::new(Duration::from_millis(10));
+        editor_state
+            .mut_buffer_by_id(0)
+            .unwrap()
+            .insert_at_cursor("fn one\nlet two\n");
+        let second_id = editor_state.create_buffer();
+        editor_state
+            .mut_buffer_by_id(second_id)
+            .unwrap()
+            .insert_at_cursor("x\n  two, two");
+
+        let matches: Vec<(usize, usize, usize, String)> = editor_state
+            .grep_buffers(&Regex::new("two").unwrap())
+            .into_iter()
+            .map(|found| (found.buffer_id, found.line_index, found.byte_index, found.line_text))
+            .collect();
+
+        assert_eq!(
+            matches,
+            [
+                (0, 1, 11, "let two".to_string()),
+                (second_id, 1, 4, "  two, two".to_string()),
+                (second_id, 1, 9, "  two, two".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn the_editors_own_writes_are_not_file_changes() {
+        let path = std::env::temp_dir().join(format!(
+            "bad_red_own_write_{}.txt",
+            std::process::id()
+        ));
+        std::fs::write(&path, "first\n").unwrap();
+        let path_string = path.to_string_lossy().into_owned();
+        let mut editor_state = EditorState::new(Duration::from_millis(10));
+        let file_id = editor_state.open_file(path_string.clone()).unwrap();
+        editor_state.link_buffer(0, file_id, true).unwrap();
+
+        editor_state
+            .mut_buffer_by_id(0)
+            .unwrap()
+            .insert_at_cursor("saved ");
+        let write_result = editor_state.write_buffer(0, None);
+        let change = editor_state.handle_file_change(&path_string);
+        let written = std::fs::read_to_string(&path);
+        let _ = std::fs::remove_file(&path);
+
+        write_result.unwrap();
+        assert_eq!(written.unwrap(), "saved first\n");
+        assert_eq!(change.unwrap(), None);
+    }
+
+    #[test]
+    fn splitting_into_a_new_buffer_shows_it_in_the_new_pane() {
+        let mut editor_state = editor_state_with("original");
+
+        let vsplit = editor_state.vsplit_new_buffer(0).unwrap();
+        let hsplit = editor_state.hsplit_new_buffer(vsplit.pane_id).unwrap();
+
+        let buffer_shown =
+            |pane_id| editor_state.pane_tree.pane_by_index(pane_id).unwrap().buffer_id;
+        assert_eq!(buffer_shown(0), 0);
+        assert_eq!(buffer_shown(vsplit.pane_id), vsplit.buffer_id);
+        assert_eq!(buffer_shown(hsplit.pane_id), hsplit.buffer_id);
+        assert_ne!(vsplit.buffer_id, 0);
+        assert_ne!(hsplit.buffer_id, vsplit.buffer_id);
+        assert_eq!(editor_state.buffer_by_id(vsplit.buffer_id).unwrap().content_copy(), "");
+    }
+
+    #[test]
+    fn linking_a_file_applies_its_extensions_options() {
+        let mut editor_state = EditorState::new(Duration::from_millis(10));
+        editor_state.file_type_options.insert(
+            "py".to_string(),
+            BufferOptions {
+                tab_width: Some(4),
+                expand_tab: Some(true),
+                ..BufferOptions::default()
+            },
+        );
+        let other_buffer = editor_state.create_buffer();
+
+        let mut paths = vec![];
+        for (buffer_id, extension) in [(0, "py"), (other_buffer, "txt")] {
+            let path = std::env::temp_dir().join(format!(
+                "bad_red_file_type_{}.{}",
+                std::process::id(),
+                extension
+            ));
+            std::fs::write(&path, "").unwrap();
+            let file_id = editor_state
+                .open_file(path.to_string_lossy().into_owned())
+                .unwrap();
+            editor_state.link_buffer(buffer_id, file_id, true).unwrap();
+            paths.push(path);
+        }
+        for path in paths {
+            let _ = std::fs::remove_file(path);
+        }
+
+        let options = &editor_state.buffer_by_id(0).unwrap().options;
+        assert_eq!(options.tab_width, Some(4));
+        assert_eq!(options.expand_tab, Some(true));
+        let options = &editor_state.buffer_by_id(other_buffer).unwrap().options;
+        assert_eq!(options.tab_width, None);
+        assert_eq!(options.expand_tab, None);
+    }
+
+    #[test]
+    fn buffer_tab_widths_only_change_their_buffer() {
+        let mut editor_state = EditorState::new(Duration::from_millis(10));
+        let other_buffer = editor_state.create_buffer();
+        editor_state.options.tab_width = 4;
+        editor_state.mut_buffer_by_id(0).unwrap().options.tab_width = Some(8);
+
+        let options = &editor_state.options;
+        let tab_width = options.tab_width_for(editor_state.buffer_by_id(0).unwrap());
+        assert_eq!(tab_width, 8);
+        assert_eq!(crate::display::width_for('\t', 2, tab_width), 6);
+
+        let tab_width = options.tab_width_for(editor_state.buffer_by_id(other_buffer).unwrap());
+        assert_eq!(tab_width, 4);
+        assert_eq!(crate::display::width_for('\t', 2, tab_width), 2);
+    }
+
+    #[test]
+    fn linking_a_file_applies_its_editorconfig() {
+        let mut editor_state = EditorState::new(Duration::from_millis(10));
+        let dir = std::env::temp_dir().join(format!(
+            "bad_red_linked_editorconfig_{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join(".editorconfig"),
+            "root = true\n[*]\ntab_width = 3\n",
+        )
+        .unwrap();
+        let path = dir.join("file.txt");
+        std::fs::write(&path, "").unwrap();
+
+        let file_id = editor_state
+            .open_file(path.to_string_lossy().into_owned())
+            .unwrap();
+        let link_result = editor_state.link_buffer(0, file_id, true);
+        let _ = std::fs::remove_dir_all(&dir);
+        link_result.unwrap();
+
+        assert_eq!(editor_state.buffer_by_id(0).unwrap().options.tab_width, Some(3));
+    }
+
+    #[test]
+    fn bells_are_visual_when_asked_for_or_set_by_the_option() {
+        let mut editor_state = EditorState::new(Duration::from_millis(10));
+
+        assert_eq!(editor_state.ring_bell(false), Bell::Audible);
+        assert_eq!(editor_state.pending_bell, Some(Bell::Audible));
+        assert!(editor_state.visual_bell_until.is_none());
+        editor_state.clear_dirty();
+        assert_eq!(editor_state.pending_bell, None);
+
+        assert_eq!(editor_state.ring_bell(true), Bell::Visual);
+        editor_state.clear_dirty();
+
+        editor_state.options.visual_bell = true;
+        assert_eq!(editor_state.ring_bell(false), Bell::Visual);
+    }
+
+    #[test]
+    fn visual_bells_flash_for_their_duration() {
+        let mut editor_state = EditorState::new(Duration::from_millis(10));
+        let rung_at = Instant::now();
+        editor_state.ring_bell(true);
+
+        // The flash outlasts the render it was rung before
+        editor_state.clear_dirty();
+        assert!(editor_state.is_visual_bell_shown(rung_at));
+        assert!(editor_state.is_visual_bell_shown(rung_at + Duration::from_millis(50)));
+        assert!(!editor_state.is_visual_bell_shown(
+            rung_at + EditorState::VISUAL_BELL_DURATION + Duration::from_millis(50)
+        ));
+
+        editor_state.visual_bell_until = Some(Instant::now());
+        editor_state.clear_dirty();
+        assert!(editor_state.visual_bell_until.is_none());
+    }
+
+    #[test]
+    fn modified_delay_is_at_least_one_millisecond() {
+        let mut options = EditorState::new(Duration::from_millis(10)).options;
+        options.update(EditorOptionList(vec![EditorOptionType::ModifiedDelay(0)]));
+        assert_eq!(options.modified_delay_ms, 1);
+
+        options.update(EditorOptionList(vec![EditorOptionType::ModifiedDelay(50)]));
+        assert_eq!(options.modified_delay_ms, 50);
+    }
+
+    #[test]
+    fn poll_rate_is_at_least_one_millisecond() {
+        let mut options = EditorState::new(Duration::from_millis(10)).options;
+        options.update(EditorOptionList(vec![EditorOptionType::PollRate(0)]));
+        assert_eq!(options.poll_rate_ms, 1);
+
+        options.update(EditorOptionList(vec![EditorOptionType::PollRate(25)]));
+        assert_eq!(options.poll_rate_ms, 25);
+    }
+
+    #[test]
+    fn forcing_a_redraw_dirties_every_pane_and_buffer() {
+        let mut editor_state = editor_state_with("original");
+        editor_state.vsplit_new_buffer(0).unwrap();
+        editor_state.create_buffer();
+        for pane_node in editor_state.pane_tree.tree.iter_mut().flatten() {
+            pane_node.is_dirty = false;
+        }
+        for buffer in editor_state.buffers.iter_mut().flatten() {
+            buffer.is_render_dirty = false;
+        }
+
+        editor_state.force_redraw();
+
+        assert!(editor_state.pane_tree.tree.iter().flatten().all(|node| node.is_dirty));
+        assert_eq!(editor_state.buffers.iter().flatten().count(), 3);
+        assert!(editor_state.buffers.iter().flatten().all(|buffer| buffer.is_render_dirty));
+    }
+}
@@ -0,0 +1,294 @@
+// This file is part of BadRed.
+
+// BadRed is free software: you can redistribute it and/or modify it under the terms of the GNU General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// BadRed is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+//! Reads the `.editorconfig` files which apply to a file, found in the file's
+//! directory and each directory above it until one is marked `root = true`.
+//! Closer files and later sections take precedence over earlier ones.
+//!
+//! Section globs support `*`, `**`, `?`, `[...]` and `{a,b}`. Numeric ranges
+//! such as `{1..3}` are not supported and match nothing.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::Path,
+};
+
+use regex::Regex;
+
+use crate::buffer::{BufferOptions, LineEnding};
+
+pub const FILE_NAME: &str = ".editorconfig";
+
+/// Buffer options set by the `.editorconfig` files applying to `path`. Options
+/// not set by any file are left `None`.
+pub fn buffer_options_for(path: &Path) -> BufferOptions {
+    let properties = properties_for(path);
+    let property = |name: &str| properties.get(name).map(String::as_str);
+    let boolean = |name: &str| match property(name) {
+        Some("true") => Some(true),
+        Some("false") => Some(false),
+        _ => None,
+    };
+
+    BufferOptions {
+        tab_width: property("tab_width")
+            .or_else(|| property("indent_size"))
+            .and_then(|width| width.parse().ok())
+            .filter(|&width| width > 0),
+        expand_tab: match property("indent_style") {
+            Some("space") => Some(true),
+            Some("tab") => Some(false),
+            _ => None,
+        },
+        line_ending: match property("end_of_line") {
+            Some("lf") => Some(LineEnding::Lf),
+            Some("crlf") => Some(LineEnding::CrLf),
+            _ => None,
+        },
+        final_newline: boolean("insert_final_newline"),
+        trim_trailing_whitespace: boolean("trim_trailing_whitespace"),
+    }
+}
+
+/// Properties from every section matching `path`, with names and values
+/// lowercased. Properties set to `unset` are removed.
+fn properties_for(path: &Path) -> HashMap<String, String> {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+
+    let mut config_files = vec![];
+    for dir in path.ancestors().skip(1) {
+        let Ok(content) = fs::read_to_string(dir.join(FILE_NAME)) else {
+            continue;
+        };
+
+        let config_file = ConfigFile::parse(&content);
+        let is_root = config_file.is_root;
+        config_files.push((dir.to_path_buf(), config_file));
+        if is_root {
+            break;
+        }
+    }
+
+    let mut properties = HashMap::new();
+    for (dir, config_file) in config_files.iter().rev() {
+        let Some(relative_path) = relative_path(dir, &path) else {
+            continue;
+        };
+
+        for section in &config_file.sections {
+            if !section.matches(&relative_path) {
+                continue;
+            }
+
+            for (name, value) in &section.properties {
+                if value == "unset" {
+                    properties.remove(name);
+                } else {
+                    properties.insert(name.clone(), value.clone());
+                }
+            }
+        }
+    }
+
+    properties
+}
+
+/// `path` relative to `dir`, separated by `/` as section globs expect
+fn relative_path(dir: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(dir).ok()?;
+    let components: Vec<String> = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+        .collect();
+
+    Some(components.join("/"))
+}
+
+struct ConfigFile {
+    is_root: bool,
+    sections: Vec<Section>,
+}
+
+struct Section {
+    glob: Option<Regex>,
+    properties: Vec<(String, String)>,
+}
+
+impl ConfigFile {
+    fn parse(content: &str) -> Self {
+        let mut is_root = false;
+        let mut sections: Vec<Section> = vec![];
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+
+            if let Some(glob) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+                sections.push(Section {
+                    glob: glob_regex(glob),
+                    properties: vec![],
+                });
+                continue;
+            }
+
+            let Some((name, value)) = line.split_once('=') else {
+                continue;
+            };
+            let name = name.trim().to_lowercase();
+            let value = value.trim().to_lowercase();
+
+            match sections.last_mut() {
+                Some(section) => section.properties.push((name, value)),
+                // Properties before the first section apply to the file itself
+                None if name == "root" => is_root = value == "true",
+                None => (),
+            }
+        }
+
+        Self { is_root, sections }
+    }
+}
+
+impl Section {
+    fn matches(&self, relative_path: &str) -> bool {
+        self.glob
+            .as_ref()
+            .is_some_and(|glob| glob.is_match(relative_path))
+    }
+}
+
+/// Regex matching the paths relative to the config file's directory which
+/// `glob` selects. Globs without a `/` match files of any directory.
+fn glob_regex(glob: &str) -> Option<Regex> {
+    let (glob, prefix) = match glob.strip_prefix('/') {
+        Some(glob) => (glob, ""),
+        None if glob.contains('/') => (glob, ""),
+        None => (glob, "(?:.*/)?"),
+    };
+
+    Regex::new(&format!("^{}{}$", prefix, glob_pattern(glob)?)).ok()
+}
+
+fn glob_pattern(glob: &str) -> Option<String> {
+    let mut pattern = String::new();
+    let mut chars = glob.chars().peekable();
+
+    while let Some(char) = chars.next() {
+        match char {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                pattern.push_str(".*");
+            }
+            '*' => pattern.push_str("[^/]*"),
+            '?' => pattern.push_str("[^/]"),
+            '[' => {
+                let mut class = String::new();
+                for char in chars.by_ref() {
+                    if char == ']' {
+                        break;
+                    }
+                    class.push(char);
+                }
+
+                let class = match class.strip_prefix('!') {
+                    Some(class) => format!("^{}", class),
+                    None => class,
+                };
+                pattern.push_str(&format!("[{}]", class.replace('\\', "\\\\")));
+            }
+            '{' => {
+                let mut depth = 1;
+                let mut alternatives = vec![String::new()];
+                for char in chars.by_ref() {
+                    match char {
+                        '{' => depth += 1,
+                        '}' => depth -= 1,
+                        ',' if depth == 1 => {
+                            alternatives.push(String::new());
+                            continue;
+                        }
+                        _ => (),
+                    }
+                    if depth == 0 {
+                        break;
+                    }
+                    alternatives.last_mut()?.push(char);
+                }
+
+                if alternatives.len() == 1 {
+                    // Numeric ranges are unsupported, so match nothing for them
+                    if alternatives[0].contains("..") {
+                        return None;
+                    }
+                    pattern.push_str(&regex::escape(&format!("{{{}}}", alternatives[0])));
+                    continue;
+                }
+
+                let alternatives = alternatives
+                    .iter()
+                    .map(|alternative| glob_pattern(alternative))
+                    .collect::<Option<Vec<_>>>()?;
+                pattern.push_str(&format!("(?:{})", alternatives.join("|")));
+            }
+            '\\' => {
+                if let Some(escaped) = chars.next() {
+                    pattern.push_str(&regex::escape(&escaped.to_string()));
+                }
+            }
+            char => pattern.push_str(&regex::escape(&char.to_string())),
+        }
+    }
+
+    Some(pattern)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn config_dir(name: &str, config: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "bad_red_editorconfig_{}_{}",
+            name,
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(FILE_NAME), config).unwrap();
+        dir
+    }
+
+    #[test]
+    fn matching_sections_set_buffer_options() {
+        let dir = config_dir(
+            "matching",
+            "root = true\n[*.rs]\nindent_style = space\ntab_width = 4\n[*.txt]\ntab_width = 8\n",
+        );
+
+        let options = buffer_options_for(&dir.join("main.rs"));
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(options.tab_width, Some(4));
+        assert_eq!(options.expand_tab, Some(true));
+    }
+
+    #[test]
+    fn zero_widths_are_ignored() {
+        let dir = config_dir(
+            "zero",
+            "root = true\n[*]\ntab_width = 0\n[*.rs]\nindent_size = 0\n",
+        );
+
+        let options = buffer_options_for(&dir.join("main.rs"));
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(options.tab_width, None);
+    }
+}
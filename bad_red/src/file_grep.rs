@@ -0,0 +1,279 @@
+// This file is part of BadRed.
+
+// BadRed is free software: you can redistribute it and/or modify it under the terms of the GNU General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// BadRed is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use bad_red_proc_macros::auto_lua;
+use regex::Regex;
+
+#[auto_lua]
+#[derive(Clone, Hash, PartialEq, Eq, Debug)]
+pub struct FileMatch {
+    pub path: String,
+    pub line: usize,
+    pub col: usize,
+    pub text: String,
+}
+
+/// Matches found by a search, and whether it was cancelled before searching
+/// every file
+#[auto_lua]
+#[derive(Clone, Hash, PartialEq, Eq, Debug)]
+pub struct GrepResult {
+    pub matches: Vec<FileMatch>,
+    pub is_cancelled: bool,
+}
+
+/// Incremental regex search through the files under a directory.
+///
+/// Files are searched a few at a time by `step` so that a large directory tree
+/// does not block the editor. The search ends early once `MAX_MATCHES` matches
+/// are found or it is cancelled through a `GrepCanceller`.
+pub struct FileGrep {
+    regex: Regex,
+    glob: Option<String>,
+    pending_dirs: Vec<PathBuf>,
+    pending_files: Vec<PathBuf>,
+    matches: Vec<FileMatch>,
+    is_cancelled: Arc<AtomicBool>,
+}
+
+/// Cancels the `FileGrep` it was taken from, which may be stepping on another
+/// thread
+#[derive(Clone)]
+pub struct GrepCanceller(Arc<AtomicBool>);
+
+impl GrepCanceller {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+impl FileGrep {
+    pub const MAX_MATCHES: usize = 10_000;
+    pub const FILES_PER_STEP: usize = 32;
+
+    pub fn new(root_path: &str, pattern: &str, glob: Option<String>) -> Result<Self, String> {
+        let root_path = shellexpand::full(root_path)
+            .map_err(|e| format!("Failed to expand grep root path due to: {}", e.var_name))?
+            .into_owned();
+        let regex =
+            Regex::new(pattern).map_err(|e| format!("Failed to create Regex for grep: {}", e))?;
+
+        let root_path = PathBuf::from(root_path);
+        let (pending_dirs, pending_files) = if root_path.is_dir() {
+            (vec![root_path], vec![])
+        } else {
+            (vec![], vec![root_path])
+        };
+
+        Ok(Self {
+            regex,
+            glob,
+            pending_dirs,
+            pending_files,
+            matches: vec![],
+            is_cancelled: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    pub fn canceller(&self) -> GrepCanceller {
+        GrepCanceller(self.is_cancelled.clone())
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.is_cancelled.load(Ordering::Relaxed)
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.is_cancelled()
+            || self.matches.len() >= Self::MAX_MATCHES
+            || (self.pending_dirs.is_empty() && self.pending_files.is_empty())
+    }
+
+    pub fn into_matches(self) -> Vec<FileMatch> {
+        self.matches
+    }
+
+    pub fn into_result(self) -> GrepResult {
+        GrepResult {
+            is_cancelled: self.is_cancelled(),
+            matches: self.matches,
+        }
+    }
+
+    /// Searches up to `FILES_PER_STEP` files, reading directories as needed to
+    /// find them. Returns whether the search is done.
+    pub fn step(&mut self) -> bool {
+        let mut files_searched = 0;
+        while files_searched < Self::FILES_PER_STEP && !self.is_done() {
+            if let Some(file) = self.pending_files.pop() {
+                self.search_file(file);
+                files_searched += 1;
+            } else if let Some(dir) = self.pending_dirs.pop() {
+                self.read_dir(dir);
+            }
+        }
+
+        self.is_done()
+    }
+
+    fn read_dir(&mut self, dir: PathBuf) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+
+        for entry in entries.flatten() {
+            // Symlinks are skipped so that linked directories cannot loop the search
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+
+            if file_type.is_dir() {
+                self.pending_dirs.push(entry.path());
+            } else if file_type.is_file() && self.matches_glob(&entry.file_name().to_string_lossy())
+            {
+                self.pending_files.push(entry.path());
+            }
+        }
+    }
+
+    fn search_file(&mut self, file: PathBuf) {
+        // Files which are not valid UTF-8 are treated as binary and skipped
+        let Ok(content) = fs::read_to_string(&file) else {
+            return;
+        };
+        let path = file.to_string_lossy().into_owned();
+
+        for (line, text) in content.split('\n').enumerate() {
+            for found in self.regex.find_iter(text) {
+                if self.matches.len() >= Self::MAX_MATCHES {
+                    return;
+                }
+
+                self.matches.push(FileMatch {
+                    path: path.clone(),
+                    line,
+                    col: found.start(),
+                    text: text.to_string(),
+                });
+            }
+        }
+    }
+
+    fn matches_glob(&self, file_name: &str) -> bool {
+        self.glob
+            .as_ref()
+            .map(|glob| glob_match(glob.as_bytes(), file_name.as_bytes()))
+            .unwrap_or(true)
+    }
+}
+
+/// Matches `name` against a glob where `*` matches any run of bytes and `?`
+/// matches any single byte.
+fn glob_match(glob: &[u8], name: &[u8]) -> bool {
+    let (mut glob_index, mut name_index) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while name_index < name.len() {
+        match glob.get(glob_index) {
+            Some(b'*') => {
+                star = Some((glob_index, name_index));
+                glob_index += 1;
+            }
+            Some(&glob_byte) if glob_byte == b'?' || glob_byte == name[name_index] => {
+                glob_index += 1;
+                name_index += 1;
+            }
+            _ => {
+                let Some((star_glob_index, star_name_index)) = star else {
+                    return false;
+                };
+                // Let the last star consume one more byte and retry
+                glob_index = star_glob_index + 1;
+                name_index = star_name_index + 1;
+                star = Some((star_glob_index, name_index));
+            }
+        }
+    }
+
+    glob[glob_index..].iter().all(|glob_byte| *glob_byte == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grep_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "bad_red_grep_{}_{}",
+            name,
+            std::process::id()
+        ));
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::write(dir.join("top.txt"), "nothing here\n").unwrap();
+        fs::write(
+            dir.join("nested").join("found.txt"),
+            "first\nthe needle line\n",
+        )
+        .unwrap();
+        fs::write(dir.join("nested").join("skipped.md"), "needle\n").unwrap();
+        dir
+    }
+
+    #[test]
+    fn searches_nested_files_matching_the_glob() {
+        let dir = grep_dir("nested");
+        let mut file_grep =
+            FileGrep::new(&dir.to_string_lossy(), "needle", Some("*.txt".to_string())).unwrap();
+        while !file_grep.step() {}
+        let result = file_grep.into_result();
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(
+            result,
+            GrepResult {
+                matches: vec![FileMatch {
+                    path: dir
+                        .join("nested")
+                        .join("found.txt")
+                        .to_string_lossy()
+                        .into_owned(),
+                    line: 1,
+                    col: 4,
+                    text: "the needle line".to_string(),
+                }],
+                is_cancelled: false,
+            }
+        );
+    }
+
+    #[test]
+    fn cancelled_searches_stop_and_report_it() {
+        let dir = grep_dir("cancelled");
+        let mut file_grep = FileGrep::new(&dir.to_string_lossy(), "needle", None).unwrap();
+        let canceller = file_grep.canceller();
+
+        let worker = std::thread::spawn(move || {
+            canceller.cancel();
+        });
+        worker.join().unwrap();
+
+        assert!(file_grep.step());
+        let result = file_grep.into_result();
+        let _ = fs::remove_dir_all(&dir);
+
+        assert!(result.is_cancelled);
+        assert!(result.matches.is_empty());
+    }
+}
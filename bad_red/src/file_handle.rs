@@ -1,6 +1,6 @@
 use std::{
     fs::{File, OpenOptions},
-    io::{Read, Write},
+    io::{Read, Seek, SeekFrom, Write},
     path::Path,
 };
 
@@ -15,6 +15,12 @@ impl Read for FileHandle {
     }
 }
 
+impl Seek for FileHandle {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.file.seek(pos)
+    }
+}
+
 pub trait FileWrite {
     fn write_file(&mut self, buf: &[u8]) -> std::io::Result<()>;
 }
@@ -33,6 +39,23 @@ impl FileWrite for FileHandle {
     }
 }
 
+/// Writer which terminates non-empty content with a newline if it is missing
+pub struct FinalNewlineWrite<'a>(pub &'a mut dyn FileWrite);
+
+impl FileWrite for FinalNewlineWrite<'_> {
+    fn write_file(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        if buf.is_empty() || buf.ends_with(b"\n") {
+            self.0.write_file(buf)
+        } else {
+            let mut terminated = Vec::with_capacity(buf.len() + 1);
+            terminated.extend_from_slice(buf);
+            terminated.push(b'\n');
+
+            self.0.write_file(&terminated)
+        }
+    }
+}
+
 impl FileHandle {
     pub fn new(path_str: String) -> std::io::Result<Self> {
         let expanded_path = shellexpand::full(&path_str).map_err(|e| {
@@ -55,6 +78,24 @@ impl FileHandle {
         })
     }
 
+    /// Opens the file at this handle's path again, so content written by
+    /// replacing the file is read rather than that of the replaced file.
+    /// Unlike `new`, fails if the file no longer exists.
+    pub fn reopen(&mut self) -> std::io::Result<()> {
+        self.file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(Path::new(self.path.as_ref()))?;
+
+        Ok(())
+    }
+
+    /// Opens the file at this handle's path separately for reading, such as
+    /// for a paged buffer to keep reading pages from
+    pub fn open_read_only(&self) -> std::io::Result<File> {
+        File::open(Path::new(self.path.as_ref()))
+    }
+
     pub fn extension(&self) -> Option<String> {
         let path: &str = &self.path;
         Path::new(path)
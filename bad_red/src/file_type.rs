@@ -0,0 +1,123 @@
+// This file is part of BadRed.
+
+// BadRed is free software: you can redistribute it and/or modify it under the terms of the GNU General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// BadRed is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+//! Names the kind of content in a buffer, such as "rust" or "sh", so scripts
+//! can pick syntax highlighting and options to match.
+
+/// File type for a file with `extension` whose content starts with
+/// `first_line`. The extension is checked first, falling back to the
+/// interpreter named by a shebang and other first line markers. `None` if
+/// neither identifies a known file type.
+pub fn detect(extension: Option<&str>, first_line: &str) -> Option<&'static str> {
+    extension
+        .and_then(for_extension)
+        .or_else(|| for_first_line(first_line))
+}
+
+fn for_extension(extension: &str) -> Option<&'static str> {
+    let file_type = match extension.to_lowercase().as_str() {
+        "rs" => "rust",
+        "lua" => "lua",
+        "py" | "pyw" => "python",
+        "sh" | "bash" | "zsh" | "ksh" => "sh",
+        "c" | "h" => "c",
+        "cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx" => "cpp",
+        "go" => "go",
+        "java" => "java",
+        "js" | "mjs" | "cjs" => "javascript",
+        "ts" => "typescript",
+        "rb" => "ruby",
+        "pl" | "pm" => "perl",
+        "md" | "markdown" => "markdown",
+        "toml" => "toml",
+        "json" => "json",
+        "yml" | "yaml" => "yaml",
+        "html" | "htm" => "html",
+        "xml" => "xml",
+        "css" => "css",
+        _ => return None,
+    };
+
+    Some(file_type)
+}
+
+fn for_first_line(first_line: &str) -> Option<&'static str> {
+    let first_line = first_line.trim();
+
+    if let Some(command) = first_line.strip_prefix("#!") {
+        return for_interpreter(interpreter_name(command)?);
+    }
+
+    let lowercase = first_line.to_lowercase();
+    if lowercase.starts_with("<!doctype html") || lowercase.starts_with("<html") {
+        Some("html")
+    } else if lowercase.starts_with("<?xml") {
+        Some("xml")
+    } else {
+        None
+    }
+}
+
+/// Name of the program a shebang runs, looking past `env` and its options to
+/// the program `env` runs.
+fn interpreter_name(command: &str) -> Option<&str> {
+    let mut words = command
+        .split_whitespace()
+        .map(|word| word.rsplit('/').next().unwrap_or(word));
+
+    let program = words.next()?;
+    if program != "env" {
+        return Some(program);
+    }
+
+    words.find(|word| !word.starts_with('-') && !word.contains('='))
+}
+
+fn for_interpreter(interpreter: &str) -> Option<&'static str> {
+    // Interpreters are often named with their version, such as "python3"
+    let interpreter =
+        interpreter.trim_end_matches(|char: char| char.is_ascii_digit() || char == '.');
+
+    let file_type = match interpreter {
+        "sh" | "bash" | "zsh" | "ksh" | "dash" => "sh",
+        "python" => "python",
+        "lua" | "luajit" => "lua",
+        "node" => "javascript",
+        "ruby" => "ruby",
+        "perl" => "perl",
+        _ => return None,
+    };
+
+    Some(file_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extensions_name_their_file_type() {
+        assert_eq!(detect(Some("rs"), "#!/bin/sh"), Some("rust"));
+        assert_eq!(detect(Some("PY"), ""), Some("python"));
+        assert_eq!(detect(Some("unknown"), "#!/bin/sh"), Some("sh"));
+    }
+
+    #[test]
+    fn shebangs_name_their_interpreters_file_type() {
+        assert_eq!(detect(None, "#!/bin/sh"), Some("sh"));
+        assert_eq!(detect(None, "#!/usr/bin/env -S python3.12 -u"), Some("python"));
+        assert_eq!(detect(None, "#!/usr/bin/env LUA_PATH=x lua"), Some("lua"));
+        assert_eq!(detect(None, "  <!DOCTYPE html>"), Some("html"));
+    }
+
+    #[test]
+    fn unknown_content_has_no_file_type() {
+        assert_eq!(detect(None, ""), None);
+        assert_eq!(detect(None, "fn main() {}"), None);
+        assert_eq!(detect(None, "#!/usr/bin/env"), None);
+        assert_eq!(detect(None, "#!/opt/custom-tool"), None);
+    }
+}
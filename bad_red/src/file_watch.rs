@@ -0,0 +1,97 @@
+// This file is part of BadRed.
+
+// BadRed is free software: you can redistribute it and/or modify it under the terms of the GNU General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// BadRed is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    sync::{
+        mpsc::{self, Receiver},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, SystemTime},
+};
+
+/// Watches files for changes made outside the editor by polling their
+/// modification times on a worker thread. Changed paths are collected by
+/// polling `changed` so they can be handled on the scheduler's thread.
+///
+/// Polling is used rather than the `notify` crate's native notifications to
+/// avoid a platform-specific dependency. The scheduler already polls for
+/// input, task results and settled edits on each loop, so a change is
+/// reported within `POLL_INTERVAL` plus one loop. Moving to native
+/// notifications is tracked in notes.md.
+pub struct FileWatcher {
+    paths: Arc<Mutex<HashSet<String>>>,
+    receiver: Receiver<String>,
+}
+
+impl FileWatcher {
+    pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+    pub fn new() -> Self {
+        let paths: Arc<Mutex<HashSet<String>>> = Arc::new(Mutex::new(HashSet::new()));
+        let (sender, receiver) = mpsc::channel();
+
+        // The worker only holds a weak reference to the paths so it stops once
+        // the watcher is dropped
+        let watched_paths = Arc::downgrade(&paths);
+        thread::spawn(move || {
+            let mut modified_times: HashMap<String, SystemTime> = HashMap::new();
+
+            loop {
+                thread::sleep(Self::POLL_INTERVAL);
+                let Some(watched_paths) = watched_paths.upgrade() else {
+                    return;
+                };
+                let paths = watched_paths
+                    .lock()
+                    .map(|paths| paths.clone())
+                    .unwrap_or_default();
+                drop(watched_paths);
+
+                modified_times.retain(|path, _| paths.contains(path));
+                for path in paths {
+                    // Files which cannot be read are skipped until they can be
+                    let Ok(modified) = fs::metadata(&path).and_then(|m| m.modified()) else {
+                        continue;
+                    };
+
+                    let previous = modified_times.insert(path.clone(), modified);
+                    let is_changed = previous.is_some_and(|previous| previous != modified);
+                    if is_changed && sender.send(path).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+
+        Self { paths, receiver }
+    }
+
+    /// Replaces the paths being watched. Newly watched paths only report
+    /// changes made after they are first polled.
+    pub fn set_paths(&self, paths: HashSet<String>) {
+        if let Ok(mut watched_paths) = self.paths.lock() {
+            *watched_paths = paths;
+        }
+    }
+
+    /// Returns the paths which have changed since the last call without
+    /// waiting on the worker.
+    pub fn changed(&self) -> Vec<String> {
+        let mut changed: Vec<String> = self.receiver.try_iter().collect();
+        changed.dedup();
+
+        changed
+    }
+}
+
+impl Default for FileWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -9,7 +9,7 @@ use std::collections::HashMap;
 use bad_red_proc_macros::auto_lua;
 use mlua::{Function, Value};
 
-use crate::keymap::RedKeyEvent;
+use crate::{keymap::RedKeyEvent, task::TaskResult};
 
 #[auto_lua]
 #[derive(Clone, Hash, PartialEq, Eq, Debug)]
@@ -20,6 +20,11 @@ pub enum HookType {
     Error(String),
     SecondaryError(String),
     PaneClosed { pane_id: usize },
+    TaskComplete { id: usize, result: TaskResult },
+    BufferModified { buffer_id: usize },
+    BufferReloaded { buffer_id: usize },
+    FileConflict { buffer_id: usize },
+    ModeChanged { from: String, to: String },
 }
 
 #[auto_lua]
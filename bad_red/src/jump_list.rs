@@ -0,0 +1,128 @@
+// This file is part of BadRed.
+
+// BadRed is free software: you can redistribute it and/or modify it under the terms of the GNU General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// BadRed is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+use std::collections::VecDeque;
+
+use bad_red_proc_macros::auto_lua;
+
+#[auto_lua]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct JumpPosition {
+    pub buffer_id: usize,
+    pub byte_index: usize,
+}
+
+#[derive(Default)]
+pub struct JumpList {
+    positions: VecDeque<JumpPosition>,
+    current_index: usize,
+}
+
+impl JumpList {
+    pub const MAX_POSITIONS: usize = 100;
+
+    pub fn new() -> Self {
+        Self {
+            positions: VecDeque::new(),
+            current_index: 0,
+        }
+    }
+
+    /// Records a position to jump back to. Any positions ahead of the current
+    /// one from previous jumps back are discarded.
+    pub fn push(&mut self, position: JumpPosition) {
+        self.positions.truncate(self.current_index + 1);
+        if self.positions.back() != Some(&position) {
+            self.positions.push_back(position);
+        }
+
+        while self.positions.len() > Self::MAX_POSITIONS {
+            self.positions.pop_front();
+        }
+
+        self.current_index = self.positions.len();
+    }
+
+    /// Steps back to the previous recorded position. `current` is recorded
+    /// when leaving the newest position so a jump forward can return to it.
+    pub fn back(&mut self, current: JumpPosition) -> Option<JumpPosition> {
+        if self.current_index == self.positions.len() {
+            self.push(current);
+            self.current_index = self.positions.len() - 1;
+        }
+
+        self.current_index = self.current_index.checked_sub(1)?;
+        self.positions.get(self.current_index).copied()
+    }
+
+    pub fn forward(&mut self) -> Option<JumpPosition> {
+        if self.current_index + 1 >= self.positions.len() {
+            return None;
+        }
+
+        self.current_index += 1;
+        self.positions.get(self.current_index).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(byte_index: usize) -> JumpPosition {
+        JumpPosition {
+            buffer_id: 0,
+            byte_index,
+        }
+    }
+
+    #[test]
+    fn jumping_back_returns_to_the_position_before_the_jump() {
+        let mut jump_list = JumpList::new();
+
+        // Jump from the first line to the start of line 100
+        jump_list.push(position(0));
+        let line_100 = position(99 * 10);
+
+        assert_eq!(jump_list.back(line_100), Some(position(0)));
+        assert_eq!(jump_list.back(position(0)), None);
+        assert_eq!(jump_list.forward(), Some(line_100));
+        assert_eq!(jump_list.forward(), None);
+    }
+
+    #[test]
+    fn pushing_after_jumping_back_discards_later_positions() {
+        let mut jump_list = JumpList::new();
+        jump_list.push(position(0));
+        jump_list.push(position(10));
+
+        assert_eq!(jump_list.back(position(20)), Some(position(10)));
+        jump_list.push(position(5));
+
+        assert_eq!(jump_list.forward(), None);
+        assert_eq!(jump_list.back(position(30)), Some(position(5)));
+        assert_eq!(jump_list.back(position(5)), Some(position(10)));
+    }
+
+    #[test]
+    fn only_the_newest_positions_are_kept() {
+        let mut jump_list = JumpList::new();
+        for byte_index in 0..JumpList::MAX_POSITIONS + 50 {
+            jump_list.push(position(byte_index));
+        }
+
+        let mut oldest = None;
+        let mut current = position(1000);
+        while let Some(previous) = jump_list.back(current) {
+            oldest = Some(previous);
+            current = previous;
+        }
+
+        assert_eq!(jump_list.positions.len(), JumpList::MAX_POSITIONS);
+        // Recording the current position on the first jump back drops one more
+        assert_eq!(oldest, Some(position(51)));
+    }
+}
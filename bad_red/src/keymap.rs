@@ -4,10 +4,11 @@
 // 
 // BadRed is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
+use bad_red_proc_macros::auto_lua;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
-use mlua::{FromLua, IntoLua, Value};
+use mlua::{FromLua, IntoLua, Table, Value};
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub struct RedKeyEvent {
@@ -292,3 +293,114 @@ impl KeyMap<'_> {
         self.map.get(&event).or(self.fallback.as_ref())
     }
 }
+
+/// Binding in a Lua keymap table, reached by pressing `keys` in order
+#[auto_lua]
+#[derive(Clone, Debug)]
+pub struct KeyBinding {
+    pub keys: Vec<String>,
+    pub has_submap: bool,
+}
+
+/// Lists the bindings of the Lua keymap table `keymap` after the keys in
+/// `prefix` are pressed, including those inherited from the keymap's parents.
+/// Keymaps bind key event strings to either functions or tables of further
+/// bindings. Bindings handled only by a keymap's `__index` fallback are not
+/// listed.
+pub fn list_bindings<'lua>(
+    keymap: Table<'lua>,
+    prefix: &[String],
+) -> mlua::Result<Vec<KeyBinding>> {
+    let mut map = keymap;
+    for key in prefix {
+        map = match map.get::<_, Value>(key.as_str())? {
+            Value::Table(submap) => submap,
+            _ => return Ok(vec![]),
+        };
+    }
+
+    let mut bindings = BTreeMap::new();
+    let mut level = Some(map);
+    while let Some(current) = level {
+        for pair in current.clone().pairs::<Value, Value>() {
+            let (key, value) = pair?;
+            let Value::String(key) = key else {
+                continue;
+            };
+            let Ok(key) = key.to_str() else {
+                continue;
+            };
+            // Keymaps also hold fields such as `parent` which are not bindings
+            if RedKeyEvent::try_from(key).is_err() {
+                continue;
+            }
+
+            let has_submap = match value {
+                Value::Table(_) => true,
+                Value::Function(_) => false,
+                _ => continue,
+            };
+            bindings.entry(key.to_string()).or_insert(has_submap);
+        }
+
+        level = current.raw_get::<_, Option<Table>>("parent")?;
+    }
+
+    Ok(bindings
+        .into_iter()
+        .map(|(key, has_submap)| KeyBinding {
+            keys: prefix.iter().cloned().chain([key]).collect(),
+            has_submap,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use mlua::Lua;
+
+    use super::*;
+
+    fn listed(bindings: Vec<KeyBinding>) -> Vec<(String, bool)> {
+        bindings
+            .into_iter()
+            .map(|binding| (binding.keys.join(" "), binding.has_submap))
+            .collect()
+    }
+
+    #[test]
+    fn lua_listing_bindings_from_the_root_and_a_prefix() {
+        let lua = Lua::new();
+        let keymap: Table = lua
+            .load(
+                r#"
+                    local noop = function() end
+                    return {
+                        parent = { q = noop, x = noop },
+                        x = { s = noop, ["C+x"] = { f = noop, g = { h = noop } } },
+                        a = noop,
+                        name = "not a binding",
+                    }
+                "#,
+            )
+            .eval()
+            .unwrap();
+
+        assert_eq!(
+            listed(list_bindings(keymap.clone(), &[]).unwrap()),
+            vec![
+                ("a".to_string(), false),
+                ("q".to_string(), false),
+                ("x".to_string(), true),
+            ]
+        );
+        assert_eq!(
+            listed(list_bindings(keymap.clone(), &["x".to_string(), "C+x".to_string()]).unwrap()),
+            vec![
+                ("x C+x f".to_string(), false),
+                ("x C+x g".to_string(), true),
+            ]
+        );
+        assert!(list_bindings(keymap, &["a".to_string()]).unwrap().is_empty());
+    }
+}
@@ -16,5 +16,19 @@ pub mod script_runtime;
 pub mod keymap;
 pub mod hook_map;
 pub mod file_handle;
+pub mod file_grep;
+pub mod file_watch;
+pub mod file_type;
+pub mod editorconfig;
 pub mod styling;
+pub mod jump_list;
+pub mod edit_repeat;
+pub mod popup;
+pub mod prompt;
+pub mod task;
+pub mod shell;
+pub mod session;
+pub mod terminal;
+#[cfg(feature = "test-support")]
+pub mod test_support;
 
@@ -9,7 +9,7 @@ use std::{fs, io, panic, path::PathBuf, time::Duration};
 use bad_red_lib::{
     display::Display,
     editor_state::{self, Editor},
-    script_handler::ScriptHandler,
+    script_handler::{self, ScriptHandler},
     script_runtime::SchedulerYield,
 };
 use crossterm::event::{self, Event, KeyCode, KeyModifiers};
@@ -26,6 +26,9 @@ struct Args {
     init_path: Option<String>,
     #[arg(long)]
     init_name: Option<String>,
+    /// Writes LuaLS annotations for the `red.call` API to the given path and exits
+    #[arg(long)]
+    lua_annotations: Option<String>,
     #[arg()]
     files: Vec<String>,
 }
@@ -34,6 +37,10 @@ const DEFAULT_INIT_SCRIPT: &'static str = "init.lua";
 
 fn main() -> io::Result<()> {
     let args = Args::parse();
+    if let Some(annotations_path) = args.lua_annotations {
+        return fs::write(annotations_path, script_handler::lua_annotations());
+    }
+
     let stdout = io::stdout();
     let mut display = Display::new(stdout)?;
 
@@ -135,7 +142,7 @@ fn run(
             }
         };
 
-        if did_input || did_run_script {
+        if did_input || did_run_script || editor.state.visual_bell_until.is_some() {
             display.render(&editor)?;
         }
 
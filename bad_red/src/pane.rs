@@ -6,13 +6,24 @@
 
 use bad_red_proc_macros::auto_lua;
 
-use crate::editor_frame::EditorFrame;
+use crate::{
+    buffer::{ContentBuffer, EditorBuffer},
+    display,
+    editor_frame::EditorFrame,
+};
 
 pub type Result<T> = std::result::Result<T, String>;
 
 pub struct PaneTree {
     root_index: usize,
     pub tree: Vec<Option<PaneNode>>,
+    maximized: Option<MaximizedPane>,
+}
+
+/// Pane filling the screen, along with the split types it overrode to do so
+struct MaximizedPane {
+    index: usize,
+    saved_split_types: Vec<(usize, SplitType)>,
 }
 
 impl PaneTree {
@@ -24,7 +35,50 @@ impl PaneTree {
                 parent_index: None,
                 is_dirty: true,
             })],
+            maximized: None,
+        }
+    }
+
+    /// Builds a tree from nodes which already refer to each other by index,
+    /// such as those of a restored session. Each split's children must be
+    /// nodes in `tree` whose parent is that split, so the nodes reachable
+    /// from the root form a tree.
+    pub fn from_nodes(root_index: usize, tree: Vec<Option<PaneNode>>) -> Result<Self> {
+        if !matches!(tree.get(root_index), Some(Some(root)) if root.parent_index.is_none()) {
+            return Err(format!(
+                "Attempted to build pane tree without a root pane at index: {}",
+                root_index
+            ));
+        }
+
+        for (index, node) in tree.iter().enumerate() {
+            let Some(PaneNode {
+                node_type: PaneNodeType::VSplit(split) | PaneNodeType::HSplit(split),
+                ..
+            }) = node
+            else {
+                continue;
+            };
+
+            for child_index in [split.first, split.second] {
+                let is_child = matches!(
+                    tree.get(child_index),
+                    Some(Some(child)) if child.parent_index == Some(index)
+                );
+                if !is_child {
+                    return Err(format!(
+                        "Attempted to build pane tree with split at index {} missing its child at index: {}",
+                        index, child_index
+                    ));
+                }
+            }
         }
+
+        Ok(Self {
+            root_index,
+            tree,
+            maximized: None,
+        })
     }
 
     pub fn root_index(&self) -> usize {
@@ -55,6 +109,14 @@ impl PaneTree {
             .flatten()
     }
 
+    pub fn pane_mut_by_index(&mut self, pane_index: usize) -> Option<&mut Pane> {
+        self.pane_node_mut_by_index(pane_index)
+            .and_then(|node| match &mut node.node_type {
+                PaneNodeType::Leaf(pane) => Some(pane),
+                PaneNodeType::VSplit(_) | PaneNodeType::HSplit(_) => None,
+            })
+    }
+
     pub fn vsplit(&mut self, pane_id: usize, new_pane_buffer: usize) -> Result<usize> {
         self.split(pane_id, new_pane_buffer, |left, right, split_percentage| {
             PaneNodeType::VSplit(Split {
@@ -98,11 +160,7 @@ impl PaneTree {
         current.parent_index = Some(split_root_index);
 
         let new_content_pane = PaneNode {
-            node_type: PaneNodeType::Leaf(Pane {
-                top_line: 0,
-                buffer_id: new_pane_buffer,
-                should_wrap: false,
-            }),
+            node_type: PaneNodeType::Leaf(Pane::new(new_pane_buffer)),
             parent_index: Some(split_root_index),
             is_dirty: true,
         };
@@ -180,6 +238,10 @@ impl PaneTree {
             }
         }?;
 
+        // The saved split types refer to the tree as it was when the pane was
+        // maximized, so the other panes are shown again before it changes
+        self.restore_maximized();
+
         self.pane_node_mut_by_index(child_to_keep)
             .ok_or_else(|| format!("Failed to find child at index: {}", child_to_keep))?
             .parent_index = grandparent_index;
@@ -356,6 +418,188 @@ impl PaneTree {
         Ok(frame)
     }
 
+    /// Balances every percent split in the subtree rooted at `index` by the
+    /// number of leaf panes beneath each of its children, so a split with one
+    /// leaf on one side and three on the other is split 25/75. Fixed size
+    /// splits are kept as they are.
+    pub fn equalize(&mut self, index: usize) -> Result<()> {
+        let node = self.pane_node_mut_by_index(index).ok_or_else(|| {
+            format!("Attempted to equalize pane at invalid index: {}", index)
+        })?;
+        node.is_dirty = true;
+
+        let (first, second) = match &node.node_type {
+            PaneNodeType::Leaf(_) => return Ok(()),
+            PaneNodeType::VSplit(split) | PaneNodeType::HSplit(split) => {
+                (split.first, split.second)
+            }
+        };
+
+        let first_leaves = self.subtree_leaf_count(first);
+        let second_leaves = self.subtree_leaf_count(second);
+        if let Some(PaneNodeType::VSplit(split) | PaneNodeType::HSplit(split)) = self
+            .pane_node_mut_by_index(index)
+            .map(|node| &mut node.node_type)
+        {
+            if let SplitType::Percent { .. } = split.split_type {
+                split.split_type = SplitType::Percent {
+                    first_percent: first_leaves as f32 / (first_leaves + second_leaves) as f32,
+                };
+            }
+        }
+
+        self.equalize(first)?;
+        self.equalize(second)
+    }
+
+    /// Makes the pane at `index` fill the screen by fixing the size of its
+    /// siblings along the path to the root to zero, or restores the prior
+    /// split types if it is already maximized. Maximizing another pane first
+    /// restores the currently maximized pane. Returns whether the pane is now
+    /// maximized.
+    pub fn toggle_maximize(&mut self, index: usize) -> Result<bool> {
+        if self.pane_node_by_index(index).is_none() {
+            return Err(format!(
+                "Attempted to maximize pane at invalid index: {}",
+                index
+            ));
+        }
+
+        if let Some(maximized_index) = self.restore_maximized() {
+            if maximized_index == index {
+                return Ok(false);
+            }
+        }
+
+        let mut saved_split_types = vec![];
+        let mut child_index = index;
+        while let Some(parent_index) = self
+            .pane_node_by_index(child_index)
+            .and_then(|node| node.parent_index)
+        {
+            if let Some(split) = self.split_mut_by_index(parent_index) {
+                saved_split_types.push((parent_index, split.split_type.clone()));
+                split.split_type = if split.first == child_index {
+                    SplitType::SecondFixed { size: 0 }
+                } else {
+                    SplitType::FirstFixed { size: 0 }
+                };
+            }
+            child_index = parent_index;
+        }
+
+        self.maximized = Some(MaximizedPane {
+            index,
+            saved_split_types,
+        });
+        self.mark_all_dirty();
+
+        Ok(true)
+    }
+
+    /// Restores the split types overridden by the maximized pane, if any.
+    /// Returns the index of the pane which was maximized.
+    fn restore_maximized(&mut self) -> Option<usize> {
+        let maximized = self.maximized.take()?;
+        for (split_index, split_type) in maximized.saved_split_types {
+            if let Some(split) = self.split_mut_by_index(split_index) {
+                split.split_type = split_type;
+            }
+        }
+        self.mark_all_dirty();
+
+        Some(maximized.index)
+    }
+
+    fn split_mut_by_index(&mut self, index: usize) -> Option<&mut Split> {
+        match &mut self.pane_node_mut_by_index(index)?.node_type {
+            PaneNodeType::Leaf(_) => None,
+            PaneNodeType::VSplit(split) | PaneNodeType::HSplit(split) => Some(split),
+        }
+    }
+
+    fn mark_all_dirty(&mut self) {
+        for node in self.tree.iter_mut().flatten() {
+            node.is_dirty = true;
+        }
+    }
+
+    fn subtree_leaf_count(&self, index: usize) -> usize {
+        match self.pane_node_by_index(index).map(|node| &node.node_type) {
+            Some(PaneNodeType::Leaf(_)) => 1,
+            Some(PaneNodeType::VSplit(split) | PaneNodeType::HSplit(split)) => {
+                self.subtree_leaf_count(split.first) + self.subtree_leaf_count(split.second)
+            }
+            None => 0,
+        }
+    }
+
+    /// Exchanges the buffers shown by the leaf panes at `pane_a` and `pane_b`,
+    /// leaving the split structure as it is. Returns the buffer ids now shown
+    /// by `pane_a` and `pane_b` respectively.
+    pub fn swap_buffers(&mut self, pane_a: usize, pane_b: usize) -> Result<(usize, usize)> {
+        let leaf_buffer_id = |pane_index: usize| {
+            self.pane_by_index(pane_index)
+                .map(|pane| pane.buffer_id)
+                .ok_or_else(|| {
+                    format!(
+                        "Attempted to swap buffers of pane which is not a leaf: {}",
+                        pane_index
+                    )
+                })
+        };
+        let buffer_a = leaf_buffer_id(pane_a)?;
+        let buffer_b = leaf_buffer_id(pane_b)?;
+
+        for (pane_index, buffer_id) in [(pane_a, buffer_b), (pane_b, buffer_a)] {
+            if let Some(node) = self.pane_node_mut_by_index(pane_index) {
+                node.is_dirty = true;
+                if let PaneNodeType::Leaf(pane) = &mut node.node_type {
+                    pane.buffer_id = buffer_id;
+                }
+            }
+        }
+
+        Ok((buffer_b, buffer_a))
+    }
+
+    /// Returns a snapshot of every pane node reachable from the root, with
+    /// each split listed before its children.
+    pub fn dump(&self) -> Vec<PaneTreeNode> {
+        let mut nodes = vec![];
+        let mut pending = vec![self.root_index];
+        while let Some(index) = pending.pop() {
+            let Some(node) = self.pane_node_by_index(index) else {
+                continue;
+            };
+
+            let children = match &node.node_type {
+                PaneNodeType::Leaf(_) => vec![],
+                PaneNodeType::VSplit(split) | PaneNodeType::HSplit(split) => {
+                    vec![split.first, split.second]
+                }
+            };
+            pending.extend(children.iter().rev());
+
+            nodes.push(PaneTreeNode {
+                index,
+                parent_index: node.parent_index,
+                node_type: node.node_type.clone(),
+                children,
+            });
+        }
+
+        nodes
+    }
+
+    /// Returns the number of leaf panes reachable from the root
+    pub fn leaf_count(&self) -> usize {
+        self.dump()
+            .iter()
+            .filter(|node| matches!(node.node_type, PaneNodeType::Leaf(_)))
+            .count()
+    }
+
     // TODO: Update with implementation to clean up closed panes
     fn close_with_children(&mut self, _index: usize, _active_pane_index: usize) -> bool {
         true
@@ -374,6 +618,16 @@ impl PaneNode {
     }
 }
 
+/// Snapshot of a pane node and its place in the pane tree
+#[auto_lua]
+#[derive(Clone, Debug)]
+pub struct PaneTreeNode {
+    pub index: usize,
+    pub parent_index: Option<usize>,
+    pub node_type: PaneNodeType,
+    pub children: Vec<usize>,
+}
+
 #[auto_lua]
 #[derive(Clone, Debug)]
 pub enum PaneNodeType {
@@ -391,7 +645,7 @@ pub struct Split {
 }
 
 #[auto_lua]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum SplitType {
     Percent { first_percent: f32 },
     FirstFixed { size: u16 },
@@ -403,7 +657,27 @@ pub enum SplitType {
 pub struct Pane {
     pub top_line: usize,
     pub buffer_id: usize,
-    pub should_wrap: bool,
+    pub wrap_mode: WrapMode,
+    /// Whether wrapped rows are indented to match the leading whitespace of
+    /// the line they continue
+    pub wrap_indent: bool,
+    /// Columns each tab is drawn as in place of reaching the next tab stop.
+    /// Only changes how tabs are shown, leaving the buffer's content as it is.
+    pub tab_spaces: Option<u16>,
+    pub folds: Vec<Fold>,
+}
+
+/// How lines longer than a pane is wide are shown
+#[auto_lua]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum WrapMode {
+    /// Lines are cut off at the pane's edge
+    None,
+    /// Lines continue on the next row from the character at the pane's edge
+    Char,
+    /// Lines continue on the next row from after the last whitespace which
+    /// fits, or from the pane's edge for words wider than the pane
+    Word,
 }
 
 impl Pane {
@@ -411,7 +685,346 @@ impl Pane {
         Self {
             top_line: 0,
             buffer_id,
-            should_wrap: false,
+            wrap_mode: WrapMode::None,
+            wrap_indent: false,
+            tab_spaces: None,
+            folds: vec![],
+        }
+    }
+
+    /// Folds the inclusive range of buffer lines. Any existing folds which
+    /// overlap the new range are merged into it.
+    pub fn add_fold(&mut self, start_line: usize, end_line: usize) {
+        let mut fold = Fold {
+            start_line: start_line.min(end_line),
+            end_line: start_line.max(end_line),
+        };
+
+        self.folds.retain(|existing| {
+            if existing.start_line <= fold.end_line && fold.start_line <= existing.end_line {
+                fold.start_line = fold.start_line.min(existing.start_line);
+                fold.end_line = fold.end_line.max(existing.end_line);
+                false
+            } else {
+                true
+            }
+        });
+
+        let insert_index = self
+            .folds
+            .partition_point(|existing| existing.start_line < fold.start_line);
+        self.folds.insert(insert_index, fold);
+    }
+
+    /// Removes the fold containing `line`, returning it if one was found.
+    pub fn remove_fold(&mut self, line: usize) -> Option<Fold> {
+        let index = self.folds.iter().position(|fold| fold.contains(line))?;
+        Some(self.folds.remove(index))
+    }
+
+    pub fn fold_containing(&self, line: usize) -> Option<&Fold> {
+        self.folds.iter().find(|fold| fold.contains(line))
+    }
+
+    /// Count of screen rows taken by lines `start_line` through `end_line`
+    /// inclusive of `buffer` when drawn `width` columns wide, following this
+    /// pane's wrapping. Each fold takes a single row.
+    pub fn visual_rows_for_lines(
+        &self,
+        buffer: &EditorBuffer,
+        start_line: usize,
+        end_line: usize,
+        width: u16,
+        tab_width: u16,
+    ) -> usize {
+        let mut rows = 0;
+        let mut line_index = start_line;
+        while line_index <= end_line {
+            if let Some(fold) = self.fold_containing(line_index) {
+                rows += 1;
+                line_index = fold.end_line + 1;
+            } else {
+                rows += display::line_visual_rows(buffer, self, line_index, width, tab_width);
+                line_index += 1;
+            }
+        }
+
+        rows
+    }
+
+    /// Top line which scrolls this pane showing `buffer` by one page of `rows`
+    /// rows `width` columns wide. Scrolling down moves past the lines drawn
+    /// entirely on the current page, while scrolling up moves to the first of
+    /// the lines which fit entirely on the page above, so wrapped lines are not
+    /// split between pages. Always scrolls by at least one line while there
+    /// are lines to scroll to.
+    pub fn page_top_line(
+        &self,
+        buffer: &EditorBuffer,
+        down: bool,
+        rows: u16,
+        width: u16,
+        tab_width: u16,
+    ) -> usize {
+        let last_line = buffer.content_line_count().saturating_sub(1);
+        let next_line = |line: usize| {
+            self.fold_containing(line)
+                .map_or(line, |fold| fold.end_line)
+                + 1
+        };
+        let previous_line = |line: usize| {
+            self.fold_containing(line - 1)
+                .map_or(line - 1, |fold| fold.start_line)
+        };
+        let rows = usize::from(rows);
+
+        let mut top_line = self.top_line;
+        let mut page_rows = 0;
+        if down {
+            while top_line < last_line {
+                let next = next_line(top_line);
+                page_rows +=
+                    self.visual_rows_for_lines(buffer, top_line, next - 1, width, tab_width);
+                if page_rows > rows {
+                    break;
+                }
+                top_line = next;
+            }
+
+            if top_line == self.top_line && top_line < last_line {
+                top_line = next_line(top_line);
+            }
+            top_line.min(last_line)
+        } else {
+            while top_line > 0 {
+                let previous = previous_line(top_line);
+                page_rows +=
+                    self.visual_rows_for_lines(buffer, previous, top_line - 1, width, tab_width);
+                if page_rows > rows {
+                    break;
+                }
+                top_line = previous;
+            }
+
+            if top_line == self.top_line && top_line > 0 {
+                top_line = previous_line(top_line);
+            }
+            top_line
+        }
+    }
+}
+
+#[auto_lua]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fold {
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+impl Fold {
+    pub fn contains(&self, line: usize) -> bool {
+        self.start_line <= line && line <= self.end_line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlapping_folds_merge() {
+        let mut pane = Pane::new(0);
+        pane.add_fold(2, 4);
+        pane.add_fold(8, 6);
+        pane.add_fold(4, 6);
+
+        assert_eq!(
+            pane.folds,
+            vec![Fold {
+                start_line: 2,
+                end_line: 8
+            }]
+        );
+    }
+
+    #[test]
+    fn removing_a_fold_returns_the_fold_containing_the_line() {
+        let mut pane = Pane::new(0);
+        pane.add_fold(2, 4);
+        pane.add_fold(7, 9);
+
+        assert_eq!(pane.remove_fold(5), None);
+        assert_eq!(
+            pane.remove_fold(8),
+            Some(Fold {
+                start_line: 7,
+                end_line: 9
+            })
+        );
+        assert!(pane.fold_containing(8).is_none());
+        assert!(pane.fold_containing(3).is_some());
+    }
+
+    #[test]
+    fn swapping_buffers_exchanges_leaf_pane_buffers() {
+        let mut pane_tree = PaneTree::new(0);
+        let split_index = pane_tree.vsplit(0, 1).unwrap();
+
+        assert_eq!(pane_tree.swap_buffers(0, 1), Ok((1, 0)));
+        assert_eq!(pane_tree.pane_by_index(0).unwrap().buffer_id, 1);
+        assert_eq!(pane_tree.pane_by_index(1).unwrap().buffer_id, 0);
+
+        assert!(pane_tree.swap_buffers(0, split_index).is_err());
+        assert_eq!(pane_tree.pane_by_index(0).unwrap().buffer_id, 1);
+    }
+
+    /// Split types of every split in the tree, by index
+    fn split_types(pane_tree: &mut PaneTree) -> Vec<(usize, SplitType)> {
+        (0..pane_tree.tree.len())
+            .filter_map(|index| {
+                pane_tree
+                    .split_mut_by_index(index)
+                    .map(|split| (index, split.split_type.clone()))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn restoring_a_maximized_pane_restores_its_split_types() {
+        let mut pane_tree = PaneTree::new(0);
+        let root_split = pane_tree.vsplit(0, 1).unwrap();
+        let inner_split = pane_tree.hsplit(1, 2).unwrap();
+        pane_tree.split_mut_by_index(inner_split).unwrap().split_type =
+            SplitType::FirstFixed { size: 5 };
+        let before = split_types(&mut pane_tree);
+
+        assert_eq!(pane_tree.toggle_maximize(1), Ok(true));
+        assert_eq!(
+            pane_tree.split_mut_by_index(root_split).unwrap().split_type,
+            SplitType::FirstFixed { size: 0 }
+        );
+        assert_eq!(
+            pane_tree.split_mut_by_index(inner_split).unwrap().split_type,
+            SplitType::SecondFixed { size: 0 }
+        );
+
+        assert_eq!(pane_tree.toggle_maximize(1), Ok(false));
+        assert_eq!(split_types(&mut pane_tree), before);
+    }
+
+    #[test]
+    fn closing_a_pane_while_maximized_restores_the_other_panes() {
+        let mut pane_tree = PaneTree::new(0);
+        let root_split = pane_tree.vsplit(0, 1).unwrap();
+        let inner_split = pane_tree.hsplit(1, 2).unwrap();
+
+        assert_eq!(pane_tree.toggle_maximize(1), Ok(true));
+        pane_tree.close_child(inner_split, true, 1).unwrap();
+
+        assert_eq!(
+            pane_tree.split_mut_by_index(root_split).unwrap().split_type,
+            SplitType::Percent { first_percent: 0.5 }
+        );
+
+        // Nothing is left maximized, so toggling maximizes rather than
+        // restoring stale split types
+        assert_eq!(pane_tree.toggle_maximize(0), Ok(true));
+        assert_eq!(
+            pane_tree.split_mut_by_index(root_split).unwrap().split_type,
+            SplitType::SecondFixed { size: 0 }
+        );
+    }
+
+    #[test]
+    fn dumping_a_single_split_lists_the_split_then_its_leaves() {
+        let mut pane_tree = PaneTree::new(0);
+        let split_index = pane_tree.vsplit(0, 1).unwrap();
+
+        let dump = pane_tree.dump();
+        let summary: Vec<_> = dump
+            .iter()
+            .map(|node| (node.index, node.parent_index, node.children.clone()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (split_index, None, vec![0, 1]),
+                (0, Some(split_index), vec![]),
+                (1, Some(split_index), vec![]),
+            ]
+        );
+        assert!(matches!(dump[0].node_type, PaneNodeType::VSplit(_)));
+        assert!(matches!(&dump[1].node_type, PaneNodeType::Leaf(pane) if pane.buffer_id == 0));
+        assert!(matches!(&dump[2].node_type, PaneNodeType::Leaf(pane) if pane.buffer_id == 1));
+        assert_eq!(pane_tree.leaf_count(), 2);
+    }
+
+    #[test]
+    fn equalizing_weights_percent_splits_by_leaf_count() {
+        let mut pane_tree = PaneTree::new(0);
+        let root_split = pane_tree.vsplit(0, 1).unwrap();
+        let middle_split = pane_tree.hsplit(1, 2).unwrap();
+        let fixed_split = pane_tree.hsplit(3, 3).unwrap();
+        for split_index in [root_split, middle_split] {
+            pane_tree.split_mut_by_index(split_index).unwrap().split_type =
+                SplitType::Percent { first_percent: 0.9 };
+        }
+        pane_tree.split_mut_by_index(fixed_split).unwrap().split_type =
+            SplitType::FirstFixed { size: 5 };
+        for pane_node in pane_tree.tree.iter_mut().flatten() {
+            pane_node.is_dirty = false;
         }
+
+        pane_tree.equalize(root_split).unwrap();
+
+        assert_eq!(
+            split_types(&mut pane_tree),
+            vec![
+                (root_split, SplitType::Percent { first_percent: 0.25 }),
+                (middle_split, SplitType::Percent { first_percent: 1.0 / 3.0 }),
+                (fixed_split, SplitType::FirstFixed { size: 5 }),
+            ]
+        );
+        assert!(pane_tree.tree.iter().flatten().all(|node| node.is_dirty));
+    }
+
+    fn char_wrapped_pane_with(content: &str) -> (Pane, EditorBuffer) {
+        let mut buffer = EditorBuffer::new();
+        buffer.insert_at_cursor(content);
+        let mut pane = Pane::new(0);
+        pane.wrap_mode = WrapMode::Char;
+        (pane, buffer)
+    }
+
+    #[test]
+    fn lines_wider_than_the_pane_take_a_row_per_wrap() {
+        let (mut pane, buffer) = char_wrapped_pane_with("aaaaaaaaaa\nb\nc\nd");
+
+        assert_eq!(pane.visual_rows_for_lines(&buffer, 0, 0, 4, 4), 3);
+        assert_eq!(pane.visual_rows_for_lines(&buffer, 0, 3, 4, 4), 6);
+        // The end of a line which fills its last row is drawn on a row of its own
+        assert_eq!(pane.visual_rows_for_lines(&buffer, 0, 0, 10, 4), 2);
+        assert_eq!(pane.visual_rows_for_lines(&buffer, 0, 0, 11, 4), 1);
+
+        pane.add_fold(1, 3);
+        assert_eq!(pane.visual_rows_for_lines(&buffer, 0, 3, 4, 4), 4);
+
+        pane.wrap_mode = WrapMode::None;
+        assert_eq!(pane.visual_rows_for_lines(&buffer, 0, 0, 4, 4), 1);
+    }
+
+    #[test]
+    fn pages_scroll_by_whole_wrapped_lines() {
+        let (mut pane, buffer) = char_wrapped_pane_with("aaaaaaaaaa\nb\nc\nd");
+
+        assert_eq!(pane.page_top_line(&buffer, true, 3, 4, 4), 1);
+        pane.top_line = 1;
+        assert_eq!(pane.page_top_line(&buffer, true, 3, 4, 4), 3);
+        pane.top_line = 3;
+        assert_eq!(pane.page_top_line(&buffer, false, 3, 4, 4), 1);
+        pane.top_line = 1;
+        // The wrapped first line does not fit a page of two rows, but the
+        // page still scrolls up to it
+        assert_eq!(pane.page_top_line(&buffer, false, 2, 4, 4), 0);
     }
 }
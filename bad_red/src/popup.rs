@@ -0,0 +1,102 @@
+// This file is part of BadRed.
+
+// BadRed is free software: you can redistribute it and/or modify it under the terms of the GNU General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// BadRed is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::editor_frame::EditorFrame;
+
+/// Floating list of menu items drawn over the pane content
+pub struct Popup {
+    pub items: Vec<String>,
+    pub selected: Option<usize>,
+    pub row: u16,
+    pub col: u16,
+}
+
+impl Popup {
+    /// Rows and columns taken by the popup's border around its items
+    pub const BORDER_SIZE: u16 = 2;
+
+    /// Returns the frame of the popup including its border. The popup is
+    /// shifted up and left to stay within `bounds`, and shrunk if it is
+    /// larger than `bounds`.
+    pub fn frame(&self, bounds: &EditorFrame) -> EditorFrame {
+        let content_rows = u16::try_from(self.items.len()).unwrap_or(u16::MAX);
+        let content_cols = self
+            .items
+            .iter()
+            .map(|item| item.width())
+            .max()
+            .unwrap_or(0);
+        let content_cols = u16::try_from(content_cols).unwrap_or(u16::MAX);
+
+        clamped_frame(
+            self.row,
+            self.col,
+            content_rows.saturating_add(Self::BORDER_SIZE),
+            content_cols.saturating_add(Self::BORDER_SIZE),
+            bounds,
+        )
+    }
+
+    /// Index of the first item shown when the popup has fewer rows than
+    /// items, keeping the selected item visible.
+    pub fn first_visible_item(&self, visible_rows: u16) -> usize {
+        self.selected
+            .map(|selected| (selected + 1).saturating_sub(visible_rows.into()))
+            .unwrap_or(0)
+    }
+}
+
+fn clamped_frame(row: u16, col: u16, rows: u16, cols: u16, bounds: &EditorFrame) -> EditorFrame {
+    let rows = rows.min(bounds.rows);
+    let cols = cols.min(bounds.cols);
+
+    EditorFrame {
+        x_col: col
+            .max(bounds.x_col)
+            .min(bounds.x_col + bounds.cols - cols),
+        y_row: row
+            .max(bounds.y_row)
+            .min(bounds.y_row + bounds.rows - rows),
+        rows,
+        cols,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bounds() -> EditorFrame {
+        EditorFrame {
+            x_col: 2,
+            y_row: 1,
+            rows: 10,
+            cols: 20,
+        }
+    }
+
+    fn placement(frame: EditorFrame) -> (u16, u16, u16, u16) {
+        (frame.x_col, frame.y_row, frame.rows, frame.cols)
+    }
+
+    #[test]
+    fn clamped_frame_keeps_a_popup_which_fits() {
+        assert_eq!(placement(clamped_frame(3, 4, 5, 6, &bounds())), (4, 3, 5, 6));
+    }
+
+    #[test]
+    fn clamped_frame_shifts_a_popup_past_the_edges_back_inside() {
+        assert_eq!(placement(clamped_frame(9, 19, 5, 6, &bounds())), (16, 6, 5, 6));
+        assert_eq!(placement(clamped_frame(0, 0, 5, 6, &bounds())), (2, 1, 5, 6));
+    }
+
+    #[test]
+    fn clamped_frame_shrinks_a_popup_larger_than_the_bounds() {
+        assert_eq!(placement(clamped_frame(4, 8, 30, 40, &bounds())), (2, 1, 10, 20));
+    }
+}
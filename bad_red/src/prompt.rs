@@ -0,0 +1,80 @@
+// This file is part of BadRed.
+
+// BadRed is free software: you can redistribute it and/or modify it under the terms of the GNU General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// BadRed is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+use crossterm::event::{KeyCode, KeyModifiers};
+use mlua::RegistryKey;
+
+use crate::{
+    buffer::{ContentBuffer, EditorBuffer, EditorBufferType},
+    keymap::RedKeyEvent,
+};
+
+/// Single line of input drawn over the bottom row of the editor. While a
+/// prompt is open, key events edit its input instead of running key hooks.
+pub struct Prompt {
+    pub prompt: String,
+    pub input: EditorBuffer,
+    /// Lua function called with the input once the prompt is submitted
+    pub on_submit: RegistryKey,
+}
+
+pub enum PromptKeyResult {
+    Handled,
+    Submit(String),
+    Cancel,
+}
+
+impl Prompt {
+    pub fn new(prompt: String, on_submit: RegistryKey) -> Self {
+        Self {
+            prompt,
+            input: EditorBuffer::new_typed(EditorBufferType::Naive),
+            on_submit,
+        }
+    }
+
+    /// Applies `event` to the prompt's input. Enter submits the input and Esc
+    /// cancels the prompt.
+    pub fn handle_key_event(&mut self, event: &RedKeyEvent) -> PromptKeyResult {
+        let is_plain = !event
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+
+        match event.code {
+            KeyCode::Enter => return PromptKeyResult::Submit(self.input.content_copy()),
+            KeyCode::Esc => return PromptKeyResult::Cancel,
+            KeyCode::Char(char) if is_plain => {
+                self.input.insert_at_cursor(char.encode_utf8(&mut [0; 4]));
+            }
+            KeyCode::Backspace => {
+                if self.input.cursor_byte_index() > 0 {
+                    let byte_index = self.input.cursor_moved_by_char(-1);
+                    self.input.set_cursor_byte_index(byte_index, false);
+                    self.input.delete_at_cursor(1);
+                }
+            }
+            KeyCode::Delete => {
+                self.input.delete_at_cursor(1);
+            }
+            KeyCode::Left => {
+                let byte_index = self.input.cursor_moved_by_char(-1);
+                self.input.set_cursor_byte_index(byte_index, false);
+            }
+            KeyCode::Right => {
+                let byte_index = self.input.cursor_moved_by_char(1);
+                self.input.set_cursor_byte_index(byte_index, false);
+            }
+            KeyCode::Home => self.input.set_cursor_byte_index(0, false),
+            KeyCode::End => {
+                let byte_index = self.input.content_byte_length();
+                self.input.set_cursor_byte_index(byte_index, false);
+            }
+            _ => (),
+        }
+
+        PromptKeyResult::Handled
+    }
+}
@@ -8,9 +8,27 @@ use std::path::PathBuf;
 
 use bad_red_proc_macros::{auto_lua_defaulting, auto_script_table};
 use mlua::{Function, Lua, Table, Value};
+use strum::IntoEnumIterator;
 
 use crate::{
-    buffer::EditorBufferType, editor_state::EditorOptionList, hook_map::{HookType, HookTypeName}, styling::Color
+    buffer::{
+        BlockSelection, BufferMatch, BufferOptionList, BufferOptionType, BufferOptions,
+        DiffOperation, EditorBufferType, Highlight, Language, LineMatch, Motion, MotionRange,
+        LineEnding, PhantomText, TextObject,
+    },
+    editor_frame::{EditorFrame, ScreenPosition},
+    editor_state::{EditorOptionList, EditorOptionType, EditorOptions, Error, TabPageInfo},
+    file_grep::{FileMatch, GrepResult},
+    hook_map::{
+        BufferFileLink, BufferFileLinkType, HookType, HookTypeName, PaneBufferChange,
+    },
+    jump_list::JumpPosition,
+    keymap::KeyBinding,
+    pane::{Fold, Pane, PaneNodeType, PaneTreeNode, Split, SplitType, WrapMode},
+    shell::ShellOutput,
+    styling::{Color, Style, Styling, TextStyle},
+    task::TaskResult,
+    terminal::{ColorDepth, TerminalCapabilities},
 };
 
 pub struct ScriptHandler {
@@ -37,6 +55,12 @@ pub enum RedCall<'lua> {
     PaneHSplit {
         index: usize,
     },
+    PaneVSplitNewBuffer {
+        index: usize,
+    },
+    PaneHSplitNewBuffer {
+        index: usize,
+    },
     ActivePaneIndex,
     RootPaneIndex,
     PaneIsFirst {
@@ -82,6 +106,21 @@ pub enum RedCall<'lua> {
         pane_index: usize,
         should_wrap: bool,
     },
+    PaneWrapMode {
+        pane_index: usize,
+    },
+    PaneSetWrapMode {
+        pane_index: usize,
+        wrap_mode: WrapMode,
+        indent: bool,
+    },
+    PaneTabSpaces {
+        pane_index: usize,
+    },
+    PaneSetTabSpaces {
+        pane_index: usize,
+        spaces: Option<u16>,
+    },
     PaneTopLine {
         pane_index: usize,
     },
@@ -92,6 +131,116 @@ pub enum RedCall<'lua> {
     PaneFrame {
         pane_index: usize,
     },
+    PaneCursorScreenPosition {
+        pane_index: usize,
+    },
+    PaneScrollPage {
+        pane_index: usize,
+        down: bool,
+    },
+    PaneFolds {
+        pane_index: usize,
+    },
+    PaneAddFold {
+        pane_index: usize,
+        start_line: usize,
+        end_line: usize,
+    },
+    PaneRemoveFold {
+        pane_index: usize,
+        line: usize,
+    },
+    PaneTreeDump,
+    PaneEqualize {
+        index: usize,
+    },
+    PaneToggleMaximize {
+        index: usize,
+    },
+    PaneCount,
+    PaneSwapBuffers {
+        pane_a: usize,
+        pane_b: usize,
+    },
+
+    TabNew {
+        buffer_id: usize,
+    },
+    TabNext,
+    TabSwitch {
+        index: usize,
+    },
+    TabClose,
+    TabList,
+
+    KeyMapList {
+        keymap: Table<'lua>,
+        prefix: Option<Vec<String>>,
+    },
+
+    ShowPopup {
+        items: Vec<String>,
+        selected: Option<usize>,
+        row: u16,
+        col: u16,
+    },
+    HidePopup,
+    PromptOpen {
+        prompt: String,
+        on_submit: Function<'lua>,
+    },
+    PromptClose,
+    ForceRedraw,
+    Batch {
+        calls: Vec<RedCall<'lua>>,
+    },
+    Bell {
+        visual: bool,
+    },
+    TerminalCapabilities,
+    Mode,
+    SetMode {
+        mode: String,
+    },
+    WindowTitle,
+    SetWindowTitle {
+        title: String,
+    },
+    SessionSave {
+        path: String,
+    },
+    SessionLoad {
+        path: String,
+    },
+
+    GrepBuffers {
+        pattern: String,
+    },
+    GrepFiles {
+        root_path: String,
+        pattern: String,
+        glob: Option<String>,
+    },
+    CancelGrepFiles {
+        id: usize,
+    },
+    SpawnGrepFiles {
+        root_path: String,
+        pattern: String,
+        glob: Option<String>,
+    },
+    SpawnReadFile {
+        path: String,
+    },
+    RunShell {
+        command: String,
+        args: Vec<String>,
+        stdin: Option<String>,
+    },
+
+    JumpListPush,
+    JumpBack,
+    JumpForward,
 
     SetHook {
         hook_name: HookTypeName,
@@ -105,6 +254,10 @@ pub enum RedCall<'lua> {
     RunScript {
         script: String,
     },
+    EvalLua {
+        script: String,
+        continuation: Function<'lua>,
+    },
 
     CurrentBufferId,
     BufferInsert {
@@ -115,10 +268,20 @@ pub enum RedCall<'lua> {
         buffer_id: usize,
         char_count: usize,
     },
+    ActiveBufferInsert {
+        content: String,
+    },
+    ActiveBufferDelete {
+        char_count: usize,
+    },
     BufferCursorMovedByChar {
         buffer_id: usize,
         char_count: isize,
     },
+    BufferCursorMovedByCharInLine {
+        buffer_id: usize,
+        char_count: isize,
+    },
     BufferIndexMovedByChar {
         buffer_id: usize,
         start_byte_index: usize,
@@ -131,6 +294,15 @@ pub enum RedCall<'lua> {
     BufferType {
         buffer_id: usize,
     },
+    BufferRevision {
+        buffer_id: usize,
+    },
+    BufferHash {
+        buffer_id: usize,
+    },
+    BufferIsModifiedFromDisk {
+        buffer_id: usize,
+    },
     BufferSetCursor {
         buffer_id: usize,
         cursor_index: usize,
@@ -140,6 +312,23 @@ pub enum RedCall<'lua> {
         buffer_id: usize,
         line_index: usize,
     },
+    BufferGotoLine {
+        buffer_id: usize,
+        line_index: usize,
+        column: usize,
+    },
+    BufferLineFirstNonWhitespace {
+        buffer_id: usize,
+        line_index: usize,
+    },
+    BufferParagraphMove {
+        buffer_id: usize,
+        forward: bool,
+    },
+    BufferMatchIndent {
+        buffer_id: usize,
+        forward: bool,
+    },
     BufferLength {
         buffer_id: usize,
     },
@@ -150,6 +339,11 @@ pub enum RedCall<'lua> {
     BufferLineCount {
         buffer_id: usize,
     },
+    BufferLineCountInRange {
+        buffer_id: usize,
+        start_byte: usize,
+        end_byte: usize,
+    },
     BufferLineStart {
         buffer_id: usize,
         line_index: usize,
@@ -164,6 +358,18 @@ pub enum RedCall<'lua> {
     BufferCursorLine {
         buffer_id: usize,
     },
+    BufferCursorLineContent {
+        buffer_id: usize,
+    },
+    BufferCursorVisualColumn {
+        buffer_id: usize,
+        tab_width: Option<u16>,
+    },
+    BufferMoveCursorLineVisual {
+        buffer_id: usize,
+        line_delta: isize,
+        tab_width: Option<u16>,
+    },
     BufferContent {
         buffer_id: usize,
     },
@@ -180,7 +386,19 @@ pub enum RedCall<'lua> {
         buffer_id: usize,
         line_index: usize,
     },
+    BufferLineRange {
+        buffer_id: usize,
+        start_line: usize,
+        end_line: usize,
+    },
     BufferOpen,
+    BufferOpenTyped {
+        buffer_type: EditorBufferType,
+    },
+    BufferOpenPaged {
+        file_id: usize,
+        window_size: Option<usize>,
+    },
     BufferClose {
         buffer_id: usize,
     },
@@ -193,20 +411,206 @@ pub enum RedCall<'lua> {
         buffer_id: usize,
         should_force: bool,
     },
+    SetFormatter {
+        extension: String,
+        command: Option<String>,
+    },
+    SetFileTypeOptions {
+        extension: String,
+        options: Option<BufferOptionList>,
+    },
     BufferWriteToFile {
         buffer_id: usize,
     },
+    BufferRevert {
+        buffer_id: usize,
+    },
     BufferCurrentFile {
         buffer_id: usize,
     },
+    BufferDetectFileType {
+        buffer_id: usize,
+    },
+    #[lua(rename = "buffer_clear_styles")]
     BufferClearStyle {
         buffer_id: usize,
     },
+    BufferStyles {
+        buffer_id: usize,
+    },
     BufferPushStyle {
         buffer_id: usize,
         name: String,
         regex: String,
     },
+    BufferDiff {
+        buffer_id: usize,
+        other_text: String,
+    },
+    BufferSetNamedMark {
+        buffer_id: usize,
+        name: String,
+        byte_index: usize,
+    },
+    BufferGotoNamedMark {
+        buffer_id: usize,
+        name: String,
+    },
+    ExpandSnippet {
+        buffer_id: usize,
+        trigger: String,
+        body: String,
+    },
+    SnippetNextStop {
+        buffer_id: usize,
+    },
+    BufferInsertPair {
+        buffer_id: usize,
+        open: String,
+        close: String,
+    },
+    BufferInsertClose {
+        buffer_id: usize,
+        close: String,
+    },
+    BufferToggleComment {
+        buffer_id: usize,
+        start_line: usize,
+        end_line: usize,
+        comment_prefix: String,
+    },
+    BufferSortLines {
+        buffer_id: usize,
+        start_line: usize,
+        end_line: usize,
+        reverse: bool,
+        numeric: bool,
+    },
+    BufferUniqueLines {
+        buffer_id: usize,
+        start_line: usize,
+        end_line: usize,
+    },
+    BufferReverseLines {
+        buffer_id: usize,
+        start_line: usize,
+        end_line: usize,
+    },
+    BufferNormalizeNewlines {
+        buffer_id: usize,
+        to: LineEnding,
+    },
+    BufferReflow {
+        buffer_id: usize,
+        line_index: usize,
+        width: usize,
+    },
+    BufferPasteLines {
+        buffer_id: usize,
+        text: String,
+        below: bool,
+    },
+    BufferDeleteToLineEnd {
+        buffer_id: usize,
+    },
+    BufferDeleteWord {
+        buffer_id: usize,
+        forward: bool,
+    },
+    BufferApplyMotion {
+        buffer_id: usize,
+        motion: Motion,
+        count: Option<usize>,
+    },
+    BufferTextObject {
+        buffer_id: usize,
+        object: TextObject,
+        inner: bool,
+    },
+    BufferSetBlockSelection {
+        buffer_id: usize,
+        start_line: usize,
+        start_col: usize,
+        end_line: usize,
+        end_col: usize,
+    },
+    BufferClearBlockSelection {
+        buffer_id: usize,
+    },
+    BufferBlockSelection {
+        buffer_id: usize,
+    },
+    BufferBlockInsert {
+        buffer_id: usize,
+        text: String,
+        pad: bool,
+    },
+    BufferBlockDelete {
+        buffer_id: usize,
+    },
+    BufferIncrementNumber {
+        buffer_id: usize,
+        delta: i64,
+    },
+    RepeatLastEdit {
+        buffer_id: usize,
+    },
+    BufferSetLanguage {
+        buffer_id: usize,
+        language: Option<String>,
+    },
+    BufferHighlights {
+        buffer_id: usize,
+        start_line: usize,
+        end_line: usize,
+    },
+    BufferAddPhantom {
+        buffer_id: usize,
+        byte_index: usize,
+        text: String,
+        style: String,
+    },
+    BufferClearPhantoms {
+        buffer_id: usize,
+    },
+    BufferSetSign {
+        buffer_id: usize,
+        line: usize,
+        text: String,
+        style: String,
+    },
+    BufferClearSigns {
+        buffer_id: usize,
+    },
+    BufferAddLineHighlight {
+        buffer_id: usize,
+        line: usize,
+        style: String,
+    },
+    BufferClearLineHighlights {
+        buffer_id: usize,
+    },
+    BufferSetSearch {
+        buffer_id: usize,
+        pattern: Option<String>,
+    },
+    BufferAddCursor {
+        buffer_id: usize,
+        byte_index: usize,
+    },
+    BufferClearSecondaryCursors {
+        buffer_id: usize,
+    },
+    BufferSecondaryCursors {
+        buffer_id: usize,
+    },
+    BufferGetOptions {
+        buffer_id: usize,
+    },
+    BufferUpdateOptions {
+        buffer_id: usize,
+        option_list: BufferOptionList,
+    },
 
     SetTextStyle {
         name: String,
@@ -238,6 +642,115 @@ pub enum RedCall<'lua> {
     },
 }
 
+/// LuaLS annotations describing `red.call` and the Lua representation of the
+/// types passed through it, for use as a `---@meta` file by Lua tooling.
+pub fn lua_annotations() -> String {
+    let type_annotations = [
+        RedCall::lua_annotation(),
+        BlockSelection::lua_annotation(),
+        BufferFileLink::lua_annotation(),
+        BufferFileLinkType::lua_annotation(),
+        BufferMatch::lua_annotation(),
+        BufferOptionType::lua_annotation(),
+        BufferOptions::lua_annotation(),
+        Color::lua_annotation(),
+        DiffOperation::lua_annotation(),
+        EditorBufferType::lua_annotation(),
+        EditorFrame::lua_annotation(),
+        EditorOptionType::lua_annotation(),
+        EditorOptions::lua_annotation(),
+        FileMatch::lua_annotation(),
+        GrepResult::lua_annotation(),
+        Fold::lua_annotation(),
+        Highlight::lua_annotation(),
+        HookType::lua_annotation(),
+        JumpPosition::lua_annotation(),
+        Language::lua_annotation(),
+        LineMatch::lua_annotation(),
+        Pane::lua_annotation(),
+        PaneBufferChange::lua_annotation(),
+        PaneNodeType::lua_annotation(),
+        PaneTreeNode::lua_annotation(),
+        WrapMode::lua_annotation(),
+        TabPageInfo::lua_annotation(),
+        KeyBinding::lua_annotation(),
+        Motion::lua_annotation(),
+        MotionRange::lua_annotation(),
+        TextObject::lua_annotation(),
+        LineEnding::lua_annotation(),
+        PhantomText::lua_annotation(),
+        ScreenPosition::lua_annotation(),
+        ShellOutput::lua_annotation(),
+        Split::lua_annotation(),
+        SplitType::lua_annotation(),
+        Style::lua_annotation(),
+        Styling::lua_annotation(),
+        TaskResult::lua_annotation(),
+        ColorDepth::lua_annotation(),
+        TerminalCapabilities::lua_annotation(),
+        TextStyle::lua_annotation(),
+        // Types converted by hand rather than by auto_lua
+        format!(
+            "---@alias HookTypeName {}\n",
+            HookTypeName::iter()
+                .map(|name| format!("\"{}\"", Into::<&'static str>::into(name)))
+                .collect::<Vec<_>>()
+                .join("|")
+        ),
+        "---@alias BufferOptionList table<string, any>\n".to_string(),
+        "---@alias EditorOptionList table<string, any>\n".to_string(),
+    ];
+
+    format!(
+        "---@meta\n\n---@class red\n---@field call red.call\n---@field try table<string, fun(...): boolean, any>\nred = {{}}\n\n---@class red.call\n{}\n{}",
+        RedCall::lua_annotations(),
+        type_annotations.join("\n"),
+    )
+}
+
+/// Wraps `coroutine.yield` so that a failing RedCall, which resumes the
+/// calling script with the call's `Error`, raises that error in the script.
+/// Only yields of a RedCall are checked, so coroutines which scripts create
+/// and resume themselves are passed values as standard.
+const RAISE_CALL_ERRORS: &str = r#"
+local is_error, is_red_call = ...
+local yield = coroutine.yield
+
+coroutine.yield = function(...)
+    if not is_red_call(...) then
+        return yield(...)
+    end
+
+    local results = table.pack(yield(...))
+    if is_error(results[1]) then
+        error(results[1], 0)
+    end
+
+    return table.unpack(results, 1, results.n)
+end
+"#;
+
+/// Builds `red.try`, which mirrors `red.call` but makes the call itself and
+/// returns `true` and the call's result, or `false` and the error's message
+/// rather than raising the error.
+const TRY_TABLE: &str = r#"
+red.try = {}
+
+for name, call in pairs(red.call) do
+    red.try[name] = function(...)
+        local ok, result = pcall(function(...)
+            return coroutine.yield(call(...))
+        end, ...)
+
+        if ok then
+            return true, result
+        else
+            return false, tostring(result)
+        end
+    end
+end
+"#;
+
 impl ScriptHandler {
     pub fn new(red_script_path: PathBuf) -> mlua::Result<Self> {
         let lua = Lua::new();
@@ -249,6 +762,20 @@ impl ScriptHandler {
 
         lua.globals().set("red", red_table)?;
 
+        let is_error = lua.create_function(|_, value: Value| {
+            Ok(matches!(value, Value::UserData(data) if data.is::<Error>()))
+        })?;
+        let is_red_call = lua.create_function(|_, value: Value| {
+            let Value::Table(table) = value else {
+                return Ok(false);
+            };
+            let type_name: Value = table.raw_get("type")?;
+
+            Ok(matches!(type_name, Value::String(name) if name.as_bytes() == b"RedCall"))
+        })?;
+        lua.load(RAISE_CALL_ERRORS).call::<_, ()>((is_error, is_red_call))?;
+        lua.load(TRY_TABLE).exec()?;
+
         {
             let package: mlua::Table = lua.globals().get("package")?;
             let current_path: String = package.get("path")?;
@@ -266,3 +793,144 @@ impl ScriptHandler {
         self.lua.load(script).exec()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bad_red_proc_macros::auto_lua;
+    use mlua::{FromLua, IntoLua};
+
+    use super::*;
+
+    #[auto_lua(transparent)]
+    #[derive(Debug, PartialEq)]
+    struct WrappedId(usize);
+
+    #[auto_lua(transparent)]
+    #[derive(Debug, PartialEq)]
+    struct WrappedNames {
+        names: Vec<String>,
+    }
+
+    #[test]
+    fn transparent_newtypes_are_annotated_as_their_field() {
+        assert_eq!(WrappedId::lua_annotation(), "---@alias WrappedId integer\n");
+        assert_eq!(WrappedNames::lua_annotation(), "---@alias WrappedNames string[]\n");
+    }
+
+    #[test]
+    fn lua_transparent_newtypes_round_trip_as_their_field() {
+        let lua = Lua::new();
+
+        let value = WrappedId(7).into_lua(&lua).unwrap();
+        assert_eq!(value.as_i64(), Some(7));
+        assert_eq!(WrappedId::from_lua(value, &lua).unwrap(), WrappedId(7));
+
+        let names = vec!["a".to_string(), "b".to_string()];
+        let value = WrappedNames { names: names.clone() }.into_lua(&lua).unwrap();
+        assert_eq!(value.as_table().unwrap().raw_len(), 2);
+        assert_eq!(WrappedNames::from_lua(value, &lua).unwrap(), WrappedNames { names });
+    }
+
+    #[test]
+    fn annotations_describe_every_call() {
+        let annotations = lua_annotations();
+
+        for call_name in RedCallName::iter() {
+            let call_name: &'static str = call_name.into();
+            assert!(
+                annotations.contains(&format!("---@field {} fun(", call_name)),
+                "Missing annotation for red.call.{}",
+                call_name,
+            );
+        }
+    }
+
+    #[test]
+    fn annotations_describe_every_type_they_use() {
+        let annotations = lua_annotations();
+        let type_names = annotations
+            .lines()
+            .filter_map(|line| line.strip_prefix("---@field "))
+            .flat_map(|field| field.split(|c: char| !c.is_alphanumeric() && c != '_'))
+            .filter(|word| word.starts_with(|c: char| c.is_ascii_uppercase()));
+
+        for type_name in type_names {
+            assert!(
+                annotations.contains(&format!("---@class {}\n", type_name))
+                    || annotations.contains(&format!("---@alias {} ", type_name)),
+                "Missing annotation for type {}",
+                type_name,
+            );
+        }
+    }
+
+    #[test]
+    fn lua_calls_missing_an_argument_name_the_call_and_its_arguments() {
+        let script_handler = ScriptHandler::new(PathBuf::new()).unwrap();
+
+        let error = script_handler
+            .lua
+            .load("return red.call.buffer_insert(0)")
+            .eval::<Value>()
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("red.call.buffer_insert"), "{}", error);
+        assert!(error.contains("buffer_id: integer, content: string"), "{}", error);
+
+        let error = script_handler
+            .lua
+            .load("return red.call.buffer_insert(0, 'a', 'b')")
+            .eval::<Value>()
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("Found 3 arguments but expected at most 2"), "{}", error);
+    }
+
+    #[test]
+    fn lua_data_variants_without_values_name_the_variant() {
+        let lua = Lua::new();
+        let table = lua.create_table().unwrap();
+        table.set("type", "RedCall").unwrap();
+        table.set("variant", "buffer_insert").unwrap();
+
+        let Err(error) = RedCall::from_lua(Value::Table(table.clone()), &lua) else {
+            panic!("Converted a data variant without its values");
+        };
+        let error = error.to_string();
+        assert!(error.contains("Missing 'values' table"), "{}", error);
+        assert!(error.contains("RedCall::BufferInsert"), "{}", error);
+
+        // Unit variants have no values to read
+        table.set("variant", "editor_exit").unwrap();
+        let call = RedCall::from_lua(Value::Table(table), &lua).unwrap();
+        assert!(matches!(call, RedCall::EditorExit));
+    }
+
+    #[test]
+    fn renamed_call_converts_under_its_new_name() {
+        let name: &'static str = RedCallName::BufferClearStyle.into();
+        assert_eq!(name, "buffer_clear_styles");
+        assert_eq!(
+            RedCallName::from_str("buffer_clear_styles").unwrap(),
+            RedCallName::BufferClearStyle
+        );
+        assert!(RedCallName::from_str("buffer_clear_style").is_err());
+
+        let name: &'static str = RedCallName::BufferPushStyle.into();
+        assert_eq!(name, "buffer_push_style");
+    }
+
+    #[test]
+    fn lua_renamed_call_round_trips_under_its_new_name() {
+        let lua = Lua::new();
+
+        let value = RedCall::BufferClearStyle { buffer_id: 3 }.into_lua(&lua).unwrap();
+        let table = value.as_table().unwrap();
+        assert_eq!(table.get::<_, String>("variant").unwrap(), "buffer_clear_styles");
+
+        let call = RedCall::from_lua(value, &lua).unwrap();
+        assert!(matches!(call, RedCall::BufferClearStyle { buffer_id: 3 }));
+    }
+}
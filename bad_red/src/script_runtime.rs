@@ -4,30 +4,117 @@
 //
 // BadRed is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
 
-use std::collections::VecDeque;
+use std::{
+    collections::{HashMap, VecDeque},
+    str::FromStr,
+    time::{Duration, Instant},
+};
 
 use crossterm::terminal;
-use mlua::{Function, IntoLua, Lua, Thread, Value};
+use mlua::{FromLua, Function, IntoLua, Lua, RegistryKey, Thread, Value};
+use regex::Regex;
 
 use crate::{
-    buffer::ContentBuffer,
-    editor_state::{EditorState, Error, Result},
+    buffer::{
+        line_diff, BlockSelection, BufferOptions, ContentBuffer, Language, LanguageName,
+        PagedBuffer, PhantomText, SearchState, Sign,
+    },
+    display,
+    editor_frame::ScreenPosition,
+    editor_state::{BufferFormat, EditorState, Error, Result},
+    edit_repeat::RepeatableEdit,
+    file_grep::{FileGrep, GrepCanceller},
+    file_type,
+    file_watch::FileWatcher,
     hook_map::{
         BufferFileLink, BufferFileLinkType, HookMap, HookType, HookTypeName, PaneBufferChange,
     },
-    pane::{PaneNodeType, Split, SplitType},
-    script_handler::RedCall,
+    jump_list::JumpPosition,
+    keymap,
+    pane::{PaneNodeType, Split, SplitType, WrapMode},
+    popup::Popup,
+    prompt::Prompt,
+    script_handler::{RedCall, RedCallName},
+    shell,
     styling::TextStyle,
+    task::{TaskCompletion, TaskResult, TaskRunner},
+    terminal::TerminalCapabilities,
 };
 
 pub struct ScriptScheduler<'lua> {
     lua: &'lua Lua,
     active: VecDeque<ProcessAwaiting<'lua>>,
+    next_eval_id: usize,
+    tasks: TaskRunner,
+    /// Cancellers of the file searches running as tasks, by task id
+    grep_cancellers: HashMap<usize, GrepCanceller>,
+    modified_buffers: ModificationTracker,
+    /// Started once the `auto_reload` option is first enabled
+    file_watcher: Option<FileWatcher>,
+}
+
+/// Last revision seen of each buffer's content, used to run `BufferModified`
+/// once a burst of edits settles rather than on every edit
+#[derive(Default)]
+struct ModificationTracker {
+    buffers: HashMap<usize, BufferModification>,
+}
+
+struct BufferModification {
+    revision: u64,
+    changed_at: Instant,
+    is_pending: bool,
+}
+
+impl ModificationTracker {
+    /// Ids of the buffers whose content has gone unchanged for `delay` since
+    /// it last changed, each given once for a burst of changes. `revisions`
+    /// holds the current revision of each buffer by id, or `None` for closed
+    /// buffers.
+    fn settled(
+        &mut self,
+        revisions: impl Iterator<Item = Option<u64>>,
+        now: Instant,
+        delay: Duration,
+    ) -> Vec<usize> {
+        let mut settled = vec![];
+        for (buffer_id, revision) in revisions.enumerate() {
+            let Some(revision) = revision else {
+                self.buffers.remove(&buffer_id);
+                continue;
+            };
+
+            let modification = self.buffers.entry(buffer_id).or_insert(BufferModification {
+                revision,
+                changed_at: now,
+                is_pending: false,
+            });
+            if modification.revision != revision {
+                modification.revision = revision;
+                modification.changed_at = now;
+                modification.is_pending = true;
+            } else if modification.is_pending && now - modification.changed_at >= delay {
+                modification.is_pending = false;
+                settled.push(buffer_id);
+            }
+        }
+
+        settled
+    }
 }
 
 struct ScriptProcess<'lua> {
     thread: Thread<'lua>,
     cause: Option<HookTypeName>,
+    eval: Option<EvalContinuation<'lua>>,
+    file_grep: Option<FileGrep>,
+    format: Option<BufferFormat>,
+}
+
+#[derive(Clone)]
+struct EvalContinuation<'lua> {
+    continuation: Function<'lua>,
+    source: String,
 }
 
 struct ProcessAwaiting<'lua> {
@@ -41,6 +128,19 @@ pub enum SchedulerYield {
     Quit,
 }
 
+/// Outcome of running a single step of the schedule with `run_one_step`
+#[derive(Debug, PartialEq)]
+pub enum SchedulerStep {
+    /// No script was waiting to run
+    Idle,
+    /// A script's call was made and the script resumed. `is_script_done` is
+    /// set if that finished the script.
+    Ran {
+        is_script_done: bool,
+    },
+    Quit,
+}
+
 impl<'lua> ScriptScheduler<'lua> {
     pub fn new(
         lua: &'lua Lua,
@@ -61,6 +161,9 @@ impl<'lua> ScriptScheduler<'lua> {
             process: ScriptProcess {
                 thread: preload_thread,
                 cause: None,
+                eval: None,
+                file_grep: None,
+                format: None,
             },
             awaiting: RedCall::None,
         });
@@ -69,6 +172,9 @@ impl<'lua> ScriptScheduler<'lua> {
             process: ScriptProcess {
                 thread: init_thread,
                 cause: None,
+                eval: None,
+                file_grep: None,
+                format: None,
             },
             awaiting: RedCall::None,
         });
@@ -96,12 +202,23 @@ impl<'lua> ScriptScheduler<'lua> {
                 process: ScriptProcess {
                     thread: initial_buffer_thread,
                     cause: None,
+                    eval: None,
+                    file_grep: None,
+                    format: None,
                 },
                 awaiting: RedCall::None,
             });
         }
 
-        Ok(Self { lua, active })
+        Ok(Self {
+            lua,
+            active,
+            next_eval_id: 0,
+            tasks: TaskRunner::new(),
+            grep_cancellers: HashMap::new(),
+            modified_buffers: ModificationTracker::default(),
+            file_watcher: None,
+        })
     }
 
     pub fn spawn_all_hooks<'f>(
@@ -132,6 +249,9 @@ impl<'lua> ScriptScheduler<'lua> {
             process: ScriptProcess {
                 thread,
                 cause: Some(hook.clone().into()),
+                eval: None,
+                file_grep: None,
+                format: None,
             },
             awaiting: RedCall::RunHook { hook },
         });
@@ -139,6 +259,99 @@ impl<'lua> ScriptScheduler<'lua> {
         Ok(())
     }
 
+    /// Spawns the Lua function stored in the registry under `key`, called with `arg`
+    pub fn spawn_registered_function<A>(&mut self, key: RegistryKey, arg: A) -> Result<()>
+    where
+        A: IntoLua<'lua>,
+    {
+        let function: Function = self.lua.registry_value(&key).map_err(|e| {
+            Error::Unrecoverable(format!("Failed to find registered function: {}", e))
+        })?;
+        let thread = self
+            .lua
+            .create_thread(function)
+            .map_err(|e| Error::Unrecoverable(format!("Failed to spawn function thread: {}", e)))?;
+        let value = arg.into_lua(self.lua).map_err(|e| {
+            Error::Recoverable(format!("Failed to convert argument value into lua: {}", e))
+        })?;
+
+        self.active.push_back(ProcessAwaiting {
+            process: ScriptProcess {
+                thread,
+                cause: None,
+                eval: None,
+                file_grep: None,
+                format: None,
+            },
+            awaiting: RedCall::Value { value },
+        });
+
+        Ok(())
+    }
+
+    /// Runs the `BufferModified` hook for each buffer whose content has gone
+    /// unchanged for the `modified_delay_ms` option since it was last edited,
+    /// so a burst of edits runs the hook once.
+    fn spawn_modified_hooks(
+        &mut self,
+        editor_state: &EditorState,
+        hook_map: &HookMap,
+    ) -> Result<()> {
+        let settled = self.modified_buffers.settled(
+            editor_state
+                .buffers
+                .iter()
+                .map(|buffer| buffer.as_ref().map(|buffer| buffer.revision)),
+            Instant::now(),
+            Duration::from_millis(editor_state.options.modified_delay_ms),
+        );
+        for buffer_id in settled {
+            let compare = buffer_id.into_lua(self.lua).ok();
+            self.spawn_all_hooks(hook_map, HookType::BufferModified { buffer_id }, compare)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reloads buffers whose linked files changed on disk while the
+    /// `auto_reload` option is set, running the `BufferReloaded` hook for
+    /// reverted buffers and the `FileConflict` hook for those with changes.
+    fn spawn_file_change_hooks(
+        &mut self,
+        editor_state: &mut EditorState,
+        hook_map: &HookMap,
+    ) -> Result<()> {
+        if !editor_state.options.auto_reload {
+            self.file_watcher = None;
+            return Ok(());
+        }
+
+        let file_watcher = self.file_watcher.get_or_insert_with(FileWatcher::new);
+        file_watcher.set_paths(editor_state.linked_file_paths());
+
+        for path in file_watcher.changed() {
+            match editor_state.handle_file_change(&path) {
+                Ok(Some(hook)) => {
+                    let buffer_id = match hook {
+                        HookType::BufferReloaded { buffer_id }
+                        | HookType::FileConflict { buffer_id } => Some(buffer_id),
+                        _ => None,
+                    };
+                    let compare = buffer_id.and_then(|id| id.into_lua(self.lua).ok());
+                    self.spawn_all_hooks(hook_map, hook, compare)?;
+                }
+                Ok(None) => (),
+                Err(error) => self.spawn_all_hooks(
+                    hook_map,
+                    HookType::Error(error.message().to_string()),
+                    None,
+                )?,
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn spawn_script(&mut self, script: String) -> Result<()> {
         let thread = self
             .lua
@@ -154,6 +367,9 @@ impl<'lua> ScriptScheduler<'lua> {
             process: ScriptProcess {
                 thread,
                 cause: None,
+                eval: None,
+                file_grep: None,
+                format: None,
             },
             awaiting: RedCall::None,
         });
@@ -161,899 +377,2480 @@ impl<'lua> ScriptScheduler<'lua> {
         Ok(())
     }
 
+    /// Whether any scripts are waiting to run
+    pub fn has_pending(&self) -> bool {
+        !self.active.is_empty()
+    }
+
     const MAX_SCRIPT_CALLS: u16 = std::u16::MAX;
     pub fn run_schedule(
         &mut self,
         editor_state: &mut EditorState,
         hook_map: &mut HookMap<'lua>,
     ) -> Result<SchedulerYield> {
+        'completion_loop: for TaskCompletion { id, result } in self.tasks.completed() {
+            // Formatters awaited by a write are given their output rather
+            // than reported through hooks
+            for awaiting in self.active.iter_mut() {
+                if let Some(format) = &mut awaiting.process.format {
+                    if format.task_id() == Some(id) {
+                        format.finish(result);
+                        continue 'completion_loop;
+                    }
+                }
+            }
+
+            self.grep_cancellers.remove(&id);
+            let compare = id.into_lua(self.lua).ok();
+            self.spawn_all_hooks(hook_map, HookType::TaskComplete { id, result }, compare)?;
+        }
+        self.spawn_modified_hooks(editor_state, hook_map)?;
+        self.spawn_file_change_hooks(editor_state, hook_map)?;
+
         if self.active.len() == 0 {
             return Ok(SchedulerYield::Skip);
         }
 
         'script_loop: for _ in 0..Self::MAX_SCRIPT_CALLS {
             for _ in 0..(self.active.len().min(10)) {
-                let Some(ProcessAwaiting {
-                    process,
-                    awaiting: red_call,
-                }) = self.active.pop_front()
-                else {
-                    return Ok(SchedulerYield::Run);
-                };
-
-                let is_script_done = match red_call {
-                    RedCall::None => self.run_script(process, hook_map, Value::Nil),
-                    RedCall::Yield => self.yield_script(process, hook_map, Value::Nil),
-
-                    RedCall::EditorExit => return Ok(SchedulerYield::Quit),
-
-                    RedCall::PaneVSplit { index: pane_index } => {
-                        editor_state.vsplit(pane_index)?;
-                        self.run_script(process, hook_map, Value::Nil)
-                    }
-                    RedCall::PaneHSplit { index: pane_index } => {
-                        editor_state.hsplit(pane_index)?;
-                        self.run_script(process, hook_map, Value::Nil)
-                    }
-                    RedCall::ActivePaneIndex => {
-                        let active_index = editor_state.active_pane_index;
-                        self.run_script(process, hook_map, active_index)
-                    }
-                    RedCall::RootPaneIndex => {
-                        let root_index = editor_state.pane_tree.root_index();
-                        self.run_script(process, hook_map, root_index)
-                    }
-                    RedCall::PaneIsFirst { index } => {
-                        let node = editor_state
-                            .pane_tree
-                            .pane_node_by_index(index)
-                            .ok_or_else(|| {
-                                Error::Unrecoverable(format!(
-                                    "Could not find active pane node while making ActivePane call"
-                                ))
-                            })?;
-                        let is_first = node
-                            .parent_index
-                            .map(|i| editor_state.pane_tree.pane_node_by_index(i))
-                            .flatten()
-                            .map(|p| match &p.node_type {
-                                crate::pane::PaneNodeType::Leaf(_) => None,
-                                crate::pane::PaneNodeType::VSplit(split)
-                                | crate::pane::PaneNodeType::HSplit(split) => {
-                                    if split.first == index {
-                                        Some(true)
-                                    } else if split.second == index {
-                                        Some(false)
-                                    } else {
-                                        None
-                                    }
-                                }
-                            });
-
-                        self.run_script(process, hook_map, is_first)
-                    }
-                    RedCall::SetActivePane { index } => {
-                        if editor_state.pane_tree.tree.len() <= index {
-                            Err(Error::Script(format!(
-                                "Attempted to set active pane to index out of bounds: {}",
-                                index
-                            )))
-                        } else {
-                            editor_state.active_pane_index = index;
-                            self.run_script(process, hook_map, Value::Nil)
+                match self.run_one_step(editor_state, hook_map)? {
+                    SchedulerStep::Idle => return Ok(SchedulerYield::Run),
+                    SchedulerStep::Quit => return Ok(SchedulerYield::Quit),
+                    SchedulerStep::Ran { is_script_done } => {
+                        if is_script_done {
+                            break 'script_loop;
                         }
                     }
-                    RedCall::PaneIndexUpFrom { index } => {
-                        if editor_state.pane_tree.tree.len() <= index {
-                            Err(Error::Script(format!(
-                                "Attempted to get parent index from pane index out of bounds: {}",
-                                index
-                            )))
-                        } else {
-                            let up_index = editor_state
-                                .pane_tree
-                                .pane_node_by_index(index)
-                                .map(|node| node.parent_index);
+                }
+            }
+        }
 
-                            self.run_script(process, hook_map, up_index)
-                        }
-                    }
-                    RedCall::PaneIndexDownFrom { index, to_first } => {
-                        if editor_state.pane_tree.tree.len() <= index {
-                            Err(Error::Script(format!(
-                                "Attempted to get child index from pane index out of bounds: {}",
-                                index
-                            )))
-                        } else {
-                            let down_index = editor_state
-                                .pane_tree
-                                .pane_node_by_index(index)
-                                .map(|node| &node.node_type)
-                                .map(|node_type| match node_type {
-                                    PaneNodeType::Leaf(_) => None,
-                                    PaneNodeType::VSplit(split)
-                                    | crate::pane::PaneNodeType::HSplit(split) => {
-                                        if to_first {
-                                            Some(split.first)
-                                        } else {
-                                            Some(split.second)
-                                        }
-                                    }
-                                });
+        Ok(SchedulerYield::Run)
+    }
 
-                            self.run_script(process, hook_map, down_index)
-                        }
-                    }
-                    RedCall::PaneType { index } => {
-                        let node_type = editor_state
-                            .pane_tree
-                            .pane_node_by_index(index)
-                            .ok_or_else(|| {
-                                Error::Script(format!(
-                                    "Attempted to get pane type from pane index out of bounds: {}",
-                                    index
-                                ))
-                            })?
-                            .node_type
-                            .clone();
-
-                        self.run_script(process, hook_map, node_type)
-                    }
-                    RedCall::PaneSetSplitPercent { index, percent } => {
-                        let node = editor_state
-                            .pane_tree
-                            .pane_node_mut_by_index(index)
-                            .ok_or_else(|| {
-                                Error::Script(format!(
-                                    "Attempted to get pane from pane index out of bounds: {}",
-                                    index
-                                ))
-                            })?;
-
-                        let (first_changed_pane, second_changed_pane) = match &node.node_type {
-                            PaneNodeType::Leaf(_) => Err(Error::Script(format!(
-                                "Attempted to set split type for a leaf node at index: {}",
-                                index
-                            ))),
-                            PaneNodeType::VSplit(old_split) => {
-                                let panes_used = (old_split.first, old_split.second);
-                                node.node_type = PaneNodeType::VSplit(Split {
-                                    first: old_split.first,
-                                    second: old_split.second,
-                                    split_type: SplitType::Percent {
-                                        first_percent: percent,
-                                    },
-                                });
+    /// Makes the RedCall of the next script waiting to run and resumes the
+    /// script with its result. Unlike `run_schedule`, does not first start
+    /// hooks for finished tasks or changed buffers, so tests can observe the
+    /// editor's state after each call a script makes.
+    pub fn run_one_step(
+        &mut self,
+        editor_state: &mut EditorState,
+        hook_map: &mut HookMap<'lua>,
+    ) -> Result<SchedulerStep> {
+        let Some(ProcessAwaiting {
+            process,
+            awaiting: red_call,
+        }) = self.active.pop_front()
+        else {
+            return Ok(SchedulerStep::Idle);
+        };
 
-                                Ok(panes_used)
-                            }
-                            PaneNodeType::HSplit(old_split) => {
-                                let panes_used = (old_split.first, old_split.second);
-                                node.node_type = PaneNodeType::HSplit(Split {
-                                    first: old_split.first,
-                                    second: old_split.second,
-                                    split_type: SplitType::Percent {
-                                        first_percent: percent,
-                                    },
-                                });
+        let thread = process.thread.clone();
+        let cause = process.cause;
+        let eval = process.eval.clone();
 
-                                Ok(panes_used)
-                            }
-                        }?;
-
-                        editor_state
-                            .pane_tree
-                            .pane_node_mut_by_index(first_changed_pane)
-                            .ok_or_else(|| {
-                                Error::Recoverable(format!(
-                                    "Failed to find pane node while changing size for index: {}",
-                                    first_changed_pane
-                                ))
-                            })?
-                            .is_dirty = true;
-                        editor_state
-                            .pane_tree
-                            .pane_node_mut_by_index(second_changed_pane)
-                            .ok_or_else(|| {
-                                Error::Recoverable(format!(
-                                    "Failed to find pane node while changing size for index: {}",
-                                    second_changed_pane
-                                ))
-                            })?
-                            .is_dirty = true;
+        let call_result = match red_call {
+            RedCall::None => self.run_script(process, hook_map, Value::Nil),
+            RedCall::Yield => self.yield_script(process, hook_map, Value::Nil),
 
-                        self.run_script(process, hook_map, Value::Nil)
+            RedCall::EditorExit => return Ok(SchedulerStep::Quit),
+
+            RedCall::PaneCloseChild { index, first_child } => {
+                let closed_id = editor_state.close_pane_child(index, first_child)?;
+
+                self.execute_script(
+                    process,
+                    Some((
+                        HookType::PaneClosed { pane_id: closed_id },
+                        closed_id.into_lua(self.lua).ok(),
+                    )),
+                    hook_map,
+                    Value::Nil,
+                    false,
+                )
+            }
+            RedCall::GrepFiles {
+                root_path,
+                pattern,
+                glob,
+            } => {
+                let mut process = process;
+                let mut file_grep = match process.file_grep.take() {
+                    Some(file_grep) => file_grep,
+                    None => {
+                        FileGrep::new(&root_path, &pattern, glob.clone()).map_err(Error::Script)?
                     }
-                    RedCall::PaneSetSplitFixed {
-                        index,
-                        size,
-                        to_first,
-                    } => {
-                        let node = editor_state
-                            .pane_tree
-                            .pane_node_mut_by_index(index)
-                            .ok_or_else(|| {
-                                Error::Script(format!(
-                                    "Attempted to get pane from pane index out of bounds: {}",
-                                    index
-                                ))
-                            })?;
-
-                        match &node.node_type {
-                            PaneNodeType::Leaf(_) => Err(Error::Script(format!(
-                                "Attempted to set split type for a leaf node at index: {}",
-                                index
-                            ))),
-                            PaneNodeType::VSplit(old_split) => {
-                                node.node_type = PaneNodeType::VSplit(Split {
-                                    first: old_split.first,
-                                    second: old_split.second,
-                                    split_type: if to_first {
-                                        SplitType::FirstFixed { size }
-                                    } else {
-                                        SplitType::SecondFixed { size }
-                                    },
-                                });
+                };
 
-                                Ok(())
-                            }
-                            PaneNodeType::HSplit(old_split) => {
-                                node.node_type = PaneNodeType::HSplit(Split {
-                                    first: old_split.first,
-                                    second: old_split.second,
-                                    split_type: if to_first {
-                                        SplitType::FirstFixed { size }
-                                    } else {
-                                        SplitType::SecondFixed { size }
-                                    },
-                                });
+                if file_grep.step() {
+                    self.run_script(process, hook_map, file_grep.into_matches())
+                } else {
+                    // Continue the search on a later tick so the editor stays responsive
+                    process.file_grep = Some(file_grep);
+                    self.active.push_back(ProcessAwaiting {
+                        process,
+                        awaiting: RedCall::GrepFiles {
+                            root_path,
+                            pattern,
+                            glob,
+                        },
+                    });
 
-                                Ok(())
-                            }
-                        }?;
+                    Ok(true)
+                }
+            }
+            RedCall::BufferWriteToFile { buffer_id } => {
+                let mut process = process;
+                let format = match process.format.take() {
+                    Some(format) => Ok(Some(format)),
+                    None => editor_state.start_write(buffer_id, &mut self.tasks),
+                };
 
-                        self.run_script(process, hook_map, Value::Nil)
-                    }
-                    RedCall::PaneBufferIndex { index } => {
-                        let Some(pane) = editor_state.pane_tree.pane_by_index(index) else {
-                            return Err(Error::Script(format!(
-                                "Attempted to retrieve buffer of pane at invalid index: {}",
-                                index
-                            )));
-                        };
-
-                        self.run_script(process, hook_map, pane.buffer_id)
-                    }
-                    RedCall::PaneCloseChild { index, first_child } => {
-                        let (new_active_pane_index, closed_id) = editor_state
-                            .pane_tree
-                            .close_child(index, first_child, editor_state.active_pane_index)
-                            .map_err(|e| {
-                                Error::Script(format!("Failed to close pane child: {}", e))
-                            })?
-                            .ok_or_else(|| {
-                                Error::Script(format!(
-                                    "No such pane found while closing child: {}",
-                                    index
-                                ))
-                            })?;
-
-                        editor_state.active_pane_index = new_active_pane_index;
-
-                        self.execute_script(
+                match format {
+                    Ok(Some(format)) if !format.is_finished() => {
+                        // Wait for the formatter on a later tick so the editor stays responsive
+                        process.format = Some(format);
+                        self.active.push_back(ProcessAwaiting {
                             process,
-                            Some((
-                                HookType::PaneClosed { pane_id: closed_id },
-                                closed_id.into_lua(self.lua).ok(),
-                            )),
-                            hook_map,
-                            Value::Nil,
-                            false,
-                        )
-                    }
-                    RedCall::PaneSetBuffer {
-                        pane_index,
-                        buffer_index,
-                    } => {
-                        let pane = editor_state
-                            .pane_tree
-                            .pane_node_mut_by_index(pane_index)
-                            .ok_or_else(|| {
-                                Error::Script(format!(
-                                    "Attempted to set buffer {} for invalid pane: {}",
-                                    buffer_index, pane_index
-                                ))
-                            })?;
-                        match pane.node_type {
-                            PaneNodeType::Leaf(ref mut pane) => {
-                                pane.buffer_id = buffer_index;
-
-                                self.spawn_all_hooks(
-                                    hook_map,
-                                    HookType::PaneBufferChanged(PaneBufferChange {
-                                        pane_id: pane_index,
-                                        buffer_id: buffer_index,
-                                    }),
-                                    None,
-                                )?;
-
-                                self.run_script(process, hook_map, Value::Nil)
-                            }
-                            PaneNodeType::VSplit(_) | PaneNodeType::HSplit(_) => {
-                                Err(Error::Script(format!(
-                                    "Attempted to set buffer {} for split pane at index {}",
-                                    buffer_index, pane_index
-                                )))
-                            }
-                        }
-                    }
-                    RedCall::PaneWrap { pane_index } => {
-                        let pane = editor_state
-                            .pane_tree
-                            .pane_node_by_index(pane_index)
-                            .ok_or_else(|| {
-                                Error::Script(format!(
-                                    "Attempted to get pane wrap flag for invalid pane index"
-                                ))
-                            })?;
-                        match &pane.node_type {
-                            PaneNodeType::Leaf(leaf) => {
-                                self.run_script(process, hook_map, leaf.should_wrap)
-                            }
-                            PaneNodeType::VSplit(_) | PaneNodeType::HSplit(_) => {
-                                self.run_script(process, hook_map, Value::Nil)
-                            }
-                        }
-                    }
-                    RedCall::PaneSetWrap {
-                        pane_index,
-                        should_wrap,
-                    } => {
-                        let pane = editor_state
-                            .pane_tree
-                            .pane_node_mut_by_index(pane_index)
-                            .ok_or_else(|| {
-                                Error::Script(format!(
-                                    "Attempted to set pane wrap flag for invalid pane index"
-                                ))
-                            })?;
-                        match &mut pane.node_type {
-                            PaneNodeType::Leaf(leaf) => leaf.should_wrap = should_wrap,
-                            PaneNodeType::VSplit(_) | PaneNodeType::HSplit(_) => (),
-                        }
+                            awaiting: RedCall::BufferWriteToFile { buffer_id },
+                        });
 
-                        self.run_script(process, hook_map, Value::Nil)
-                    }
-                    RedCall::PaneTopLine { pane_index } => {
-                        let pane = editor_state
-                            .pane_tree
-                            .pane_node_by_index(pane_index)
-                            .ok_or_else(|| {
-                                Error::Script(format!(
-                                    "Attempted to get pane top line for invalid pane index"
-                                ))
-                            })?;
-                        let top_line = match &pane.node_type {
-                            PaneNodeType::Leaf(leaf) => Some(leaf.top_line),
-                            PaneNodeType::VSplit(_) | PaneNodeType::HSplit(_) => None,
-                        };
-
-                        self.run_script(process, hook_map, top_line)
+                        Ok(true)
                     }
-                    RedCall::PaneSetTopLine { pane_index, line } => {
-                        let pane = editor_state
-                            .pane_tree
-                            .pane_node_mut_by_index(pane_index)
-                            .ok_or_else(|| {
-                                Error::Script(format!(
-                                    "Attempted to get pane top line for invalid pane index."
-                                ))
-                            })?;
-                        match &mut pane.node_type {
-                            PaneNodeType::Leaf(leaf) => leaf.top_line = line,
-                            PaneNodeType::VSplit(_) | PaneNodeType::HSplit(_) => (),
-                        }
+                    Ok(format) => editor_state
+                        .write_buffer(buffer_id, format)
+                        .and_then(|formatter_failure| {
+                            self.run_script(process, hook_map, formatter_failure)
+                        }),
+                    Err(error) => Err(error),
+                }
+            }
+            RedCall::RunHook { hook } => match hook {
+                HookType::KeyEvent(event) => self.run_script(process, hook_map, event),
+                HookType::Error(error_description) => {
+                    self.run_script(process, hook_map, error_description)
+                }
+                HookType::SecondaryError(error_description) => {
+                    self.run_script(process, hook_map, error_description)
+                }
+                HookType::PaneClosed { pane_id } => self.run_script(process, hook_map, pane_id),
+                HookType::BufferModified { buffer_id }
+                | HookType::BufferReloaded { buffer_id }
+                | HookType::FileConflict { buffer_id } => {
+                    self.run_script(process, hook_map, buffer_id)
+                }
+                HookType::ModeChanged { from, to } => {
+                    let change = self
+                        .lua
+                        .create_table()
+                        .and_then(|change| {
+                            change.set("from", from)?;
+                            change.set("to", to)?;
+                            Ok(change)
+                        })
+                        .map_err(|e| {
+                            Error::Recoverable(format!(
+                                "Failed to convert mode change into lua: {}",
+                                e
+                            ))
+                        })?;
 
-                        self.run_script(process, hook_map, Value::Nil)
-                    }
-                    RedCall::PaneFrame { pane_index } => {
-                        let window_size = terminal::window_size().map_err(|e| {
-                            Error::Recoverable(format!("Could not retrieve window size: {}", e))
+                    self.run_script(process, hook_map, change)
+                }
+                HookType::PaneBufferChanged(pane_buffer_change) => {
+                    self.run_script(process, hook_map, pane_buffer_change)
+                }
+                HookType::BufferFileLinked(buffer_file_link) => {
+                    self.run_script(process, hook_map, buffer_file_link)
+                }
+                HookType::TaskComplete { id, result } => {
+                    let completion = self
+                        .lua
+                        .create_table()
+                        .and_then(|completion| {
+                            completion.set("id", id)?;
+                            completion.set("result", result)?;
+                            Ok(completion)
+                        })
+                        .map_err(|e| {
+                            Error::Recoverable(format!(
+                                "Failed to convert task completion into lua: {}",
+                                e
+                            ))
                         })?;
 
-                        let pane_frame = editor_state
-                            .pane_tree
-                            .pane_size(pane_index, window_size.rows, window_size.columns)
-                            .map_err(|e| {
-                                Error::Script(format!(
-                                    "Attempted to get size of pane for invalid pane index. {}",
-                                    e
-                                ))
-                            })?;
-                        self.run_script(process, hook_map, pane_frame)
-                    }
+                    self.run_script(process, hook_map, completion)
+                }
+            },
 
-                    RedCall::BufferInsert { buffer_id, content } => {
-                        let Some(buffer) = editor_state.mut_buffer_by_id(buffer_id) else {
-                            return Err(Error::Script(format!(
-                                "Attempted to insert text into a buffer with invalid id: {}",
-                                buffer_id
-                            )));
-                        };
-                        buffer.insert_at_cursor(&content);
+            RedCall::RunScript { script } => {
+                fn spawn_thread<'lua>(
+                    lua: &'lua Lua,
+                    script: String,
+                ) -> mlua::Result<mlua::Thread<'lua>> {
+                    let function = lua.load(script).into_function()?;
+                    lua.create_thread(function)
+                }
 
-                        self.run_script(process, hook_map, RedCall::None)
+                let script_thread = spawn_thread(&self.lua, script).map_err(|e| {
+                    Error::Script(format!("Failed to create Lua thread for RunScript: {}", e))
+                });
+
+                match script_thread {
+                    Ok(script_thread) => {
+                        self.active.push_back(ProcessAwaiting {
+                            process: ScriptProcess {
+                                thread: script_thread,
+                                cause: None,
+                                eval: None,
+                                file_grep: None,
+                                format: None,
+                            },
+                            awaiting: RedCall::None,
+                        });
+                        self.run_script(process, hook_map, Value::Nil)
                     }
-                    RedCall::CurrentBufferId => {
-                        let pane = editor_state
-                            .pane_tree
-                            .pane_by_index(editor_state.active_pane_index)
-                            .ok_or_else(|| {
-                                Error::Script(format!(
-                                    "Attempted to find active buffer id without active pane"
-                                ))
-                            })?;
-
-                        self.run_script(process, hook_map, pane.buffer_id)
+                    Err(error) => self
+                        .spawn_all_hooks(hook_map, HookType::Error(format!("{}", error)), None)
+                        .map(|_| true),
+                }
+            }
+            RedCall::EvalLua {
+                script,
+                continuation,
+            } => {
+                let eval_thread = self
+                    .lua
+                    .load(script.as_str())
+                    .into_function()
+                    .and_then(|function| self.lua.create_thread(function));
+
+                match eval_thread {
+                    Ok(eval_thread) => {
+                        let eval_id = self.next_eval_id;
+                        self.next_eval_id += 1;
+
+                        self.active.push_back(ProcessAwaiting {
+                            process: ScriptProcess {
+                                thread: eval_thread,
+                                cause: None,
+                                eval: Some(EvalContinuation {
+                                    continuation,
+                                    source: script,
+                                }),
+                                file_grep: None,
+                                format: None,
+                            },
+                            awaiting: RedCall::None,
+                        });
+                        self.run_script(process, hook_map, eval_id)
                     }
-                    RedCall::SetHook {
-                        hook_name,
-                        function,
-                        compare,
-                    } => {
-                        hook_map.add_hook(hook_name, function, compare);
-
+                    Err(error) => {
+                        self.spawn_all_hooks(
+                            hook_map,
+                            HookType::Error(format!(
+                                "Failed to load Lua for EvalLua: {}\nIn evaluated Lua: {}",
+                                error, script
+                            )),
+                            None,
+                        )?;
                         self.run_script(process, hook_map, Value::Nil)
                     }
-                    RedCall::RunHook { hook } => match hook {
-                        HookType::KeyEvent(event) => self.run_script(process, hook_map, event),
-                        HookType::Error(error_description) => {
-                            self.run_script(process, hook_map, error_description)
-                        }
-                        HookType::SecondaryError(error_description) => {
-                            self.run_script(process, hook_map, error_description)
-                        }
-                        HookType::PaneClosed { pane_id } => {
-                            self.run_script(process, hook_map, pane_id)
-                        }
-                        HookType::PaneBufferChanged(pane_buffer_change) => {
-                            self.run_script(process, hook_map, pane_buffer_change)
-                        }
-                        HookType::BufferFileLinked(buffer_file_link) => {
-                            self.run_script(process, hook_map, buffer_file_link)
-                        }
-                    },
+                }
+            }
+            RedCall::Value { value } => self.run_script(process, hook_map, value),
+            red_call => self
+                .call_value(editor_state, hook_map, red_call)
+                .and_then(|value| self.run_script(process, hook_map, value)),
+        };
 
-                    RedCall::RunScript { script } => {
-                        fn spawn_thread<'lua>(
-                            lua: &'lua Lua,
-                            script: String,
-                        ) -> mlua::Result<mlua::Thread<'lua>> {
-                            let function = lua.load(script).into_function()?;
-                            lua.create_thread(function)
-                        }
+        let is_script_done = match call_result {
+            // Failed calls are raised in the calling script so that it may catch them
+            Err(error @ (Error::Script(_) | Error::Recoverable(_)))
+                if thread.status() == mlua::ThreadStatus::Resumable =>
+            {
+                let process = ScriptProcess {
+                    thread,
+                    cause,
+                    eval,
+                    file_grep: None,
+                    format: None,
+                };
+                self.run_script(process, hook_map, error)?
+            }
+            call_result => call_result?,
+        };
 
-                        let script_thread = spawn_thread(&self.lua, script).map_err(|e| {
-                            Error::Script(format!(
-                                "Failed to create Lua thread for RunScript: {}",
-                                e
-                            ))
-                        });
+        Ok(SchedulerStep::Ran { is_script_done })
+    }
 
-                        match script_thread {
-                            Ok(script_thread) => {
-                                self.active.push_back(ProcessAwaiting {
-                                    process: ScriptProcess {
-                                        thread: script_thread,
-                                        cause: None,
-                                    },
-                                    awaiting: RedCall::None,
-                                });
-                                self.run_script(process, hook_map, Value::Nil)
+    /// Makes a RedCall which only acts on the editor's state, returning the
+    /// value its script is resumed with. Calls which control the calling
+    /// script, such as yielding or running hooks, are made by `run_schedule`.
+    fn call_value(
+        &mut self,
+        editor_state: &mut EditorState,
+        hook_map: &mut HookMap<'lua>,
+        red_call: RedCall<'lua>,
+    ) -> Result<Value<'lua>> {
+        match red_call {
+            RedCall::PaneVSplit { index: pane_index } => {
+                editor_state.vsplit(pane_index)?;
+                self.lua_value(Value::Nil)
+            }
+            RedCall::PaneHSplit { index: pane_index } => {
+                editor_state.hsplit(pane_index)?;
+                self.lua_value(Value::Nil)
+            }
+            RedCall::PaneVSplitNewBuffer { index: pane_index } => {
+                let new_pane = editor_state.vsplit_new_buffer(pane_index)?;
+                self.lua_value(new_pane)
+            }
+            RedCall::PaneHSplitNewBuffer { index: pane_index } => {
+                let new_pane = editor_state.hsplit_new_buffer(pane_index)?;
+                self.lua_value(new_pane)
+            }
+            RedCall::ActivePaneIndex => {
+                let active_index = editor_state.active_pane_index;
+                self.lua_value(active_index)
+            }
+            RedCall::RootPaneIndex => {
+                let root_index = editor_state.pane_tree.root_index();
+                self.lua_value(root_index)
+            }
+            RedCall::PaneIsFirst { index } => {
+                let node = editor_state
+                    .pane_tree
+                    .pane_node_by_index(index)
+                    .ok_or_else(|| {
+                        Error::Unrecoverable(format!(
+                            "Could not find active pane node while making ActivePane call"
+                        ))
+                    })?;
+                let is_first = node
+                    .parent_index
+                    .map(|i| editor_state.pane_tree.pane_node_by_index(i))
+                    .flatten()
+                    .map(|p| match &p.node_type {
+                        crate::pane::PaneNodeType::Leaf(_) => None,
+                        crate::pane::PaneNodeType::VSplit(split)
+                        | crate::pane::PaneNodeType::HSplit(split) => {
+                            if split.first == index {
+                                Some(true)
+                            } else if split.second == index {
+                                Some(false)
+                            } else {
+                                None
                             }
-                            Err(error) => self
-                                .spawn_all_hooks(
-                                    hook_map,
-                                    HookType::Error(format!("{}", error)),
-                                    None,
-                                )
-                                .map(|_| true),
                         }
-                    }
-                    RedCall::BufferDelete {
-                        buffer_id,
-                        char_count,
-                    } => {
-                        let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
-                            Error::Script(format!(
-                                "Attempted to delete characters from non-existent buffer: {}",
-                                buffer_id
-                            ))
-                        })?;
-
-                        let deleted_string = buffer.delete_at_cursor(char_count);
-
-                        self.run_script(process, hook_map, deleted_string)
-                    }
-                    RedCall::BufferCursorMovedByChar {
-                        buffer_id,
-                        char_count,
-                    } => {
-                        let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
-                            Error::Script(format!(
-                                "Attempted BufferCursorMovedByChar for non-existent buffer: {}",
-                                buffer_id
-                            ))
-                        })?;
+                    });
 
-                        let moved_cursor = buffer.cursor_moved_by_char(char_count);
+                self.lua_value(is_first)
+            }
+            RedCall::SetActivePane { index } => {
+                if editor_state.pane_tree.tree.len() <= index {
+                    Err(Error::Script(format!(
+                        "Attempted to set active pane to index out of bounds: {}",
+                        index
+                    )))
+                } else {
+                    editor_state.set_active_pane(index);
+                    self.lua_value(Value::Nil)
+                }
+            }
+            RedCall::PaneIndexUpFrom { index } => {
+                if editor_state.pane_tree.tree.len() <= index {
+                    Err(Error::Script(format!(
+                        "Attempted to get parent index from pane index out of bounds: {}",
+                        index
+                    )))
+                } else {
+                    let up_index = editor_state
+                        .pane_tree
+                        .pane_node_by_index(index)
+                        .map(|node| node.parent_index);
+
+                    self.lua_value(up_index)
+                }
+            }
+            RedCall::PaneIndexDownFrom { index, to_first } => {
+                if editor_state.pane_tree.tree.len() <= index {
+                    Err(Error::Script(format!(
+                        "Attempted to get child index from pane index out of bounds: {}",
+                        index
+                    )))
+                } else {
+                    let down_index = editor_state
+                        .pane_tree
+                        .pane_node_by_index(index)
+                        .map(|node| &node.node_type)
+                        .map(|node_type| match node_type {
+                            PaneNodeType::Leaf(_) => None,
+                            PaneNodeType::VSplit(split)
+                            | crate::pane::PaneNodeType::HSplit(split) => {
+                                if to_first {
+                                    Some(split.first)
+                                } else {
+                                    Some(split.second)
+                                }
+                            }
+                        });
 
-                        self.run_script(process, hook_map, moved_cursor)
-                    }
-                    RedCall::BufferIndexMovedByChar {
-                        buffer_id,
-                        start_byte_index,
-                        char_count,
-                    } => {
-                        let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
-                            Error::Script(format!(
-                                "Attempted BufferIndexMovedByChar for non-existent buffer: {}",
-                                buffer_id
-                            ))
-                        })?;
+                    self.lua_value(down_index)
+                }
+            }
+            RedCall::PaneType { index } => {
+                let node_type = editor_state
+                    .pane_tree
+                    .pane_node_by_index(index)
+                    .ok_or_else(|| {
+                        Error::Script(format!(
+                            "Attempted to get pane type from pane index out of bounds: {}",
+                            index
+                        ))
+                    })?
+                    .node_type
+                    .clone();
 
-                        let moved_index = buffer.index_moved_by_char(start_byte_index, char_count);
+                self.lua_value(node_type)
+            }
+            RedCall::PaneSetSplitPercent { index, percent } => {
+                let node = editor_state
+                    .pane_tree
+                    .pane_node_mut_by_index(index)
+                    .ok_or_else(|| {
+                        Error::Script(format!(
+                            "Attempted to get pane from pane index out of bounds: {}",
+                            index
+                        ))
+                    })?;
+
+                let (first_changed_pane, second_changed_pane) = match &node.node_type {
+                    PaneNodeType::Leaf(_) => Err(Error::Script(format!(
+                        "Attempted to set split type for a leaf node at index: {}",
+                        index
+                    ))),
+                    PaneNodeType::VSplit(old_split) => {
+                        let panes_used = (old_split.first, old_split.second);
+                        node.node_type = PaneNodeType::VSplit(Split {
+                            first: old_split.first,
+                            second: old_split.second,
+                            split_type: SplitType::Percent {
+                                first_percent: percent,
+                            },
+                        });
 
-                        self.run_script(process, hook_map, moved_index)
+                        Ok(panes_used)
                     }
-                    RedCall::BufferLength { buffer_id } => {
-                        let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
-                            Error::Script(format!(
-                                "Attempted BufferLength for non-existent buffer: {}",
-                                buffer_id
-                            ))
-                        })?;
+                    PaneNodeType::HSplit(old_split) => {
+                        let panes_used = (old_split.first, old_split.second);
+                        node.node_type = PaneNodeType::HSplit(Split {
+                            first: old_split.first,
+                            second: old_split.second,
+                            split_type: SplitType::Percent {
+                                first_percent: percent,
+                            },
+                        });
 
-                        self.run_script(process, hook_map, buffer.content_byte_length())
+                        Ok(panes_used)
                     }
-                    RedCall::BufferLineLength {
-                        buffer_id,
-                        line_index,
-                    } => {
-                        let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
-                            Error::Script(format!(
-                                "Attempted BufferLineLength for non-existent buffer: {}",
-                                buffer_id
-                            ))
-                        })?;
+                }?;
 
-                        self.run_script(process, hook_map, buffer.content_line_length(line_index))
+                editor_state
+                    .pane_tree
+                    .pane_node_mut_by_index(first_changed_pane)
+                    .ok_or_else(|| {
+                        Error::Recoverable(format!(
+                            "Failed to find pane node while changing size for index: {}",
+                            first_changed_pane
+                        ))
+                    })?
+                    .is_dirty = true;
+                editor_state
+                    .pane_tree
+                    .pane_node_mut_by_index(second_changed_pane)
+                    .ok_or_else(|| {
+                        Error::Recoverable(format!(
+                            "Failed to find pane node while changing size for index: {}",
+                            second_changed_pane
+                        ))
+                    })?
+                    .is_dirty = true;
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::PaneSetSplitFixed {
+                index,
+                size,
+                to_first,
+            } => {
+                let node = editor_state
+                    .pane_tree
+                    .pane_node_mut_by_index(index)
+                    .ok_or_else(|| {
+                        Error::Script(format!(
+                            "Attempted to get pane from pane index out of bounds: {}",
+                            index
+                        ))
+                    })?;
+
+                match &node.node_type {
+                    PaneNodeType::Leaf(_) => Err(Error::Script(format!(
+                        "Attempted to set split type for a leaf node at index: {}",
+                        index
+                    ))),
+                    PaneNodeType::VSplit(old_split) => {
+                        node.node_type = PaneNodeType::VSplit(Split {
+                            first: old_split.first,
+                            second: old_split.second,
+                            split_type: if to_first {
+                                SplitType::FirstFixed { size }
+                            } else {
+                                SplitType::SecondFixed { size }
+                            },
+                        });
+
+                        Ok(())
                     }
-                    RedCall::BufferLineCount { buffer_id } => {
-                        let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
-                            Error::Script(format!(
-                                "Attempted BufferLineCount for non-existent buffer: {}",
-                                buffer_id
-                            ))
-                        })?;
+                    PaneNodeType::HSplit(old_split) => {
+                        node.node_type = PaneNodeType::HSplit(Split {
+                            first: old_split.first,
+                            second: old_split.second,
+                            split_type: if to_first {
+                                SplitType::FirstFixed { size }
+                            } else {
+                                SplitType::SecondFixed { size }
+                            },
+                        });
+
+                        Ok(())
+                    }
+                }?;
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::PaneBufferIndex { index } => {
+                let Some(pane) = editor_state.pane_tree.pane_by_index(index) else {
+                    return Err(Error::Script(format!(
+                        "Attempted to retrieve buffer of pane at invalid index: {}",
+                        index
+                    )));
+                };
+
+                self.lua_value(pane.buffer_id)
+            }
+            RedCall::PaneSetBuffer {
+                pane_index,
+                buffer_index,
+            } => {
+                let pane = editor_state
+                    .pane_tree
+                    .pane_node_mut_by_index(pane_index)
+                    .ok_or_else(|| {
+                        Error::Script(format!(
+                            "Attempted to set buffer {} for invalid pane: {}",
+                            buffer_index, pane_index
+                        ))
+                    })?;
+                match pane.node_type {
+                    PaneNodeType::Leaf(ref mut pane) => {
+                        pane.buffer_id = buffer_index;
+
+                        self.spawn_all_hooks(
+                            hook_map,
+                            HookType::PaneBufferChanged(PaneBufferChange {
+                                pane_id: pane_index,
+                                buffer_id: buffer_index,
+                            }),
+                            None,
+                        )?;
+
+                        self.lua_value(Value::Nil)
+                    }
+                    PaneNodeType::VSplit(_) | PaneNodeType::HSplit(_) => {
+                        Err(Error::Script(format!(
+                            "Attempted to set buffer {} for split pane at index {}",
+                            buffer_index, pane_index
+                        )))
+                    }
+                }
+            }
+            RedCall::PaneWrap { pane_index } => {
+                let pane = editor_state
+                    .pane_tree
+                    .pane_node_by_index(pane_index)
+                    .ok_or_else(|| {
+                        Error::Script(format!(
+                            "Attempted to get pane wrap flag for invalid pane index"
+                        ))
+                    })?;
+                match &pane.node_type {
+                    PaneNodeType::Leaf(leaf) => self.lua_value(leaf.wrap_mode != WrapMode::None),
+                    PaneNodeType::VSplit(_) | PaneNodeType::HSplit(_) => self.lua_value(Value::Nil),
+                }
+            }
+            RedCall::PaneSetWrap {
+                pane_index,
+                should_wrap,
+            } => {
+                let pane = editor_state
+                    .pane_tree
+                    .pane_node_mut_by_index(pane_index)
+                    .ok_or_else(|| {
+                        Error::Script(format!(
+                            "Attempted to set pane wrap flag for invalid pane index"
+                        ))
+                    })?;
+                match &mut pane.node_type {
+                    PaneNodeType::Leaf(leaf) => {
+                        leaf.wrap_mode = if should_wrap {
+                            WrapMode::Char
+                        } else {
+                            WrapMode::None
+                        }
+                    }
+                    PaneNodeType::VSplit(_) | PaneNodeType::HSplit(_) => (),
+                }
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::PaneWrapMode { pane_index } => {
+                let pane = editor_state.pane_tree.pane_by_index(pane_index).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted to get wrap mode of non-leaf pane: {}",
+                        pane_index
+                    ))
+                })?;
+
+                self.lua_value(pane.wrap_mode)
+            }
+            RedCall::PaneSetWrapMode {
+                pane_index,
+                wrap_mode,
+                indent,
+            } => {
+                let pane_node = editor_state
+                    .pane_tree
+                    .pane_node_mut_by_index(pane_index)
+                    .ok_or_else(|| {
+                        Error::Script(format!(
+                            "Attempted to set wrap mode of invalid pane: {}",
+                            pane_index
+                        ))
+                    })?;
+                let PaneNodeType::Leaf(pane) = &mut pane_node.node_type else {
+                    return Err(Error::Script(format!(
+                        "Attempted to set wrap mode of split pane: {}",
+                        pane_index
+                    )));
+                };
+                pane.wrap_mode = wrap_mode;
+                pane.wrap_indent = indent;
+                pane_node.is_dirty = true;
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::PaneTabSpaces { pane_index } => {
+                let pane = editor_state.pane_tree.pane_by_index(pane_index).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted to get tab spaces of non-leaf pane: {}",
+                        pane_index
+                    ))
+                })?;
+
+                self.lua_value(pane.tab_spaces)
+            }
+            RedCall::PaneSetTabSpaces { pane_index, spaces } => {
+                let pane_node = editor_state
+                    .pane_tree
+                    .pane_node_mut_by_index(pane_index)
+                    .ok_or_else(|| {
+                        Error::Script(format!(
+                            "Attempted to set tab spaces of invalid pane: {}",
+                            pane_index
+                        ))
+                    })?;
+                let PaneNodeType::Leaf(pane) = &mut pane_node.node_type else {
+                    return Err(Error::Script(format!(
+                        "Attempted to set tab spaces of split pane: {}",
+                        pane_index
+                    )));
+                };
+                pane.tab_spaces = spaces;
+                pane_node.is_dirty = true;
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::PaneTopLine { pane_index } => {
+                let pane = editor_state
+                    .pane_tree
+                    .pane_node_by_index(pane_index)
+                    .ok_or_else(|| {
+                        Error::Script(format!(
+                            "Attempted to get pane top line for invalid pane index"
+                        ))
+                    })?;
+                let top_line = match &pane.node_type {
+                    PaneNodeType::Leaf(leaf) => Some(leaf.top_line),
+                    PaneNodeType::VSplit(_) | PaneNodeType::HSplit(_) => None,
+                };
+
+                self.lua_value(top_line)
+            }
+            RedCall::PaneSetTopLine { pane_index, line } => {
+                let pane = editor_state
+                    .pane_tree
+                    .pane_node_mut_by_index(pane_index)
+                    .ok_or_else(|| {
+                        Error::Script(format!(
+                            "Attempted to get pane top line for invalid pane index."
+                        ))
+                    })?;
+                match &mut pane.node_type {
+                    PaneNodeType::Leaf(leaf) => leaf.top_line = line,
+                    PaneNodeType::VSplit(_) | PaneNodeType::HSplit(_) => (),
+                }
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::PaneFrame { pane_index } => {
+                let window_size = terminal::window_size().map_err(|e| {
+                    Error::Recoverable(format!("Could not retrieve window size: {}", e))
+                })?;
+
+                let pane_frame = editor_state
+                    .pane_tree
+                    .pane_size(pane_index, window_size.rows, window_size.columns)
+                    .map_err(|e| {
+                        Error::Script(format!(
+                            "Attempted to get size of pane for invalid pane index. {}",
+                            e
+                        ))
+                    })?;
+                self.lua_value(pane_frame)
+            }
+            RedCall::PaneCursorScreenPosition { pane_index } => {
+                let window_size = terminal::window_size().map_err(|e| {
+                    Error::Recoverable(format!("Could not retrieve window size: {}", e))
+                })?;
+
+                let pane_frame = editor_state
+                    .pane_tree
+                    .pane_size(pane_index, window_size.rows, window_size.columns)
+                    .map_err(|e| {
+                        Error::Script(format!(
+                            "Attempted to get cursor position of pane for invalid pane index. {}",
+                            e
+                        ))
+                    })?;
+                let pane = editor_state
+                    .pane_tree
+                    .pane_by_index(pane_index)
+                    .ok_or_else(|| {
+                        Error::Script(format!(
+                            "Attempted to get cursor position of non-leaf pane: {}",
+                            pane_index
+                        ))
+                    })?;
+                let buffer = editor_state.buffer_by_id(pane.buffer_id).ok_or_else(|| {
+                    Error::Unrecoverable(format!(
+                        "Found pane {} showing non-existent buffer: {}",
+                        pane_index, pane.buffer_id
+                    ))
+                })?;
+
+                let is_active = editor_state.active_pane_index == pane_index;
+                let position = display::cursor_screen_location(
+                    buffer,
+                    pane,
+                    &pane_frame,
+                    display::pane_cursor_byte_index(
+                        buffer,
+                        editor_state.active_tab_index,
+                        pane_index,
+                        is_active,
+                    ),
+                    editor_state.options.tab_width_for(buffer),
+                )
+                .map(|(row, col)| ScreenPosition { row, col });
+
+                self.lua_value(position)
+            }
+            RedCall::PaneScrollPage { pane_index, down } => {
+                let window_size = terminal::window_size().map_err(|e| {
+                    Error::Recoverable(format!("Could not retrieve window size: {}", e))
+                })?;
+
+                let pane_frame = editor_state
+                    .pane_tree
+                    .pane_size(pane_index, window_size.rows, window_size.columns)
+                    .map_err(|e| {
+                        Error::Script(format!(
+                            "Attempted to scroll pane for invalid pane index. {}",
+                            e
+                        ))
+                    })?;
+                let pane = editor_state
+                    .pane_tree
+                    .pane_by_index(pane_index)
+                    .ok_or_else(|| {
+                        Error::Script(format!("Attempted to scroll non-leaf pane: {}", pane_index))
+                    })?;
+                let buffer = editor_state.buffer_by_id(pane.buffer_id).ok_or_else(|| {
+                    Error::Unrecoverable(format!(
+                        "Found pane {} showing non-existent buffer: {}",
+                        pane_index, pane.buffer_id
+                    ))
+                })?;
+
+                let width = pane_frame.cols - display::sign_column_width(buffer, pane_frame.cols);
+                let top_line = pane.page_top_line(
+                    buffer,
+                    down,
+                    pane_frame.rows,
+                    width,
+                    editor_state.options.tab_width_for(buffer),
+                );
+                if let Some(PaneNodeType::Leaf(pane)) = editor_state
+                    .pane_tree
+                    .pane_node_mut_by_index(pane_index)
+                    .map(|pane_node| &mut pane_node.node_type)
+                {
+                    pane.top_line = top_line;
+                }
+
+                self.lua_value(top_line)
+            }
+            RedCall::PaneFolds { pane_index } => {
+                let pane = editor_state
+                    .pane_tree
+                    .pane_by_index(pane_index)
+                    .ok_or_else(|| {
+                        Error::Script(format!(
+                            "Attempted to get folds for invalid pane index: {}",
+                            pane_index
+                        ))
+                    })?;
+                let folds = pane.folds.clone();
+
+                self.lua_value(folds)
+            }
+            RedCall::PaneAddFold {
+                pane_index,
+                start_line,
+                end_line,
+            } => {
+                let pane_node = editor_state
+                    .pane_tree
+                    .pane_node_mut_by_index(pane_index)
+                    .ok_or_else(|| {
+                        Error::Script(format!(
+                            "Attempted to add fold to invalid pane index: {}",
+                            pane_index
+                        ))
+                    })?;
+                match &mut pane_node.node_type {
+                    PaneNodeType::Leaf(leaf) => leaf.add_fold(start_line, end_line),
+                    PaneNodeType::VSplit(_) | PaneNodeType::HSplit(_) => {
+                        return Err(Error::Script(format!(
+                            "Attempted to add fold to split pane at index: {}",
+                            pane_index
+                        )))
+                    }
+                }
+                pane_node.is_dirty = true;
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::PaneRemoveFold { pane_index, line } => {
+                let pane_node = editor_state
+                    .pane_tree
+                    .pane_node_mut_by_index(pane_index)
+                    .ok_or_else(|| {
+                        Error::Script(format!(
+                            "Attempted to remove fold from invalid pane index: {}",
+                            pane_index
+                        ))
+                    })?;
+                let removed = match &mut pane_node.node_type {
+                    PaneNodeType::Leaf(leaf) => leaf.remove_fold(line),
+                    PaneNodeType::VSplit(_) | PaneNodeType::HSplit(_) => {
+                        return Err(Error::Script(format!(
+                            "Attempted to remove fold from split pane at index: {}",
+                            pane_index
+                        )))
+                    }
+                };
+                pane_node.is_dirty = true;
+
+                self.lua_value(removed)
+            }
+            RedCall::PaneTreeDump => self.lua_value(editor_state.pane_tree.dump()),
+            RedCall::PaneCount => self.lua_value(editor_state.pane_tree.leaf_count()),
+            RedCall::TabNew { buffer_id } => self.lua_value(editor_state.new_tab(buffer_id)?),
+            RedCall::TabNext => {
+                let next_index = (editor_state.active_tab_index + 1) % editor_state.tab_count();
+                editor_state.switch_tab(next_index)?;
+
+                self.lua_value(next_index)
+            }
+            RedCall::TabSwitch { index } => {
+                editor_state.switch_tab(index)?;
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::TabClose => {
+                for pane_id in editor_state.close_tab()? {
+                    self.spawn_all_hooks(
+                        hook_map,
+                        HookType::PaneClosed { pane_id },
+                        pane_id.into_lua(self.lua).ok(),
+                    )?;
+                }
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::TabList => self.lua_value(editor_state.tab_list()),
+            RedCall::PaneSwapBuffers { pane_a, pane_b } => {
+                let (buffer_a, buffer_b) = editor_state
+                    .pane_tree
+                    .swap_buffers(pane_a, pane_b)
+                    .map_err(Error::Script)?;
+
+                for (pane_id, buffer_id) in [(pane_a, buffer_a), (pane_b, buffer_b)] {
+                    self.spawn_all_hooks(
+                        hook_map,
+                        HookType::PaneBufferChanged(PaneBufferChange { pane_id, buffer_id }),
+                        None,
+                    )?;
+                }
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::PaneEqualize { index } => {
+                editor_state
+                    .pane_tree
+                    .equalize(index)
+                    .map_err(Error::Script)?;
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::PaneToggleMaximize { index } => {
+                let is_maximized = editor_state
+                    .pane_tree
+                    .toggle_maximize(index)
+                    .map_err(Error::Script)?;
+
+                self.lua_value(is_maximized)
+            }
+
+            RedCall::BufferInsert { buffer_id, content } => {
+                let options = editor_state.options.clone();
+                let Some(buffer) = editor_state.mut_buffer_by_id(buffer_id) else {
+                    return Err(Error::Script(format!(
+                        "Attempted to insert text into a buffer with invalid id: {}",
+                        buffer_id
+                    )));
+                };
+
+                let content = if options.expand_tab_for(buffer) && content.contains('\t') {
+                    buffer.insert_expanded_at_cursors(&content, options.tab_width_for(buffer))
+                } else {
+                    buffer.insert_at_cursors(&content);
+                    content
+                };
+                editor_state.last_edit = Some(RepeatableEdit::Insert(content));
+
+                self.lua_value(RedCall::None)
+            }
+            RedCall::CurrentBufferId => {
+                let buffer_id = editor_state.active_buffer_id()?;
+                self.lua_value(buffer_id)
+            }
+            RedCall::ActiveBufferInsert { content } => {
+                let buffer_id = editor_state.active_buffer_id()?;
+                self.call_value(
+                    editor_state,
+                    hook_map,
+                    RedCall::BufferInsert { buffer_id, content },
+                )
+            }
+            RedCall::ActiveBufferDelete { char_count } => {
+                let buffer_id = editor_state.active_buffer_id()?;
+                self.call_value(
+                    editor_state,
+                    hook_map,
+                    RedCall::BufferDelete {
+                        buffer_id,
+                        char_count,
+                    },
+                )
+            }
+            RedCall::ShowPopup {
+                items,
+                selected,
+                row,
+                col,
+            } => {
+                // A popup already shown may have covered cells this one won't
+                if editor_state.popup.is_some() {
+                    editor_state.force_redraw();
+                }
+                editor_state.popup = Some(Popup {
+                    items,
+                    selected,
+                    row,
+                    col,
+                });
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::PromptOpen { prompt, on_submit } => {
+                let on_submit = self.lua.create_registry_value(on_submit).map_err(|e| {
+                    Error::Script(format!("Failed to store prompt submit function: {}", e))
+                })?;
+                editor_state.close_prompt();
+                editor_state.prompt = Some(Prompt::new(prompt, on_submit));
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::PromptClose => {
+                editor_state.close_prompt();
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::KeyMapList { keymap, prefix } => {
+                let bindings = keymap::list_bindings(keymap, &prefix.unwrap_or_default())
+                    .map_err(|e| Error::Script(format!("Failed to list keymap bindings: {}", e)))?;
+
+                self.lua_value(bindings)
+            }
+            RedCall::HidePopup => {
+                if editor_state.popup.take().is_some() {
+                    editor_state.force_redraw();
+                }
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::ForceRedraw => {
+                editor_state.force_redraw();
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::Bell { visual } => {
+                editor_state.ring_bell(visual);
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::TerminalCapabilities => self.lua_value(TerminalCapabilities::detect()),
+            RedCall::Mode => {
+                let mode = editor_state.mode.clone();
+
+                self.lua_value(mode)
+            }
+            RedCall::SetMode { mode } => {
+                if editor_state.mode != mode {
+                    let from = std::mem::replace(&mut editor_state.mode, mode.clone());
+                    let compare = self.lua.create_string(&mode).ok().map(Value::String);
+                    self.spawn_all_hooks(
+                        hook_map,
+                        HookType::ModeChanged { from, to: mode },
+                        compare,
+                    )?;
+                }
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::WindowTitle => {
+                let title = editor_state.window_title.clone();
+
+                self.lua_value(title)
+            }
+            RedCall::SetWindowTitle { title } => {
+                editor_state.window_title = title;
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::SessionSave { path } => {
+                editor_state.save_session(&path)?;
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::SessionLoad { path } => {
+                editor_state.load_session(&path)?;
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::GrepBuffers { pattern } => {
+                let regex = Regex::new(&pattern).map_err(|e| {
+                    Error::Script(format!("Failed to create Regex for grep: {}", e))
+                })?;
+
+                self.lua_value(editor_state.grep_buffers(&regex))
+            }
+            RedCall::SpawnGrepFiles {
+                root_path,
+                pattern,
+                glob,
+            } => {
+                let mut file_grep =
+                    FileGrep::new(&root_path, &pattern, glob).map_err(Error::Script)?;
+                let canceller = file_grep.canceller();
+                let task_id = self.tasks.spawn(move || {
+                    while !file_grep.step() {}
+                    TaskResult::FileMatches(file_grep.into_result())
+                });
+                self.grep_cancellers.insert(task_id, canceller);
+
+                self.lua_value(task_id)
+            }
+            RedCall::SpawnReadFile { path } => {
+                let task_id = self.tasks.spawn(move || {
+                    let content = shellexpand::full(&path)
+                        .map_err(|e| format!("Failed to expand file path due to: {}", e.var_name))
+                        .and_then(|path| {
+                            std::fs::read_to_string(path.as_ref())
+                                .map_err(|e| format!("Failed to read file {}: {}", path, e))
+                        });
+
+                    match content {
+                        Ok(content) => TaskResult::FileContent(content),
+                        Err(message) => TaskResult::Failed(message),
+                    }
+                });
+
+                self.lua_value(task_id)
+            }
+            RedCall::RunShell {
+                command,
+                args,
+                stdin,
+            } => {
+                let task_id = self
+                    .tasks
+                    .spawn(move || match shell::run(&command, &args, stdin) {
+                        Ok(output) => TaskResult::ShellOutput(output),
+                        Err(message) => TaskResult::Failed(message),
+                    });
+
+                self.lua_value(task_id)
+            }
+            RedCall::CancelGrepFiles { id } => {
+                let canceller = self.grep_cancellers.remove(&id);
+                if let Some(canceller) = &canceller {
+                    canceller.cancel();
+                }
+
+                self.lua_value(canceller.is_some())
+            }
+            RedCall::JumpListPush => {
+                let position = editor_state.active_jump_position().ok_or_else(|| {
+                    Error::Script("Attempted JumpListPush without an active buffer".to_string())
+                })?;
+                editor_state.jump_list.push(position);
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::JumpBack => {
+                let current = editor_state.active_jump_position().ok_or_else(|| {
+                    Error::Script("Attempted JumpBack without an active buffer".to_string())
+                })?;
+                let position = editor_state.jump_list.back(current);
+                self.jump_to(editor_state, hook_map, position)?;
+
+                self.lua_value(position)
+            }
+            RedCall::JumpForward => {
+                let position = editor_state.jump_list.forward();
+                self.jump_to(editor_state, hook_map, position)?;
+
+                self.lua_value(position)
+            }
+            RedCall::SetHook {
+                hook_name,
+                function,
+                compare,
+            } => {
+                hook_map.add_hook(hook_name, function, compare);
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::BufferDelete {
+                buffer_id,
+                char_count,
+            } => {
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted to delete characters from non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                let deleted_string = buffer.delete_at_cursors(char_count);
+                editor_state.last_edit = Some(RepeatableEdit::Delete(char_count));
+
+                self.lua_value(deleted_string)
+            }
+            RedCall::BufferCursorMovedByChar {
+                buffer_id,
+                char_count,
+            } => {
+                let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferCursorMovedByChar for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                let moved_cursor = buffer.cursor_moved_by_char(char_count);
+
+                self.lua_value(moved_cursor)
+            }
+            RedCall::BufferCursorMovedByCharInLine {
+                buffer_id,
+                char_count,
+            } => {
+                let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferCursorMovedByCharInLine for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                let moved_cursor =
+                    buffer.index_moved_by_char_in_line(buffer.cursor_byte_index(), char_count);
+
+                self.lua_value(moved_cursor)
+            }
+            RedCall::BufferIndexMovedByChar {
+                buffer_id,
+                start_byte_index,
+                char_count,
+            } => {
+                let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferIndexMovedByChar for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                let moved_index = buffer.index_moved_by_char(start_byte_index, char_count);
+
+                self.lua_value(moved_index)
+            }
+            RedCall::BufferLength { buffer_id } => {
+                let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferLength for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                self.lua_value(buffer.content_byte_length())
+            }
+            RedCall::BufferLineLength {
+                buffer_id,
+                line_index,
+            } => {
+                let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferLineLength for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                self.lua_value(buffer.content_line_length(line_index))
+            }
+            RedCall::BufferLineCount { buffer_id } => {
+                let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferLineCount for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                self.lua_value(buffer.content_line_count())
+            }
+            RedCall::BufferLineCountInRange {
+                buffer_id,
+                start_byte,
+                end_byte,
+            } => {
+                let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferLineCountInRange for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                self.lua_value(buffer.line_count_in_byte_range(start_byte..end_byte))
+            }
+            RedCall::BufferLineStart {
+                buffer_id,
+                line_index,
+            } => {
+                let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferLineStart for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                self.lua_value(buffer.line_start_byte_index(line_index))
+            }
+            RedCall::BufferLineEnd {
+                buffer_id,
+                line_index,
+            } => {
+                let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferLineEnd for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                self.lua_value(buffer.line_end_byte_index(line_index))
+            }
+            RedCall::BufferLineContaining {
+                buffer_id,
+                byte_index,
+            } => {
+                let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted to retrieve line index containing byte index for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                self.lua_value(buffer.line_index_for_byte_index(byte_index))
+            }
+            RedCall::BufferCursor { buffer_id } => {
+                let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferCursorIndex for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                self.lua_value(buffer.cursor_byte_index())
+            }
+            RedCall::BufferCursorLine { buffer_id } => {
+                let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferCursorLine for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                self.lua_value(buffer.cursor_line_index())
+            }
+            RedCall::BufferCursorLineContent { buffer_id } => {
+                let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferCursorLineContent for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                self.lua_value(buffer.content_copy_line(buffer.cursor_line_index()))
+            }
+            RedCall::BufferCursorVisualColumn {
+                buffer_id,
+                tab_width,
+            } => {
+                let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferCursorVisualColumn for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                let tab_width = tab_width.unwrap_or(editor_state.options.tab_width_for(buffer));
+
+                self.lua_value(buffer.visual_column_at(buffer.cursor_byte_index(), tab_width))
+            }
+            RedCall::BufferMoveCursorLineVisual {
+                buffer_id,
+                line_delta,
+                tab_width,
+            } => {
+                let options = editor_state.options.clone();
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferMoveCursorLineVisual for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                let tab_width = tab_width.unwrap_or(options.tab_width_for(buffer));
+
+                self.lua_value(buffer.move_cursor_line_visual(line_delta, tab_width))
+            }
+            RedCall::BufferSetCursor {
+                buffer_id,
+                cursor_index,
+                keep_col_index,
+            } => {
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferSetCursorIndex for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                buffer.set_cursor_byte_index(cursor_index, keep_col_index);
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::BufferSetCursorLine {
+                buffer_id,
+                line_index,
+            } => {
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferSeCursorLine for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                buffer.set_cursor_line_index(line_index);
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::BufferGotoLine {
+                buffer_id,
+                line_index,
+                column,
+            } => {
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferGotoLine for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                let byte_index = buffer.goto_line(line_index, column);
+
+                self.lua_value(byte_index)
+            }
+            RedCall::BufferLineFirstNonWhitespace {
+                buffer_id,
+                line_index,
+            } => {
+                let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferLineFirstNonWhitespace for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                let byte_index = buffer.first_non_whitespace_byte(line_index);
+
+                self.lua_value(byte_index)
+            }
+            RedCall::BufferParagraphMove { buffer_id, forward } => {
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferParagraphMove for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                let byte_index = buffer.paragraph_boundary(buffer.cursor_byte_index(), forward);
+                buffer.set_cursor_byte_index(byte_index, false);
+
+                self.lua_value(byte_index)
+            }
+            RedCall::BufferMatchIndent { buffer_id, forward } => {
+                let options = editor_state.options.clone();
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferMatchIndent for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                let tab_width = options.tab_width_for(buffer).into();
+                let byte_index = buffer
+                    .matching_indent_line(buffer.cursor_line_index(), forward, tab_width)
+                    .and_then(|line_index| buffer.first_non_whitespace_byte(line_index));
+                if let Some(byte_index) = byte_index {
+                    buffer.set_cursor_byte_index(byte_index, false);
+                }
+
+                self.lua_value(byte_index)
+            }
+            RedCall::BufferContent { buffer_id } => {
+                let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferContent for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                self.lua_value(buffer.content_copy())
+            }
+            RedCall::BufferOpen => {
+                let new_buffer_id = editor_state.create_buffer();
+                self.lua_value(new_buffer_id)
+            }
+            RedCall::BufferOpenTyped { buffer_type } => {
+                let new_buffer_id = editor_state.create_buffer_typed(buffer_type);
+                self.lua_value(new_buffer_id)
+            }
+            RedCall::BufferOpenPaged {
+                file_id,
+                window_size,
+            } => {
+                let new_buffer_id = editor_state.create_paged_buffer(
+                    file_id,
+                    window_size.unwrap_or(PagedBuffer::DEFAULT_WINDOW_SIZE),
+                )?;
+                self.lua_value(new_buffer_id)
+            }
+            RedCall::BufferClose { buffer_id } => {
+                editor_state.remove_buffer(buffer_id)?;
+                self.lua_value(Value::Nil)
+            }
+            RedCall::BufferLinkFile {
+                buffer_id,
+                file_id,
+                should_overwrite_buffer,
+            } => {
+                editor_state.link_buffer(buffer_id, file_id, should_overwrite_buffer)?;
+
+                self.spawn_all_hooks(
+                    hook_map,
+                    HookType::BufferFileLinked(BufferFileLink {
+                        link_type: BufferFileLinkType::Link,
+                        buffer_id,
+                        file_id,
+                    }),
+                    None,
+                )?;
+                self.lua_value(Value::Nil)
+            }
+            RedCall::BufferUnlinkFile {
+                buffer_id,
+                should_force,
+            } => {
+                let file_id = editor_state.unlink_buffer(buffer_id, should_force)?;
+
+                self.spawn_all_hooks(
+                    hook_map,
+                    HookType::BufferFileLinked(BufferFileLink {
+                        link_type: BufferFileLinkType::Unlink,
+                        buffer_id,
+                        file_id,
+                    }),
+                    None,
+                )?;
+                self.lua_value(file_id)
+            }
+            RedCall::SetFormatter { extension, command } => {
+                match command {
+                    Some(command) => editor_state.formatters.insert(extension, command),
+                    None => editor_state.formatters.remove(&extension),
+                };
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::SetFileTypeOptions { extension, options } => {
+                match options {
+                    Some(option_list) => {
+                        let mut options = BufferOptions::default();
+                        options.update(option_list);
+                        editor_state.file_type_options.insert(extension, options)
+                    }
+                    None => editor_state.file_type_options.remove(&extension),
+                };
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::BufferRevert { buffer_id } => {
+                editor_state.revert_buffer(buffer_id)?;
+                self.lua_value(Value::Nil)
+            }
+            RedCall::BufferCurrentFile { buffer_id } => {
+                let file_id = editor_state.buffer_file_map.get_by_left(&buffer_id).ok_or_else(||
+                    Error::Script(format!("Attempted to get current file id for buffer without linked file id: {}", buffer_id))
+                )?;
+
+                self.lua_value(*file_id)
+            }
+            RedCall::BufferDetectFileType { buffer_id } => {
+                let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferDetectFileType for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+                let first_line = buffer.content_copy_line(0).unwrap_or_default();
+                let extension = editor_state
+                    .buffer_file_map
+                    .get_by_left(&buffer_id)
+                    .and_then(|file_id| editor_state.files.get(*file_id))
+                    .and_then(|file| file.as_ref())
+                    .and_then(|file| file.extension());
+
+                self.lua_value(file_type::detect(extension.as_deref(), &first_line))
+            }
+            RedCall::BufferClearStyle { buffer_id } => {
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Failed to retrieve buffer for id: {} during BufferClearStyle.",
+                        buffer_id
+                    ))
+                })?;
+                buffer.styling.clear();
+                self.lua_value(Value::Nil)
+            }
+            RedCall::BufferStyles { buffer_id } => {
+                let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Failed to retrieve buffer for id: {} during BufferStyles.",
+                        buffer_id
+                    ))
+                })?;
+                self.lua_value(buffer.styling.clone())
+            }
+            RedCall::BufferPushStyle {
+                buffer_id,
+                name,
+                regex,
+            } => {
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Failed to retrieve buffer for id: {} during BufferPushStyle.",
+                        buffer_id
+                    ))
+                })?;
+                buffer.styling.push_style(name, regex).map_err(|e| {
+                    Error::Script(format!("Failed to create Regex for styling: {:?}", e))
+                })?;
+                self.lua_value(Value::Nil)
+            }
+            RedCall::BufferDiff {
+                buffer_id,
+                other_text,
+            } => {
+                let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferDiff for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                let diff = line_diff(&buffer.content_copy(), &other_text);
+
+                self.lua_value(diff)
+            }
+            RedCall::BufferSetNamedMark {
+                buffer_id,
+                name,
+                byte_index,
+            } => {
+                let mark_name = mark_name_char(&name)?;
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferSetNamedMark for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                let byte_index = byte_index.min(buffer.content_byte_length());
+                buffer.named_marks.insert(mark_name, byte_index);
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::BufferGotoNamedMark { buffer_id, name } => {
+                let mark_name = mark_name_char(&name)?;
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferGotoNamedMark for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                let mark = buffer.named_marks.get(&mark_name).copied();
+                if let Some(mark) = mark {
+                    buffer.set_cursor_byte_index(mark, false);
+                }
+
+                self.lua_value(mark)
+            }
+            RedCall::ExpandSnippet {
+                buffer_id,
+                trigger,
+                body,
+            } => {
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted ExpandSnippet for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                let did_expand = buffer.expand_snippet(&trigger, &body);
+
+                self.lua_value(did_expand)
+            }
+            RedCall::SnippetNextStop { buffer_id } => {
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted SnippetNextStop for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                let stop = buffer.next_snippet_stop();
+
+                self.lua_value(stop)
+            }
+            RedCall::BufferInsertPair {
+                buffer_id,
+                open,
+                close,
+            } => {
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferInsertPair for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                buffer.insert_pair(&open, &close);
+                editor_state.last_edit = Some(RepeatableEdit::InsertPair { open, close });
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::BufferInsertClose { buffer_id, close } => {
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferInsertClose for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                let did_step_over = buffer.insert_close(&close);
+
+                self.lua_value(did_step_over)
+            }
+            RedCall::BufferToggleComment {
+                buffer_id,
+                start_line,
+                end_line,
+                comment_prefix,
+            } => {
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferToggleComment for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                buffer.toggle_comment(start_line, end_line, &comment_prefix);
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::BufferSortLines {
+                buffer_id,
+                start_line,
+                end_line,
+                reverse,
+                numeric,
+            } => {
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferSortLines for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                buffer.sort_lines(start_line, end_line, reverse, numeric);
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::BufferUniqueLines {
+                buffer_id,
+                start_line,
+                end_line,
+            } => {
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferUniqueLines for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                let removed_count = buffer.unique_lines(start_line, end_line);
+
+                self.lua_value(removed_count)
+            }
+            RedCall::BufferReverseLines {
+                buffer_id,
+                start_line,
+                end_line,
+            } => {
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferReverseLines for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                buffer.reverse_lines(start_line, end_line);
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::BufferNormalizeNewlines { buffer_id, to } => {
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferNormalizeNewlines for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                self.lua_value(buffer.normalize_newlines(to))
+            }
+            RedCall::BufferReflow {
+                buffer_id,
+                line_index,
+                width,
+            } => {
+                let options = editor_state.options.clone();
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferReflow for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                let tab_width = options.tab_width_for(buffer).into();
+                self.lua_value(buffer.reflow(line_index, width, tab_width))
+            }
+            RedCall::BufferPasteLines {
+                buffer_id,
+                text,
+                below,
+            } => {
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferPasteLines for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                buffer.paste_lines(&text, below);
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::BufferDeleteToLineEnd { buffer_id } => {
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferDeleteToLineEnd for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                let deleted = buffer.delete_to_line_end();
+
+                self.lua_value(deleted)
+            }
+            RedCall::BufferDeleteWord { buffer_id, forward } => {
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferDeleteWord for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                let deleted = buffer.delete_word(forward);
+
+                self.lua_value(deleted)
+            }
+            RedCall::BufferApplyMotion {
+                buffer_id,
+                motion,
+                count,
+            } => {
+                let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferApplyMotion for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                self.lua_value(buffer.motion_range(motion, count.unwrap_or(1)))
+            }
+            RedCall::BufferTextObject {
+                buffer_id,
+                object,
+                inner,
+            } => {
+                let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferTextObject for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                self.lua_value(buffer.text_object_range(object, inner))
+            }
+            RedCall::BufferSetBlockSelection {
+                buffer_id,
+                start_line,
+                start_col,
+                end_line,
+                end_col,
+            } => {
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferSetBlockSelection for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                buffer.block_selection = Some(BlockSelection {
+                    start_line,
+                    start_col,
+                    end_line,
+                    end_col,
+                });
 
-                        self.run_script(process, hook_map, buffer.content_line_count())
-                    }
-                    RedCall::BufferLineStart {
-                        buffer_id,
-                        line_index,
-                    } => {
-                        let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
-                            Error::Script(format!(
-                                "Attempted BufferLineStart for non-existent buffer: {}",
-                                buffer_id
-                            ))
-                        })?;
+                self.lua_value(Value::Nil)
+            }
+            RedCall::BufferClearBlockSelection { buffer_id } => {
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferClearBlockSelection for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
 
-                        self.run_script(process, hook_map, buffer.line_start_byte_index(line_index))
-                    }
-                    RedCall::BufferLineEnd {
-                        buffer_id,
-                        line_index,
-                    } => {
-                        let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
-                            Error::Script(format!(
-                                "Attempted BufferLineEnd for non-existent buffer: {}",
-                                buffer_id
-                            ))
-                        })?;
+                buffer.block_selection = None;
 
-                        self.run_script(process, hook_map, buffer.line_end_byte_index(line_index))
-                    }
-                    RedCall::BufferLineContaining {
-                        buffer_id,
-                        byte_index,
-                    } => {
-                        let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
-                            Error::Script(format!(
-                                "Attempted to retrieve line index containing byte index for non-existent buffer: {}",
-                                buffer_id
-                            ))
-                        })?;
+                self.lua_value(Value::Nil)
+            }
+            RedCall::BufferBlockSelection { buffer_id } => {
+                let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferBlockSelection for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
 
-                        self.run_script(
-                            process,
-                            hook_map,
-                            buffer.line_index_for_byte_index(byte_index),
-                        )
-                    }
-                    RedCall::BufferCursor { buffer_id } => {
-                        let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
-                            Error::Script(format!(
-                                "Attempted BufferCursorIndex for non-existent buffer: {}",
-                                buffer_id
-                            ))
-                        })?;
+                let selection = buffer.block_selection;
 
-                        self.run_script(process, hook_map, buffer.cursor_byte_index())
-                    }
-                    RedCall::BufferCursorLine { buffer_id } => {
-                        let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
-                            Error::Script(format!(
-                                "Attempted BufferCursorLine for non-existent buffer: {}",
-                                buffer_id
-                            ))
-                        })?;
+                self.lua_value(selection)
+            }
+            RedCall::BufferBlockInsert {
+                buffer_id,
+                text,
+                pad,
+            } => {
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferBlockInsert for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
 
-                        self.run_script(process, hook_map, buffer.cursor_line_index())
-                    }
-                    RedCall::BufferSetCursor {
-                        buffer_id,
-                        cursor_index,
-                        keep_col_index,
-                    } => {
-                        let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
-                            Error::Script(format!(
-                                "Attempted BufferSetCursorIndex for non-existent buffer: {}",
-                                buffer_id
-                            ))
-                        })?;
+                buffer.block_insert(&text, pad);
 
-                        buffer.set_cursor_byte_index(cursor_index, keep_col_index);
+                self.lua_value(Value::Nil)
+            }
+            RedCall::BufferBlockDelete { buffer_id } => {
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferBlockDelete for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
 
-                        self.run_script(process, hook_map, Value::Nil)
-                    }
-                    RedCall::BufferSetCursorLine {
-                        buffer_id,
-                        line_index,
-                    } => {
-                        let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
-                            Error::Script(format!(
-                                "Attempted BufferSeCursorLine for non-existent buffer: {}",
-                                buffer_id
-                            ))
-                        })?;
+                buffer.block_delete();
 
-                        buffer.set_cursor_line_index(line_index);
+                self.lua_value(Value::Nil)
+            }
+            RedCall::BufferIncrementNumber { buffer_id, delta } => {
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferIncrementNumber for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
 
-                        self.run_script(process, hook_map, Value::Nil)
-                    }
-                    RedCall::BufferContent { buffer_id } => {
-                        let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
+                let new_value = buffer.increment_number(delta);
+                editor_state.last_edit = Some(RepeatableEdit::IncrementNumber(delta));
+
+                self.lua_value(new_value)
+            }
+            RedCall::RepeatLastEdit { buffer_id } => {
+                let last_edit = editor_state.last_edit.clone();
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted RepeatLastEdit for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                let did_repeat = if let Some(last_edit) = &last_edit {
+                    last_edit.apply(buffer);
+                    true
+                } else {
+                    false
+                };
+
+                self.lua_value(did_repeat)
+            }
+            RedCall::BufferSetLanguage {
+                buffer_id,
+                language,
+            } => {
+                let language = language
+                    .map(|language| {
+                        LanguageName::from_str(&language).map_err(|_| {
                             Error::Script(format!(
-                                "Attempted BufferContent for non-existent buffer: {}",
-                                buffer_id
+                                "Attempted to set unknown buffer language: {}",
+                                language
                             ))
-                        })?;
+                        })
+                    })
+                    .transpose()?
+                    .map(|language| match language {
+                        LanguageName::Lua => Language::Lua,
+                    });
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferSetLanguage for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
 
-                        self.run_script(process, hook_map, buffer.content_copy())
-                    }
-                    RedCall::BufferOpen => {
-                        let new_buffer_id = editor_state.create_buffer();
-                        self.run_script(process, hook_map, new_buffer_id)
-                    }
-                    RedCall::BufferClose { buffer_id } => {
-                        editor_state.remove_buffer(buffer_id)?;
-                        self.run_script(process, hook_map, Value::Nil)
-                    }
-                    RedCall::BufferLinkFile {
-                        buffer_id,
-                        file_id,
-                        should_overwrite_buffer,
-                    } => {
-                        editor_state.link_buffer(buffer_id, file_id, should_overwrite_buffer)?;
+                buffer.set_language(language);
 
-                        self.spawn_all_hooks(
-                            hook_map,
-                            HookType::BufferFileLinked(BufferFileLink {
-                                link_type: BufferFileLinkType::Link,
-                                buffer_id,
-                                file_id,
-                            }),
-                            None,
-                        )?;
-                        self.run_script(process, hook_map, Value::Nil)
-                    }
-                    RedCall::BufferUnlinkFile {
-                        buffer_id,
-                        should_force,
-                    } => {
-                        let file_id = editor_state.unlink_buffer(buffer_id, should_force)?;
+                self.lua_value(Value::Nil)
+            }
+            RedCall::BufferHighlights {
+                buffer_id,
+                start_line,
+                end_line,
+            } => {
+                let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferHighlights for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
 
-                        self.spawn_all_hooks(
-                            hook_map,
-                            HookType::BufferFileLinked(BufferFileLink {
-                                link_type: BufferFileLinkType::Unlink,
-                                buffer_id,
-                                file_id,
-                            }),
-                            None,
-                        )?;
-                        self.run_script(process, hook_map, file_id)
-                    }
-                    RedCall::BufferWriteToFile { buffer_id } => {
-                        editor_state.write_buffer(buffer_id)?;
-                        self.run_script(process, hook_map, Value::Nil)
-                    }
-                    RedCall::BufferCurrentFile { buffer_id } => {
-                        let file_id = editor_state.buffer_file_map.get_by_left(&buffer_id).ok_or_else(||
-                            Error::Script(format!("Attempted to get current file id for buffer without linked file id: {}", buffer_id))
-                        )?;
+                let highlights = buffer.highlights(start_line, end_line);
 
-                        self.run_script(process, hook_map, *file_id)
-                    }
-                    RedCall::BufferClearStyle { buffer_id } => {
-                        let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
-                            Error::Script(format!(
-                                "Failed to retrieve buffer for id: {} during BufferClearStyle.",
-                                buffer_id
-                            ))
-                        })?;
-                        buffer.styling.clear();
-                        self.run_script(process, hook_map, Value::Nil)
-                    }
-                    RedCall::BufferPushStyle {
-                        buffer_id,
-                        name,
-                        regex,
-                    } => {
-                        let buffer =
-                            editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
-                                Error::Script(format!(
-                                    "Failed to retrieve buffer for id: {} during BufferPushStyle.",
-                                    buffer_id
-                                ))
-                            })?;
-                        buffer.styling.push_style(name, regex)
-                            .map_err(|e| Error::Script(format!(
-                                "Failed to create Regex for styling: {:?}", e
-                            )))?;
-                        self.run_script(process, hook_map, Value::Nil)
-                    }
+                self.lua_value(highlights)
+            }
+            RedCall::BufferAddPhantom {
+                buffer_id,
+                byte_index,
+                text,
+                style,
+            } => {
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferAddPhantom for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                let byte_index = byte_index.min(buffer.content_byte_length());
+                buffer.phantoms.push(PhantomText {
+                    byte_index,
+                    text,
+                    style,
+                });
+                buffer.is_render_dirty = true;
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::BufferClearPhantoms { buffer_id } => {
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferClearPhantoms for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                buffer.phantoms.clear();
+                buffer.is_render_dirty = true;
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::BufferSetSign {
+                buffer_id,
+                line,
+                text,
+                style,
+            } => {
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferSetSign for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                buffer.signs.insert(line, Sign { text, style });
+                buffer.is_render_dirty = true;
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::BufferClearSigns { buffer_id } => {
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferClearSigns for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                buffer.signs.clear();
+                buffer.is_render_dirty = true;
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::BufferAddLineHighlight {
+                buffer_id,
+                line,
+                style,
+            } => {
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferAddLineHighlight for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                buffer.line_highlights.insert(line, style);
+                buffer.is_render_dirty = true;
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::BufferClearLineHighlights { buffer_id } => {
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferClearLineHighlights for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                buffer.line_highlights.clear();
+                buffer.is_render_dirty = true;
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::BufferSetSearch { buffer_id, pattern } => {
+                let regex = pattern
+                    .map(|pattern| {
+                        Regex::new(&pattern).map_err(|e| {
+                            Error::Script(format!("Failed to create Regex for search: {}", e))
+                        })
+                    })
+                    .transpose()?;
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferSetSearch for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                buffer.search = regex.map(|regex| SearchState::new(buffer.content.as_ref(), regex));
+                buffer.is_render_dirty = true;
+
+                self.lua_value(buffer.search.as_ref().map(|search| search.matches.len()))
+            }
+            RedCall::BufferAddCursor {
+                buffer_id,
+                byte_index,
+            } => {
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferAddCursor for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                self.lua_value(buffer.add_cursor(byte_index))
+            }
+            RedCall::BufferClearSecondaryCursors { buffer_id } => {
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferClearSecondaryCursors for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                buffer.clear_secondary_cursors();
+
+                self.lua_value(Value::Nil)
+            }
+            RedCall::BufferSecondaryCursors { buffer_id } => {
+                let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferSecondaryCursors for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                self.lua_value(buffer.secondary_cursors.clone())
+            }
+            RedCall::BufferGetOptions { buffer_id } => {
+                let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferGetOptions for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                self.lua_value(buffer.options.clone())
+            }
+            RedCall::BufferUpdateOptions {
+                buffer_id,
+                option_list,
+            } => {
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferUpdateOptions for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                buffer.options.update(option_list);
+                buffer.is_render_dirty = true;
 
-                    RedCall::SetTextStyle {
-                        name,
+                self.lua_value(Value::Nil)
+            }
+
+            RedCall::SetTextStyle {
+                name,
+                background,
+                foreground,
+            } => {
+                editor_state.style_map.insert(
+                    name,
+                    TextStyle {
                         background,
                         foreground,
-                    } => {
-                        editor_state.style_map.insert(
-                            name,
-                            TextStyle {
-                                background,
-                                foreground,
-                            },
-                        );
+                    },
+                );
 
-                        self.run_script(process, hook_map, Value::Nil)
-                    }
+                self.lua_value(Value::Nil)
+            }
 
-                    RedCall::FileOpen { path_string } => {
-                        let id = editor_state.open_file(path_string)?;
+            RedCall::FileOpen { path_string } => {
+                let id = editor_state.open_file(path_string)?;
 
-                        self.run_script(process, hook_map, id)
-                    }
-                    RedCall::FileClose {
-                        file_id,
-                        should_force_close,
-                    } => {
-                        editor_state.close_file(file_id, should_force_close)?;
+                self.lua_value(id)
+            }
+            RedCall::FileClose {
+                file_id,
+                should_force_close,
+            } => {
+                editor_state.close_file(file_id, should_force_close)?;
 
-                        self.run_script(process, hook_map, Value::Nil)
-                    }
-                    RedCall::FileCurrentBuffer { file_id } => {
-                        let buffer_id = editor_state
-                            .buffer_file_map
-                            .get_by_right(&file_id)
-                            .ok_or_else(|| {
-                                Error::Script(format!(
-                                    "Attempted to get current buffer id for file at id: {}",
-                                    file_id
-                                ))
-                            })?;
-
-                        self.run_script(process, hook_map, *buffer_id)
-                    }
-                    RedCall::FileExtension { file_id } => {
-                        let file = editor_state
-                            .files
-                            .get(file_id)
-                            .map(|f| f.as_ref())
-                            .flatten()
-                            .ok_or_else(|| {
-                                Error::Script(format!("Failed to get file for id: {}", file_id))
-                            })?;
-
-                        self.run_script(process, hook_map, file.extension())
-                    }
-                    RedCall::BufferContentAt {
-                        buffer_id,
-                        byte_index,
-                        char_count,
-                    } => {
-                        let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
-                            Error::Script(format!(
-                                "Attempted to get current buffer with invalid id: {}",
-                                buffer_id
-                            ))
-                        })?;
+                self.lua_value(Value::Nil)
+            }
+            RedCall::FileCurrentBuffer { file_id } => {
+                let buffer_id = editor_state
+                    .buffer_file_map
+                    .get_by_right(&file_id)
+                    .ok_or_else(|| {
+                        Error::Script(format!(
+                            "Attempted to get current buffer id for file at id: {}",
+                            file_id
+                        ))
+                    })?;
+
+                self.lua_value(*buffer_id)
+            }
+            RedCall::FileExtension { file_id } => {
+                let file = editor_state
+                    .files
+                    .get(file_id)
+                    .map(|f| f.as_ref())
+                    .flatten()
+                    .ok_or_else(|| {
+                        Error::Script(format!("Failed to get file for id: {}", file_id))
+                    })?;
+
+                self.lua_value(file.extension())
+            }
+            RedCall::BufferContentAt {
+                buffer_id,
+                byte_index,
+                char_count,
+            } => {
+                let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted to get current buffer with invalid id: {}",
+                        buffer_id
+                    ))
+                })?;
 
-                        let content = buffer.content_copy_at_byte_index(byte_index, char_count);
+                let content = buffer.content_copy_at_byte_index(byte_index, char_count);
 
-                        self.run_script(process, hook_map, content)
-                    }
-                    RedCall::BufferLineContent {
-                        buffer_id,
-                        line_index,
-                    } => {
-                        let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
-                            Error::Script(format!(
-                                "Attempted to get buffer line content with invalid id: {}",
-                                buffer_id
-                            ))
-                        })?;
+                self.lua_value(content)
+            }
+            RedCall::BufferLineContent {
+                buffer_id,
+                line_index,
+            } => {
+                let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted to get buffer line content with invalid id: {}",
+                        buffer_id
+                    ))
+                })?;
 
-                        let content = buffer.content_copy_line(line_index);
+                let content = buffer.content_copy_line(line_index);
 
-                        self.run_script(process, hook_map, content)
-                    }
-                    RedCall::BufferSetType {
-                        buffer_id,
-                        buffer_type,
-                    } => {
-                        let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
-                            Error::Script(format!(
-                                "Attempted to get buffer for type set with invalid id: {}",
-                                buffer_id
-                            ))
-                        })?;
+                self.lua_value(content)
+            }
+            RedCall::BufferLineRange {
+                buffer_id,
+                start_line,
+                end_line,
+            } => {
+                let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferLineRange for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
 
-                        buffer.set_type(buffer_type);
+                let end_line = end_line.min(buffer.content_line_count().saturating_sub(1));
+                let lines: Vec<String> = (start_line..=end_line)
+                    .filter_map(|line_index| buffer.content_copy_line(line_index))
+                    .collect();
 
-                        self.run_script(process, hook_map, Value::Nil)
-                    }
-                    RedCall::BufferType { buffer_id } => {
-                        let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
-                            Error::Script(format!(
-                                "Attempted to get buffer type with invalid id: {}",
-                                buffer_id
-                            ))
-                        })?;
+                self.lua_value(lines)
+            }
+            RedCall::BufferSetType {
+                buffer_id,
+                buffer_type,
+            } => {
+                let buffer = editor_state.mut_buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted to get buffer for type set with invalid id: {}",
+                        buffer_id
+                    ))
+                })?;
 
-                        self.run_script(process, hook_map, buffer.buffer_type)
-                    }
-                    RedCall::Value { value } => self.run_script(process, hook_map, value),
-                    RedCall::UpdateOptions { option_list } => {
-                        editor_state.options.update(option_list);
+                if !buffer.set_type(buffer_type) {
+                    return Err(Error::Script(format!(
+                        "Attempted to change buffer id {} to or from a paged buffer",
+                        buffer_id
+                    )));
+                }
 
-                        self.run_script(process, hook_map, Value::Nil)
-                    }
-                    RedCall::EditorOptions => {
-                        self.run_script(process, hook_map, editor_state.options.clone())
-                    }
-                }?;
+                self.lua_value(Value::Nil)
+            }
+            RedCall::BufferType { buffer_id } => {
+                let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted to get buffer type with invalid id: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                self.lua_value(buffer.buffer_type)
+            }
+            RedCall::BufferRevision { buffer_id } => {
+                let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted to get revision of non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                self.lua_value(buffer.revision)
+            }
+            RedCall::BufferHash { buffer_id } => {
+                let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted to get hash of non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                // Reinterpreted as signed so the full hash fits a Lua integer
+                self.lua_value(buffer.content_hash() as i64)
+            }
+            RedCall::BufferIsModifiedFromDisk { buffer_id } => {
+                let buffer = editor_state.buffer_by_id(buffer_id).ok_or_else(|| {
+                    Error::Script(format!(
+                        "Attempted BufferIsModifiedFromDisk for non-existent buffer: {}",
+                        buffer_id
+                    ))
+                })?;
+
+                self.lua_value(buffer.is_modified_from_disk())
+            }
+            RedCall::UpdateOptions { option_list } => {
+                editor_state.update_options(option_list);
 
-                if is_script_done {
-                    break 'script_loop;
+                self.lua_value(Value::Nil)
+            }
+            RedCall::EditorOptions => self.lua_value(editor_state.options.clone()),
+            RedCall::Batch { calls } => {
+                // Results are packed with their count as `table.pack` does, so
+                // calls returning nil do not cut the list short
+                let results = self.lua.create_table().map_err(|e| {
+                    Error::Recoverable(format!("Failed to create batch results table: {}", e))
+                })?;
+                let count = calls.len();
+                for (index, call) in calls.into_iter().enumerate() {
+                    let value = self.call_value(editor_state, hook_map, call)?;
+                    results.raw_set(index + 1, value).map_err(|e| {
+                        Error::Recoverable(format!("Failed to set batch result: {}", e))
+                    })?;
                 }
+                results.raw_set("n", count).map_err(|e| {
+                    Error::Recoverable(format!("Failed to set batch result count: {}", e))
+                })?;
+
+                self.lua_value(results)
             }
+            unbatchable => Err(Error::Script(format!(
+                "RedCall cannot be batched: {}",
+                Into::<&'static str>::into(RedCallName::from(&unbatchable))
+            ))),
         }
+    }
 
-        Ok(SchedulerYield::Run)
+    fn lua_value<A>(&self, value: A) -> Result<Value<'lua>>
+    where
+        A: IntoLua<'lua>,
+    {
+        value.into_lua(self.lua).map_err(|e| {
+            Error::Recoverable(format!("Failed to convert call result into lua: {}", e))
+        })
+    }
+
+    fn jump_to(
+        &mut self,
+        editor_state: &mut EditorState,
+        hook_map: &HookMap,
+        position: Option<JumpPosition>,
+    ) -> Result<()> {
+        let Some(position) = position else {
+            return Ok(());
+        };
+
+        if let Some(pane_buffer_change) = editor_state.jump_to(position)? {
+            self.spawn_all_hooks(
+                hook_map,
+                HookType::PaneBufferChanged(pane_buffer_change),
+                None,
+            )?;
+        }
+
+        Ok(())
     }
 
     fn run_script<A>(
@@ -1080,6 +2877,36 @@ impl<'lua> ScriptScheduler<'lua> {
         self.execute_script(process, None, hook_map, arg, true)
     }
 
+    fn resume_eval<A>(
+        &mut self,
+        thread: &Thread<'lua>,
+        eval: &EvalContinuation<'lua>,
+        arg: A,
+    ) -> mlua::Result<RedCall<'lua>>
+    where
+        A: IntoLua<'lua>,
+    {
+        let value: Value = thread.resume(arg)?;
+        if thread.status() != mlua::ThreadStatus::Unresumable {
+            return RedCall::from_lua(value, self.lua);
+        }
+
+        // Chunk has returned. Hand its result off to the continuation as a new process.
+        let continuation_thread = self.lua.create_thread(eval.continuation.clone())?;
+        self.active.push_back(ProcessAwaiting {
+            process: ScriptProcess {
+                thread: continuation_thread,
+                cause: None,
+                eval: None,
+                file_grep: None,
+                format: None,
+            },
+            awaiting: RedCall::Value { value },
+        });
+
+        Ok(RedCall::None)
+    }
+
     fn execute_script<A>(
         &mut self,
         process: ScriptProcess<'lua>,
@@ -1110,26 +2937,28 @@ impl<'lua> ScriptScheduler<'lua> {
         } else {
             match process.thread.status() {
                 mlua::ThreadStatus::Resumable => {
-                    match process
-                        .thread
-                        .resume(arg)
-                        .map_err(|e| Error::Script(format!("{}", e)))
-                    {
+                    let resumed = match &process.eval {
+                        Some(eval) => self
+                            .resume_eval(&process.thread, eval, arg)
+                            .map_err(|e| {
+                                Error::Script(format!("{}\nIn evaluated Lua: {}", e, eval.source))
+                            }),
+                        None => process
+                            .thread
+                            .resume(arg)
+                            .map_err(|e| Error::Script(format!("{}", e))),
+                    };
+
+                    match resumed {
                         Ok(red_call) => {
                             if should_yield {
                                 self.active.push_back(ProcessAwaiting {
-                                    process: ScriptProcess {
-                                        thread: process.thread,
-                                        cause: process.cause,
-                                    },
+                                    process,
                                     awaiting: red_call,
                                 });
                             } else {
                                 self.active.push_front(ProcessAwaiting {
-                                    process: ScriptProcess {
-                                        thread: process.thread,
-                                        cause: process.cause,
-                                    },
+                                    process,
                                     awaiting: red_call,
                                 });
                             }
@@ -1159,3 +2988,68 @@ impl<'lua> ScriptScheduler<'lua> {
         }
     }
 }
+
+fn mark_name_char(name: &str) -> Result<char> {
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some(mark_name), None) => Ok(mark_name),
+        _ => Err(Error::Script(format!(
+            "Expected a single character for mark name. Found: {:?}",
+            name
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn five_edits_fire_one_coalesced_hook() {
+        let mut tracker = ModificationTracker::default();
+        let delay = Duration::from_millis(300);
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+
+        assert!(tracker.settled([Some(0)].into_iter(), at(0), delay).is_empty());
+        for revision in 1..=5 {
+            let settled = tracker.settled([Some(revision)].into_iter(), at(revision * 50), delay);
+            assert!(settled.is_empty());
+        }
+
+        assert!(tracker.settled([Some(5)].into_iter(), at(500), delay).is_empty());
+        assert_eq!(tracker.settled([Some(5)].into_iter(), at(550), delay), vec![0]);
+        assert!(tracker.settled([Some(5)].into_iter(), at(1000), delay).is_empty());
+    }
+
+    #[test]
+    fn buffers_settle_independently() {
+        let mut tracker = ModificationTracker::default();
+        let delay = Duration::from_millis(100);
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+
+        tracker.settled([Some(0), Some(0)].into_iter(), at(0), delay);
+        tracker.settled([Some(1), Some(0)].into_iter(), at(10), delay);
+        tracker.settled([Some(1), Some(1)].into_iter(), at(60), delay);
+
+        assert_eq!(tracker.settled([Some(1), Some(1)].into_iter(), at(110), delay), vec![0]);
+        assert_eq!(tracker.settled([Some(1), Some(1)].into_iter(), at(160), delay), vec![1]);
+    }
+
+    #[test]
+    fn closed_buffers_are_forgotten() {
+        let mut tracker = ModificationTracker::default();
+        let delay = Duration::from_millis(100);
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+
+        tracker.settled([Some(0)].into_iter(), at(0), delay);
+        tracker.settled([Some(1)].into_iter(), at(10), delay);
+        tracker.settled([None].into_iter(), at(20), delay);
+
+        // A new buffer reusing the id starts without a pending change
+        assert!(tracker.settled([Some(7)].into_iter(), at(30), delay).is_empty());
+        assert!(tracker.settled([Some(7)].into_iter(), at(500), delay).is_empty());
+    }
+}
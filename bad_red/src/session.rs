@@ -0,0 +1,518 @@
+// This file is part of BadRed.
+
+// BadRed is free software: you can redistribute it and/or modify it under the terms of the GNU General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// BadRed is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+//! Snapshot of the editor's buffers, pane layout, and options which can be
+//! written to a file and restored later. Only plain data is kept, so Lua hooks
+//! and keymaps are left to the init script to set up again.
+//!
+//! Sessions are written as lines of space separated fields. Buffer content and
+//! file paths may hold any text, so they are written after the line describing
+//! them with their byte lengths given on that line.
+
+use std::collections::HashMap;
+
+use crate::{
+    buffer::{EditorBufferType, EditorBufferTypeName},
+    editor_state::EditorOptions,
+    pane::{Fold, Pane, PaneNodeType, Split, SplitType, WrapMode, WrapModeName},
+};
+
+pub type Result<T> = std::result::Result<T, String>;
+
+const HEADER: &str = "bad_red session 2";
+/// Header of sessions written before leaf panes kept their tab spaces, which
+/// are still read
+const HEADER_V1: &str = "bad_red session 1";
+/// Largest buffer id or pane index read from a session. Ids are kept as
+/// indices into lists, so a corrupt session with a huge id is refused rather
+/// than allocating a list that long.
+const MAX_INDEX: usize = u16::MAX as usize;
+
+pub struct Session {
+    pub options: EditorOptions,
+    /// Buffers by id, with closed buffers left as `None` so ids are kept
+    pub buffers: Vec<Option<SessionBuffer>>,
+    /// Pane nodes by index, with removed nodes left as `None` so indices are
+    /// kept
+    pub panes: Vec<Option<SessionPane>>,
+    pub root_pane_index: usize,
+    pub active_pane_index: usize,
+}
+
+pub struct SessionBuffer {
+    pub buffer_type: EditorBufferType,
+    pub content: String,
+    pub cursor_byte_index: usize,
+    /// Saved cursors of inactive panes showing this buffer, by pane index
+    pub pane_cursors: HashMap<usize, usize>,
+    /// Path of the file linked to the buffer, linked again when restored
+    pub path: Option<String>,
+}
+
+pub struct SessionPane {
+    pub parent_index: Option<usize>,
+    pub node_type: PaneNodeType,
+}
+
+impl Session {
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{}\n", HEADER);
+
+        let options = &self.options;
+        for (name, value) in [
+            ("tab_width", options.tab_width.to_string()),
+            ("expand_tab", options.expand_tab.to_string()),
+            ("final_newline", options.final_newline.to_string()),
+            ("visual_bell", options.visual_bell.to_string()),
+            ("inactive_cursor", options.inactive_cursor.to_string()),
+            ("modified_delay_ms", options.modified_delay_ms.to_string()),
+            ("auto_reload", options.auto_reload.to_string()),
+            ("poll_rate_ms", options.poll_rate_ms.to_string()),
+        ] {
+            text.push_str(&format!("option {} {}\n", name, value));
+        }
+
+        for (buffer_id, buffer) in self.buffers.iter().enumerate() {
+            let Some(buffer) = buffer else {
+                continue;
+            };
+
+            let buffer_type: &'static str = EditorBufferTypeName::from(&buffer.buffer_type).into();
+            let path = buffer.path.as_deref().unwrap_or_default();
+            text.push_str(&format!(
+                "buffer {} {} {} {} {} {}\n{}{}\n",
+                buffer_id,
+                buffer_type,
+                buffer.cursor_byte_index,
+                buffer.path.is_some(),
+                path.len(),
+                buffer.content.len(),
+                path,
+                buffer.content,
+            ));
+
+            for (pane_index, byte_index) in &buffer.pane_cursors {
+                text.push_str(&format!(
+                    "pane_cursor {} {} {}\n",
+                    buffer_id, pane_index, byte_index
+                ));
+            }
+        }
+
+        for (pane_index, pane) in self.panes.iter().enumerate() {
+            let Some(pane) = pane else {
+                continue;
+            };
+
+            let parent_index = pane
+                .parent_index
+                .map(|index| index.to_string())
+                .unwrap_or_else(|| "-".to_string());
+            let node = match &pane.node_type {
+                PaneNodeType::Leaf(leaf) => {
+                    let wrap_mode: &'static str = WrapModeName::from(&leaf.wrap_mode).into();
+                    let tab_spaces = leaf
+                        .tab_spaces
+                        .map(|spaces| spaces.to_string())
+                        .unwrap_or_else(|| "-".to_string());
+                    let mut node = format!(
+                        "leaf {} {} {} {} {}",
+                        leaf.buffer_id, leaf.top_line, wrap_mode, leaf.wrap_indent, tab_spaces
+                    );
+                    for fold in &leaf.folds {
+                        node.push_str(&format!(" {}-{}", fold.start_line, fold.end_line));
+                    }
+
+                    node
+                }
+                PaneNodeType::VSplit(split) => format!("vsplit {}", split_text(split)),
+                PaneNodeType::HSplit(split) => format!("hsplit {}", split_text(split)),
+            };
+
+            text.push_str(&format!("pane {} {} {}\n", pane_index, parent_index, node));
+        }
+
+        text.push_str(&format!(
+            "active {} {}\n",
+            self.root_pane_index, self.active_pane_index
+        ));
+
+        text
+    }
+
+    /// Parses a session written by `to_text`. Options missing from the
+    /// session keep their values from `options`.
+    pub fn parse(text: &str, options: EditorOptions) -> Result<Self> {
+        let mut reader = SessionReader { rest: text };
+        let has_tab_spaces = match reader.line() {
+            Some(HEADER) => true,
+            Some(HEADER_V1) => false,
+            _ => return Err("Session does not start with a session header".to_string()),
+        };
+
+        let mut options = options;
+        let mut buffers: Vec<Option<SessionBuffer>> = vec![];
+        let mut panes: Vec<Option<SessionPane>> = vec![];
+        let mut active = None;
+
+        while let Some(line) = reader.line() {
+            let mut fields = line.split(' ');
+            match fields.next() {
+                Some("option") => {
+                    let name = next_field(&mut fields, line)?;
+                    let value = next_field(&mut fields, line)?;
+                    set_option(&mut options, name, value)?;
+                }
+                Some("buffer") => {
+                    let buffer_id = parse_field(&mut fields, line)?;
+                    let buffer_type = match next_field(&mut fields, line)? {
+                        "naive" => EditorBufferType::Naive,
+                        "gap" => EditorBufferType::Gap,
+                        "paged" => EditorBufferType::Paged,
+                        other => return Err(format!("Unknown buffer type in session: {}", other)),
+                    };
+                    let cursor_byte_index = parse_field(&mut fields, line)?;
+                    let has_path: bool = parse_field(&mut fields, line)?;
+                    let path_length = parse_field(&mut fields, line)?;
+                    let content_length = parse_field(&mut fields, line)?;
+
+                    let path = reader.take(path_length)?.to_string();
+                    let content = reader.take(content_length)?.to_string();
+                    reader.line();
+
+                    set_at(
+                        &mut buffers,
+                        buffer_id,
+                        SessionBuffer {
+                            buffer_type,
+                            content,
+                            cursor_byte_index,
+                            pane_cursors: HashMap::new(),
+                            path: has_path.then_some(path),
+                        },
+                    )?;
+                }
+                Some("pane_cursor") => {
+                    let buffer_id: usize = parse_field(&mut fields, line)?;
+                    let pane_index = parse_field(&mut fields, line)?;
+                    let byte_index = parse_field(&mut fields, line)?;
+
+                    buffers
+                        .get_mut(buffer_id)
+                        .and_then(|buffer| buffer.as_mut())
+                        .ok_or_else(|| {
+                            format!("Pane cursor found for unknown buffer: {}", buffer_id)
+                        })?
+                        .pane_cursors
+                        .insert(pane_index, byte_index);
+                }
+                Some("pane") => {
+                    let pane_index = parse_field(&mut fields, line)?;
+                    let parent_index = match next_field(&mut fields, line)? {
+                        "-" => None,
+                        index => Some(parse_value(index, line)?),
+                    };
+                    let node_type = match next_field(&mut fields, line)? {
+                        "leaf" => {
+                            PaneNodeType::Leaf(parse_leaf(&mut fields, line, has_tab_spaces)?)
+                        }
+                        "vsplit" => PaneNodeType::VSplit(parse_split(&mut fields, line)?),
+                        "hsplit" => PaneNodeType::HSplit(parse_split(&mut fields, line)?),
+                        other => return Err(format!("Unknown pane type in session: {}", other)),
+                    };
+
+                    set_at(
+                        &mut panes,
+                        pane_index,
+                        SessionPane {
+                            parent_index,
+                            node_type,
+                        },
+                    )?;
+                }
+                Some("active") => {
+                    let root_pane_index = parse_field(&mut fields, line)?;
+                    let active_pane_index = parse_field(&mut fields, line)?;
+                    active = Some((root_pane_index, active_pane_index));
+                }
+                _ => return Err(format!("Unknown line in session: {}", line)),
+            }
+        }
+
+        let (root_pane_index, active_pane_index) =
+            active.ok_or_else(|| "Session is missing its active pane".to_string())?;
+
+        Ok(Self {
+            options,
+            buffers,
+            panes,
+            root_pane_index,
+            active_pane_index,
+        })
+    }
+}
+
+/// Reads a session's text a line or a counted number of bytes at a time
+struct SessionReader<'a> {
+    rest: &'a str,
+}
+
+impl<'a> SessionReader<'a> {
+    fn line(&mut self) -> Option<&'a str> {
+        if self.rest.is_empty() {
+            return None;
+        }
+
+        let (line, rest) = self.rest.split_once('\n').unwrap_or((self.rest, ""));
+        self.rest = rest;
+        Some(line)
+    }
+
+    fn take(&mut self, byte_length: usize) -> Result<&'a str> {
+        if !self.rest.is_char_boundary(byte_length) {
+            return Err(format!(
+                "Session ended or split a char while reading {} bytes",
+                byte_length
+            ));
+        }
+
+        let (taken, rest) = self.rest.split_at(byte_length);
+        self.rest = rest;
+        Ok(taken)
+    }
+}
+
+fn split_text(split: &Split) -> String {
+    let split_type = match split.split_type {
+        SplitType::Percent { first_percent } => format!("percent {}", first_percent),
+        SplitType::FirstFixed { size } => format!("first_fixed {}", size),
+        SplitType::SecondFixed { size } => format!("second_fixed {}", size),
+    };
+
+    format!("{} {} {}", split.first, split.second, split_type)
+}
+
+fn parse_leaf<'a>(
+    fields: &mut impl Iterator<Item = &'a str>,
+    line: &str,
+    has_tab_spaces: bool,
+) -> Result<Pane> {
+    let mut pane = Pane::new(parse_field(fields, line)?);
+    pane.top_line = parse_field(fields, line)?;
+    pane.wrap_mode = match next_field(fields, line)? {
+        "none" => WrapMode::None,
+        "char" => WrapMode::Char,
+        "word" => WrapMode::Word,
+        other => return Err(format!("Unknown wrap mode in session: {}", other)),
+    };
+    pane.wrap_indent = parse_field(fields, line)?;
+    if has_tab_spaces {
+        pane.tab_spaces = match next_field(fields, line)? {
+            "-" => None,
+            spaces => Some(parse_value(spaces, line)?),
+        };
+    }
+
+    for fold in fields {
+        let (start_line, end_line) = fold
+            .split_once('-')
+            .ok_or_else(|| format!("Malformed fold in session line: {}", line))?;
+        pane.folds.push(Fold {
+            start_line: parse_value(start_line, line)?,
+            end_line: parse_value(end_line, line)?,
+        });
+    }
+
+    Ok(pane)
+}
+
+fn parse_split<'a>(fields: &mut impl Iterator<Item = &'a str>, line: &str) -> Result<Split> {
+    let first = parse_field(fields, line)?;
+    let second = parse_field(fields, line)?;
+    let split_type = match next_field(fields, line)? {
+        "percent" => SplitType::Percent {
+            first_percent: parse_field(fields, line)?,
+        },
+        "first_fixed" => SplitType::FirstFixed {
+            size: parse_field(fields, line)?,
+        },
+        "second_fixed" => SplitType::SecondFixed {
+            size: parse_field(fields, line)?,
+        },
+        other => return Err(format!("Unknown split type in session: {}", other)),
+    };
+
+    Ok(Split {
+        first,
+        second,
+        split_type,
+    })
+}
+
+fn set_option(options: &mut EditorOptions, name: &str, value: &str) -> Result<()> {
+    match name {
+        "tab_width" => options.tab_width = parse_value(value, name)?,
+        "expand_tab" => options.expand_tab = parse_value(value, name)?,
+        "final_newline" => options.final_newline = parse_value(value, name)?,
+        "visual_bell" => options.visual_bell = parse_value(value, name)?,
+        "inactive_cursor" => options.inactive_cursor = parse_value(value, name)?,
+        "modified_delay_ms" => {
+            options.modified_delay_ms = parse_value::<u64>(value, name)?.max(1)
+        }
+        "auto_reload" => options.auto_reload = parse_value(value, name)?,
+        "poll_rate_ms" => options.poll_rate_ms = parse_value::<u64>(value, name)?.max(1),
+        // Options unknown to this version are left as they are
+        _ => (),
+    }
+
+    Ok(())
+}
+
+fn set_at<T>(items: &mut Vec<Option<T>>, index: usize, item: T) -> Result<()> {
+    if index > MAX_INDEX {
+        return Err(format!("Index {} in session is larger than allowed", index));
+    }
+
+    if items.len() <= index {
+        items.resize_with(index + 1, || None);
+    }
+    items[index] = Some(item);
+
+    Ok(())
+}
+
+fn next_field<'a>(fields: &mut impl Iterator<Item = &'a str>, line: &str) -> Result<&'a str> {
+    fields
+        .next()
+        .ok_or_else(|| format!("Missing field in session line: {}", line))
+}
+
+fn parse_field<'a, T: std::str::FromStr>(
+    fields: &mut impl Iterator<Item = &'a str>,
+    line: &str,
+) -> Result<T> {
+    parse_value(next_field(fields, line)?, line)
+}
+
+fn parse_value<T: std::str::FromStr>(value: &str, context: &str) -> Result<T> {
+    value
+        .parse()
+        .map_err(|_| format!("Malformed value {:?} in session: {}", value, context))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{buffer::ContentBuffer, editor_state::EditorState};
+
+    fn default_options() -> EditorOptions {
+        EditorState::new(Duration::from_millis(10)).options
+    }
+
+    fn leaf(session: &Session, pane_index: usize) -> &Pane {
+        match &session.panes[pane_index].as_ref().unwrap().node_type {
+            PaneNodeType::Leaf(pane) => pane,
+            PaneNodeType::VSplit(_) | PaneNodeType::HSplit(_) => panic!("Expected leaf pane"),
+        }
+    }
+
+    #[test]
+    fn round_trips_through_text() {
+        let mut editor_state = EditorState::new(Duration::from_millis(10));
+        editor_state.options.tab_width = 4;
+        editor_state
+            .mut_buffer_by_id(0)
+            .unwrap()
+            .insert_at_cursor("one\ntwo ünïcode\n");
+        editor_state.pane_tree.vsplit(0, 0).unwrap();
+        // Splitting adds the new leaf pane before the split containing it
+        let split_pane = 1;
+        let pane = editor_state.pane_tree.pane_mut_by_index(split_pane).unwrap();
+        pane.tab_spaces = Some(2);
+        pane.add_fold(0, 1);
+
+        let session = editor_state.session();
+        let text = session.to_text();
+        let parsed = Session::parse(&text, default_options()).unwrap();
+
+        assert_eq!(parsed.to_text(), text);
+        assert_eq!(parsed.options.tab_width, 4);
+        assert_eq!(
+            parsed.buffers[0].as_ref().unwrap().content,
+            "one\ntwo ünïcode\n"
+        );
+        let pane = leaf(&parsed, split_pane);
+        assert_eq!(pane.tab_spaces, Some(2));
+        assert_eq!(pane.folds, vec![Fold { start_line: 0, end_line: 1 }]);
+
+        let mut restored = EditorState::new(Duration::from_millis(10));
+        restored.restore_session(parsed).unwrap();
+        assert_eq!(restored.session().to_text(), text);
+    }
+
+    #[test]
+    fn refuses_huge_ids() {
+        let text = "bad_red session 2\n\
+                    buffer 99999999999 gap 0 false 0 0\n\n\
+                    active 0 0\n";
+
+        assert!(Session::parse(text, default_options()).is_err());
+    }
+
+    #[test]
+    fn refuses_to_restore_splits_with_missing_children() {
+        let text = "bad_red session 2\n\
+                    buffer 0 gap 0 false 0 0\n\n\
+                    pane 0 - vsplit 1 7 percent 50\n\
+                    pane 1 0 leaf 0 0 none false -\n\
+                    active 0 1\n";
+        let session = Session::parse(text, default_options()).unwrap();
+
+        let mut editor_state = EditorState::new(Duration::from_millis(10));
+        assert!(editor_state.restore_session(session).is_err());
+    }
+
+    #[test]
+    fn parses_version_1_leaf_panes_without_tab_spaces() {
+        let text = "bad_red session 1\n\
+                    buffer 0 gap 0 false 0 2\nhi\n\
+                    pane 0 - leaf 0 4 word true 3-5\n\
+                    active 0 0\n";
+
+        let session = Session::parse(text, default_options()).unwrap();
+        let pane = leaf(&session, 0);
+
+        assert_eq!(pane.top_line, 4);
+        assert_eq!(pane.tab_spaces, None);
+        assert_eq!(pane.folds, vec![Fold { start_line: 3, end_line: 5 }]);
+    }
+
+    #[test]
+    fn parses_leaf_panes_with_tab_spaces() {
+        let text = "bad_red session 2\n\
+                    buffer 0 gap 0 false 0 2\nhi\n\
+                    pane 0 - leaf 0 4 word true 2 3-5\n\
+                    active 0 0\n";
+
+        let session = Session::parse(text, default_options()).unwrap();
+        let pane = leaf(&session, 0);
+
+        assert_eq!(pane.tab_spaces, Some(2));
+        assert_eq!(pane.folds, vec![Fold { start_line: 3, end_line: 5 }]);
+    }
+
+    #[test]
+    fn restored_timing_options_are_at_least_one_millisecond() {
+        let mut options = default_options();
+        set_option(&mut options, "poll_rate_ms", "0").unwrap();
+        set_option(&mut options, "modified_delay_ms", "0").unwrap();
+
+        assert_eq!(options.poll_rate_ms, 1);
+        assert_eq!(options.modified_delay_ms, 1);
+    }
+}
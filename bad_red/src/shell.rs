@@ -0,0 +1,93 @@
+// This file is part of BadRed.
+
+// BadRed is free software: you can redistribute it and/or modify it under the terms of the GNU General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// BadRed is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+    thread,
+};
+
+use bad_red_proc_macros::auto_lua;
+
+#[auto_lua]
+#[derive(Clone, Hash, PartialEq, Eq, Debug)]
+pub struct ShellOutput {
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_code: Option<i32>,
+}
+
+impl ShellOutput {
+    pub fn is_success(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+/// Runs `command` with `args` to completion, feeding it `stdin` if provided.
+///
+/// Errs only if the process could not be run. A process which runs but exits
+/// unsuccessfully is returned as output with its exit code, which is `None` if
+/// it was ended by a signal.
+pub fn run(command: &str, args: &[String], stdin: Option<String>) -> Result<ShellOutput, String> {
+    let mut child = Command::new(command)
+        .args(args)
+        .stdin(if stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to spawn shell command {}: {}", command, e))?;
+
+    // Written from another thread so a process filling its output pipe before
+    // reading all of its input cannot deadlock with us.
+    let stdin_writer = child.stdin.take().zip(stdin).map(|(mut child_stdin, stdin)| {
+        thread::spawn(move || child_stdin.write_all(stdin.as_bytes()))
+    });
+
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Failed to wait on shell command {}: {}", command, e))?;
+
+    if let Some(stdin_writer) = stdin_writer {
+        // A process may exit without reading all of its input. This is not an
+        // error of the command's, so any failed write is ignored.
+        let _ = stdin_writer.join();
+    }
+
+    Ok(ShellOutput {
+        stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        exit_code: output.status.code(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn piped_content_is_transformed() {
+        let output = run("cat", &[], Some("kept\n".to_string())).unwrap();
+        assert_eq!(output.stdout, "kept\n");
+        assert!(output.is_success());
+
+        let output = run("sort", &[], Some("b\nc\na\n".to_string())).unwrap();
+        assert_eq!(output.stdout, "a\nb\nc\n");
+    }
+
+    #[test]
+    fn failing_to_spawn_differs_from_failing_exit() {
+        let output = run("false", &[], None).unwrap();
+        assert!(!output.is_success());
+        assert_eq!(output.exit_code, Some(1));
+
+        let error = run("bad_red_missing_command", &[], None).unwrap_err();
+        assert!(error.contains("Failed to spawn"));
+    }
+}
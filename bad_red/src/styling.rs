@@ -9,38 +9,57 @@ use std::collections::HashMap;
 use bad_red_proc_macros::auto_lua;
 use regex::Regex;
 
+/// Regex styles of a buffer, converting to and from Lua as the list of its
+/// styles
+#[auto_lua(transparent)]
+#[derive(Clone, Debug)]
 pub struct Styling {
     pub style_list: Vec<Style>,
 }
 
 impl Styling {
     pub const DEFAULT_NAME: &str = "default";
+    /// Reserved style name used to draw the summary line of folded lines
+    pub const FOLD_NAME: &str = "fold";
+    /// Reserved style names whose backgrounds are drawn behind a buffer's
+    /// search matches, with the match under the cursor drawn in the current
+    /// match style
+    pub const SEARCH_NAME: &str = "search";
+    pub const SEARCH_CURRENT_NAME: &str = "search_current";
+    /// Reserved style name used to draw tabs in panes which draw tabs as a
+    /// fixed number of spaces
+    pub const TAB_NAME: &str = "tab";
 
     pub fn new() -> Self {
         Self { style_list: vec![] }
     }
 
     pub fn push_style(&mut self, name: String, regex: String) -> Result<(), String> {
-        self.style_list.push(Style {
+        self.push(Style {
             name,
-            regex: Regex::new(&format!("^({})", &regex)).map_err(|e| match e {
-                regex::Error::Syntax(reason) => reason,
-                regex::Error::CompiledTooBig(size) => {
-                    format!("Could not compile regex to size: {}", size)
-                }
-                _ => format!("Unknown regex faliure"),
-            })?,
-        });
-
-        Ok(())
+            pattern: regex,
+            regex: None,
+        })
     }
 
     pub fn clear(&mut self) {
         self.style_list.clear();
     }
 
-    pub fn push(&mut self, style: Style) {
+    /// Pushes `style`, compiling its regex from its pattern
+    pub fn push(&mut self, mut style: Style) -> Result<(), String> {
+        style.regex = Some(
+            Regex::new(&format!("^({})", style.pattern)).map_err(|e| match e {
+                regex::Error::Syntax(reason) => reason,
+                regex::Error::CompiledTooBig(size) => {
+                    format!("Could not compile regex to size: {}", size)
+                }
+                _ => "Unknown regex faliure".to_string(),
+            })?,
+        );
         self.style_list.push(style);
+
+        Ok(())
     }
 }
 
@@ -71,7 +90,71 @@ pub struct TextStyle {
     pub foreground: Color,
 }
 
+/// Style named `name` drawn over text matching `pattern`. The compiled regex
+/// is left out of the Lua representation, so styles converted from Lua are
+/// only matched once pushed with `Styling::push`.
+#[auto_lua]
+#[derive(Clone, Debug)]
 pub struct Style {
     pub name: String,
-    pub regex: Regex,
+    pub pattern: String,
+    #[lua(skip)]
+    pub regex: Option<Regex>,
+}
+
+#[cfg(test)]
+mod tests {
+    use mlua::{FromLua, IntoLua, Lua};
+
+    use super::*;
+
+    #[test]
+    fn pushed_styles_are_compiled_from_their_pattern() {
+        let mut styling = Styling::new();
+        styling.push_style("number".to_string(), "[0-9]+".to_string()).unwrap();
+
+        let regex = styling.style_list[0].regex.as_ref().unwrap();
+        assert_eq!(regex.find("12ab").map(|found| found.end()), Some(2));
+        assert!(regex.find("ab12").is_none());
+        assert!(styling.push_style("bad".to_string(), "(".to_string()).is_err());
+        assert_eq!(styling.style_list.len(), 1);
+    }
+
+    #[test]
+    fn lua_style_round_trip_skips_compiled_regex() {
+        let lua = Lua::new();
+        let mut styling = Styling::new();
+        styling.push_style("number".to_string(), "[0-9]+".to_string()).unwrap();
+
+        let value = styling.style_list[0].clone().into_lua(&lua).unwrap();
+        let values = value.as_table().unwrap().get::<_, mlua::Table>("values").unwrap();
+        assert_eq!(values.get::<_, String>("pattern").unwrap(), "[0-9]+");
+        assert!(values.get::<_, mlua::Value>("regex").unwrap().is_nil());
+
+        let style = Style::from_lua(value.clone(), &lua).unwrap();
+        assert_eq!(style.name, "number");
+        assert_eq!(style.pattern, "[0-9]+");
+        assert!(style.regex.is_none());
+    }
+
+    #[test]
+    fn lua_styling_converts_as_its_style_list() {
+        let lua = Lua::new();
+        let mut styling = Styling::new();
+        styling.push_style("a".to_string(), "a".to_string()).unwrap();
+        styling.push_style("b".to_string(), "b".to_string()).unwrap();
+
+        let value = styling.into_lua(&lua).unwrap();
+        let list = value.as_table().unwrap();
+        assert_eq!(list.raw_len(), 2);
+        assert!(list.get::<_, mlua::Value>("type").unwrap().is_nil());
+
+        let styling = Styling::from_lua(value.clone(), &lua).unwrap();
+        let names = styling
+            .style_list
+            .iter()
+            .map(|style| style.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["a", "b"]);
+    }
 }
@@ -0,0 +1,114 @@
+// This file is part of BadRed.
+
+// BadRed is free software: you can redistribute it and/or modify it under the terms of the GNU General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// BadRed is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+use std::{
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+use bad_red_proc_macros::auto_lua;
+
+use crate::{file_grep::GrepResult, shell::ShellOutput};
+
+#[auto_lua]
+#[derive(Clone, Hash, PartialEq, Eq, Debug)]
+pub enum TaskResult {
+    FileMatches(GrepResult),
+    FileContent(String),
+    ShellOutput(ShellOutput),
+    Failed(String),
+}
+
+pub struct TaskCompletion {
+    pub id: usize,
+    pub result: TaskResult,
+}
+
+/// Runs tasks on worker threads. Results are collected by polling
+/// `completed` so they can be handled on the scheduler's thread.
+pub struct TaskRunner {
+    sender: Sender<TaskCompletion>,
+    receiver: Receiver<TaskCompletion>,
+    next_id: usize,
+}
+
+impl TaskRunner {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        Self {
+            sender,
+            receiver,
+            next_id: 0,
+        }
+    }
+
+    /// Starts `task` on a new worker thread, returning the id its result will
+    /// be reported with.
+    pub fn spawn(&mut self, task: impl FnOnce() -> TaskResult + Send + 'static) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let sender = self.sender.clone();
+        thread::spawn(move || {
+            // The runner may have been dropped while the task ran. Its result
+            // has nowhere to go in that case.
+            let _ = sender.send(TaskCompletion {
+                id,
+                result: task(),
+            });
+        });
+
+        id
+    }
+
+    /// Returns the results of tasks which have finished since the last call
+    /// without waiting on tasks which are still running.
+    pub fn completed(&self) -> Vec<TaskCompletion> {
+        self.receiver.try_iter().collect()
+    }
+}
+
+impl Default for TaskRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn results_are_collected_once_their_task_completes() {
+        let mut tasks = TaskRunner::new();
+        let (release, released) = mpsc::channel::<()>();
+        let id = tasks.spawn(move || {
+            let _ = released.recv();
+            TaskResult::FileContent("done".to_string())
+        });
+
+        // Collecting does not wait on the task, which is still blocked
+        assert!(tasks.completed().is_empty());
+
+        release.send(()).unwrap();
+        for _ in 0..500 {
+            let completed = tasks.completed();
+            if let Some(completion) = completed.first() {
+                assert_eq!(completion.id, id);
+                assert_eq!(
+                    completion.result,
+                    TaskResult::FileContent("done".to_string())
+                );
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("Task did not complete");
+    }
+}
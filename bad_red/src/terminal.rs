@@ -0,0 +1,93 @@
+// This file is part of BadRed.
+
+// BadRed is free software: you can redistribute it and/or modify it under the terms of the GNU General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// BadRed is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+use std::{
+    env,
+    io::{self, IsTerminal},
+};
+
+use bad_red_proc_macros::auto_lua;
+
+#[auto_lua]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ColorDepth {
+    Monochrome,
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+/// Features of the terminal the editor is running in, detected from the
+/// environment so that scripts can degrade gracefully.
+#[auto_lua]
+#[derive(Clone, Debug)]
+pub struct TerminalCapabilities {
+    pub color_depth: ColorDepth,
+    pub rows: u16,
+    pub cols: u16,
+    pub is_tty: bool,
+    pub is_unicode: bool,
+}
+
+impl TerminalCapabilities {
+    pub fn detect() -> Self {
+        let (cols, rows) = crossterm::terminal::size().unwrap_or((0, 0));
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .into_iter()
+            .find_map(|name| env::var(name).ok().filter(|value| !value.is_empty()));
+
+        Self {
+            color_depth: color_depth_from(
+                env::var("COLORTERM").ok().as_deref(),
+                env::var("TERM").ok().as_deref(),
+            ),
+            rows,
+            cols,
+            is_tty: io::stdout().is_terminal(),
+            is_unicode: locale.is_some_and(|locale| {
+                let locale = locale.to_ascii_lowercase();
+                locale.contains("utf-8") || locale.contains("utf8")
+            }),
+        }
+    }
+}
+
+/// Parses the color depth from the values of the `COLORTERM` and `TERM`
+/// environment variables.
+pub fn color_depth_from(colorterm: Option<&str>, term: Option<&str>) -> ColorDepth {
+    if matches!(colorterm, Some("truecolor" | "24bit")) {
+        return ColorDepth::TrueColor;
+    }
+
+    match term {
+        Some("dumb") => ColorDepth::Monochrome,
+        Some(term) if term.contains("truecolor") || term.contains("direct") => {
+            ColorDepth::TrueColor
+        }
+        Some(term) if term.contains("256color") => ColorDepth::Ansi256,
+        _ => ColorDepth::Ansi16,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colorterm_advertising_truecolor_overrides_term() {
+        assert_eq!(color_depth_from(Some("truecolor"), Some("xterm")), ColorDepth::TrueColor);
+        assert_eq!(color_depth_from(Some("24bit"), Some("dumb")), ColorDepth::TrueColor);
+    }
+
+    #[test]
+    fn unrecognized_colorterm_falls_back_to_term() {
+        assert_eq!(color_depth_from(Some("yes"), Some("xterm-256color")), ColorDepth::Ansi256);
+        assert_eq!(color_depth_from(None, Some("xterm-direct")), ColorDepth::TrueColor);
+        assert_eq!(color_depth_from(None, Some("dumb")), ColorDepth::Monochrome);
+        assert_eq!(color_depth_from(None, Some("xterm")), ColorDepth::Ansi16);
+        assert_eq!(color_depth_from(None, None), ColorDepth::Ansi16);
+    }
+}
@@ -0,0 +1,148 @@
+// This file is part of BadRed.
+
+// BadRed is free software: you can redistribute it and/or modify it under the terms of the GNU General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// BadRed is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+//! Harness for driving an `Editor` from tests without a terminal. Only built
+//! with the `test-support` feature.
+//!
+//! ```no_run
+//! use std::path::PathBuf;
+//!
+//! use bad_red_lib::{script_handler::ScriptHandler, test_support::Headless};
+//! use crossterm::event::{KeyCode, KeyModifiers};
+//! use bad_red_lib::keymap::RedKeyEvent;
+//!
+//! let script_handler = ScriptHandler::new(PathBuf::new()).unwrap();
+//! let mut headless = Headless::with_init(
+//!     &script_handler.lua,
+//!     r#"
+//!         coroutine.yield(red.call.set_hook("key_event", function(event)
+//!             coroutine.yield(red.call.active_buffer_insert("hello"))
+//!         end))
+//!     "#,
+//! )
+//! .unwrap();
+//!
+//! headless
+//!     .press(&[RedKeyEvent {
+//!         code: KeyCode::Char('h'),
+//!         modifiers: KeyModifiers::NONE,
+//!     }])
+//!     .unwrap();
+//! assert_eq!(headless.active_buffer_content().as_deref(), Some("hello"));
+//! ```
+
+use mlua::Lua;
+
+use crate::{
+    buffer::ContentBuffer,
+    editor_state::{Editor, Error, Result},
+    keymap::RedKeyEvent,
+    pane::PaneTree,
+    script_runtime::{SchedulerStep, SchedulerYield},
+};
+
+include!(concat!(env!("OUT_DIR"), "/consts_defs.rs"));
+
+/// Editor driven by scripted key events rather than a terminal. Scripts are
+/// run until they finish after each step, so results can be checked directly.
+pub struct Headless<'lua> {
+    pub editor: Editor<'lua>,
+    /// Whether a script has asked the editor to exit
+    pub has_exited: bool,
+}
+
+impl<'lua> Headless<'lua> {
+    /// Most times the scheduler is run while waiting for scripts to finish,
+    /// so a script which never finishes fails rather than hangs the test.
+    pub const MAX_SCHEDULER_RUNS: usize = 10_000;
+
+    /// Creates an editor running the default init script
+    pub fn new(lua: &'lua Lua) -> Result<Self> {
+        Self::with_init(lua, generated::INIT)
+    }
+
+    /// Creates an editor running `init_script` in place of the default init
+    /// script, then runs scripts until the init script has finished.
+    pub fn with_init(lua: &'lua Lua, init_script: &str) -> Result<Self> {
+        let editor = Editor::new(
+            lua,
+            generated::PRELOAD.to_string(),
+            init_script.to_string(),
+            vec![],
+        )?;
+
+        let mut headless = Self {
+            editor,
+            has_exited: false,
+        };
+        headless.run_until_idle()?;
+
+        Ok(headless)
+    }
+
+    /// Handles each of `key_events` in order, running scripts until they have
+    /// finished after each event.
+    pub fn press(&mut self, key_events: &[RedKeyEvent]) -> Result<()> {
+        for key_event in key_events {
+            self.editor.handle_red_key_event(key_event.clone())?;
+            self.run_until_idle()?;
+        }
+
+        Ok(())
+    }
+
+    /// Runs the scheduler until no scripts are left to run or a script exits
+    /// the editor.
+    pub fn run_until_idle(&mut self) -> Result<()> {
+        for _ in 0..Self::MAX_SCHEDULER_RUNS {
+            match self.editor.run_scripts()? {
+                SchedulerYield::Run => (),
+                SchedulerYield::Skip => return Ok(()),
+                SchedulerYield::Quit => {
+                    self.has_exited = true;
+                    return Ok(());
+                }
+            }
+        }
+
+        Err(Error::Unrecoverable(format!(
+            "Scripts were still running after {} scheduler runs",
+            Self::MAX_SCHEDULER_RUNS
+        )))
+    }
+
+    /// Makes the next call of a waiting script, so the editor can be checked
+    /// between the calls a script makes.
+    pub fn step(&mut self) -> Result<SchedulerStep> {
+        let step = self
+            .editor
+            .script_scheduler
+            .run_one_step(&mut self.editor.state, &mut self.editor.hook_map)?;
+        if step == SchedulerStep::Quit {
+            self.has_exited = true;
+        }
+
+        Ok(step)
+    }
+
+    pub fn buffer_content(&self, buffer_id: usize) -> Option<String> {
+        self.editor
+            .state
+            .buffer_by_id(buffer_id)
+            .map(|buffer| buffer.content_copy())
+    }
+
+    /// Content of the buffer shown in the active pane
+    pub fn active_buffer_content(&self) -> Option<String> {
+        let buffer_id = self.editor.state.active_buffer_id().ok()?;
+
+        self.buffer_content(buffer_id)
+    }
+
+    pub fn pane_tree(&self) -> &PaneTree {
+        &self.editor.state.pane_tree
+    }
+}
@@ -0,0 +1,480 @@
+// This file is part of BadRed.
+
+// BadRed is free software: you can redistribute it and/or modify it under the terms of the GNU General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+//
+// BadRed is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+use std::path::PathBuf;
+
+use bad_red_lib::{
+    buffer::ContentBuffer,
+    keymap::RedKeyEvent,
+    script_handler::ScriptHandler,
+    script_runtime::SchedulerStep,
+    test_support::Headless,
+};
+use crossterm::event::{KeyCode, KeyModifiers};
+
+fn key(c: char) -> RedKeyEvent {
+    RedKeyEvent {
+        code: KeyCode::Char(c),
+        modifiers: KeyModifiers::NONE,
+    }
+}
+
+#[test]
+fn key_event_hook_inserts_into_active_buffer() {
+    let script_handler = ScriptHandler::new(PathBuf::new()).unwrap();
+    let mut headless = Headless::with_init(
+        &script_handler.lua,
+        r#"
+            coroutine.yield(red.call.set_hook("key_event", function(event)
+                coroutine.yield(red.call.active_buffer_insert("hello"))
+            end))
+        "#,
+    )
+    .unwrap();
+
+    headless.press(&[key('h')]).unwrap();
+    assert_eq!(headless.active_buffer_content().as_deref(), Some("hello"));
+
+    headless.press(&[key('h'), key('h')]).unwrap();
+    assert_eq!(
+        headless.active_buffer_content().as_deref(),
+        Some("hellohellohello")
+    );
+}
+
+#[test]
+fn init_script_runs_before_with_init_returns() {
+    let script_handler = ScriptHandler::new(PathBuf::new()).unwrap();
+    let headless = Headless::with_init(
+        &script_handler.lua,
+        r#"
+            coroutine.yield(red.call.pane_v_split(0))
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(headless.pane_tree().dump().len(), 3);
+    assert!(!headless.has_exited);
+}
+
+#[test]
+fn exiting_script_marks_headless_exited() {
+    let script_handler = ScriptHandler::new(PathBuf::new()).unwrap();
+    let mut headless = Headless::with_init(
+        &script_handler.lua,
+        r#"
+            coroutine.yield(red.call.set_hook("key_event", function(event)
+                coroutine.yield(red.call.editor_exit())
+            end))
+        "#,
+    )
+    .unwrap();
+
+    assert!(!headless.has_exited);
+    headless.press(&[key('q')]).unwrap();
+    assert!(headless.has_exited);
+}
+
+#[test]
+fn step_runs_one_call_at_a_time() {
+    let script_handler = ScriptHandler::new(PathBuf::new()).unwrap();
+    let mut headless = Headless::with_init(&script_handler.lua, "").unwrap();
+    assert_eq!(headless.step().unwrap(), SchedulerStep::Idle);
+}
+
+#[test]
+fn switching_modes_routes_a_key_to_each_modes_binding() {
+    let script_handler = ScriptHandler::new(PathBuf::new()).unwrap();
+    let mut headless = Headless::with_init(
+        &script_handler.lua,
+        r#"
+            red.doc = require("doc")
+            red.keymap = require("keymap")
+            local editor = require("editor")
+
+            red.keymap.set_mode_map("normal", {
+                x = function() coroutine.yield(red.call.active_buffer_insert("n")) end,
+                i = function() editor.set_mode("insert") end,
+            })
+            red.keymap.set_mode_map("insert", {
+                x = function() coroutine.yield(red.call.active_buffer_insert("i")) end,
+            })
+            editor.set_mode("normal")
+
+            coroutine.yield(red.call.set_hook("key_event", function(event)
+                red.keymap.event(event)
+            end))
+        "#,
+    )
+    .unwrap();
+
+    headless.press(&[key('x')]).unwrap();
+    assert_eq!(headless.active_buffer_content().as_deref(), Some("n"));
+
+    headless.press(&[key('i'), key('x')]).unwrap();
+    assert_eq!(headless.editor.state.mode, "insert");
+    assert_eq!(headless.active_buffer_content().as_deref(), Some("ni"));
+}
+
+#[test]
+fn swapping_pane_buffers_fires_a_change_hook_for_each_pane() {
+    let script_handler = ScriptHandler::new(PathBuf::new()).unwrap();
+    let headless = Headless::with_init(
+        &script_handler.lua,
+        r#"
+            local split = coroutine.yield(red.call.pane_v_split_new_buffer(0))
+            local log = coroutine.yield(red.call.buffer_open())
+            coroutine.yield(red.call.set_hook("pane_buffer_changed", function(change)
+                coroutine.yield(red.call.buffer_insert(
+                    log,
+                    change.pane_id .. ":" .. change.buffer_id .. ";"
+                ))
+            end))
+            coroutine.yield(red.call.pane_swap_buffers(0, split.pane_id))
+        "#,
+    )
+    .unwrap();
+
+    let pane_tree = headless.pane_tree();
+    assert_eq!(pane_tree.pane_by_index(0).unwrap().buffer_id, 1);
+    assert_eq!(pane_tree.pane_by_index(1).unwrap().buffer_id, 0);
+
+    let log = headless.buffer_content(2).unwrap();
+    assert!(log.contains("0:1;"));
+    assert!(log.contains("1:0;"));
+    assert_eq!(log.len(), "0:1;1:0;".len());
+}
+
+#[test]
+fn active_buffer_edits_change_the_active_panes_buffer() {
+    let script_handler = ScriptHandler::new(PathBuf::new()).unwrap();
+    let mut headless = Headless::with_init(
+        &script_handler.lua,
+        r#"
+            coroutine.yield(red.call.buffer_insert(0, "first"))
+            local split = coroutine.yield(red.call.pane_v_split_new_buffer(0))
+            coroutine.yield(red.call.set_active_pane(split.pane_id))
+            coroutine.yield(red.call.set_hook("key_event", function(event)
+                coroutine.yield(red.call.active_buffer_insert("abc"))
+                coroutine.yield(red.call.buffer_set_cursor(split.buffer_id, 0, false))
+                coroutine.yield(red.call.active_buffer_delete(1))
+            end))
+        "#,
+    )
+    .unwrap();
+
+    headless.press(&[key('x')]).unwrap();
+    assert_eq!(headless.active_buffer_content().as_deref(), Some("bc"));
+    assert_eq!(headless.buffer_content(0).as_deref(), Some("first"));
+}
+
+#[test]
+fn stepping_observes_each_call_of_a_script() {
+    let script_handler = ScriptHandler::new(PathBuf::new()).unwrap();
+    let mut headless = Headless::with_init(
+        &script_handler.lua,
+        r#"
+            coroutine.yield(red.call.set_hook("key_event", function(event)
+                coroutine.yield(red.call.active_buffer_insert("a"))
+                coroutine.yield(red.call.active_buffer_insert("b"))
+            end))
+        "#,
+    )
+    .unwrap();
+    headless.editor.handle_red_key_event(key('x')).unwrap();
+
+    // Steps starting the hook make no call, so step until the first insert
+    let mut steps = 0;
+    while headless.active_buffer_content().as_deref() == Some("") {
+        assert_ne!(headless.step().unwrap(), SchedulerStep::Idle);
+        steps += 1;
+        assert!(steps < 10);
+    }
+    assert_eq!(headless.active_buffer_content().as_deref(), Some("a"));
+
+    assert_eq!(
+        headless.step().unwrap(),
+        SchedulerStep::Ran {
+            is_script_done: true
+        }
+    );
+    assert_eq!(headless.active_buffer_content().as_deref(), Some("ab"));
+    assert_eq!(headless.step().unwrap(), SchedulerStep::Idle);
+}
+
+#[test]
+fn task_results_are_reported_through_the_task_complete_hook() {
+    let path = std::env::temp_dir().join(format!("bad_red_task_{}", std::process::id()));
+    std::fs::write(&path, "from disk").unwrap();
+    let script_handler = ScriptHandler::new(PathBuf::new()).unwrap();
+    let mut headless = Headless::with_init(
+        &script_handler.lua,
+        &format!(
+            r#"
+                coroutine.yield(red.call.set_hook("task_complete", function(completion)
+                    coroutine.yield(red.call.buffer_insert(0, completion.result.values[1]))
+                end))
+                coroutine.yield(red.call.spawn_read_file("{}"))
+            "#,
+            path.to_string_lossy()
+        ),
+    )
+    .unwrap();
+
+    // The scheduler went idle without waiting on the task
+    let mut runs = 0;
+    while headless.buffer_content(0).as_deref() == Some("") {
+        assert!(runs < 500);
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        headless.run_until_idle().unwrap();
+        runs += 1;
+    }
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(headless.buffer_content(0).as_deref(), Some("from disk"));
+}
+
+#[test]
+fn caught_call_errors_expose_their_kind_and_message() {
+    let script_handler = ScriptHandler::new(PathBuf::new()).unwrap();
+    let headless = Headless::with_init(
+        &script_handler.lua,
+        r#"
+            local ok, error = pcall(function()
+                return coroutine.yield(red.call.buffer_insert(99, "text"))
+            end)
+            local report = tostring(ok) .. "|" .. error.kind .. "|" .. error.message
+
+            -- Coroutines of the script's own are resumed with values as given
+            local inner = coroutine.create(function()
+                return coroutine.yield("from inner")
+            end)
+            local _, yielded = coroutine.resume(inner)
+            local _, returned = coroutine.resume(inner, "to inner")
+            report = report .. "|" .. yielded .. "|" .. returned
+
+            coroutine.yield(red.call.buffer_insert(0, report))
+        "#,
+    )
+    .unwrap();
+
+    let report = headless.buffer_content(0).unwrap();
+    let parts: Vec<&str> = report.split('|').collect();
+    assert_eq!(parts[0], "false");
+    assert_eq!(parts[1], "script");
+    assert!(parts[2].contains("99"));
+    assert_eq!(&parts[3..], ["from inner", "to inner"]);
+}
+
+#[test]
+fn trying_a_call_on_an_invalid_buffer_returns_false_and_a_message() {
+    let script_handler = ScriptHandler::new(PathBuf::new()).unwrap();
+    let headless = Headless::with_init(
+        &script_handler.lua,
+        r#"
+            local ok, message = red.try.buffer_insert(99, "text")
+            coroutine.yield(red.call.buffer_insert(0, tostring(ok) .. "|" .. type(message)))
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(headless.buffer_content(0).as_deref(), Some("false|string"));
+}
+
+/// Steps a script started by a key event until it first changes buffer 0,
+/// returning the number of steps taken.
+fn steps_until_buffer_changes(headless: &mut Headless) -> usize {
+    headless.editor.handle_red_key_event(key('x')).unwrap();
+
+    let mut steps = 0;
+    while headless.buffer_content(0).as_deref() == Some("") {
+        assert_ne!(headless.step().unwrap(), SchedulerStep::Idle);
+        steps += 1;
+        assert!(steps < 20);
+    }
+
+    steps
+}
+
+#[test]
+fn batched_calls_are_made_in_a_single_step() {
+    let script_handler = ScriptHandler::new(PathBuf::new()).unwrap();
+    let mut batched = Headless::with_init(
+        &script_handler.lua,
+        r#"
+            coroutine.yield(red.call.set_hook("key_event", function(event)
+                local results = coroutine.yield(red.call.batch({
+                    red.call.buffer_length(0),
+                    red.call.jump_list_push(),
+                    red.call.buffer_cursor(0),
+                }))
+                coroutine.yield(red.call.buffer_insert(0, string.format(
+                    "%d|%s|%s|%s", results.n, results[1], results[2], results[3]
+                )))
+            end))
+        "#,
+    )
+    .unwrap();
+    let single_script_handler = ScriptHandler::new(PathBuf::new()).unwrap();
+    let mut single = Headless::with_init(
+        &single_script_handler.lua,
+        r#"
+            coroutine.yield(red.call.set_hook("key_event", function(event)
+                local length = coroutine.yield(red.call.buffer_length(0))
+                coroutine.yield(red.call.buffer_insert(0, tostring(length)))
+            end))
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        steps_until_buffer_changes(&mut batched),
+        steps_until_buffer_changes(&mut single)
+    );
+    assert_eq!(batched.buffer_content(0).as_deref(), Some("3|0|nil|0"));
+}
+
+#[test]
+fn repeating_an_insert_applies_it_at_the_moved_cursor() {
+    let script_handler = ScriptHandler::new(PathBuf::new()).unwrap();
+    let headless = Headless::with_init(
+        &script_handler.lua,
+        r#"
+            coroutine.yield(red.call.buffer_insert(0, "ab"))
+            coroutine.yield(red.call.buffer_insert(0, "x"))
+            coroutine.yield(red.call.buffer_set_cursor(0, 0, false))
+            coroutine.yield(red.call.repeat_last_edit(0))
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(headless.buffer_content(0).as_deref(), Some("xabx"));
+}
+
+#[test]
+fn evaluated_lua_passes_its_result_to_the_continuation() {
+    let script_handler = ScriptHandler::new(PathBuf::new()).unwrap();
+    let headless = Headless::with_init(
+        &script_handler.lua,
+        r#"
+            coroutine.yield(red.call.eval_lua("return 1+1", function(result)
+                coroutine.yield(red.call.buffer_insert(0, math.type(result) .. "|" .. result))
+            end))
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(headless.buffer_content(0).as_deref(), Some("integer|2"));
+}
+
+#[test]
+fn setting_the_window_title_updates_the_stored_title() {
+    let script_handler = ScriptHandler::new(PathBuf::new()).unwrap();
+    let headless = Headless::with_init(
+        &script_handler.lua,
+        r#"
+            coroutine.yield(red.call.set_window_title("notes.txt - BadRed"))
+            coroutine.yield(red.call.buffer_insert(0, coroutine.yield(red.call.window_title())))
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(headless.editor.state.window_title, "notes.txt - BadRed");
+    assert_eq!(headless.buffer_content(0).as_deref(), Some("notes.txt - BadRed"));
+}
+
+#[test]
+fn line_ranges_match_each_lines_content() {
+    let script_handler = ScriptHandler::new(PathBuf::new()).unwrap();
+    Headless::with_init(
+        &script_handler.lua,
+        r#"
+            coroutine.yield(red.call.buffer_insert(0, "zero\none\ntwo\nthree"))
+            local range = coroutine.yield(red.call.buffer_line_range(0, 1, 3))
+            range_lines = table.concat(range, "|")
+            local lines = {}
+            for line_index = 1, 3 do
+                table.insert(lines, coroutine.yield(red.call.buffer_line_content(0, line_index)))
+            end
+            each_line = table.concat(lines, "|")
+        "#,
+    )
+    .unwrap();
+
+    let globals = script_handler.lua.globals();
+    let range_lines: String = globals.get("range_lines").unwrap();
+    let each_line: String = globals.get("each_line").unwrap();
+    assert_eq!(range_lines, each_line);
+    assert!(range_lines.starts_with("one"));
+    assert!(range_lines.ends_with("three"));
+}
+
+#[test]
+fn cursor_line_content_is_the_line_the_cursor_moved_to() {
+    let script_handler = ScriptHandler::new(PathBuf::new()).unwrap();
+    Headless::with_init(
+        &script_handler.lua,
+        r#"
+            coroutine.yield(red.call.buffer_insert(0, "first\nsecond\nthird"))
+            coroutine.yield(red.call.buffer_set_cursor_line(0, 1))
+            cursor_line = coroutine.yield(red.call.buffer_cursor_line_content(0))
+        "#,
+    )
+    .unwrap();
+
+    let cursor_line: String = script_handler.lua.globals().get("cursor_line").unwrap();
+    assert_eq!(cursor_line.trim_end(), "second");
+}
+
+#[test]
+fn cursor_visual_column_expands_a_leading_tab() {
+    let script_handler = ScriptHandler::new(PathBuf::new()).unwrap();
+    Headless::with_init(
+        &script_handler.lua,
+        r#"
+            coroutine.yield(red.call.buffer_insert(0, "first\n\tab"))
+            coroutine.yield(red.call.buffer_set_cursor(0, 8, false))
+            four_wide = coroutine.yield(red.call.buffer_cursor_visual_column(0, 4))
+            eight_wide = coroutine.yield(red.call.buffer_cursor_visual_column(0, 8))
+        "#,
+    )
+    .unwrap();
+
+    let globals = script_handler.lua.globals();
+    assert_eq!(globals.get::<_, u16>("four_wide").unwrap(), 5);
+    assert_eq!(globals.get::<_, u16>("eight_wide").unwrap(), 9);
+}
+
+#[test]
+fn typing_into_an_open_prompt_edits_its_input_not_the_active_buffer() {
+    let script_handler = ScriptHandler::new(PathBuf::new()).unwrap();
+    let mut headless = Headless::with_init(
+        &script_handler.lua,
+        r#"
+            coroutine.yield(red.call.set_hook("key_event", function(event)
+                coroutine.yield(red.call.active_buffer_insert("key"))
+            end))
+            coroutine.yield(red.call.prompt_open("Name: ", function(input)
+                coroutine.yield(red.call.active_buffer_insert("submitted " .. input))
+            end))
+        "#,
+    )
+    .unwrap();
+
+    headless.press(&[key('a'), key('b')]).unwrap();
+    let prompt = headless.editor.state.prompt.as_ref().unwrap();
+    assert_eq!(prompt.input.content_copy(), "ab");
+    assert_eq!(headless.active_buffer_content().as_deref(), Some(""));
+
+    headless
+        .press(&[RedKeyEvent {
+            code: KeyCode::Enter,
+            modifiers: KeyModifiers::NONE,
+        }])
+        .unwrap();
+    assert!(headless.editor.state.prompt.is_none());
+    assert_eq!(headless.active_buffer_content().as_deref(), Some("submitted ab"));
+}
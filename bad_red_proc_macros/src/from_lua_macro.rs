@@ -15,6 +15,8 @@ use syn::Generics;
 use syn::Lifetime;
 use syn::{DataEnum, DataStruct, DeriveInput, Ident};
 
+use crate::lua_attrs::is_skipped;
+
 pub fn from_lua_impl(derive_input: &DeriveInput, has_default: bool) -> TokenStream {
     let DeriveInput {
         ident,
@@ -32,10 +34,40 @@ pub fn from_lua_impl(derive_input: &DeriveInput, has_default: bool) -> TokenStre
     }
 }
 
+/// FromLua for a single field struct read directly from the field's Lua value
+/// rather than from a type table.
+pub fn from_lua_transparent_impl(derive_input: &DeriveInput) -> TokenStream {
+    let DeriveInput {
+        ident,
+        data,
+        generics,
+        ..
+    } = derive_input;
+
+    let syn::Data::Struct(strct) = data else {
+        panic!("auto_lua(transparent) is only supported on structs");
+    };
+    if strct.fields.len() != 1 {
+        panic!("auto_lua(transparent) requires a struct with exactly one field");
+    }
+
+    let field_from_lua = quote! { mlua::FromLua::from_lua(value, _lua)? };
+    let struct_init = match &strct.fields {
+        syn::Fields::Named(named) => {
+            let field_ident = named.named[0].ident.as_ref().unwrap();
+            quote! { #ident { #field_ident: #field_from_lua } }
+        }
+        syn::Fields::Unnamed(_) => quote! { #ident(#field_from_lua) },
+        syn::Fields::Unit => unreachable!(),
+    };
+
+    gen_from_lua_impl(&ident, &generics, &quote! { Ok(#struct_init) })
+}
+
 fn body_from_lua_struct(ident: &Ident, strct: &DataStruct, has_default: bool) -> TokenStream {
     match &strct.fields {
         syn::Fields::Named(named) => {
-            let struct_init = from_lua_impl_struct_named_fields(&ident, &named);
+            let struct_init = from_lua_impl_struct_named_fields(&ident, &ident.to_string(), &named);
             let body = from_lua_impl_struct_type(&ident, &struct_init, has_default);
 
             quote! {
@@ -43,7 +75,8 @@ fn body_from_lua_struct(ident: &Ident, strct: &DataStruct, has_default: bool) ->
             }
         }
         syn::Fields::Unnamed(unnamed) => {
-            let struct_init = from_lua_impl_struct_unnamed_fields(&ident, &unnamed);
+            let struct_init =
+                from_lua_impl_struct_unnamed_fields(&ident, &ident.to_string(), &unnamed);
             let body = from_lua_impl_struct_type(&ident, &struct_init, has_default);
             quote! {
                 #body
@@ -95,6 +128,7 @@ fn from_lua_unit_struct_init(ident: &Ident) -> TokenStream {
 
 fn from_lua_impl_struct_unnamed_fields(
     init_name: &impl ToTokens,
+    type_name: &str,
     fields: &FieldsUnnamed,
 ) -> TokenStream {
     let field_idents = fields
@@ -105,15 +139,22 @@ fn from_lua_impl_struct_unnamed_fields(
 
     let field_extractions = field_idents.clone().rev().map(|(field_name, f)| {
         let ty = &f.ty;
-        quote! {
-            let #field_name: #ty = table.pop()?;
+        if is_skipped(f) {
+            quote! {
+                let #field_name: #ty = Default::default();
+            }
+        } else {
+            quote! {
+                let #field_name: #ty = table.pop()?;
+            }
         }
     });
 
     let field_list = field_idents.map(|(name, _)| name);
+    let values_table = from_lua_values_table(type_name);
 
     quote! {
-        let table = table.get::<&str, mlua::Table>("values")?;
+        #values_table
         #(#field_extractions);*;
         Ok(#init_name(#(#field_list),*))
     }
@@ -121,6 +162,7 @@ fn from_lua_impl_struct_unnamed_fields(
 
 fn from_lua_impl_struct_named_fields(
     init_expr: &impl ToTokens,
+    type_name: &str,
     fields: &FieldsNamed,
 ) -> TokenStream {
     let idents_fields_zip = fields.named.iter().map(|f| (f.clone().ident.unwrap(), f));
@@ -128,20 +170,47 @@ fn from_lua_impl_struct_named_fields(
     let field_extractions = idents_fields_zip.clone().map(|(ident, field)| {
         let ty = &field.ty;
         let ident_str = ident.to_string();
-        quote! {
-            let #ident = table.get::<&str, #ty>(#ident_str)?;
+        if is_skipped(field) {
+            quote! {
+                let #ident: #ty = Default::default();
+            }
+        } else {
+            quote! {
+                let #ident = table.get::<&str, #ty>(#ident_str)?;
+            }
         }
     });
 
     let field_idents = idents_fields_zip.map(|(ident, _)| ident);
+    let values_table = from_lua_values_table(type_name);
 
     quote! {
-        let table = table.get::<&str, mlua::Table>("values")?;
+        #values_table
         #(#field_extractions);*;
         Ok(#init_expr { #(#field_idents),* })
     }
 }
 
+/// Reads the "values" table holding a type's fields, erring with the type's
+/// name if it is missing rather than with mlua's generic conversion error.
+fn from_lua_values_table(type_name: &str) -> TokenStream {
+    quote! {
+        let table = match table.get::<&str, Option<mlua::Table>>("values")? {
+            Some(values) => values,
+            None => {
+                return Err(mlua::Error::FromLuaConversionError {
+                    from: "Table",
+                    to: #type_name,
+                    message: Some(format!(
+                        "Missing 'values' table holding the fields of {}",
+                        #type_name,
+                    )),
+                })
+            }
+        };
+    }
+}
+
 fn from_lua_enum(ident: &Ident, generics: &Generics, enm: &DataEnum, has_default: bool) -> TokenStream {
     let init_body = from_lua_enum_init(&ident, &enm);
     let table_init = from_lua_impl_struct_type(&ident, &init_body, has_default);
@@ -185,13 +254,16 @@ fn from_lua_enum_variants(
     let arm_iter = enm.variants.iter().map(|variant| {
         let variant_ident = &variant.ident;
         let variant_init_name = quote! { #enum_ident::#variant_ident };
+        let variant_type_name = format!("{}::{}", enum_ident, variant_ident);
         let values_init = match &variant.fields {
             syn::Fields::Named(named) => from_lua_impl_struct_named_fields(
                 &variant_init_name,
+                &variant_type_name,
                 &named,
             ),
             syn::Fields::Unnamed(unnamed) => from_lua_impl_struct_unnamed_fields(
                 &variant_init_name,
+                &variant_type_name,
                 &unnamed,
             ),
             syn::Fields::Unit => {
@@ -10,6 +10,8 @@ use syn::{
     Lifetime, Variant,
 };
 
+use crate::lua_attrs::is_skipped;
+
 pub fn into_lua_impl(derive_input: &DeriveInput) -> TokenStream {
     let DeriveInput {
         ident,
@@ -23,6 +25,31 @@ pub fn into_lua_impl(derive_input: &DeriveInput) -> TokenStream {
     gen_into_lua_impl(&ident, &generics, &into_lua_function_impl)
 }
 
+/// IntoLua for a single field struct written directly as the field's Lua value
+/// rather than as a type table.
+pub fn into_lua_transparent_impl(derive_input: &DeriveInput) -> TokenStream {
+    let DeriveInput {
+        ident,
+        generics,
+        data,
+        ..
+    } = derive_input;
+
+    let Data::Struct(strct) = data else {
+        panic!("auto_lua(transparent) is only supported on structs");
+    };
+    let field = match &strct.fields {
+        Fields::Named(named) if named.named.len() == 1 => {
+            let field_ident = named.named[0].ident.as_ref().unwrap();
+            quote! { self.#field_ident }
+        }
+        Fields::Unnamed(unnamed) if unnamed.unnamed.len() == 1 => quote! { self.0 },
+        _ => panic!("auto_lua(transparent) requires a struct with exactly one field"),
+    };
+
+    gen_into_lua_impl(&ident, &generics, &quote! { mlua::IntoLua::into_lua(#field, lua) })
+}
+
 fn gen_into_lua_impl(ident: &Ident, generics: &Generics, impl_body: &impl ToTokens) -> TokenStream {
     let lua_lifetime = generics
         .lifetimes()
@@ -91,21 +118,26 @@ fn table_fields_inserts(fields: &Fields) -> TokenStream {
 }
 
 fn table_unnamed_fields_inserts(unnamed: &FieldsUnnamed) -> TokenStream {
-    let field_pushes = unnamed.unnamed.iter().enumerate().map(|(i, _)| {
-        let index = Index {
-            index: i as u32,
-            span: Span::call_site(),
-        };
-        quote! {
-            values.push(self.#index)?;
-        }
-    });
+    let field_pushes = unnamed
+        .unnamed
+        .iter()
+        .enumerate()
+        .filter(|(_, f)| !is_skipped(f))
+        .map(|(i, _)| {
+            let index = Index {
+                index: i as u32,
+                span: Span::call_site(),
+            };
+            quote! {
+                values.push(self.#index)?;
+            }
+        });
 
     fields_value_table(field_pushes)
 }
 
 fn table_named_fields_inserts(named: &FieldsNamed) -> TokenStream {
-    let field_pushes = named.named.iter().map(|f| {
+    let field_pushes = named.named.iter().filter(|f| !is_skipped(f)).map(|f| {
         let ident = f.ident.as_ref().expect("Named field found without ident");
         let ident_string = ident.to_string();
         quote! {
@@ -175,20 +207,28 @@ fn table_enum_unnamed_variant_arm(
     variant_ident: &Ident,
     unnamed: &FieldsUnnamed,
 ) -> TokenStream {
-    let fields_names = unnamed
+    let fields_patterns = unnamed.unnamed.iter().enumerate().map(|(i, f)| {
+        if is_skipped(f) {
+            quote! { _ }
+        } else {
+            format_ident!("field{}", i).into_token_stream()
+        }
+    });
+
+    let fields_inserts = unnamed
         .unnamed
         .iter()
         .enumerate()
-        .map(|(i, _)| format_ident!("field{}", i));
-
-    let fields_inserts = fields_names.clone().map(|ident| {
-        quote! {
-            values.push(#ident)?;
-        }
-    });
+        .filter(|(_, f)| !is_skipped(f))
+        .map(|(i, _)| {
+            let ident = format_ident!("field{}", i);
+            quote! {
+                values.push(#ident)?;
+            }
+        });
 
     quote! {
-        #enum_ident::#variant_ident(#(#fields_names),*) => {
+        #enum_ident::#variant_ident(#(#fields_patterns),*) => {
             let values = lua.create_table()?;
             #(#fields_inserts)*
             Some(values)
@@ -201,7 +241,7 @@ fn table_enum_named_variant_arm(
     variant_ident: &Ident,
     named: &FieldsNamed,
 ) -> TokenStream {
-    let fields_idents = named.named.iter().map(|f| {
+    let fields_idents = named.named.iter().filter(|f| !is_skipped(f)).map(|f| {
         f.ident
             .as_ref()
             .expect("Named fields expected to have ident. Found: none")
@@ -214,7 +254,7 @@ fn table_enum_named_variant_arm(
     });
 
     quote! {
-        #enum_ident::#variant_ident { #(#fields_idents),* } => {
+        #enum_ident::#variant_ident { #(#fields_idents,)* .. } => {
             let values = lua.create_table()?;
             #(#fields_inserts)*
             Some(values)
@@ -6,13 +6,21 @@
 use quote::quote;
 
 use proc_macro::TokenStream;
-use syn::DeriveInput;
+use syn::{parse::Parser, DeriveInput};
 
 mod from_lua_macro;
 mod into_lua_macro;
 mod type_derives;
+mod lua_attrs;
+mod lua_annotations;
 mod script_table;
 
+/// Implements FromLua and IntoLua as a table tagged with the type's name. With
+/// `#[auto_lua(transparent)]`, a single field struct instead converts as its
+/// field's value. Fields marked `#[lua(skip)]` are left out of the table and
+/// set to their Default when converting from Lua. Enum variants marked
+/// `#[lua(rename = "...")]` use the given name in place of their snake_case
+/// identifier.
 #[proc_macro_attribute]
 pub fn auto_lua(args: TokenStream, item: TokenStream) -> TokenStream {
     auto_lua_impl(args, item, false)
@@ -28,13 +36,47 @@ pub fn auto_script_table(_args: TokenStream, item: TokenStream) -> TokenStream {
     script_table::script_table_impl(item.into()).into()
 }
 
-fn auto_lua_impl(_args: TokenStream, item: TokenStream, has_default: bool) -> TokenStream {
+fn auto_lua_impl(args: TokenStream, item: TokenStream, has_default: bool) -> TokenStream {
+    let mut is_transparent = false;
+    let args_parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("transparent") {
+            is_transparent = true;
+            Ok(())
+        } else {
+            Err(meta.error("Unsupported auto_lua argument"))
+        }
+    });
+    if let Err(e) = args_parser.parse(args) {
+        return e.to_compile_error().into();
+    }
+
     let item = proc_macro2::TokenStream::from(item);
-    let typedef: DeriveInput = syn::parse2(item.clone()).expect("Failed to parse");
+    let typedef: DeriveInput = syn::parse2(item).expect("Failed to parse");
+    if let syn::Data::Union(unn) = &typedef.data {
+        return syn::Error::new_spanned(unn.union_token, "auto_lua does not support unions")
+            .to_compile_error()
+            .into();
+    }
     let derives = type_derives::type_derives(&typedef);
-    let from_lua_impl = from_lua_macro::from_lua_impl(&typedef, has_default);
-    let into_lua_impl = into_lua_macro::into_lua_impl(&typedef);
+    let (from_lua_impl, into_lua_impl) = if is_transparent {
+        (
+            from_lua_macro::from_lua_transparent_impl(&typedef),
+            into_lua_macro::into_lua_transparent_impl(&typedef),
+        )
+    } else {
+        (
+            from_lua_macro::from_lua_impl(&typedef, has_default),
+            into_lua_macro::into_lua_impl(&typedef),
+        )
+    };
     let name_lua_impls = type_derives::name_type_impls(&typedef);
+    let annotation_impl = lua_annotations::annotation_impl(&typedef, is_transparent);
+
+    let mut item = typedef;
+    type_derives::rename_variants(&mut item);
+    if let Err(e) = lua_attrs::strip_lua_attrs(&mut item) {
+        return e.to_compile_error().into();
+    }
 
     quote! {
         #derives
@@ -44,6 +86,8 @@ fn auto_lua_impl(_args: TokenStream, item: TokenStream, has_default: bool) -> To
         #into_lua_impl
 
         #name_lua_impls
+
+        #annotation_impl
     }
     .into()
 }
@@ -0,0 +1,135 @@
+// This file is part of BadRed.
+//
+// BadRed is free software: you can redistribute it and/or modify it under the terms of the GNU General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// 
+// BadRed is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Fields, GenericArgument, PathArguments, Type};
+
+use crate::lua_attrs::is_skipped;
+
+/// Generates a `lua_annotation` function returning LuaLS annotations
+/// describing the type's Lua representation.
+pub fn annotation_impl(typedef: &DeriveInput, is_transparent: bool) -> TokenStream {
+    let ident = &typedef.ident;
+    let ident_str = ident.to_string();
+    let (impl_generics, ty_generics, where_clause) = typedef.generics.split_for_impl();
+
+    let body = match &typedef.data {
+        Data::Struct(strct) if is_transparent => {
+            let inner_type = strct.fields.iter().next().map(|f| lua_type(&f.ty));
+            let annotation = format!("---@alias {} {}\n", ident_str, inner_type.unwrap_or_default());
+            quote! { #annotation.to_string() }
+        }
+        Data::Struct(strct) => {
+            let mut annotation = format!("---@class {0}\n---@field type \"{0}\"\n", ident_str);
+            if !strct.fields.is_empty() {
+                annotation += &format!("---@field values {}\n", fields_table_type(&strct.fields));
+            }
+            quote! { #annotation.to_string() }
+        }
+        Data::Enum(enm) => {
+            let name_ident = format_ident!("{}Name", ident);
+            let variant_idents = enm.variants.iter().map(|v| &v.ident);
+            let header = format!("---@class {0}\n---@field type \"{0}\"\n", ident_str);
+            quote! {
+                let variant_names = [
+                    #(format!("\"{}\"", Into::<&'static str>::into(#name_ident::#variant_idents))),*
+                ];
+                format!(
+                    "{}---@field variant {}\n---@field values table?\n",
+                    #header,
+                    variant_names.join("|"),
+                )
+            }
+        }
+        Data::Union(unn) => {
+            return syn::Error::new_spanned(unn.union_token, "auto_lua does not support unions")
+                .to_compile_error()
+        }
+    };
+
+    quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            pub fn lua_annotation() -> String {
+                #body
+            }
+        }
+    }
+}
+
+/// LuaLS parameter list for the fields, such as `buffer_id: integer, content: string`
+pub fn fields_params(fields: &Fields) -> String {
+    fields
+        .iter()
+        .filter(|f| !is_skipped(f))
+        .enumerate()
+        .map(|(i, f)| {
+            let name = f
+                .ident
+                .as_ref()
+                .map(|ident| ident.to_string())
+                .unwrap_or_else(|| format!("field{}", i));
+            format!("{}: {}", name, lua_type(&f.ty))
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn fields_table_type(fields: &Fields) -> String {
+    let entries = fields
+        .iter()
+        .filter(|f| !is_skipped(f))
+        .enumerate()
+        .map(|(i, f)| match &f.ident {
+            Some(ident) => format!("{}: {}", ident, lua_type(&f.ty)),
+            None => format!("[{}]: {}", i + 1, lua_type(&f.ty)),
+        })
+        .collect::<Vec<_>>();
+
+    format!("{{ {} }}", entries.join(", "))
+}
+
+/// LuaLS type name for a Rust type. Types not built into Lua are named after
+/// the Rust type, matching the class of an auto_lua type.
+pub fn lua_type(ty: &Type) -> String {
+    let segment = match ty {
+        Type::Reference(reference) => return lua_type(&reference.elem),
+        Type::Tuple(tuple) if tuple.elems.is_empty() => return "nil".to_string(),
+        Type::Path(path) => path.path.segments.last(),
+        _ => None,
+    };
+    let Some(segment) = segment else {
+        return "any".to_string();
+    };
+
+    let type_args = match &segment.arguments {
+        PathArguments::AngleBracketed(args) => args
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                GenericArgument::Type(ty) => Some(lua_type(ty)),
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    };
+
+    match (segment.ident.to_string().as_str(), type_args.as_slice()) {
+        (
+            "u8" | "u16" | "u32" | "u64" | "usize" | "i8" | "i16" | "i32" | "i64" | "isize",
+            _,
+        ) => "integer".to_string(),
+        ("f32" | "f64", _) => "number".to_string(),
+        ("bool", _) => "boolean".to_string(),
+        ("String" | "str" | "char", _) => "string".to_string(),
+        ("Value", _) => "any".to_string(),
+        ("Function", _) => "function".to_string(),
+        ("Table", _) => "table".to_string(),
+        ("Option", [inner]) => format!("{}?", inner),
+        ("Vec", [inner]) => format!("{}[]", inner),
+        ("HashMap", [key, value]) => format!("table<{}, {}>", key, value),
+        (name, _) => name.to_string(),
+    }
+}
@@ -0,0 +1,72 @@
+// This file is part of BadRed.
+//
+// BadRed is free software: you can redistribute it and/or modify it under the terms of the GNU General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// 
+// BadRed is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+use syn::{Attribute, Data, DeriveInput, Field, LitStr, Variant};
+
+/// Options set by `#[lua(...)]` attributes on a field or variant
+#[derive(Default)]
+struct LuaAttrs {
+    skip: bool,
+    rename: Option<String>,
+}
+
+impl LuaAttrs {
+    fn parse(attrs: &[Attribute]) -> Self {
+        let mut lua_attrs = Self::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("lua")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("skip") {
+                    lua_attrs.skip = true;
+                    Ok(())
+                } else if meta.path.is_ident("rename") {
+                    let name: LitStr = meta.value()?.parse()?;
+                    lua_attrs.rename = Some(name.value());
+                    Ok(())
+                } else {
+                    Err(meta.error("Unsupported lua attribute"))
+                }
+            })
+            .expect("Failed to parse lua attribute");
+        }
+
+        lua_attrs
+    }
+}
+
+/// Whether the field is marked `#[lua(skip)]`, leaving it out of the Lua
+/// representation and filling it with its Default when converting from Lua.
+pub fn is_skipped(field: &Field) -> bool {
+    LuaAttrs::parse(&field.attrs).skip
+}
+
+/// Name set on the variant by `#[lua(rename = "...")]` to use in place of its
+/// snake_case identifier.
+pub fn renamed(variant: &Variant) -> Option<String> {
+    LuaAttrs::parse(&variant.attrs).rename
+}
+
+/// Removes `#[lua(...)]` attributes from the type so they are not left for
+/// the compiler once auto_lua has read them.
+pub fn strip_lua_attrs(typedef: &mut DeriveInput) -> syn::Result<()> {
+    let strip = |attrs: &mut Vec<Attribute>| attrs.retain(|attr| !attr.path().is_ident("lua"));
+
+    match &mut typedef.data {
+        Data::Struct(strct) => strct.fields.iter_mut().for_each(|f| strip(&mut f.attrs)),
+        Data::Enum(enm) => {
+            for variant in enm.variants.iter_mut() {
+                strip(&mut variant.attrs);
+                variant.fields.iter_mut().for_each(|f| strip(&mut f.attrs));
+            }
+        }
+        Data::Union(unn) => {
+            return Err(syn::Error::new_spanned(
+                unn.union_token,
+                "auto_lua does not support unions",
+            ))
+        }
+    }
+
+    Ok(())
+}
@@ -7,6 +7,8 @@ use proc_macro2::{Span, TokenStream};
 use quote::{format_ident, quote};
 use syn::{Data, DataEnum, DeriveInput, Generics, Ident, Lifetime, Variant};
 
+use crate::lua_annotations::{fields_params, lua_type};
+
 pub fn script_table_impl(token_stream: TokenStream) -> TokenStream {
     let DeriveInput {
         ident,
@@ -31,6 +33,11 @@ fn script_table_impl_body(ident: &Ident, _generics: &Generics, data: &Data) -> T
         Data::Union(_) => unimplemented!(),
     };
 
+    let annotations_body = match data {
+        Data::Enum(enm) => script_table_annotations_body(ident, enm),
+        Data::Struct(_) | Data::Union(_) => unimplemented!(),
+    };
+
     let lifetime = Lifetime::new("'lua", Span::call_site());
     let anon_lifetime = Lifetime::new("'_", Span::call_site());
 
@@ -41,6 +48,36 @@ fn script_table_impl_body(ident: &Ident, _generics: &Generics, data: &Data) -> T
                 #body
             }
         }
+
+        impl #ident <#anon_lifetime> {
+            /// LuaLS annotations for the functions of the script table
+            pub fn lua_annotations() -> String {
+                #annotations_body
+            }
+        }
+    }
+}
+
+fn script_table_annotations_body(enum_ident: &Ident, enm: &DataEnum) -> TokenStream {
+    let name_ident = format_ident!("{}Name", enum_ident);
+    let return_type = lua_type(&syn::parse_quote! { #enum_ident });
+    let field_lines = enm.variants.iter().map(|variant| {
+        let variant_ident = &variant.ident;
+        let params = fields_params(&variant.fields);
+        quote! {
+            annotations.push_str(&format!(
+                "---@field {} fun({}): {}\n",
+                Into::<&'static str>::into(#name_ident::#variant_ident),
+                #params,
+                #return_type,
+            ));
+        }
+    });
+
+    quote! {
+        let mut annotations = String::new();
+        #(#field_lines)*
+        annotations
     }
 }
 
@@ -76,11 +113,34 @@ fn script_table_enum_arm(enum_ident: &Ident, name_ident: &Ident, variant: &Varia
     let variant_ident = &variant.ident;
     let variant_args = script_table_variant_args(variant);
     let variant_params = script_table_variant_params(variant);
+    let arg_count = variant.fields.len();
+    let expected_args = fields_params(&variant.fields);
     quote! {
         #name_ident::#variant_ident => {
+            let call_name: &'static str = case.into();
             table.set(
-                Into::<&'static str>::into(case),
-                lua.create_function(|_, #variant_args| Ok(#enum_ident::#variant_ident #variant_params))?,
+                call_name,
+                lua.create_function(move |lua, args: mlua::MultiValue| {
+                    // Conversion errors are reported with the call's expected
+                    // arguments since mlua's own errors do not name the call
+                    let arg_error = |message: String| {
+                        mlua::Error::RuntimeError(format!(
+                            "Invalid arguments to red.call.{}. Expected ({}): {}",
+                            call_name, #expected_args, message,
+                        ))
+                    };
+                    if args.len() > #arg_count {
+                        return Err(arg_error(format!(
+                            "Found {} arguments but expected at most {}",
+                            args.len(),
+                            #arg_count,
+                        )));
+                    }
+
+                    let #variant_args = mlua::FromLuaMulti::from_lua_multi(args, lua)
+                        .map_err(|e| arg_error(e.to_string()))?;
+                    Ok(#enum_ident::#variant_ident #variant_params)
+                })?,
             )?;
         }
     }
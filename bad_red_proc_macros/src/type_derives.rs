@@ -7,6 +7,8 @@ use quote::{format_ident, quote};
 use proc_macro2::{TokenStream, Ident, Span};
 use syn::{DeriveInput, Lifetime};
 
+use crate::lua_attrs::renamed;
+
 pub fn type_derives(typedef: &DeriveInput) -> TokenStream {
     match typedef.data {
         syn::Data::Struct(_) => quote! {},
@@ -24,6 +26,22 @@ pub fn type_derives(typedef: &DeriveInput) -> TokenStream {
     }
 }
 
+/// Passes `#[lua(rename = "...")]` variant names on to the strum derives so the
+/// enum's Name type converts to and from the renamed string.
+pub fn rename_variants(typedef: &mut DeriveInput) {
+    let syn::Data::Enum(enm) = &mut typedef.data else {
+        return;
+    };
+
+    for variant in enm.variants.iter_mut() {
+        if let Some(name) = renamed(variant) {
+            variant.attrs.push(syn::parse_quote! {
+                #[strum_discriminants(strum(serialize = #name))]
+            });
+        }
+    }
+}
+
 pub fn name_type_impls(typedef: &DeriveInput) -> TokenStream {
     match &typedef.data {
         syn::Data::Struct(_) => quote! {},